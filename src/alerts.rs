@@ -0,0 +1,469 @@
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::MonitoringConfig;
+use crate::metrics;
+
+/// How urgent an alert is. Each channel only receives alerts at or above
+/// its configured minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Routine events such as fills and market rotation
+    Info,
+    /// Needs attention soon: exits, disputes, risk limits
+    Warning,
+    /// Needs attention now: kill switch, lost access to funds
+    Critical,
+}
+
+/// Payload format of an incoming webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Discord,
+    Slack,
+    /// `{"severity", "message", "timestamp"}` for custom receivers
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub kind: WebhookKind,
+    pub url: String,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Info
+}
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Upgrade a plain connection with STARTTLS (usually port 587)
+    Starttls,
+    /// TLS from the first byte (usually port 465)
+    Tls,
+    /// No encryption; only for local relays
+    None,
+}
+
+/// Email channel, meant for paging on critical events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default = "default_smtp_tls")]
+    pub tls: SmtpTls,
+    /// Login user; leave empty for relays without authentication
+    #[serde(default)]
+    pub username: String,
+    /// Environment variable holding the SMTP password
+    #[serde(default = "default_smtp_password_env")]
+    pub password_env: String,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_smtp_min_severity")]
+    pub min_severity: Severity,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+fn default_smtp_tls() -> SmtpTls {
+    SmtpTls::Starttls
+}
+fn default_smtp_password_env() -> String {
+    "SMTP_PASSWORD".into()
+}
+fn default_smtp_min_severity() -> Severity {
+    Severity::Critical
+}
+
+/// Build the alert email. The subject is the message's first line.
+pub fn build_email(smtp: &SmtpConfig, severity: Severity, message: &str) -> Result<Message> {
+    let from: Mailbox = smtp.from.parse().context("parsing smtp.from")?;
+    let subject = message.lines().next().unwrap_or_default();
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(format!("[polymarket-lp {severity:?}] {subject}"));
+    for to in &smtp.to {
+        let to: Mailbox = to
+            .parse()
+            .with_context(|| format!("parsing smtp.to {to:?}"))?;
+        builder = builder.to(to);
+    }
+    builder
+        .body(message.to_string())
+        .context("building alert email")
+}
+
+async fn send_email(smtp: &SmtpConfig, severity: Severity, message: &str) -> Result<()> {
+    let email = build_email(smtp, severity, message)?;
+    let mut transport = match smtp.tls {
+        SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+            .context("configuring SMTP STARTTLS")?,
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)
+            .context("configuring SMTP TLS")?,
+        SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+    }
+    .port(smtp.port);
+    if !smtp.username.is_empty() {
+        let password = std::env::var(&smtp.password_env)
+            .with_context(|| format!("environment variable '{}' not set", smtp.password_env))?;
+        transport = transport.credentials(Credentials::new(smtp.username.clone(), password));
+    }
+    transport
+        .build()
+        .send(email)
+        .await
+        .context("sending alert email")?;
+    info!(to = ?smtp.to, ?severity, "Email alert sent");
+    Ok(())
+}
+
+/// JSON body for a webhook of the given kind.
+pub fn webhook_payload(kind: WebhookKind, severity: Severity, message: &str) -> serde_json::Value {
+    match kind {
+        WebhookKind::Discord => serde_json::json!({ "content": message }),
+        WebhookKind::Slack => serde_json::json!({ "text": message }),
+        WebhookKind::Json => serde_json::json!({
+            "severity": severity,
+            "message": message,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }),
+    }
+}
+
+async fn send_webhook(webhook: &WebhookConfig, severity: Severity, message: &str) -> Result<()> {
+    reqwest::Client::new()
+        .post(&webhook.url)
+        .json(&webhook_payload(webhook.kind, severity, message))
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .with_context(|| format!("sending {:?} webhook alert", webhook.kind))?;
+    info!(kind = ?webhook.kind, ?severity, "Webhook alert sent");
+    Ok(())
+}
+
+/// What an alert is about, for per-rule settings in `[alerts.rules]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertRule {
+    /// A fill's markout matured; value is the fill's notional in USDC
+    Fill,
+    /// Markets added, rotated out, retired, or changing state
+    MarketChange,
+    /// Stop-loss or take-profit started an unwind
+    Exit,
+    /// Placement, cancel, fill, or trade-through rates off their baseline
+    FlowAnomaly,
+    /// Market closed, disputed, or resolved
+    Resolution,
+    KillSwitch,
+    /// Portfolio VaR above its limit; value is the VaR in USDC
+    Var,
+    /// Local clock drift past `monitoring.max_clock_skew_ms`, or back within it
+    ClockSkew,
+    /// A background task given up on
+    TaskFailure,
+    /// Operator pause and resume
+    Operator,
+}
+
+/// Overrides for one alert rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConfig {
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+    /// Suppress alerts whose value (see `AlertRule`) is below this
+    #[serde(default)]
+    pub threshold: Option<Decimal>,
+    /// Minimum time between two alerts of this rule; defaults to
+    /// `alerts.cooldown_secs`
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+    /// Send at this severity instead of the built-in one, which decides the
+    /// channels it reaches
+    #[serde(default)]
+    pub severity: Option<Severity>,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_rule_enabled(),
+            threshold: None,
+            cooldown_secs: None,
+            severity: None,
+        }
+    }
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+/// Daily UTC window in which only critical alerts are sent. A window whose
+/// end is before its start wraps past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// The `[alerts]` section: thresholds, cooldowns, and severity routing per
+/// rule, plus quiet hours.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Minimum time between two alerts of the same rule (0 = none)
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    #[serde(default)]
+    pub rules: HashMap<AlertRule, RuleConfig>,
+}
+
+/// One alert on its way to the channels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub rule: AlertRule,
+    pub severity: Severity,
+    pub message: String,
+    /// Magnitude compared against the rule's threshold
+    pub value: Option<Decimal>,
+}
+
+impl Alert {
+    pub fn new(rule: AlertRule, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            severity,
+            message: message.into(),
+            value: None,
+        }
+    }
+
+    pub fn with_value(mut self, value: Decimal) -> Self {
+        self.value = Some(value);
+        self
+    }
+}
+
+/// Applies `[alerts]` to each alert before it is sent, remembering when
+/// each rule last fired for its cooldown.
+#[derive(Debug, Clone, Default)]
+pub struct Alerter {
+    config: AlertsConfig,
+    last_sent: HashMap<AlertRule, Instant>,
+}
+
+impl Alerter {
+    pub fn new(config: AlertsConfig) -> Self {
+        Self {
+            config,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    /// Apply a reloaded `[alerts]` section, keeping running cooldowns.
+    pub fn reconfigure(&mut self, config: AlertsConfig) {
+        self.config = config;
+    }
+
+    /// The severity to send `alert` at, or `None` if a rule suppresses it.
+    /// An admitted alert starts its rule's cooldown.
+    pub fn admit(&mut self, alert: &Alert, now: Instant, time: NaiveTime) -> Option<Severity> {
+        let rule = self
+            .config
+            .rules
+            .get(&alert.rule)
+            .cloned()
+            .unwrap_or_default();
+        if !rule.enabled {
+            return None;
+        }
+        if let (Some(threshold), Some(value)) = (rule.threshold, alert.value)
+            && value < threshold
+        {
+            return None;
+        }
+        let severity = rule.severity.unwrap_or(alert.severity);
+        if severity < Severity::Critical
+            && self.config.quiet_hours.is_some_and(|q| q.contains(time))
+        {
+            return None;
+        }
+        let cooldown = Duration::from_secs(rule.cooldown_secs.unwrap_or(self.config.cooldown_secs));
+        if let Some(last) = self.last_sent.get(&alert.rule)
+            && now.duration_since(*last) < cooldown
+        {
+            return None;
+        }
+        self.last_sent.insert(alert.rule, now);
+        Some(severity)
+    }
+
+    /// Send `alert` through the configured channels unless a rule
+    /// suppresses it.
+    pub async fn send(&mut self, monitoring: &MonitoringConfig, alert: Alert) {
+        let time = chrono::Utc::now().time();
+        match self.admit(&alert, Instant::now(), time) {
+            Some(severity) => send(monitoring, severity, &alert.message).await,
+            None => debug!(rule = ?alert.rule, message = %alert.message, "Alert suppressed"),
+        }
+    }
+}
+
+/// Send an alert to every configured channel whose minimum severity it
+/// meets. Failures are logged per channel and never propagated.
+pub async fn send(monitoring: &MonitoringConfig, severity: Severity, message: &str) {
+    if severity >= monitoring.telegram_min_severity
+        && let Err(e) = metrics::send_telegram_alert(
+            &monitoring.telegram_bot_token,
+            &monitoring.telegram_chat_id,
+            message,
+        )
+        .await
+    {
+        warn!(error = %e, "Failed to send Telegram alert");
+    }
+
+    for webhook in monitoring
+        .webhooks
+        .iter()
+        .filter(|w| severity >= w.min_severity)
+    {
+        if let Err(e) = send_webhook(webhook, severity, message).await {
+            warn!(error = %e, "Failed to send webhook alert");
+        }
+    }
+
+    if let Some(smtp) = &monitoring.smtp
+        && severity >= smtp.min_severity
+        && let Err(e) = send_email(smtp, severity, message).await
+    {
+        warn!(error = %e, "Failed to send email alert");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_webhook_payloads() {
+        let discord = webhook_payload(WebhookKind::Discord, Severity::Info, "hi");
+        assert_eq!(discord["content"], "hi");
+        let slack = webhook_payload(WebhookKind::Slack, Severity::Info, "hi");
+        assert_eq!(slack["text"], "hi");
+        let json = webhook_payload(WebhookKind::Json, Severity::Critical, "hi");
+        assert_eq!(json["severity"], "critical");
+        assert_eq!(json["message"], "hi");
+    }
+
+    #[test]
+    fn test_alert_email() {
+        let smtp: SmtpConfig = toml::from_str(
+            r#"
+            host = "smtp.example.com"
+            from = "bot@example.com"
+            to = ["ops@example.com", "oncall@example.com"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(smtp.port, 587);
+        assert_eq!(smtp.tls, SmtpTls::Starttls);
+        assert_eq!(smtp.min_severity, Severity::Critical);
+
+        let email = build_email(&smtp, Severity::Critical, "Kill switch tripped\nDetails").unwrap();
+        let raw = String::from_utf8(email.formatted()).unwrap();
+        assert!(raw.contains("Subject: [polymarket-lp Critical] Kill switch tripped"));
+        assert!(raw.contains("oncall@example.com"));
+    }
+
+    #[test]
+    fn test_alert_rules_thresholds_cooldowns_and_quiet_hours() {
+        let config: AlertsConfig = toml::from_str(
+            r#"
+            cooldown_secs = 60
+            quiet_hours = { start = "22:00:00", end = "07:00:00" }
+
+            [rules.fill]
+            threshold = 100
+            cooldown_secs = 0
+
+            [rules.var]
+            severity = "critical"
+
+            [rules.operator]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        let mut alerter = Alerter::new(config);
+        let now = Instant::now();
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let night = NaiveTime::from_hms_opt(23, 30, 0).unwrap();
+
+        let fill = |usd| Alert::new(AlertRule::Fill, Severity::Info, "fill").with_value(usd);
+        assert_eq!(alerter.admit(&fill(dec!(50)), now, noon), None);
+        let big = fill(dec!(150));
+        assert_eq!(alerter.admit(&big, now, noon), Some(Severity::Info));
+        assert_eq!(alerter.admit(&big, now, noon), Some(Severity::Info));
+        assert_eq!(alerter.admit(&big, now, night), None);
+
+        // Default cooldown applies to rules without their own
+        let exit = Alert::new(AlertRule::Exit, Severity::Warning, "exit");
+        assert_eq!(alerter.admit(&exit, now, noon), Some(Severity::Warning));
+        let soon = now + Duration::from_secs(30);
+        assert_eq!(alerter.admit(&exit, soon, noon), None);
+        let later = now + Duration::from_secs(61);
+        assert_eq!(alerter.admit(&exit, later, noon), Some(Severity::Warning));
+
+        // Rerouted to critical, so it still fires in quiet hours
+        let var = Alert::new(AlertRule::Var, Severity::Warning, "var");
+        assert_eq!(alerter.admit(&var, now, night), Some(Severity::Critical));
+        let pause = Alert::new(AlertRule::Operator, Severity::Info, "paused");
+        assert_eq!(alerter.admit(&pause, now, noon), None);
+    }
+
+    #[test]
+    fn test_webhook_config_parses() {
+        let webhook: WebhookConfig = toml::from_str(
+            r#"
+            kind = "discord"
+            url = "https://discord.com/api/webhooks/x"
+            min_severity = "critical"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(webhook.kind, WebhookKind::Discord);
+        assert!(Severity::Warning < webhook.min_severity);
+    }
+}
@@ -0,0 +1,458 @@
+use polymarket_client_sdk::clob::types::Side;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::config::RiskConfig;
+use crate::orders::TrackedOrder;
+
+/// Recent events needed before a rate can count as a spike, so a handful of
+/// orders against a near-silent baseline doesn't alert.
+const MIN_SPIKE_EVENTS: u64 = 20;
+/// Fill timestamps kept for the typical gap between fills.
+const MAX_FILL_SAMPLES: usize = 20;
+/// Fills needed before a drought can be judged against the typical gap.
+const MIN_FILL_SAMPLES: usize = 3;
+/// Window over which trade-throughs are counted toward an alert.
+const TRADE_THROUGH_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Our own order actions tracked per market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKind {
+    Placements,
+    Cancels,
+}
+
+/// Order flow that strays far from the market's own baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlowAnomaly {
+    /// Placements or cancels per minute over the recent window, far above
+    /// the baseline rate
+    RateSpike {
+        kind: FlowKind,
+        recent_per_min: Decimal,
+        baseline_per_min: Decimal,
+    },
+    /// No fills for many times the usual gap while orders were resting
+    FillDrought {
+        silent: Duration,
+        typical_gap: Duration,
+    },
+    /// Trades printed through our resting quotes without filling them
+    TradeThrough { count: usize, window: Duration },
+}
+
+impl FlowAnomaly {
+    /// Rate spikes suggest a requote loop, so quoting is slowed; a drought
+    /// only needs a human to look.
+    pub fn throttles(&self) -> bool {
+        matches!(self, FlowAnomaly::RateSpike { .. })
+    }
+}
+
+impl fmt::Display for FlowAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowAnomaly::RateSpike {
+                kind,
+                recent_per_min,
+                baseline_per_min,
+            } => write!(
+                f,
+                "{kind:?} at {recent_per_min:.1}/min vs baseline {baseline_per_min:.1}/min"
+            ),
+            FlowAnomaly::FillDrought {
+                silent,
+                typical_gap,
+            } => write!(
+                f,
+                "no fills for {}m (usually every {}m)",
+                silent.as_secs() / 60,
+                typical_gap.as_secs() / 60
+            ),
+            FlowAnomaly::TradeThrough { count, window } => write!(
+                f,
+                "{count} trades through our quotes without a fill in {}m",
+                window.as_secs() / 60
+            ),
+        }
+    }
+}
+
+/// Thresholds for flagging anomalous order flow. Zero multiples disable a
+/// check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowLimits {
+    pub rate_multiple: Decimal,
+    pub fill_gap_multiple: Decimal,
+    /// Recent window compared against the baseline
+    pub window: Duration,
+    /// History before the recent window that sets the normal rate
+    pub baseline: Duration,
+    /// How long requoting pauses after a rate spike
+    pub throttle: Duration,
+    /// How often the last trade is checked against resting quotes (zero
+    /// disables)
+    pub trade_through_check: Duration,
+    /// Trade-throughs within an hour that raise an anomaly (zero never does)
+    pub trade_through_alerts: u32,
+}
+
+impl Default for FlowLimits {
+    fn default() -> Self {
+        Self {
+            rate_multiple: Decimal::ZERO,
+            fill_gap_multiple: Decimal::ZERO,
+            window: Duration::from_secs(300),
+            baseline: Duration::from_secs(3600),
+            throttle: Duration::from_secs(600),
+            trade_through_check: Duration::ZERO,
+            trade_through_alerts: 0,
+        }
+    }
+}
+
+impl FlowLimits {
+    pub fn from_config(risk_config: &RiskConfig) -> Self {
+        Self {
+            rate_multiple: risk_config.anomaly_rate_multiple,
+            fill_gap_multiple: risk_config.anomaly_fill_gap_multiple,
+            window: Duration::from_secs(risk_config.anomaly_window_secs),
+            baseline: Duration::from_secs(risk_config.anomaly_baseline_secs),
+            throttle: Duration::from_secs(risk_config.anomaly_throttle_secs),
+            trade_through_check: Duration::from_secs(risk_config.trade_through_check_secs),
+            trade_through_alerts: risk_config.trade_through_alert_count,
+        }
+    }
+}
+
+/// Rolling record of one market's placements, cancels, and fills.
+#[derive(Debug, Clone, Default)]
+pub struct FlowMonitor {
+    events: VecDeque<(Instant, FlowKind, u64)>,
+    fills: VecDeque<Instant>,
+    /// First activity seen; no baseline exists before a full history
+    started: Option<Instant>,
+    /// Drought already reported; cleared by the next fill
+    drought_flagged: bool,
+}
+
+impl FlowMonitor {
+    pub fn record(&mut self, kind: FlowKind, count: usize, now: Instant) {
+        if count == 0 {
+            return;
+        }
+        self.started.get_or_insert(now);
+        self.events.push_back((now, kind, count as u64));
+    }
+
+    pub fn record_fill(&mut self, now: Instant) {
+        self.started.get_or_insert(now);
+        self.fills.push_back(now);
+        if self.fills.len() > MAX_FILL_SAMPLES {
+            self.fills.pop_front();
+        }
+        self.drought_flagged = false;
+    }
+
+    /// The first anomaly in current flow, if any. `quoting` is whether
+    /// orders are resting, without which a lack of fills is expected.
+    pub fn check(
+        &mut self,
+        limits: &FlowLimits,
+        now: Instant,
+        quoting: bool,
+    ) -> Option<FlowAnomaly> {
+        let history = limits.baseline + limits.window;
+        while let Some((at, _, _)) = self.events.front() {
+            if now.saturating_duration_since(*at) <= history {
+                break;
+            }
+            self.events.pop_front();
+        }
+
+        if limits.rate_multiple > Decimal::ZERO
+            && self
+                .started
+                .is_some_and(|s| now.saturating_duration_since(s) >= history)
+        {
+            for kind in [FlowKind::Cancels, FlowKind::Placements] {
+                if let Some(spike) = self.rate_spike(kind, limits, now) {
+                    return Some(spike);
+                }
+            }
+        }
+
+        if limits.fill_gap_multiple > Decimal::ZERO && quoting && !self.drought_flagged {
+            let drought = self.fill_drought(limits, now);
+            self.drought_flagged = drought.is_some();
+            return drought;
+        }
+        None
+    }
+
+    fn rate_spike(&self, kind: FlowKind, limits: &FlowLimits, now: Instant) -> Option<FlowAnomaly> {
+        let (mut recent, mut baseline) = (0u64, 0u64);
+        for (at, k, count) in &self.events {
+            if *k != kind {
+                continue;
+            }
+            if now.saturating_duration_since(*at) <= limits.window {
+                recent += count;
+            } else {
+                baseline += count;
+            }
+        }
+        if recent < MIN_SPIKE_EVENTS {
+            return None;
+        }
+        let per_min = |count: u64, span: Duration| {
+            Decimal::from(count) * Decimal::from(60) / Decimal::from(span.as_secs().max(1))
+        };
+        let recent_per_min = per_min(recent, limits.window);
+        let baseline_per_min = per_min(baseline, limits.baseline);
+        (recent_per_min > baseline_per_min * limits.rate_multiple).then_some(
+            FlowAnomaly::RateSpike {
+                kind,
+                recent_per_min,
+                baseline_per_min,
+            },
+        )
+    }
+
+    fn fill_drought(&self, limits: &FlowLimits, now: Instant) -> Option<FlowAnomaly> {
+        if self.fills.len() < MIN_FILL_SAMPLES {
+            return None;
+        }
+        let (first, last) = (*self.fills.front()?, *self.fills.back()?);
+        let typical_gap = last.saturating_duration_since(first) / (self.fills.len() as u32 - 1);
+        let silent = now.saturating_duration_since(last);
+        let limit = typical_gap.mul_f64(limits.fill_gap_multiple.to_f64().unwrap_or(0.0));
+        // Never flag a gap shorter than the recent window, however busy the
+        // market usually is
+        (silent > limit.max(limits.window)).then_some(FlowAnomaly::FillDrought {
+            silent,
+            typical_gap,
+        })
+    }
+}
+
+/// A resting quote as seen at the last trade-through check.
+#[derive(Debug, Clone)]
+struct WatchedQuote {
+    order_id: String,
+    /// Price in YES terms: a NO bid is a YES ask at the complement
+    yes_price: Decimal,
+    /// Buys YES (a YES bid or a NO ask)
+    long_yes: bool,
+    filled: Decimal,
+}
+
+/// Catches trades printed through our quoted price that never filled us,
+/// meaning the order wasn't live, was cancelled by a race, or tracking has
+/// drifted from the exchange.
+#[derive(Debug, Clone, Default)]
+pub struct TradeThroughs {
+    last_check: Option<Instant>,
+    last_print: Option<Decimal>,
+    watched: Vec<WatchedQuote>,
+    incidents: VecDeque<Instant>,
+    /// Alert already raised for the current run of incidents
+    flagged: bool,
+}
+
+impl TradeThroughs {
+    pub fn due(&self, now: Instant, every: Duration) -> bool {
+        !every.is_zero()
+            && self
+                .last_check
+                .is_none_or(|at| now.saturating_duration_since(at) >= every)
+    }
+
+    /// Compare the latest YES print with the quotes resting at the previous
+    /// check. True if it traded through one that is still live and unfilled
+    /// since then. Only a new print counts, and only strictly through the
+    /// price, since a trade at our price may just have beaten us in the queue.
+    pub fn check(
+        &mut self,
+        now: Instant,
+        yes_print: Decimal,
+        yes_token: &str,
+        orders: &[TrackedOrder],
+    ) -> bool {
+        let new_print = self.last_print.is_some_and(|last| last != yes_print);
+        let traded_through = new_print
+            && self.watched.iter().any(|w| {
+                let through = if w.long_yes {
+                    yes_print < w.yes_price
+                } else {
+                    yes_print > w.yes_price
+                };
+                through
+                    && orders
+                        .iter()
+                        .any(|o| o.order_id == w.order_id && o.is_active() && o.filled == w.filled)
+            });
+        if traded_through {
+            self.incidents.push_back(now);
+        }
+
+        self.last_check = Some(now);
+        self.last_print = Some(yes_print);
+        self.watched = orders
+            .iter()
+            .filter(|o| o.is_active())
+            .map(|o| {
+                let is_yes = o.token_id == yes_token;
+                WatchedQuote {
+                    order_id: o.order_id.clone(),
+                    yes_price: if is_yes {
+                        o.price
+                    } else {
+                        Decimal::ONE - o.price
+                    },
+                    long_yes: is_yes == (o.side == Side::Buy),
+                    filled: o.filled,
+                }
+            })
+            .collect();
+        traded_through
+    }
+
+    /// An anomaly once the incidents within the last hour reach `threshold`,
+    /// raised once until the count drops back below it.
+    pub fn anomaly(&mut self, now: Instant, threshold: u32) -> Option<FlowAnomaly> {
+        while let Some(at) = self.incidents.front() {
+            if now.saturating_duration_since(*at) <= TRADE_THROUGH_WINDOW {
+                break;
+            }
+            self.incidents.pop_front();
+        }
+        let count = self.incidents.len();
+        if threshold == 0 || count < threshold as usize {
+            self.flagged = false;
+            return None;
+        }
+        if self.flagged {
+            return None;
+        }
+        self.flagged = true;
+        Some(FlowAnomaly::TradeThrough {
+            count,
+            window: TRADE_THROUGH_WINDOW,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::OrderStatus;
+    use rust_decimal_macros::dec;
+
+    fn limits() -> FlowLimits {
+        FlowLimits {
+            rate_multiple: dec!(10),
+            fill_gap_multiple: dec!(5),
+            ..FlowLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_cancel_spike() {
+        let start = Instant::now();
+        let mut flow = FlowMonitor::default();
+        // One cancel a minute for the baseline hour
+        for m in 0..65u64 {
+            flow.record(FlowKind::Cancels, 1, start + Duration::from_secs(m * 60));
+        }
+        let now = start + Duration::from_secs(65 * 60);
+        assert_eq!(flow.check(&limits(), now, false), None);
+
+        // Then 100 cancels in a minute
+        flow.record(FlowKind::Cancels, 100, now);
+        let anomaly = flow.check(&limits(), now, false).unwrap();
+        assert!(anomaly.throttles());
+        assert!(matches!(
+            anomaly,
+            FlowAnomaly::RateSpike {
+                kind: FlowKind::Cancels,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_trade_through_needs_new_print_past_live_quote() {
+        let start = Instant::now();
+        let order = |id: &str, token: &str, side, price| TrackedOrder {
+            order_id: id.into(),
+            token_id: token.into(),
+            side,
+            price,
+            size: dec!(100),
+            filled: Decimal::ZERO,
+            status: OrderStatus::Open,
+        };
+        // YES bid at 0.48; NO bid at 0.48 is a YES ask at 0.52
+        let orders = vec![
+            order("bid", "yes", Side::Buy, dec!(0.48)),
+            order("no_bid", "no", Side::Buy, dec!(0.48)),
+        ];
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut throughs = TradeThroughs::default();
+        assert!(!throughs.check(at(0), dec!(0.50), "yes", &orders));
+        // Printing at our price may just be queue priority
+        assert!(!throughs.check(at(60), dec!(0.48), "yes", &orders));
+        assert!(throughs.check(at(120), dec!(0.47), "yes", &orders));
+        // The same print again is not a new trade
+        assert!(!throughs.check(at(180), dec!(0.47), "yes", &orders));
+        assert!(throughs.check(at(240), dec!(0.53), "yes", &orders));
+        assert_eq!(throughs.anomaly(at(240), 3), None);
+
+        // A fill on the quote explains the print
+        let mut filled = orders.clone();
+        filled[0].filled = dec!(10);
+        filled[0].status = OrderStatus::PartiallyFilled;
+        assert!(!throughs.check(at(300), dec!(0.46), "yes", &filled));
+
+        assert!(throughs.check(at(360), dec!(0.45), "yes", &filled));
+        let anomaly = throughs.anomaly(at(360), 3).unwrap();
+        assert!(!anomaly.throttles());
+        assert_eq!(throughs.anomaly(at(420), 3), None);
+    }
+
+    #[test]
+    fn test_fill_drought() {
+        let start = Instant::now();
+        let mut flow = FlowMonitor::default();
+        for m in 0..4u64 {
+            flow.record_fill(start + Duration::from_secs(m * 120));
+        }
+        let last = start + Duration::from_secs(360);
+        // Usual gap is 2 minutes; the floor is the 5-minute window
+        assert_eq!(
+            flow.check(&limits(), last + Duration::from_secs(240), true),
+            None
+        );
+        let anomaly = flow
+            .check(&limits(), last + Duration::from_secs(660), true)
+            .unwrap();
+        assert!(!anomaly.throttles());
+        // Reported once per drought, and never while not quoting
+        assert_eq!(
+            flow.check(&limits(), last + Duration::from_secs(900), true),
+            None
+        );
+        let mut idle = FlowMonitor {
+            fills: flow.fills.clone(),
+            ..FlowMonitor::default()
+        };
+        assert_eq!(
+            idle.check(&limits(), last + Duration::from_secs(900), false),
+            None
+        );
+    }
+}
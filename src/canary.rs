@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use comfy_table::Table;
+use comfy_table::presets::UTF8_FULL;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::path::Path;
+
+use crate::config::StrategyConfig;
+use crate::metrics::{MarketMetrics, MetricFn};
+
+/// A canary run: the configured strategy applies only to `condition_id`;
+/// every other market keeps `baseline`.
+#[derive(Debug, Clone)]
+pub struct CanaryRun {
+    pub condition_id: String,
+    pub baseline: StrategyConfig,
+}
+
+/// Load the `[strategy]` section the portfolio last ran with. A missing
+/// file means no run has recorded one yet.
+pub fn load_profile(path: &Path) -> Result<Option<StrategyConfig>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("reading strategy profile {}", path.display()))?;
+    let profile = serde_json::from_str(&content).context("parsing strategy profile")?;
+    Ok(Some(profile))
+}
+
+pub fn save_profile(path: &Path, strategy: &StrategyConfig) -> Result<()> {
+    let json = serde_json::to_string_pretty(strategy)?;
+    std::fs::write(path, json)
+        .with_context(|| format!("writing strategy profile {}", path.display()))
+}
+
+/// Strategy settings that differ between the canary and the baseline, as
+/// `name: baseline -> canary`.
+pub fn changed_settings(baseline: &StrategyConfig, canary: &StrategyConfig) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(baseline), serde_json::to_value(canary))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(name, value)| old.get(*name) != Some(value))
+        .map(|(name, value)| {
+            let was = old.get(name).map_or("-".to_string(), |v| v.to_string());
+            format!("{name}: {was} -> {value}")
+        })
+        .collect()
+}
+
+/// Side-by-side comparison of the canary market against the average
+/// baseline market.
+pub fn format_comparison(
+    canary: &MarketMetrics,
+    baseline: &[&MarketMetrics],
+    changes: &[String],
+) -> String {
+    let count = Decimal::from(baseline.len().max(1));
+    let mean = |f: MetricFn| -> Decimal { baseline.iter().map(|m| f(m)).sum::<Decimal>() / count };
+    let rows: [(&str, MetricFn); 7] = [
+        ("Net PnL ($)", |m| m.net_pnl()),
+        ("Spread PnL ($)", |m| m.spread_pnl),
+        ("Rewards ($)", |m| m.reward_pnl),
+        ("Fills", |m| Decimal::from(m.total_fills)),
+        ("Fill rate (%)", |m| m.fill_rate() * dec!(100)),
+        ("Uptime (%)", |m| m.uptime_pct()),
+        ("Band uptime (%)", |m| m.band_uptime_pct()),
+    ];
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        "Metric".to_string(),
+        "Canary".to_string(),
+        format!("Baseline avg ({} markets)", baseline.len()),
+        "Diff".to_string(),
+    ]);
+    for (name, f) in rows {
+        let (ours, theirs) = (f(canary), mean(f));
+        table.add_row(vec![
+            name.to_string(),
+            format!("{ours:.2}"),
+            format!("{theirs:.2}"),
+            format!("{:+.2}", ours - theirs),
+        ]);
+    }
+
+    let mut out = format!("Canary: {}\n", canary.question);
+    if changes.is_empty() {
+        out.push_str("Strategy unchanged from baseline\n");
+    } else {
+        out.push_str(&format!("Changed: {}\n", changes.join(", ")));
+    }
+    out.push_str(&table.to_string());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_round_trip_and_changes() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_canary");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("strategy_profile.json");
+
+        assert!(load_profile(&path).unwrap().is_none());
+        let baseline = StrategyConfig::default();
+        save_profile(&path, &baseline).unwrap();
+        let loaded = load_profile(&path).unwrap().unwrap();
+        assert!(changed_settings(&baseline, &loaded).is_empty());
+
+        let canary = StrategyConfig {
+            num_levels: 3,
+            ..loaded
+        };
+        assert_eq!(
+            changed_settings(&baseline, &canary),
+            vec!["num_levels: 2 -> 3"]
+        );
+
+        let mut ours = MarketMetrics::new("c".into(), "Canary?".into());
+        ours.spread_pnl = dec!(12);
+        let mut other = MarketMetrics::new("b".into(), "Other?".into());
+        other.spread_pnl = dec!(4);
+        let report = format_comparison(&ours, &[&other, &other], &[]);
+        assert!(report.contains("Canary?"));
+        assert!(report.contains("+8.00"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
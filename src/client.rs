@@ -1,54 +1,323 @@
 use anyhow::{Context, Result};
-use polymarket_client_sdk::auth::{LocalSigner, Signer};
+use polymarket_client_sdk::auth::{self, ApiKey, Credentials, LocalSigner, Signer};
 use polymarket_client_sdk::clob;
+use polymarket_client_sdk::clob::types::SignatureType;
+use polymarket_client_sdk::types::Address;
 use polymarket_client_sdk::POLYGON;
+use std::future::Future;
 use std::str::FromStr;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 use crate::config::Config;
+use crate::credentials::{CachedCredentials, CredentialsCache};
+use crate::rpc::PolygonRpc;
+
+/// A client paired with the request timeout configured for its endpoint.
+pub struct Timed<C> {
+    client: C,
+    timeout: Option<Duration>,
+}
+
+impl<C> Timed<C> {
+    pub fn new(client: C, timeout_secs: u64) -> Self {
+        Self {
+            client,
+            timeout: timeout(timeout_secs),
+        }
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Await a request made with this client, failing once the endpoint's
+    /// timeout passes.
+    pub async fn send<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.timeout {
+            Some(limit) => tokio::time::timeout(limit, request)
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {}s", limit.as_secs()))),
+            None => request.await,
+        }
+    }
+}
+
+/// A configured timeout in seconds, where 0 means wait indefinitely.
+pub fn timeout(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
 
 /// Create an unauthenticated CLOB client for read-only operations.
-pub fn create_unauthenticated_client() -> Result<clob::Client<polymarket_client_sdk::auth::state::Unauthenticated>> {
-    let client = clob::Client::new("https://clob.polymarket.com", clob::Config::default())
+pub fn create_unauthenticated_client(
+    config: &Config,
+) -> Result<clob::Client<polymarket_client_sdk::auth::state::Unauthenticated>> {
+    let client = clob::Client::new(&config.endpoints.clob_url, clob::Config::default())
         .context("creating CLOB client")?;
     Ok(client)
 }
 
-/// Create an authenticated CLOB client from config.
-pub async fn create_authenticated_client(
-    config: &Config,
-) -> Result<clob::Client<polymarket_client_sdk::auth::state::Authenticated<polymarket_client_sdk::auth::Normal>>> {
+type AuthClient = clob::Client<auth::state::Authenticated<auth::Normal>>;
+
+/// Where `authenticate` gets its API credentials from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMode {
+    /// Reuse the cached credentials for this wallet when there are any
+    Cached,
+    /// Derive the current credentials again, replacing the cache entry
+    Rederive,
+    /// Create credentials under the next nonce and switch to them
+    Rotate,
+}
+
+/// Create an authenticated CLOB client from config, reusing cached API
+/// credentials when there are any.
+pub async fn create_authenticated_client(config: &Config) -> Result<AuthClient> {
+    authenticate(config, CredentialMode::Cached).await
+}
+
+/// Authenticate, taking API credentials from the secrets file, the
+/// credentials cache, or the exchange as `mode` says.
+pub async fn authenticate(config: &Config, mode: CredentialMode) -> Result<AuthClient> {
     let private_key = config.private_key()?;
     let signer = LocalSigner::from_str(&private_key)
         .context("parsing private key")?
         .with_chain_id(Some(POLYGON));
 
-    let clob_config = clob::Config::builder()
-        .use_server_time(true)
-        .build();
+    let client = if let Some(api) = &config.secrets.api {
+        // Credentials from the secrets file are used as given, never cached
+        let key = ApiKey::parse_str(&api.key).context("parsing API key from secrets file")?;
+        let credentials = Credentials::new(key, api.secret.clone(), api.passphrase.clone());
+        login(config, &signer, Some(credentials)).await?
+    } else if config.wallet.credentials_cache.is_empty() {
+        login(config, &signer, None).await?
+    } else {
+        let cache = CredentialsCache::new(&config.wallet.credentials_cache, &private_key);
+        let address = signer.address().to_string();
+        let cached = cache.load(&address).unwrap_or_else(|e| {
+            warn!(
+                error = format!("{e:#}"),
+                "Unreadable credentials cache, re-deriving"
+            );
+            None
+        });
+        match (&cached, mode) {
+            (Some(entry), CredentialMode::Cached) => {
+                login(config, &signer, Some(entry.credentials()?)).await?
+            }
+            _ => {
+                let current = cached.as_ref().map_or(0, |c| c.nonce);
+                let nonce = match mode {
+                    CredentialMode::Rotate => current + 1,
+                    _ => current,
+                };
+                let credentials = unauthenticated(config)?
+                    .create_or_derive_api_key(&signer, (nonce > 0).then_some(nonce))
+                    .await
+                    .context("deriving API credentials")?;
+                let client = login(config, &signer, Some(credentials.clone())).await?;
+                let entry = CachedCredentials::new(&credentials, nonce, chrono::Utc::now());
+                match cache.store(&address, &entry) {
+                    Ok(()) => info!(%address, nonce, "Cached API credentials"),
+                    Err(e) => warn!(error = format!("{e:#}"), "Could not cache API credentials"),
+                }
+                client
+            }
+        }
+    };
 
-    let unauth = clob::Client::new("https://clob.polymarket.com", clob_config)
-        .context("creating CLOB client")?;
+    info!(
+        address = %client.address(),
+        wallet_type = %config.wallet.signature_type,
+        "Authenticated with Polymarket CLOB"
+    );
+    Ok(client)
+}
 
-    let sig_type = match config.wallet.signature_type.as_str() {
-        "proxy" => polymarket_client_sdk::clob::types::SignatureType::Proxy,
-        "gnosis_safe" => polymarket_client_sdk::clob::types::SignatureType::GnosisSafe,
-        _ => polymarket_client_sdk::clob::types::SignatureType::Eoa,
-    };
+/// A fresh unauthenticated client on the exchange's clock. Each login
+/// needs its own: the SDK refuses to authenticate a client that is shared.
+fn unauthenticated(config: &Config) -> Result<clob::Client<auth::state::Unauthenticated>> {
+    let clob_config = clob::Config::builder().use_server_time(true).build();
+    clob::Client::new(&config.endpoints.clob_url, clob_config).context("creating CLOB client")
+}
 
-    let client = unauth
-        .authentication_builder(&signer)
-        .signature_type(sig_type)
+async fn login(
+    config: &Config,
+    signer: &impl Signer,
+    credentials: Option<Credentials>,
+) -> Result<AuthClient> {
+    let mut builder = unauthenticated(config)?
+        .authentication_builder(signer)
+        .signature_type(signature_type(config));
+    if let Some(funder) = funder_address(config)? {
+        builder = builder.funder(funder);
+    }
+    if let Some(credentials) = credentials {
+        builder = builder.credentials(credentials);
+    }
+    builder
         .authenticate()
         .await
-        .context("authenticating CLOB client")?;
+        .context("authenticating CLOB client")
+}
 
-    info!(address = %client.address(), "Authenticated with Polymarket CLOB");
-    Ok(client)
+/// Phrases the CLOB uses when a request is rejected for its credentials,
+/// signature, or nonce rather than its content.
+const AUTH_ERROR_MARKERS: &[&str] = &[
+    "401",
+    "unauthorized",
+    "invalid api key",
+    "invalid signature",
+    "invalid nonce",
+];
+
+/// Whether an error means our API credentials or signing have gone bad.
+pub fn is_auth_error(error: &anyhow::Error) -> bool {
+    let text = format!("{error:#}").to_lowercase();
+    AUTH_ERROR_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+}
+
+/// Re-authentication attempts since a request last got through.
+#[derive(Debug)]
+pub struct AuthRecovery {
+    max_attempts: u32,
+    cooldown: Duration,
+    attempts: u32,
+    last_attempt: Option<Instant>,
+    alerted: bool,
+}
+
+impl AuthRecovery {
+    pub fn new(max_attempts: u32, cooldown_secs: u64) -> Self {
+        Self {
+            max_attempts,
+            cooldown: Duration::from_secs(cooldown_secs),
+            attempts: 0,
+            last_attempt: None,
+            alerted: false,
+        }
+    }
+
+    /// Start an attempt, unless the last one was within the cooldown.
+    pub fn begin_attempt(&mut self, now: Instant) -> bool {
+        if self
+            .last_attempt
+            .is_some_and(|at| now.saturating_duration_since(at) < self.cooldown)
+        {
+            return false;
+        }
+        self.last_attempt = Some(now);
+        self.attempts += 1;
+        true
+    }
+
+    /// A request got through, so whatever was failing has recovered.
+    pub fn recovered(&mut self) {
+        self.attempts = 0;
+        self.alerted = false;
+    }
+
+    /// The attempt count, the first time it reaches `max_attempts`.
+    pub fn exhausted(&mut self) -> Option<u32> {
+        if self.alerted || self.attempts < self.max_attempts {
+            return None;
+        }
+        self.alerted = true;
+        Some(self.attempts)
+    }
+}
+
+/// Map the configured wallet type onto the SDK's signature type.
+pub fn signature_type(config: &Config) -> SignatureType {
+    match config.wallet.signature_type.as_str() {
+        "proxy" => SignatureType::Proxy,
+        "gnosis_safe" => SignatureType::GnosisSafe,
+        _ => SignatureType::Eoa,
+    }
+}
+
+/// Parse the proxy/Safe funder address, if configured.
+pub fn funder_address(config: &Config) -> Result<Option<Address>> {
+    if config.wallet.funder_address.is_empty() {
+        return Ok(None);
+    }
+    let address = Address::from_str(&config.wallet.funder_address)
+        .context("parsing wallet.funder_address")?;
+    Ok(Some(address))
+}
+
+/// The address that actually holds funds and positions: the funder for
+/// proxy/Safe wallets, otherwise the signer itself.
+pub fn wallet_address(config: &Config, signer: &impl Signer) -> Result<Address> {
+    Ok(funder_address(config)?.unwrap_or_else(|| signer.address()))
 }
 
 /// Create a Gamma API client for market discovery.
-pub fn create_gamma_client() -> Result<polymarket_client_sdk::gamma::Client> {
-    let client = polymarket_client_sdk::gamma::Client::default();
-    Ok(client)
+pub fn create_gamma_client(config: &Config) -> Result<Timed<polymarket_client_sdk::gamma::Client>> {
+    let endpoints = &config.endpoints;
+    let client = polymarket_client_sdk::gamma::Client::new(&endpoints.gamma_url)
+        .context("creating Gamma client")?;
+    Ok(Timed::new(client, endpoints.gamma_timeout_secs))
+}
+
+/// Create a Data API client for wallet positions.
+pub fn create_data_client(config: &Config) -> Result<Timed<polymarket_client_sdk::data::Client>> {
+    let endpoints = &config.endpoints;
+    let client = polymarket_client_sdk::data::Client::new(&endpoints.data_url)
+        .context("creating Data API client")?;
+    Ok(Timed::new(client, endpoints.data_timeout_secs))
+}
+
+/// Create a Polygon RPC reader if `wallet.rpc_url` is configured.
+pub fn create_rpc_client(config: &Config) -> Option<PolygonRpc> {
+    if config.wallet.rpc_url.is_empty() {
+        return None;
+    }
+    Some(PolygonRpc::new(&config.wallet.rpc_url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_send_gives_up_after_timeout() {
+        let timed = Timed {
+            client: (),
+            timeout: Some(Duration::from_millis(20)),
+        };
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        };
+        let err = timed.send(slow).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+
+        let unbounded = Timed::new((), 0);
+        assert_eq!(unbounded.send(async { Ok(7) }).await.unwrap(), 7);
+    }
+
+    #[test]
+    fn test_auth_recovery_alerts_once_attempts_run_out() {
+        let rejected = anyhow::anyhow!("Unauthorized/Invalid api key");
+        assert!(is_auth_error(&rejected));
+        let unfunded = anyhow::anyhow!("not enough balance / allowance");
+        assert!(!is_auth_error(&unfunded));
+
+        let mut recovery = AuthRecovery::new(2, 30);
+        let now = Instant::now();
+        assert!(recovery.begin_attempt(now));
+        assert!(!recovery.begin_attempt(now + Duration::from_secs(10)));
+        assert_eq!(recovery.exhausted(), None);
+
+        assert!(recovery.begin_attempt(now + Duration::from_secs(30)));
+        assert_eq!(recovery.exhausted(), Some(2));
+        assert_eq!(recovery.exhausted(), None, "alerts once");
+
+        recovery.recovered();
+        assert!(recovery.begin_attempt(now + Duration::from_secs(60)));
+        assert_eq!(recovery.exhausted(), None);
+    }
 }
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::alerts::Severity;
+use crate::exchange::MarketData;
+
+/// Source of monotonic time for engine timers, rescan intervals, and rate-limit
+/// windows. Swapping in `SimClock` makes that logic deterministic in tests and
+/// replays.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Time elapsed since `earlier` according to this clock.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall-clock time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Manually advanced clock. Time only moves when `advance` is called.
+#[derive(Debug)]
+pub struct SimClock {
+    now: Mutex<Instant>,
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// How far the exchange's clock runs ahead of ours (negative if behind).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockSkew {
+    offset_ms: i64,
+}
+
+impl ClockSkew {
+    /// Estimate from a server timestamp read between `sent` and `received`.
+    /// The server only reports whole seconds, so it is taken to be halfway
+    /// through that second, and read halfway through the round trip.
+    pub fn measure(sent: DateTime<Utc>, server_secs: i64, received: DateTime<Utc>) -> Self {
+        let local_ms = sent.timestamp_millis() + (received - sent).num_milliseconds() / 2;
+        Self {
+            offset_ms: server_secs * 1000 + 500 - local_ms,
+        }
+    }
+
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms
+    }
+
+    /// The current time on the exchange's clock, for anything it checks
+    /// against its own, like order expirations.
+    pub fn exchange_now(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::milliseconds(self.offset_ms)
+    }
+}
+
+/// Read the exchange's clock and measure our skew from it.
+pub async fn measure_skew(exchange: &impl MarketData) -> Result<ClockSkew> {
+    let sent = Utc::now();
+    let server_secs = exchange.server_time().await?;
+    Ok(ClockSkew::measure(sent, server_secs, Utc::now()))
+}
+
+/// Schedules server-time checks and tracks whether drift is over the limit.
+#[derive(Debug, Clone)]
+pub struct SkewMonitor {
+    interval: Option<Duration>,
+    max_skew_ms: u64,
+    last_check: Option<Instant>,
+    drifted: bool,
+    pub skew: ClockSkew,
+}
+
+impl SkewMonitor {
+    pub fn new(check_secs: u64, max_skew_ms: u64) -> Self {
+        Self {
+            interval: (check_secs > 0).then(|| Duration::from_secs(check_secs)),
+            max_skew_ms,
+            last_check: None,
+            drifted: false,
+            skew: ClockSkew::default(),
+        }
+    }
+
+    /// Due at startup, then every interval.
+    pub fn due(&self, now: Instant) -> bool {
+        match (self.last_check, self.interval) {
+            (None, _) => true,
+            (Some(last), Some(interval)) => now.saturating_duration_since(last) >= interval,
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Measure skew against the exchange. Returns the alert to send when
+    /// drift has just crossed `max_skew_ms` in either direction. A failed
+    /// check still counts, so an unreachable endpoint is retried at the
+    /// normal interval.
+    pub async fn check(
+        &mut self,
+        now: Instant,
+        exchange: &impl MarketData,
+    ) -> Option<(Severity, String)> {
+        self.last_check = Some(now);
+        let skew = match measure_skew(exchange).await {
+            Ok(skew) => skew,
+            Err(e) => {
+                warn!(error = %e, "Server time check failed");
+                return None;
+            }
+        };
+        debug!(offset_ms = skew.offset_ms, "Measured clock skew");
+        if !self.record(skew) {
+            return None;
+        }
+        Some(if self.drifted {
+            warn!(
+                offset_ms = skew.offset_ms,
+                max_ms = self.max_skew_ms,
+                "Clock skew over limit"
+            );
+            (
+                Severity::Warning,
+                format!(
+                    "Local clock is {}ms off the exchange's (limit {}ms); check NTP, signed requests may be rejected",
+                    skew.offset_ms, self.max_skew_ms
+                ),
+            )
+        } else {
+            (
+                Severity::Info,
+                format!(
+                    "Local clock back within {}ms of the exchange's ({}ms)",
+                    self.max_skew_ms, skew.offset_ms
+                ),
+            )
+        })
+    }
+
+    /// Record a measurement. Returns true when drift crosses the limit in
+    /// either direction, which is when an alert is due.
+    pub fn record(&mut self, skew: ClockSkew) -> bool {
+        self.skew = skew;
+        let drifted = skew.offset_ms.unsigned_abs() > self.max_skew_ms;
+        std::mem::replace(&mut self.drifted, drifted) != drifted
+    }
+
+    pub fn drifted(&self) -> bool {
+        self.drifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sim_clock_advances_only_on_demand() {
+        let clock = SimClock::new();
+        let start = clock.now();
+        assert_eq!(clock.elapsed(start), Duration::ZERO);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(clock.elapsed(start), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_skew_measured_from_round_trip_midpoint() {
+        let sent = DateTime::from_timestamp_millis(1_000_000_000).unwrap();
+        let received = sent + chrono::Duration::milliseconds(200);
+        // Server read 3.5s into its second vs our 0.1s midpoint
+        let skew = ClockSkew::measure(sent, 1_000_003, received);
+        assert_eq!(skew.offset_ms(), 3400);
+    }
+
+    #[tokio::test]
+    async fn test_skew_alerts_on_crossing_limit_only() {
+        use crate::exchange::mock::MockExchange;
+
+        let exchange = MockExchange::new();
+        exchange.state().server_offset_secs = 10;
+        let mut monitor = SkewMonitor::new(0, 2000);
+        let now = Instant::now();
+        assert!(monitor.due(now));
+
+        let (severity, _) = monitor.check(now, &exchange).await.unwrap();
+        assert_eq!(severity, Severity::Warning);
+        // Checks only at startup, and alerts once per crossing
+        assert!(!monitor.due(now + Duration::from_secs(3600)));
+        assert!(monitor.check(now, &exchange).await.is_none());
+
+        exchange.state().server_offset_secs = 0;
+        let (severity, _) = monitor.check(now, &exchange).await.unwrap();
+        assert_eq!(severity, Severity::Info);
+        assert!(!monitor.drifted());
+    }
+}
@@ -1,9 +1,19 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::alerts::{AlertsConfig, Severity, SmtpConfig, WebhookConfig};
+use crate::db::RetentionConfig;
+use crate::midpoint::MidpointSource;
+use crate::quoter::{CollapsePolicy, ExtremePolicy};
+use crate::risk::InventoryCapBasis;
+use crate::scanner::ScoreWeights;
+use crate::secrets::{self, Secrets};
+use crate::telemetry::LogRotation;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub wallet: WalletConfig,
     #[serde(default)]
@@ -14,6 +24,21 @@ pub struct Config {
     pub risk: RiskConfig,
     #[serde(default)]
     pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub ctf: CtfConfig,
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    #[serde(default)]
+    pub experiment: ExperimentConfig,
+    #[serde(default)]
+    pub endpoints: EndpointsConfig,
+    /// Decrypted from `wallet.secrets_file` at startup, never from config
+    #[serde(skip)]
+    pub secrets: Secrets,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +47,30 @@ pub struct WalletConfig {
     pub private_key_env: String,
     #[serde(default = "default_signature_type")]
     pub signature_type: String,
+    /// Optional Polygon JSON-RPC URL for reading on-chain balances (empty = disabled)
+    #[serde(default)]
+    pub rpc_url: String,
+    /// Proxy or Safe address that holds funds (required for proxy / gnosis_safe)
+    #[serde(default)]
+    pub funder_address: String,
+    /// Re-authentication attempts after auth failures before alerting
+    #[serde(default = "default_reauth_max_attempts")]
+    pub reauth_max_attempts: u32,
+    /// Minimum time between re-authentication attempts
+    #[serde(default = "default_reauth_cooldown_secs")]
+    pub reauth_cooldown_secs: u64,
+    /// Optional age-encrypted secrets file holding the private key, API
+    /// credentials, and Telegram token (empty = plaintext env vars only)
+    #[serde(default)]
+    pub secrets_file: String,
+    /// Environment variable holding the secrets passphrase; prompted for
+    /// on the terminal when unset
+    #[serde(default = "default_passphrase_env")]
+    pub passphrase_env: String,
+    /// Directory caching derived API credentials per wallet, encrypted with
+    /// the wallet key (empty = derive on every start)
+    #[serde(default = "default_credentials_cache")]
+    pub credentials_cache: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,16 +79,148 @@ pub struct StrategyConfig {
     pub base_offset_cents: Decimal,
     #[serde(default = "default_min_offset")]
     pub min_offset_cents: Decimal,
+    /// Base offset in ticks of each market's tick size, replacing
+    /// `base_offset_cents`
+    #[serde(default)]
+    pub base_offset_ticks: Option<u32>,
+    /// Minimum offset in ticks, replacing `min_offset_cents`
+    #[serde(default)]
+    pub min_offset_ticks: Option<u32>,
     #[serde(default = "default_requote_interval")]
     pub requote_interval_secs: u64,
     #[serde(default = "default_requote_threshold")]
     pub requote_threshold_cents: Decimal,
+    /// How often single-market runs poll REST for the midpoint when the
+    /// WebSocket isn't supplying it; requotes still follow the interval and
+    /// threshold above
+    #[serde(default = "default_rest_poll_interval")]
+    pub rest_poll_interval_secs: u64,
+    /// Fastest REST polling, reached while the midpoint keeps moving
+    #[serde(default = "default_rest_poll_min")]
+    pub rest_poll_min_secs: u64,
     #[serde(default = "default_order_size")]
     pub order_size: Decimal,
     #[serde(default = "default_num_levels")]
     pub num_levels: u32,
+    /// Quote as many tick-spaced levels as fit inside each market's reward
+    /// band (up to `max_levels`) instead of a fixed `num_levels`
+    #[serde(default)]
+    pub dynamic_levels: bool,
+    /// Most levels per side with `dynamic_levels`
+    #[serde(default = "default_max_levels")]
+    pub max_levels: u32,
     #[serde(default = "default_inventory_cap")]
     pub inventory_cap: Decimal,
+    /// Scale each market's cap to its 24h volume or book depth, recomputed
+    /// at rescan, with `inventory_cap` as the ceiling
+    #[serde(default)]
+    pub inventory_cap_basis: InventoryCapBasis,
+    /// Share of the `inventory_cap_basis` figure to allow as inventory
+    #[serde(default = "default_inventory_cap_fraction")]
+    pub inventory_cap_fraction: Decimal,
+    /// Most USDC of resting buys per token (YES and NO each), on top of the
+    /// market's capital limit (0 = capital limit only)
+    #[serde(default)]
+    pub max_token_notional: Decimal,
+    /// Size asks to the YES/NO tokens actually held (plus queued splits)
+    /// instead of assuming they can always be delivered
+    #[serde(default)]
+    pub backed_asks: bool,
+    /// With `backed_asks`, USDC to split into YES + NO pairs when asks are
+    /// short of tokens (0 = never split). CTF splits aren't implemented yet,
+    /// so `Config::load` rejects anything above 0
+    #[serde(default)]
+    pub auto_split_amount: Decimal,
+    /// How strongly inventory skew shifts order size from bids to asks
+    /// (0 = the same size both sides)
+    #[serde(default)]
+    pub size_skew: Decimal,
+    /// Vary each order's size randomly by up to this percentage either way,
+    /// never below the reward minimum (0 = fixed sizes)
+    #[serde(default)]
+    pub size_jitter_pct: Decimal,
+    /// Move each level's prices away from the midpoint by a random 0 to this
+    /// many ticks, staying inside the reward band (0 = fixed spacing)
+    #[serde(default)]
+    pub offset_jitter_ticks: u32,
+    /// What to quote once the midpoint is within `extreme_price` of 0 or 1
+    #[serde(default)]
+    pub extreme_policy: ExtremePolicy,
+    /// Distance from 0 or 1 at which `extreme_policy` takes over
+    #[serde(default = "default_extreme_price")]
+    pub extreme_price: Decimal,
+    /// What to quote while other makers' best bid and ask are one tick
+    /// apart or crossed
+    #[serde(default)]
+    pub collapse_policy: CollapsePolicy,
+    /// Extra distance from the midpoint under the `widen` collapse policy
+    #[serde(default = "default_collapse_widen_ticks")]
+    pub collapse_widen_ticks: u32,
+    /// Inventory held longer than this gets extra skew toward selling it; past
+    /// twice this age the market switches to unwinding (0 = off)
+    #[serde(default)]
+    pub max_inventory_age_secs: u64,
+    /// Never post an order further than this from the midpoint (cents)
+    #[serde(default = "default_max_price_deviation")]
+    pub max_price_deviation_cents: Decimal,
+    /// Watch each market this long after it starts, seeding the volatility
+    /// estimate, before posting the first orders (0 = quote immediately)
+    #[serde(default)]
+    pub warmup_secs: u64,
+    /// Shares the book must rest on each side within
+    /// `max_price_deviation_cents` of the midpoint before a market's first
+    /// orders go out (0 = enter any book)
+    #[serde(default)]
+    pub entry_min_depth: Decimal,
+    /// Wait before checking a too-thin book again
+    #[serde(default = "default_entry_retry_secs")]
+    pub entry_retry_secs: u64,
+    /// Seed each market's volatility estimate from this many hours of
+    /// exchange price history when it starts (0 = build it live)
+    #[serde(default = "default_volatility_seed_hours")]
+    pub volatility_seed_hours: u64,
+    /// Ramp order size up to `order_size` over this many successful quoting
+    /// ticks after warm-up (0 = full size from the start)
+    #[serde(default)]
+    pub warmup_ramp_ticks: u32,
+    /// Longest a requote may take from deciding to requote until every new
+    /// order is acknowledged; slower requotes are logged with a breakdown
+    /// and counted (0 = off)
+    #[serde(default = "default_requote_budget_ms")]
+    pub requote_budget_ms: u64,
+    /// Drop the outermost quote level after each over-budget requote, adding
+    /// one back after each requote that comes in under budget
+    #[serde(default)]
+    pub shed_levels_on_overrun: bool,
+    /// Between ticks, sign the ladders for midpoint moves of up to this many
+    /// ticks either way so the next requote can post them at once (0 = off)
+    #[serde(default)]
+    pub presign_ticks: u32,
+    /// How long after a fill to measure its markout for adverse selection
+    #[serde(default = "default_markout_secs")]
+    pub markout_secs: u64,
+    /// Mean adverse markout (cents) at which quotes start shading toward
+    /// the reward-band edge (0 = off)
+    #[serde(default = "default_shade_start_cents")]
+    pub shade_start_cents: Decimal,
+    /// Mean adverse markout (cents) at which quotes sit at the band edge
+    #[serde(default = "default_shade_full_cents")]
+    pub shade_full_cents: Decimal,
+    /// Exponent on the shading ramp: 1 is linear, above 1 shades late,
+    /// below 1 shades early
+    #[serde(default = "default_shade_curve")]
+    pub shade_curve: Decimal,
+    /// Price anchors to quote around, in order of preference; the first
+    /// healthy one wins
+    #[serde(default = "default_midpoint_sources")]
+    pub midpoint_sources: Vec<MidpointSource>,
+    /// A WebSocket midpoint older than this fails over to the next source
+    #[serde(default = "default_midpoint_stale_secs")]
+    pub midpoint_stale_secs: u64,
+    /// A source that errored is skipped for this long (the last source in
+    /// the chain is always tried)
+    #[serde(default = "default_midpoint_retry_secs")]
+    pub midpoint_retry_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +241,66 @@ pub struct MarketsConfig {
     /// Tags to avoid (e.g., politics, niche events with insider risk)
     #[serde(default)]
     pub avoid_tags: Vec<String>,
+    /// How often to poll managed markets for closure/dispute/resolution
+    #[serde(default = "default_resolution_check_secs")]
+    pub resolution_check_secs: u64,
+    /// How often to refresh tick size / reward band for managed markets
+    #[serde(default = "default_param_refresh_secs")]
+    pub param_refresh_secs: u64,
+    /// How often to rescan for markets to add, remove, and reallocate
+    #[serde(default = "default_rescan_interval_secs")]
+    pub rescan_interval_secs: u64,
+    /// Add newly ranked markets at rescan
+    #[serde(default = "default_true")]
+    pub rescan_add_new: bool,
+    /// Wind down markets that fell out of the ranking at rescan
+    #[serde(default = "default_true")]
+    pub rescan_remove_stale: bool,
+    /// Rebalance capital allocations across managed markets at rescan
+    #[serde(default = "default_true")]
+    pub rescan_reallocate: bool,
+    /// Between rescans, lend the unused allocation of inventory-capped
+    /// markets to markets whose quotes are capital-bound
+    #[serde(default)]
+    pub intraday_rebalance: bool,
+    /// Consecutive rescans a market must rank before it is added
+    #[serde(default = "default_add_after_rescans")]
+    pub add_after_rescans: u32,
+    /// Consecutive rescans a managed market must miss the ranking before removal
+    #[serde(default = "default_remove_after_rescans")]
+    pub remove_after_rescans: u32,
+    /// Wait this long before re-adding a market that was removed
+    #[serde(default = "default_readd_cooldown_secs")]
+    pub readd_cooldown_secs: u64,
+    /// Markets paying at least this per day are tier 1
+    #[serde(default = "default_tier1_min_reward")]
+    pub tier1_min_reward: Decimal,
+    /// Markets paying at least this per day are tier 2; the rest are tier 3
+    #[serde(default = "default_tier2_min_reward")]
+    pub tier2_min_reward: Decimal,
+    /// Pin markets to a tier by condition ID (1, 2, or 3)
+    #[serde(default)]
+    pub tiers: HashMap<String, u8>,
+    /// Replace `strategy.midpoint_sources` for markets by condition ID
+    #[serde(default)]
+    pub midpoint_source_overrides: HashMap<String, Vec<MidpointSource>>,
+    /// Markets paper-traded alongside the live portfolio: quoted and filled
+    /// in simulation only, reported apart from live totals
+    #[serde(default)]
+    pub dry_run_markets: Vec<String>,
+    /// Rank markets by this weighted formula instead of reward/liquidity
+    #[serde(default)]
+    pub score_weights: Option<ScoreWeights>,
+    /// Tier-3 markets get a REST tick only every this many loop iterations
+    #[serde(default = "default_tier3_poll_every")]
+    pub tier3_poll_every: u64,
+    /// How often to snapshot each managed market's book for band crowding
+    /// (0 = off)
+    #[serde(default = "default_crowding_sample_secs")]
+    pub crowding_sample_secs: u64,
+    /// Lowest score multiplier a collapsing band share can apply at rescan
+    #[serde(default = "default_crowding_min_factor")]
+    pub crowding_min_factor: Decimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,34 +317,298 @@ pub struct RiskConfig {
     /// Pause quoting entirely if net loss per market exceeds this
     #[serde(default = "default_per_market_loss_limit")]
     pub per_market_loss_limit: Decimal,
+    /// Where the kill-switch latch is persisted across restarts
+    #[serde(default = "default_risk_state_file")]
+    pub state_file: String,
+    /// Stop quoting and unwind a market once its unrealized loss reaches this (0 = off)
+    #[serde(default)]
+    pub stop_loss: Decimal,
+    /// Stop quoting and unwind a market once its unrealized profit reaches this (0 = off)
+    #[serde(default)]
+    pub take_profit: Decimal,
+    /// Flag when 1-day 95% VaR exceeds this fraction of `max_total_capital` (0 = off)
+    #[serde(default = "default_var_limit_fraction")]
+    pub var_limit_fraction: Decimal,
+    /// Flag a market whose recent placement or cancel rate exceeds this
+    /// multiple of its baseline rate, and pause its requotes (0 = off)
+    #[serde(default = "default_anomaly_rate_multiple")]
+    pub anomaly_rate_multiple: Decimal,
+    /// Flag a quoting market with no fills for this multiple of its usual
+    /// gap between fills (0 = off)
+    #[serde(default = "default_anomaly_fill_gap_multiple")]
+    pub anomaly_fill_gap_multiple: Decimal,
+    /// Recent window compared against the baseline
+    #[serde(default = "default_anomaly_window_secs")]
+    pub anomaly_window_secs: u64,
+    /// Preceding history that sets each market's normal rates
+    #[serde(default = "default_anomaly_baseline_secs")]
+    pub anomaly_baseline_secs: u64,
+    /// How long requotes stay paused after a rate spike
+    #[serde(default = "default_anomaly_throttle_secs")]
+    pub anomaly_throttle_secs: u64,
+    /// How often to compare the market's last trade with our resting quotes
+    /// to catch trades through our price that never filled us (0 = off)
+    #[serde(default = "default_trade_through_check_secs")]
+    pub trade_through_check_secs: u64,
+    /// Flag a market with this many trade-throughs within an hour (0 = count
+    /// only, never flag)
+    #[serde(default = "default_trade_through_alert_count")]
+    pub trade_through_alert_count: u32,
+    /// Try to cancel every open order if the run loop panics
+    #[serde(default = "default_true")]
+    pub cancel_on_panic: bool,
+    /// Post orders as GTD, expiring this long after they're signed and
+    /// replaced before then, so they die soon after the bot does (0 = GTC)
+    #[serde(default)]
+    pub deadman_secs: u64,
+    /// Groups of markets whose positions are netted before skewing quotes
+    /// against `inventory_cap`. The kill switch sums PnL over every market,
+    /// so offsetting positions already cancel out there
+    #[serde(default)]
+    pub correlations: Vec<CorrelationGroup>,
+}
+
+/// Markets that express the same bet. Holding YES in any of `markets` is
+/// treated like holding NO in any of `inverse`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorrelationGroup {
+    /// Condition IDs that move together
+    #[serde(default)]
+    pub markets: Vec<String>,
+    /// Condition IDs whose YES is the NO of `markets`
+    #[serde(default)]
+    pub inverse: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
     #[serde(default = "default_log_level")]
     pub log_level: String,
+    /// Also write logs to this file, independent of stdout
+    #[serde(default)]
+    pub log_file: Option<String>,
+    /// Verbosity of `log_file`; defaults to full debug output
+    #[serde(default = "default_log_file_level")]
+    pub log_file_level: String,
+    #[serde(default = "default_log_rotation")]
+    pub log_rotation: LogRotation,
+    /// File size that triggers a roll when `log_rotation = "size"`
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u64,
+    /// Rolled log files kept before the oldest is deleted
+    #[serde(default = "default_log_max_files")]
+    pub log_max_files: usize,
     #[serde(default)]
     pub telegram_bot_token: String,
     #[serde(default)]
     pub telegram_chat_id: String,
+    /// Send a summary of each finished UTC day to Telegram
+    #[serde(default = "default_true")]
+    pub daily_summary: bool,
+    /// Alert (at `info`) on each fill once its markout is known, with price
+    /// vs midpoint, resulting inventory, and skew; fills are logged either way
+    #[serde(default = "default_true")]
+    pub fill_alerts: bool,
+    /// Least severe alert sent to Telegram
+    #[serde(default = "default_telegram_min_severity")]
+    pub telegram_min_severity: Severity,
+    /// Discord / Slack / generic JSON webhooks, each with its own severity floor
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Email channel, by default for critical alerts only
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    /// Per-market PnL and activity metrics, read by `status` and `history`
+    #[serde(default = "default_metrics_file")]
+    pub metrics_file: String,
+    /// Leave orders resting at shutdown for the next run to adopt, instead
+    /// of cancelling them (also `run --keep-orders-on-exit`)
+    #[serde(default)]
+    pub keep_orders_on_exit: bool,
+    /// Where orders left resting at shutdown are recorded
+    #[serde(default = "default_retained_orders_file")]
+    pub retained_orders_file: String,
+    /// Per-market states set with the `state` command
+    #[serde(default = "default_market_states_file")]
+    pub market_states_file: String,
+    /// Snapshots from past `status` runs, for showing what changed since
+    #[serde(default = "default_status_history_file")]
+    pub status_history_file: String,
+    /// SQLite history of orders, fills, quotes, and risk events (empty = off)
+    #[serde(default = "default_database_file")]
+    pub database_file: String,
+    /// Per-market fill probability curves calibrated from live ladders
+    /// (empty = not kept across runs)
+    #[serde(default = "default_fill_model_file")]
+    pub fill_model_file: String,
+    /// At live startup, cancel open orders in markets the run won't manage
+    #[serde(default = "default_true")]
+    pub cleanup_orphans_on_start: bool,
+    /// The `[strategy]` section multi-market runs last used; `run --canary`
+    /// keeps every other market on it
+    #[serde(default = "default_strategy_profile_file")]
+    pub strategy_profile_file: String,
+    /// How often running bots write their metrics to `metrics_file`
+    #[serde(default = "default_metrics_save_secs")]
+    pub metrics_save_secs: u64,
+    /// Days of per-market metrics archives to keep before compacting them
+    /// into daily totals
+    #[serde(default = "default_metrics_retention_days")]
+    pub metrics_retention_days: u32,
+    /// Finished UTC days of PnL behind the Sharpe, Sortino, and win-rate
+    /// figures in `status` and the daily summary
+    #[serde(default = "default_performance_window_days")]
+    pub performance_window_days: u32,
+    /// How often live runs compare the local clock with the exchange's
+    /// (0 = only at startup)
+    #[serde(default = "default_clock_skew_check_secs")]
+    pub clock_skew_check_secs: u64,
+    /// Alert when the local clock drifts further than this from the exchange
+    #[serde(default = "default_max_clock_skew_ms")]
+    pub max_clock_skew_ms: u64,
+    /// Restarts in a row before a panicking or exited background task (e.g.
+    /// a WebSocket loop) is given up on; a critical one then stops the run
+    #[serde(default = "default_task_max_restarts")]
+    pub task_max_restarts: u32,
+    /// Wait before restarting a background task, doubling per restart
+    #[serde(default = "default_task_restart_backoff_secs")]
+    pub task_restart_backoff_secs: u64,
+    /// OTLP/HTTP collector base URL (e.g. Jaeger or Tempo) for span export;
+    /// unset disables tracing export
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    #[serde(default = "default_otlp_service_name")]
+    pub otlp_service_name: String,
+    /// node_exporter textfile-collector file (`*.prom`) rewritten with
+    /// per-market metrics every `metrics_save_secs`; unset disables
+    #[serde(default)]
+    pub prometheus_textfile: Option<String>,
+}
+
+/// A/B test of two strategy parameter sets across managed markets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seeds market-to-arm assignment; change it to reshuffle the arms
+    #[serde(default)]
+    pub seed: u64,
+    /// Fraction of markets assigned to arm B
+    #[serde(default = "default_share_b")]
+    pub share_b: Decimal,
+    /// `[strategy]` settings that differ for arm B
+    #[serde(default)]
+    pub strategy_b: toml::Table,
+}
+
+/// API hosts, to point the bot at a proxy, a mock exchange, or a closer
+/// region. Timeouts of 0 wait indefinitely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointsConfig {
+    #[serde(default = "default_clob_url")]
+    pub clob_url: String,
+    /// Market and user WebSocket channels
+    #[serde(default = "default_ws_url")]
+    pub ws_url: String,
+    #[serde(default = "default_gamma_url")]
+    pub gamma_url: String,
+    #[serde(default = "default_data_url")]
+    pub data_url: String,
+    /// Per-request timeout for CLOB REST calls
+    #[serde(default = "default_clob_timeout_secs")]
+    pub clob_timeout_secs: u64,
+    /// Timeout for opening a WebSocket subscription, and for its heartbeat
+    #[serde(default = "default_ws_timeout_secs")]
+    pub ws_timeout_secs: u64,
+    #[serde(default = "default_gamma_timeout_secs")]
+    pub gamma_timeout_secs: u64,
+    #[serde(default = "default_data_timeout_secs")]
+    pub data_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtfConfig {
+    /// Defer non-urgent merges/redemptions while Polygon gas is above this (gwei)
+    #[serde(default = "default_max_gas_gwei")]
+    pub max_gas_gwei: Decimal,
+    /// Run a deferred operation regardless of gas after waiting this long
+    #[serde(default = "default_max_defer_secs")]
+    pub max_defer_secs: u64,
+}
+
+/// Per-endpoint request budgets: a 10s burst window and a 10min sustained
+/// window each. Posts and cancels count orders; GETs count requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_post_burst")]
+    pub post_burst: usize,
+    #[serde(default = "default_post_sustained")]
+    pub post_sustained: usize,
+    #[serde(default = "default_cancel_burst")]
+    pub cancel_burst: usize,
+    #[serde(default = "default_cancel_sustained")]
+    pub cancel_sustained: usize,
+    #[serde(default = "default_get_burst")]
+    pub get_burst: usize,
+    #[serde(default = "default_get_sustained")]
+    pub get_sustained: usize,
+    /// Utilization (%) above which requote intervals start stretching
+    #[serde(default = "default_adaptive_start_pct")]
+    pub adaptive_start_pct: Decimal,
+    /// Longest stretch applied to the lowest-reward market at 100% utilization
+    #[serde(default = "default_max_interval_scale")]
+    pub max_interval_scale: Decimal,
+    /// Share of each budget (%) held back for tier-1 markets
+    #[serde(default = "default_tier1_reserve_pct")]
+    pub tier1_reserve_pct: Decimal,
 }
 
 // Defaults
 fn default_private_key_env() -> String {
     "POLYMARKET_PRIVATE_KEY".into()
 }
+fn default_passphrase_env() -> String {
+    "POLYMARKET_LP_PASSPHRASE".into()
+}
+fn default_credentials_cache() -> String {
+    "credentials".into()
+}
 fn default_signature_type() -> String {
     "eoa".into()
 }
+fn default_reauth_max_attempts() -> u32 {
+    3
+}
+fn default_reauth_cooldown_secs() -> u64 {
+    30
+}
 fn default_base_offset() -> Decimal {
     Decimal::new(10, 1) // 1.0
 }
 fn default_min_offset() -> Decimal {
     Decimal::new(5, 1) // 0.5
 }
+fn default_extreme_price() -> Decimal {
+    Decimal::new(5, 2) // 0.05
+}
+fn default_collapse_widen_ticks() -> u32 {
+    2
+}
+fn default_entry_retry_secs() -> u64 {
+    60
+}
+fn default_volatility_seed_hours() -> u64 {
+    24
+}
 fn default_requote_interval() -> u64 {
     30
 }
+fn default_rest_poll_interval() -> u64 {
+    5
+}
+fn default_rest_poll_min() -> u64 {
+    1
+}
 fn default_requote_threshold() -> Decimal {
     Decimal::new(5, 1) // 0.5
 }
@@ -113,9 +618,42 @@ fn default_order_size() -> Decimal {
 fn default_num_levels() -> u32 {
     2
 }
+fn default_max_levels() -> u32 {
+    5
+}
+fn default_inventory_cap_fraction() -> Decimal {
+    Decimal::new(5, 2) // 0.05
+}
 fn default_inventory_cap() -> Decimal {
     Decimal::new(5000, 0)
 }
+fn default_max_price_deviation() -> Decimal {
+    Decimal::new(5, 0) // 5 cents
+}
+fn default_requote_budget_ms() -> u64 {
+    900
+}
+fn default_markout_secs() -> u64 {
+    60
+}
+fn default_shade_start_cents() -> Decimal {
+    Decimal::new(5, 1)
+}
+fn default_shade_full_cents() -> Decimal {
+    Decimal::new(2, 0)
+}
+fn default_shade_curve() -> Decimal {
+    Decimal::ONE
+}
+fn default_midpoint_sources() -> Vec<MidpointSource> {
+    vec![MidpointSource::Ws, MidpointSource::Rest]
+}
+fn default_midpoint_stale_secs() -> u64 {
+    60
+}
+fn default_midpoint_retry_secs() -> u64 {
+    30
+}
 fn default_market_mode() -> String {
     "auto".into()
 }
@@ -131,6 +669,45 @@ fn default_prefer_fee_enabled() -> bool {
 fn default_min_resolution_days() -> u32 {
     7
 }
+fn default_resolution_check_secs() -> u64 {
+    300
+}
+fn default_risk_state_file() -> String {
+    "risk_state.json".into()
+}
+fn default_param_refresh_secs() -> u64 {
+    600
+}
+fn default_true() -> bool {
+    true
+}
+fn default_rescan_interval_secs() -> u64 {
+    3600
+}
+fn default_add_after_rescans() -> u32 {
+    2
+}
+fn default_remove_after_rescans() -> u32 {
+    2
+}
+fn default_readd_cooldown_secs() -> u64 {
+    6 * 3600
+}
+fn default_tier1_min_reward() -> Decimal {
+    Decimal::new(50, 0)
+}
+fn default_tier2_min_reward() -> Decimal {
+    Decimal::new(10, 0)
+}
+fn default_tier3_poll_every() -> u64 {
+    4
+}
+fn default_crowding_sample_secs() -> u64 {
+    300
+}
+fn default_crowding_min_factor() -> Decimal {
+    Decimal::new(25, 2)
+}
 fn default_max_total_capital() -> Decimal {
     Decimal::new(2000, 0)
 }
@@ -146,20 +723,261 @@ fn default_skew_factor() -> Decimal {
 fn default_per_market_loss_limit() -> Decimal {
     Decimal::new(50, 0) // $50
 }
+fn default_var_limit_fraction() -> Decimal {
+    Decimal::new(25, 2) // 25% of capital
+}
+fn default_anomaly_rate_multiple() -> Decimal {
+    Decimal::from(10)
+}
+fn default_anomaly_fill_gap_multiple() -> Decimal {
+    Decimal::from(5)
+}
+fn default_anomaly_window_secs() -> u64 {
+    300
+}
+fn default_anomaly_baseline_secs() -> u64 {
+    3600
+}
+fn default_anomaly_throttle_secs() -> u64 {
+    600
+}
+fn default_trade_through_check_secs() -> u64 {
+    60
+}
+fn default_trade_through_alert_count() -> u32 {
+    3
+}
+fn default_clob_url() -> String {
+    "https://clob.polymarket.com".to_string()
+}
+fn default_ws_url() -> String {
+    "wss://ws-subscriptions-clob.polymarket.com".to_string()
+}
+fn default_gamma_url() -> String {
+    "https://gamma-api.polymarket.com".to_string()
+}
+fn default_data_url() -> String {
+    "https://data-api.polymarket.com".to_string()
+}
+fn default_clob_timeout_secs() -> u64 {
+    10
+}
+fn default_ws_timeout_secs() -> u64 {
+    15
+}
+fn default_gamma_timeout_secs() -> u64 {
+    30
+}
+fn default_data_timeout_secs() -> u64 {
+    30
+}
+fn default_share_b() -> Decimal {
+    Decimal::new(5, 1) // 0.5
+}
+fn default_max_gas_gwei() -> Decimal {
+    Decimal::new(100, 0)
+}
+fn default_max_defer_secs() -> u64 {
+    86400 // 24h
+}
+fn default_post_burst() -> usize {
+    3500
+}
+fn default_post_sustained() -> usize {
+    36000
+}
+fn default_cancel_burst() -> usize {
+    3000
+}
+fn default_cancel_sustained() -> usize {
+    30000
+}
+fn default_get_burst() -> usize {
+    1500
+}
+fn default_get_sustained() -> usize {
+    9000
+}
+fn default_adaptive_start_pct() -> Decimal {
+    Decimal::new(50, 0)
+}
+fn default_max_interval_scale() -> Decimal {
+    Decimal::new(4, 0)
+}
+fn default_tier1_reserve_pct() -> Decimal {
+    Decimal::new(20, 0)
+}
+fn default_telegram_min_severity() -> Severity {
+    Severity::Info
+}
+fn default_metrics_file() -> String {
+    "metrics.json".into()
+}
+fn default_retained_orders_file() -> String {
+    "retained_orders.json".into()
+}
+fn default_market_states_file() -> String {
+    "market_states.json".into()
+}
+fn default_database_file() -> String {
+    "history.db".into()
+}
+fn default_status_history_file() -> String {
+    "status_history.json".into()
+}
+fn default_fill_model_file() -> String {
+    "fill_model.json".into()
+}
+fn default_strategy_profile_file() -> String {
+    "strategy_profile.json".into()
+}
+fn default_metrics_save_secs() -> u64 {
+    60
+}
+fn default_metrics_retention_days() -> u32 {
+    30
+}
+fn default_performance_window_days() -> u32 {
+    30
+}
+fn default_clock_skew_check_secs() -> u64 {
+    300
+}
+fn default_max_clock_skew_ms() -> u64 {
+    2000
+}
+fn default_task_max_restarts() -> u32 {
+    5
+}
+fn default_task_restart_backoff_secs() -> u64 {
+    1
+}
+fn default_log_file_level() -> String {
+    "debug".into()
+}
+fn default_log_rotation() -> LogRotation {
+    LogRotation::Daily
+}
+fn default_log_max_size_mb() -> u64 {
+    100
+}
+fn default_log_max_files() -> usize {
+    14
+}
+fn default_otlp_service_name() -> String {
+    "polymarket-lp".into()
+}
 fn default_log_level() -> String {
     "info".into()
 }
 
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self {
+            private_key_env: default_private_key_env(),
+            signature_type: default_signature_type(),
+            rpc_url: String::new(),
+            funder_address: String::new(),
+            reauth_max_attempts: default_reauth_max_attempts(),
+            reauth_cooldown_secs: default_reauth_cooldown_secs(),
+            secrets_file: String::new(),
+            passphrase_env: default_passphrase_env(),
+            credentials_cache: default_credentials_cache(),
+        }
+    }
+}
+
+impl WalletConfig {
+    /// Check that the wallet type is known and that proxy/Safe wallets name their funder.
+    pub fn validate(&self) -> Result<()> {
+        match self.signature_type.as_str() {
+            "eoa" => Ok(()),
+            "proxy" | "gnosis_safe" => {
+                if self.funder_address.is_empty() {
+                    bail!(
+                        "wallet.funder_address is required for signature_type '{}'",
+                        self.signature_type
+                    );
+                }
+                Ok(())
+            }
+            other => bail!(
+                "unknown wallet.signature_type '{other}' (expected eoa, proxy, or gnosis_safe)"
+            ),
+        }
+    }
+}
+
 impl Default for StrategyConfig {
     fn default() -> Self {
         Self {
             base_offset_cents: default_base_offset(),
             min_offset_cents: default_min_offset(),
+            base_offset_ticks: None,
+            min_offset_ticks: None,
             requote_interval_secs: default_requote_interval(),
             requote_threshold_cents: default_requote_threshold(),
+            rest_poll_interval_secs: default_rest_poll_interval(),
+            rest_poll_min_secs: default_rest_poll_min(),
             order_size: default_order_size(),
             num_levels: default_num_levels(),
+            dynamic_levels: false,
+            max_levels: default_max_levels(),
             inventory_cap: default_inventory_cap(),
+            inventory_cap_basis: InventoryCapBasis::default(),
+            inventory_cap_fraction: default_inventory_cap_fraction(),
+            max_token_notional: Decimal::ZERO,
+            backed_asks: false,
+            auto_split_amount: Decimal::ZERO,
+            size_skew: Decimal::ZERO,
+            size_jitter_pct: Decimal::ZERO,
+            offset_jitter_ticks: 0,
+            extreme_policy: ExtremePolicy::default(),
+            extreme_price: default_extreme_price(),
+            collapse_policy: CollapsePolicy::default(),
+            collapse_widen_ticks: default_collapse_widen_ticks(),
+            max_inventory_age_secs: 0,
+            max_price_deviation_cents: default_max_price_deviation(),
+            warmup_secs: 0,
+            entry_min_depth: Decimal::ZERO,
+            entry_retry_secs: default_entry_retry_secs(),
+            volatility_seed_hours: default_volatility_seed_hours(),
+            warmup_ramp_ticks: 0,
+            requote_budget_ms: default_requote_budget_ms(),
+            shed_levels_on_overrun: false,
+            presign_ticks: 0,
+            markout_secs: default_markout_secs(),
+            shade_start_cents: default_shade_start_cents(),
+            shade_full_cents: default_shade_full_cents(),
+            shade_curve: default_shade_curve(),
+            midpoint_sources: default_midpoint_sources(),
+            midpoint_stale_secs: default_midpoint_stale_secs(),
+            midpoint_retry_secs: default_midpoint_retry_secs(),
+        }
+    }
+}
+
+impl StrategyConfig {
+    /// Check offsets set in ticks: quotes need at least a tick of offset,
+    /// and the floor can't sit above the base.
+    pub fn validate_offsets(&self) -> Result<()> {
+        if self.base_offset_ticks == Some(0) || self.min_offset_ticks == Some(0) {
+            bail!("strategy.base_offset_ticks and min_offset_ticks must be at least 1");
+        }
+        if let (Some(base), Some(min)) = (self.base_offset_ticks, self.min_offset_ticks)
+            && min > base
+        {
+            bail!("strategy.min_offset_ticks ({min}) is above base_offset_ticks ({base})");
+        }
+        Ok(())
+    }
+}
+
+impl MarketsConfig {
+    /// Apply the settings pinned for one market to its strategy.
+    pub fn apply_overrides(&self, condition_id: &str, strategy: &mut StrategyConfig) {
+        if let Some(sources) = self.midpoint_source_overrides.get(condition_id) {
+            strategy.midpoint_sources = sources.clone();
         }
     }
 }
@@ -174,6 +992,25 @@ impl Default for MarketsConfig {
             manual_markets: vec![],
             min_resolution_days: default_min_resolution_days(),
             avoid_tags: vec![],
+            resolution_check_secs: default_resolution_check_secs(),
+            param_refresh_secs: default_param_refresh_secs(),
+            rescan_interval_secs: default_rescan_interval_secs(),
+            rescan_add_new: true,
+            rescan_remove_stale: true,
+            rescan_reallocate: true,
+            intraday_rebalance: false,
+            add_after_rescans: default_add_after_rescans(),
+            remove_after_rescans: default_remove_after_rescans(),
+            readd_cooldown_secs: default_readd_cooldown_secs(),
+            tier1_min_reward: default_tier1_min_reward(),
+            tier2_min_reward: default_tier2_min_reward(),
+            tiers: HashMap::new(),
+            midpoint_source_overrides: HashMap::new(),
+            dry_run_markets: vec![],
+            score_weights: None,
+            tier3_poll_every: default_tier3_poll_every(),
+            crowding_sample_secs: default_crowding_sample_secs(),
+            crowding_min_factor: default_crowding_min_factor(),
         }
     }
 }
@@ -186,6 +1023,20 @@ impl Default for RiskConfig {
             kill_switch_loss: default_kill_switch_loss(),
             skew_factor: default_skew_factor(),
             per_market_loss_limit: default_per_market_loss_limit(),
+            state_file: default_risk_state_file(),
+            stop_loss: Decimal::ZERO,
+            take_profit: Decimal::ZERO,
+            var_limit_fraction: default_var_limit_fraction(),
+            anomaly_rate_multiple: default_anomaly_rate_multiple(),
+            anomaly_fill_gap_multiple: default_anomaly_fill_gap_multiple(),
+            anomaly_window_secs: default_anomaly_window_secs(),
+            anomaly_baseline_secs: default_anomaly_baseline_secs(),
+            anomaly_throttle_secs: default_anomaly_throttle_secs(),
+            trade_through_check_secs: default_trade_through_check_secs(),
+            trade_through_alert_count: default_trade_through_alert_count(),
+            cancel_on_panic: true,
+            deadman_secs: 0,
+            correlations: vec![],
         }
     }
 }
@@ -194,8 +1045,88 @@ impl Default for MonitoringConfig {
     fn default() -> Self {
         Self {
             log_level: default_log_level(),
+            log_file: None,
+            log_file_level: default_log_file_level(),
+            log_rotation: default_log_rotation(),
+            log_max_size_mb: default_log_max_size_mb(),
+            log_max_files: default_log_max_files(),
             telegram_bot_token: String::new(),
             telegram_chat_id: String::new(),
+            daily_summary: true,
+            fill_alerts: true,
+            telegram_min_severity: default_telegram_min_severity(),
+            webhooks: vec![],
+            smtp: None,
+            metrics_file: default_metrics_file(),
+            keep_orders_on_exit: false,
+            retained_orders_file: default_retained_orders_file(),
+            market_states_file: default_market_states_file(),
+            status_history_file: default_status_history_file(),
+            database_file: default_database_file(),
+            fill_model_file: default_fill_model_file(),
+            cleanup_orphans_on_start: true,
+            strategy_profile_file: default_strategy_profile_file(),
+            metrics_save_secs: default_metrics_save_secs(),
+            metrics_retention_days: default_metrics_retention_days(),
+            performance_window_days: default_performance_window_days(),
+            clock_skew_check_secs: default_clock_skew_check_secs(),
+            max_clock_skew_ms: default_max_clock_skew_ms(),
+            task_max_restarts: default_task_max_restarts(),
+            task_restart_backoff_secs: default_task_restart_backoff_secs(),
+            otlp_endpoint: None,
+            otlp_service_name: default_otlp_service_name(),
+            prometheus_textfile: None,
+        }
+    }
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seed: 0,
+            share_b: default_share_b(),
+            strategy_b: toml::Table::new(),
+        }
+    }
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            clob_url: default_clob_url(),
+            ws_url: default_ws_url(),
+            gamma_url: default_gamma_url(),
+            data_url: default_data_url(),
+            clob_timeout_secs: default_clob_timeout_secs(),
+            ws_timeout_secs: default_ws_timeout_secs(),
+            gamma_timeout_secs: default_gamma_timeout_secs(),
+            data_timeout_secs: default_data_timeout_secs(),
+        }
+    }
+}
+
+impl Default for CtfConfig {
+    fn default() -> Self {
+        Self {
+            max_gas_gwei: default_max_gas_gwei(),
+            max_defer_secs: default_max_defer_secs(),
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            post_burst: default_post_burst(),
+            post_sustained: default_post_sustained(),
+            cancel_burst: default_cancel_burst(),
+            cancel_sustained: default_cancel_sustained(),
+            get_burst: default_get_burst(),
+            get_sustained: default_get_sustained(),
+            adaptive_start_pct: default_adaptive_start_pct(),
+            max_interval_scale: default_max_interval_scale(),
+            tier1_reserve_pct: default_tier1_reserve_pct(),
         }
     }
 }
@@ -206,10 +1137,49 @@ impl Config {
             std::fs::read_to_string(path).with_context(|| format!("reading config from {path:?}"))?;
         let config: Config =
             toml::from_str(&contents).with_context(|| format!("parsing config from {path:?}"))?;
+        config.wallet.validate()?;
+        if config.strategy.auto_split_amount > Decimal::ZERO {
+            bail!(
+                "strategy.auto_split_amount needs CTF splits, which aren't implemented yet; set it to 0"
+            );
+        }
+        if config.strategy.midpoint_sources.is_empty()
+            || config
+                .markets
+                .midpoint_source_overrides
+                .values()
+                .any(Vec::is_empty)
+        {
+            bail!("midpoint_sources must name at least one source");
+        }
+        config.strategy.validate_offsets()?;
         Ok(config)
     }
 
+    /// Decrypt `wallet.secrets_file`, if configured, prompting for the
+    /// passphrase unless its env var is set.
+    pub fn unlock_secrets(self) -> Result<Self> {
+        if self.wallet.secrets_file.is_empty() {
+            return Ok(self);
+        }
+        let passphrase = secrets::passphrase(&self.wallet.passphrase_env)?;
+        let secrets = Secrets::load(Path::new(&self.wallet.secrets_file), passphrase)?;
+        Ok(self.with_secrets(secrets))
+    }
+
+    /// Apply decrypted secrets over the plaintext settings.
+    pub fn with_secrets(mut self, secrets: Secrets) -> Self {
+        if let Some(token) = &secrets.telegram_bot_token {
+            self.monitoring.telegram_bot_token = token.clone();
+        }
+        self.secrets = secrets;
+        self
+    }
+
     pub fn private_key(&self) -> Result<String> {
+        if let Some(key) = &self.secrets.private_key {
+            return Ok(key.clone());
+        }
         std::env::var(&self.wallet.private_key_env).with_context(|| {
             format!(
                 "environment variable '{}' not set",
@@ -225,22 +1195,26 @@ mod tests {
 
     #[test]
     fn test_default_config_roundtrip() {
-        let config = Config {
-            wallet: WalletConfig {
-                private_key_env: "POLYMARKET_PRIVATE_KEY".into(),
-                signature_type: "eoa".into(),
-            },
-            strategy: StrategyConfig::default(),
-            markets: MarketsConfig::default(),
-            risk: RiskConfig::default(),
-            monitoring: MonitoringConfig::default(),
-        };
+        let config = Config::default();
         let toml_str = toml::to_string_pretty(&config).unwrap();
         let parsed: Config = toml::from_str(&toml_str).unwrap();
         assert_eq!(parsed.strategy.base_offset_cents, config.strategy.base_offset_cents);
         assert_eq!(parsed.markets.max_markets, 20);
     }
 
+    #[test]
+    fn test_load_rejects_auto_split() {
+        let path = std::env::temp_dir().join("polymarket_lp_test_auto_split.toml");
+        std::fs::write(
+            &path,
+            "[wallet]\nprivate_key_env = \"MY_KEY\"\n\n[strategy]\nauto_split_amount = 100\n",
+        )
+        .unwrap();
+        let err = Config::load(&path).unwrap_err();
+        assert!(err.to_string().contains("auto_split_amount"));
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_minimal_config() {
         let toml_str = r#"
@@ -251,4 +1225,47 @@ private_key_env = "MY_KEY"
         assert_eq!(config.wallet.private_key_env, "MY_KEY");
         assert_eq!(config.strategy.order_size, Decimal::new(500, 0));
     }
+
+    #[test]
+    fn test_correlation_groups() {
+        let toml_str = r#"
+[wallet]
+[[risk.correlations]]
+markets = ["0xa", "0xb"]
+inverse = ["0xc"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.risk.correlations.len(), 1);
+        assert_eq!(config.risk.correlations[0].markets, vec!["0xa", "0xb"]);
+        assert_eq!(config.risk.correlations[0].inverse, vec!["0xc"]);
+    }
+
+    #[test]
+    fn test_wallet_validation() {
+        let mut wallet = WalletConfig::default();
+        assert!(wallet.validate().is_ok());
+
+        wallet.signature_type = "gnosis_safe".into();
+        assert!(wallet.validate().is_err()); // missing funder
+        wallet.funder_address = "0x0000000000000000000000000000000000000001".into();
+        assert!(wallet.validate().is_ok());
+
+        wallet.signature_type = "multisig".into();
+        assert!(wallet.validate().is_err());
+    }
+
+    #[test]
+    fn test_offset_ticks_validation() {
+        let mut strategy = StrategyConfig {
+            base_offset_ticks: Some(2),
+            min_offset_ticks: Some(1),
+            ..StrategyConfig::default()
+        };
+        assert!(strategy.validate_offsets().is_ok());
+        strategy.min_offset_ticks = Some(3);
+        assert!(strategy.validate_offsets().is_err());
+        strategy.min_offset_ticks = None;
+        strategy.base_offset_ticks = Some(0);
+        assert!(strategy.validate_offsets().is_err());
+    }
 }
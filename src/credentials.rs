@@ -0,0 +1,109 @@
+use age::secrecy::{ExposeSecret, SecretString};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use polymarket_client_sdk::auth::{ApiKey, Credentials};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::secrets;
+
+/// API credentials derived for one wallet, with the nonce they were
+/// created under so a rotation can move past it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CachedCredentials {
+    pub key: String,
+    pub secret: String,
+    pub passphrase: String,
+    pub nonce: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CachedCredentials {
+    pub fn new(credentials: &Credentials, nonce: u32, created_at: DateTime<Utc>) -> Self {
+        Self {
+            key: credentials.key().to_string(),
+            secret: credentials.secret().expose_secret().to_string(),
+            passphrase: credentials.passphrase().expose_secret().to_string(),
+            nonce,
+            created_at,
+        }
+    }
+
+    pub fn credentials(&self) -> Result<Credentials> {
+        let key = ApiKey::parse_str(&self.key).context("parsing cached API key")?;
+        Ok(Credentials::new(
+            key,
+            self.secret.clone(),
+            self.passphrase.clone(),
+        ))
+    }
+}
+
+/// A directory of derived API credentials, one file per wallet address.
+/// Each file is age-encrypted with the wallet's own private key as the
+/// passphrase, so it is only readable by whoever can already sign for it.
+pub struct CredentialsCache {
+    dir: PathBuf,
+    private_key: SecretString,
+}
+
+impl CredentialsCache {
+    pub fn new(dir: impl Into<PathBuf>, private_key: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            private_key: SecretString::from(private_key.to_string()),
+        }
+    }
+
+    fn path(&self, address: &str) -> PathBuf {
+        self.dir.join(format!("{}.age", address.to_lowercase()))
+    }
+
+    /// Cached credentials for `address`; none when nothing was cached yet.
+    pub fn load(&self, address: &str) -> Result<Option<CachedCredentials>> {
+        let path = self.path(address);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let sealed = std::fs::read(&path).with_context(|| format!("reading {path:?}"))?;
+        let plaintext = secrets::decrypt(&sealed, self.passphrase())
+            .with_context(|| format!("decrypting {path:?}"))?;
+        let cached = toml::from_str(&plaintext).with_context(|| format!("parsing {path:?}"))?;
+        Ok(Some(cached))
+    }
+
+    pub fn store(&self, address: &str, cached: &CachedCredentials) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("creating {:?}", self.dir))?;
+        let sealed = secrets::encrypt(&toml::to_string(cached)?, self.passphrase())?;
+        let path = self.path(address);
+        std::fs::write(&path, sealed).with_context(|| format!("writing {path:?}"))
+    }
+
+    fn passphrase(&self) -> SecretString {
+        SecretString::from(self.private_key.expose_secret().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip_per_wallet() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_credentials");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = CredentialsCache::new(&dir, "0xkey");
+        let credentials = Credentials::new(ApiKey::nil(), "s".into(), "p".into());
+        let cached = CachedCredentials::new(&credentials, 2, Utc::now());
+
+        assert!(cache.load("0xAbC").unwrap().is_none());
+        cache.store("0xAbC", &cached).unwrap();
+        let loaded = cache.load("0xabc").unwrap().unwrap();
+        assert_eq!(loaded.secret, "s");
+        assert_eq!(loaded.nonce, 2);
+        // Another wallet's key cannot open it
+        let other = CredentialsCache::new(&dir, "0xother");
+        assert!(other.load("0xabc").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,137 @@
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::quoter;
+
+/// Snapshots kept per market (a day at the default 5-minute sampling).
+const MAX_SAMPLES: usize = 288;
+/// Snapshots in each of the two windows compared for the share trend.
+const TREND_SAMPLES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CrowdingSample {
+    at: Instant,
+    competitor: Decimal,
+    ours: Decimal,
+}
+
+/// Reward-band size held by other makers over time. Medians over a window
+/// of snapshots stand in for the size they keep there persistently, so
+/// orders that flash in and out don't register as crowding.
+#[derive(Debug, Clone, Default)]
+pub struct BandCrowding {
+    samples: VecDeque<CrowdingSample>,
+}
+
+impl BandCrowding {
+    pub fn record(&mut self, at: Instant, competitor: Decimal, ours: Decimal) {
+        self.samples.push_back(CrowdingSample {
+            at,
+            competitor,
+            ours,
+        });
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn last_sampled(&self) -> Option<Instant> {
+        self.samples.back().map(|s| s.at)
+    }
+
+    /// Median competitor band size over the latest window.
+    pub fn persistent_competitor(&self) -> Option<Decimal> {
+        let recent = self.window(0)?;
+        median(recent.iter().map(|s| s.competitor).collect())
+    }
+
+    /// Our median share of band size over the latest window.
+    pub fn share(&self) -> Option<Decimal> {
+        self.window(0).and_then(share)
+    }
+
+    /// Latest-window share over the preceding window's; below 1 means
+    /// other makers are crowding us out.
+    pub fn share_trend(&self) -> Option<Decimal> {
+        let earlier = self.window(1).and_then(share)?;
+        let recent = self.share()?;
+        if earlier.is_zero() {
+            return None;
+        }
+        Some(recent / earlier)
+    }
+
+    /// Score multiplier for ranking and capital: the share trend, capped at
+    /// 1 and floored at `floor`. Markets without enough history keep 1.
+    pub fn score_factor(&self, floor: Decimal) -> Decimal {
+        self.share_trend()
+            .map_or(Decimal::ONE, |trend| trend.clamp(floor, Decimal::ONE))
+    }
+
+    /// The `n`th most recent full window of `TREND_SAMPLES` snapshots.
+    fn window(&self, n: usize) -> Option<Vec<CrowdingSample>> {
+        let end = self.samples.len().checked_sub(n * TREND_SAMPLES)?;
+        let start = end.checked_sub(TREND_SAMPLES)?;
+        Some(self.samples.range(start..end).copied().collect())
+    }
+}
+
+fn share(samples: Vec<CrowdingSample>) -> Option<Decimal> {
+    let ours = median(samples.iter().map(|s| s.ours).collect())?;
+    let competitor = median(samples.iter().map(|s| s.competitor).collect())?;
+    Some(quoter::reward_share(ours, competitor))
+}
+
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    Some(values[values.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::BookSnapshot;
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+
+    #[test]
+    fn test_band_size() {
+        let book = BookSnapshot {
+            bids: vec![(dec!(0.48), dec!(100)), (dec!(0.40), dec!(500))],
+            asks: vec![(dec!(0.52), dec!(50)), (dec!(0.53), dec!(5))],
+        };
+        // 0.40 is outside the 3c band; 5 is under the minimum size
+        assert_eq!(book.band_size(dec!(0.50), dec!(0.03), dec!(10)), dec!(150));
+    }
+
+    #[test]
+    fn test_share_trend() {
+        let start = Instant::now();
+        let mut crowding = BandCrowding::default();
+        assert_eq!(crowding.score_factor(dec!(0.25)), Decimal::ONE);
+
+        // Half the band for a window, then competitors triple their size;
+        // one flash order doesn't move the median
+        for i in 0..TREND_SAMPLES * 2 {
+            let competitor = match i {
+                5 => dec!(10000),
+                i if i < TREND_SAMPLES => dec!(100),
+                _ => dec!(300),
+            };
+            crowding.record(
+                start + Duration::from_secs(i as u64 * 300),
+                competitor,
+                dec!(100),
+            );
+        }
+        assert_eq!(crowding.persistent_competitor(), Some(dec!(300)));
+        assert_eq!(crowding.share(), Some(dec!(0.25)));
+        assert_eq!(crowding.share_trend(), Some(dec!(0.5)));
+        assert_eq!(crowding.score_factor(dec!(0.25)), dec!(0.5));
+        assert_eq!(crowding.score_factor(dec!(0.75)), dec!(0.75));
+    }
+}
@@ -0,0 +1,474 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, params};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::config::MonitoringConfig;
+use crate::fills::FillNotice;
+use crate::orders::TrackedOrder;
+use crate::quoter::Quote;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    order_id TEXT PRIMARY KEY,
+    condition_id TEXT NOT NULL,
+    token_id TEXT NOT NULL,
+    side TEXT NOT NULL,
+    price REAL NOT NULL,
+    size REAL NOT NULL,
+    filled REAL NOT NULL,
+    status TEXT NOT NULL,
+    placed_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS fills (
+    id INTEGER PRIMARY KEY,
+    condition_id TEXT NOT NULL,
+    market TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    side TEXT NOT NULL,
+    price REAL NOT NULL,
+    size REAL NOT NULL,
+    midpoint REAL,
+    vs_mid_cents REAL,
+    markout_cents REAL,
+    inventory_yes REAL NOT NULL,
+    inventory_no REAL NOT NULL,
+    skew REAL NOT NULL,
+    filled_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS quotes (
+    id INTEGER PRIMARY KEY,
+    condition_id TEXT NOT NULL,
+    midpoint REAL NOT NULL,
+    level INTEGER NOT NULL,
+    bid_price REAL NOT NULL,
+    ask_price REAL NOT NULL,
+    bid_size REAL NOT NULL,
+    ask_size REAL NOT NULL,
+    quoted_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS risk_events (
+    id INTEGER PRIMARY KEY,
+    condition_id TEXT,
+    kind TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS orders_by_market ON orders (condition_id, placed_at);
+CREATE INDEX IF NOT EXISTS fills_by_market ON fills (condition_id, filled_at);
+CREATE INDEX IF NOT EXISTS quotes_by_market ON quotes (condition_id, quoted_at);
+";
+
+/// The `[retention]` section: days of history kept per database table
+/// (0 = forever), and how often a running bot prunes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Quoted ladders, written on every requote
+    #[serde(default = "default_quotes_days")]
+    pub quotes_days: u32,
+    /// Orders, by when they were last updated
+    #[serde(default = "default_orders_days")]
+    pub orders_days: u32,
+    #[serde(default)]
+    pub fills_days: u32,
+    #[serde(default)]
+    pub risk_events_days: u32,
+    /// How often a running bot prunes expired rows (0 = only on `compact`)
+    #[serde(default = "default_prune_interval_secs")]
+    pub prune_interval_secs: u64,
+}
+
+fn default_quotes_days() -> u32 {
+    7
+}
+fn default_orders_days() -> u32 {
+    30
+}
+fn default_prune_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            quotes_days: default_quotes_days(),
+            orders_days: default_orders_days(),
+            fills_days: 0,
+            risk_events_days: 0,
+            prune_interval_secs: default_prune_interval_secs(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// (table, timestamp column, days kept) for each table with a limit.
+    fn limits(&self) -> Vec<(&'static str, &'static str, u32)> {
+        [
+            ("quotes", "quoted_at", self.quotes_days),
+            ("orders", "updated_at", self.orders_days),
+            ("fills", "filled_at", self.fills_days),
+            ("risk_events", "at", self.risk_events_days),
+        ]
+        .into_iter()
+        .filter(|(_, _, days)| *days > 0)
+        .collect()
+    }
+}
+
+/// SQLite history of orders, fills, quotes, and risk events. Engines keep
+/// their live working set in memory and write every change here, so the
+/// full history survives restarts and can be queried with `query`.
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+/// Columns and rows of an ad-hoc query, rendered as text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Database {
+    /// Open (creating if needed) the database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("opening history database {}", path.display()))?;
+        // WAL lets `query` read while the bot writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::with_schema(conn)
+    }
+
+    /// Open the configured database for writing; `None` when
+    /// `monitoring.database_file` is empty.
+    pub fn open_configured(monitoring: &MonitoringConfig) -> Result<Option<Arc<Self>>> {
+        if monitoring.database_file.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Arc::new(Self::open(Path::new(
+            &monitoring.database_file,
+        ))?)))
+    }
+
+    /// Open an existing database without write access, for `query`.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("opening history database {}", path.display()))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_schema(Connection::open_in_memory()?)
+    }
+
+    fn with_schema(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .context("creating history database schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| anyhow!("history database lock poisoned"))
+    }
+
+    /// Insert orders, or update the fill and status of ones already seen.
+    pub fn record_orders(&self, condition_id: &str, orders: &[TrackedOrder]) -> Result<()> {
+        if orders.is_empty() {
+            return Ok(());
+        }
+        let now = timestamp(Utc::now());
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO orders
+                     (order_id, condition_id, token_id, side, price, size, filled, status,
+                      placed_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+                 ON CONFLICT (order_id) DO UPDATE
+                 SET filled = excluded.filled, status = excluded.status,
+                     updated_at = excluded.updated_at",
+            )?;
+            for order in orders {
+                insert.execute(params![
+                    order.order_id,
+                    condition_id,
+                    order.token_id,
+                    format!("{:?}", order.side),
+                    real(order.price),
+                    real(order.size),
+                    real(order.filled),
+                    format!("{:?}", order.status),
+                    now,
+                ])?;
+            }
+        }
+        tx.commit().context("recording orders")
+    }
+
+    /// Record a fill once its markout is known.
+    pub fn record_fill(
+        &self,
+        condition_id: &str,
+        notice: &FillNotice,
+        filled_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.conn()?
+            .execute(
+                "INSERT INTO fills
+                     (condition_id, market, outcome, side, price, size, midpoint, vs_mid_cents,
+                      markout_cents, inventory_yes, inventory_no, skew, filled_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    condition_id,
+                    notice.market,
+                    notice.outcome,
+                    format!("{:?}", notice.side),
+                    real(notice.price),
+                    real(notice.size),
+                    notice.midpoint.map(real),
+                    notice.vs_mid_cents().map(real),
+                    notice.markout_cents.map(real),
+                    real(notice.inventory_yes),
+                    real(notice.inventory_no),
+                    real(notice.skew),
+                    timestamp(filled_at),
+                ],
+            )
+            .context("recording fill")?;
+        Ok(())
+    }
+
+    /// Record the ladder quoted around `midpoint`.
+    pub fn record_quotes(
+        &self,
+        condition_id: &str,
+        midpoint: Decimal,
+        quotes: &[Quote],
+    ) -> Result<()> {
+        if quotes.is_empty() {
+            return Ok(());
+        }
+        let now = timestamp(Utc::now());
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO quotes
+                     (condition_id, midpoint, level, bid_price, ask_price, bid_size, ask_size,
+                      quoted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for quote in quotes {
+                insert.execute(params![
+                    condition_id,
+                    real(midpoint),
+                    quote.level,
+                    real(quote.bid_price),
+                    real(quote.ask_price),
+                    real(quote.bid_size),
+                    real(quote.ask_size),
+                    now,
+                ])?;
+            }
+        }
+        tx.commit().context("recording quotes")
+    }
+
+    /// Record an exit, flow anomaly, kill switch, or similar; `condition_id`
+    /// is `None` for portfolio-wide events.
+    pub fn record_risk_event(
+        &self,
+        condition_id: Option<&str>,
+        kind: &str,
+        detail: &str,
+    ) -> Result<()> {
+        self.conn()?
+            .execute(
+                "INSERT INTO risk_events (condition_id, kind, detail, at) VALUES (?1, ?2, ?3, ?4)",
+                params![condition_id, kind, detail, timestamp(Utc::now())],
+            )
+            .context("recording risk event")?;
+        Ok(())
+    }
+
+    /// Delete rows older than `retention` allows as of `now`. Returns the
+    /// rows removed from each table that had any.
+    pub fn prune(
+        &self,
+        retention: &RetentionConfig,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(&'static str, usize)>> {
+        let conn = self.conn()?;
+        let mut removed = Vec::new();
+        for (table, column, days) in retention.limits() {
+            let cutoff = timestamp(now - chrono::Duration::days(i64::from(days)));
+            let count = conn
+                .execute(
+                    &format!("DELETE FROM {table} WHERE {column} < ?1"),
+                    [cutoff],
+                )
+                .with_context(|| format!("pruning {table}"))?;
+            if count > 0 {
+                removed.push((table, count));
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Rebuild the file to hand space freed by pruning back to the disk.
+    /// Pruning alone only lets SQLite reuse it.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn()?
+            .execute_batch("VACUUM")
+            .context("vacuuming history database")
+    }
+
+    /// Run one SQL statement and collect its rows as text.
+    pub fn query(&self, sql: &str) -> Result<QueryResult> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(sql).context("preparing query")?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let cells = (0..columns.len())
+                .map(|i| row.get_ref(i).map(render))
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            out.push(cells);
+        }
+        Ok(QueryResult { columns, rows: out })
+    }
+}
+
+/// Prune `database` off the async runtime, logging what was removed.
+pub async fn prune_in_background(database: Arc<Database>, retention: RetentionConfig) {
+    let pruned = tokio::task::spawn_blocking(move || database.prune(&retention, Utc::now())).await;
+    match pruned {
+        Ok(Ok(removed)) => {
+            for (table, rows) in removed {
+                info!(table, rows, "Pruned expired history");
+            }
+        }
+        Ok(Err(e)) => warn!(error = %e, "History pruning failed"),
+        Err(e) => warn!(error = %e, "History pruning task failed"),
+    }
+}
+
+fn real(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn render(value: ValueRef<'_>) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+        ValueRef::Blob(b) => format!("<{} bytes>", b.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::OrderStatus;
+    use polymarket_client_sdk::clob::types::Side;
+    use rust_decimal_macros::dec;
+    use std::time::Instant;
+
+    #[test]
+    fn test_history_records_and_queries() {
+        let db = Database::open_in_memory().unwrap();
+        let mut order = TrackedOrder {
+            order_id: "o1".into(),
+            token_id: "yes".into(),
+            side: Side::Buy,
+            price: dec!(0.48),
+            size: dec!(100),
+            filled: Decimal::ZERO,
+            status: OrderStatus::Open,
+        };
+        db.record_orders("0xabc", std::slice::from_ref(&order))
+            .unwrap();
+        order.filled = dec!(100);
+        order.status = OrderStatus::Filled;
+        db.record_orders("0xabc", &[order]).unwrap();
+
+        let fill = |markout| FillNotice {
+            market: "Will it rain?".into(),
+            outcome: "YES",
+            side: Side::Buy,
+            price: dec!(0.48),
+            size: dec!(100),
+            midpoint: Some(dec!(0.50)),
+            inventory_yes: dec!(100),
+            inventory_no: Decimal::ZERO,
+            skew: dec!(0.02),
+            at: Instant::now(),
+            markout_cents: Some(markout),
+        };
+        db.record_fill("0xabc", &fill(dec!(-3)), Utc::now())
+            .unwrap();
+        db.record_fill("0xabc", &fill(dec!(1)), Utc::now()).unwrap();
+
+        let orders = db
+            .query("SELECT order_id, filled, status FROM orders")
+            .unwrap();
+        assert_eq!(orders.columns, vec!["order_id", "filled", "status"]);
+        assert_eq!(orders.rows, vec![vec!["o1", "100", "Filled"]]);
+        let moved = db
+            .query("SELECT markout_cents FROM fills WHERE abs(markout_cents) > 2")
+            .unwrap();
+        assert_eq!(moved.rows, vec![vec!["-3"]]);
+    }
+
+    #[test]
+    fn test_prune_drops_rows_past_retention() {
+        let db = Database::open_in_memory().unwrap();
+        let now = Utc::now();
+        let fill = FillNotice {
+            market: "Will it rain?".into(),
+            outcome: "NO",
+            side: Side::Sell,
+            price: dec!(0.52),
+            size: dec!(10),
+            midpoint: None,
+            inventory_yes: Decimal::ZERO,
+            inventory_no: Decimal::ZERO,
+            skew: Decimal::ZERO,
+            at: Instant::now(),
+            markout_cents: None,
+        };
+        db.record_fill("0xabc", &fill, now - chrono::Duration::days(40))
+            .unwrap();
+        db.record_fill("0xabc", &fill, now).unwrap();
+
+        // Fills are kept forever by default
+        let mut retention = RetentionConfig::default();
+        assert!(db.prune(&retention, now).unwrap().is_empty());
+        retention.fills_days = 30;
+        assert_eq!(db.prune(&retention, now).unwrap(), vec![("fills", 1)]);
+        db.vacuum().unwrap();
+        let left = db.query("SELECT count(*) FROM fills").unwrap();
+        assert_eq!(left.rows, vec![vec!["1"]]);
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::client;
+use crate::clock::ClockSkew;
+use crate::config::Config;
+
+/// Margin the exchange requires between now and a GTD order's expiration;
+/// an order's effective lifetime is its expiration less this.
+pub const GTD_SECURITY_SECS: i64 = 60;
+
+/// Longest a panicking run loop waits on the cancel-all.
+const PANIC_CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Expiration for a GTD order that should stay live for `ttl` from now on
+/// the exchange's clock.
+pub fn gtd_expiration(skew: ClockSkew, ttl: Duration) -> DateTime<Utc> {
+    skew.exchange_now()
+        + chrono::Duration::seconds(GTD_SECURITY_SECS)
+        + chrono::Duration::from_std(ttl).unwrap_or_default()
+}
+
+/// Cancel every open order if the run loop panics, before the process
+/// exits. Panics on other threads are left alone: those are supervised
+/// tasks that get restarted.
+pub fn install_panic_cancel(config: &Config) {
+    let config = config.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic| {
+        previous(panic);
+        if std::thread::current().name() != Some("main") {
+            return;
+        }
+        error!("Run loop panicked, cancelling all orders");
+        // The panicking thread may be inside a runtime, so cancel from a
+        // fresh thread with its own runtime
+        let config = config.clone();
+        match std::thread::spawn(move || cancel_all_now(&config)).join() {
+            Ok(Ok(())) => info!("Cancelled all orders after panic"),
+            Ok(Err(e)) => error!(error = format!("{e:#}"), "Cancel-all after panic failed"),
+            Err(_) => error!("Cancel-all after panic failed"),
+        }
+    }));
+}
+
+fn cancel_all_now(config: &Config) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("starting runtime")?;
+    runtime.block_on(async {
+        tokio::time::timeout(PANIC_CANCEL_TIMEOUT, async {
+            let client = client::create_authenticated_client(config).await?;
+            client
+                .cancel_all_orders()
+                .await
+                .context("cancelling all orders")?;
+            Ok(())
+        })
+        .await
+        .context("timed out cancelling orders")?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gtd_expiration_adds_security_margin_on_exchange_clock() {
+        // Exchange clock 5s ahead of ours
+        let now = Utc::now();
+        let skew = ClockSkew::measure(now, now.timestamp() + 5, now);
+        let expiration = gtd_expiration(skew, Duration::from_secs(120));
+        let lifetime = (expiration - Utc::now()).num_seconds();
+        assert!((184..=186).contains(&lifetime), "lifetime {lifetime}s");
+    }
+}
@@ -1,22 +1,86 @@
-use anyhow::{Context, Result};
-use polymarket_client_sdk::auth;
-use polymarket_client_sdk::clob;
-use polymarket_client_sdk::clob::types::request::MidpointRequest;
+use anyhow::{Result, anyhow};
 use polymarket_client_sdk::clob::types::Side;
-use polymarket_client_sdk::auth::Signer;
-use polymarket_client_sdk::types::U256;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
+use std::collections::VecDeque;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::{debug, info};
+use tracing::{debug, info, instrument, warn};
 
-use crate::config::StrategyConfig;
-use crate::orders::{self, OrderStatus, TrackedOrder};
-use crate::quoter::{self, Quote, QuoteParams};
+use crate::anomaly::{FlowAnomaly, FlowKind, FlowLimits, FlowMonitor, TradeThroughs};
+use crate::clock::{self, SharedClock};
+use crate::config::{RiskConfig, StrategyConfig};
+use crate::crowding::BandCrowding;
+use crate::db::Database;
+use crate::exchange::{BookSnapshot, ExchangeApi, MarketData, NewOrder};
+use crate::fill_model::FillCurve;
+use crate::fills::{FillNotice, FillNotices};
+use crate::metrics::{self, MarketMetrics, PortfolioMetrics, RoundTrips};
+use crate::midpoint::{self, MidpointSource, SourceHealth};
+use crate::orders::{self, LevelFills, OrderStatus, PriceGuard, RetainedMarket, TrackedOrder};
+use crate::quoter::{self, BookState, CollapsePolicy, Jitter, Quote, QuoteParams, Sides};
+use crate::risk::{self, AdverseSelection, ExitLimits, ExitReason, InventoryLots, MarketInventory};
 use crate::scanner::MarketInfo;
 use crate::ws::WsEvent;
 
+/// Minimum spacing between midpoint samples kept for volatility.
+const VOL_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest gap credited by one reward-band uptime sample.
+const BAND_MAX_GAP_MINUTES: u64 = 5;
+/// Midpoint samples kept (one day at one per minute).
+const VOL_MAX_SAMPLES: usize = 1440;
+
+/// Market priority set by the manager. Tier 1 requotes on smaller moves and
+/// gets first claim on rate-limit budget; tier 3 is polled slowly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    One,
+    Two,
+    Three,
+}
+
+impl Tier {
+    pub fn from_number(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(Tier::One),
+            2 => Some(Tier::Two),
+            3 => Some(Tier::Three),
+            _ => None,
+        }
+    }
+
+    /// Multiplier on `requote_threshold_cents`.
+    pub fn threshold_factor(self) -> Decimal {
+        match self {
+            Tier::One => dec!(0.5),
+            Tier::Two => Decimal::ONE,
+            Tier::Three => Decimal::TWO,
+        }
+    }
+}
+
+/// How the first live requote in a market is confirmed (`run --live --confirm`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmMode {
+    /// Print the ladder and wait for `y` on stdin
+    Prompt,
+    /// Print the ladder and proceed (`--yes`)
+    AutoYes,
+}
+
+/// Where one requote's time went, from deciding to requote until every new
+/// order was acknowledged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequoteTiming {
+    pub cancelling: Duration,
+    pub signing: Duration,
+    /// Posting, net of signing
+    pub posting: Duration,
+    pub total: Duration,
+}
+
 /// State for a single market's quoting engine.
 pub struct QuoteEngine {
     pub market: MarketInfo,
@@ -26,6 +90,8 @@ pub struct QuoteEngine {
     pub last_requote: Option<Instant>,
     pub current_quotes: Vec<Quote>,
     pub tracked_orders: Vec<TrackedOrder>,
+    /// Fills per ladder level this cycle, so requotes post only the remainder
+    pub level_fills: LevelFills,
     pub inventory_yes: Decimal,
     pub inventory_no: Decimal,
     /// Cumulative fill value for PnL tracking
@@ -33,10 +99,110 @@ pub struct QuoteEngine {
     pub total_sold_value: Decimal,
     /// Whether WS is connected (affects tick behavior)
     pub ws_connected: bool,
+    /// Which midpoint sources are healthy, and the one in use
+    pub midpoint_health: SourceHealth,
+    /// Current REST polling interval and the midpoint it last polled
+    poll_interval: Duration,
+    last_polled_midpoint: Option<Decimal>,
+    /// Per-market PnL and activity metrics
+    pub metrics: MarketMetrics,
+    /// Quoting stopped because the market closed or its outcome is disputed
+    pub halted: bool,
+    /// Quoting suspended by the operator (SIGUSR1); fills on orders already
+    /// matched are still tracked
+    pub paused: bool,
+    /// Price regime (near 0/1 or not) the current tick size was fetched for
+    pub extreme_regime: Option<bool>,
+    /// Time source for requote timers
+    pub clock: SharedClock,
+    /// Stop-loss / take-profit thresholds on this market's PnL
+    pub exit_limits: ExitLimits,
+    /// Set once an exit threshold fires; the engine then only sells down inventory
+    pub exit_reason: Option<ExitReason>,
+    /// Lifetime of our GTD orders (`risk.deadman_secs`); they're replaced at
+    /// half of it so none lapse while the bot is running
+    pub deadman: Option<Duration>,
+    /// FIFO lots behind `inventory_yes` / `inventory_no`, for inventory aging
+    pub lots_yes: InventoryLots,
+    pub lots_no: InventoryLots,
+    /// Net YES held through correlated markets (see `risk.correlations`),
+    /// counted toward this market's inventory skew
+    pub correlated_inventory: Decimal,
+    /// Recent midpoints for volatility / VaR estimates
+    pub midpoint_samples: VecDeque<(Instant, Decimal)>,
+    /// Most capital (inventory cost basis + resting buys) this market may
+    /// commit; quote levels that would exceed it are dropped
+    pub capital_limit: Option<Decimal>,
+    /// Pending review of this market's first ladder; cleared once confirmed
+    pub confirm: Option<ConfirmMode>,
+    /// Top of the YES book from the last WS book update; unknown while the
+    /// WebSocket is down, since nothing else keeps it current
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+    /// Stretch (>= 1) applied to the requote interval and threshold when
+    /// rate-limit budget runs low
+    pub requote_scale: Decimal,
+    pub tier: Tier,
+    /// When reward-band uptime was last sampled
+    pub last_band_sample: Option<Instant>,
+    /// Buy lots awaiting a matching sell, for spread capture
+    pub round_trips: RoundTrips,
+    /// Rolling placements/cancels/fills checked against their own baseline
+    pub flow: FlowMonitor,
+    pub flow_limits: FlowLimits,
+    /// Latest order-flow anomaly, awaiting pickup by the manager's alerts
+    pub flow_anomaly: Option<FlowAnomaly>,
+    /// Trades printed through our quotes without filling them
+    pub trade_throughs: TradeThroughs,
+    /// Requotes are paused until this time after a placement/cancel spike
+    pub throttled_until: Option<Instant>,
+    /// Other makers' reward-band size from periodic book snapshots
+    pub crowding: BandCrowding,
+    /// Other makers' two-sided reward score from the latest book snapshot
+    pub competitor_score: Option<Decimal>,
+    /// Fill markouts, driving quote shading toward the band edge
+    pub adverse: AdverseSelection,
+    /// Fills awaiting their markout before being reported
+    pub fills: FillNotices,
+    /// Order, fill, quote, and risk-event history, if one is open
+    pub database: Option<Arc<Database>>,
+    /// USDC queued for a CTF split into this market's tokens; counted as
+    /// deliverable for asks until the split lands
+    pub pending_split: Decimal,
+    /// Split wanted to back asks, awaiting pickup by the manager's CTF queue
+    pub split_request: Option<Decimal>,
+    /// First tick, from which the warm-up period runs
+    pub warmup_started: Option<Instant>,
+    /// Set once the book has passed the entry depth check
+    pub entered: bool,
+    /// Chance of a quote filling by distance and size, calibrated from our
+    /// live ladders; drives paper fills for dry-run markets
+    pub fill_curve: FillCurve,
+    /// Midpoint the current ladder was quoted against
+    quoted_midpoint: Option<Decimal>,
+    /// When a too-thin book may next be checked
+    entry_retry_at: Option<Instant>,
+    /// Successful quoting ticks since warm-up, driving the size ramp
+    pub ramp_ticks: u32,
+    /// Outer levels dropped after over-budget requotes
+    pub levels_shed: u32,
+    /// Per-run seed for size and offset jitter
+    jitter_seed: u64,
+    /// Ladders quoted so far; each gets fresh jitter
+    pub quote_epoch: u64,
+    /// Sides quoted at the last midpoint under the extreme-price policy
+    pub quote_sides: Sides,
+    /// Whether other makers have collapsed or crossed the YES spread
+    pub book_state: BookState,
 }
 
 impl QuoteEngine {
     pub fn new(market: MarketInfo, config: StrategyConfig, dry_run: bool) -> Self {
+        let mut metrics =
+            MarketMetrics::new(market.condition_id.clone(), market.question.clone());
+        metrics.token_yes_id = market.token_yes_id.clone();
+        metrics.token_no_id = market.token_no_id.clone();
+        let poll_interval = Duration::from_secs(config.rest_poll_interval_secs);
         Self {
             market,
             config,
@@ -45,32 +211,632 @@ impl QuoteEngine {
             last_requote: None,
             current_quotes: Vec::new(),
             tracked_orders: Vec::new(),
+            level_fills: LevelFills::default(),
             inventory_yes: Decimal::ZERO,
             inventory_no: Decimal::ZERO,
             total_bought_value: Decimal::ZERO,
             total_sold_value: Decimal::ZERO,
             ws_connected: false,
+            midpoint_health: SourceHealth::default(),
+            poll_interval,
+            last_polled_midpoint: None,
+            metrics,
+            halted: false,
+            paused: false,
+            extreme_regime: None,
+            clock: clock::system(),
+            exit_limits: ExitLimits::default(),
+            exit_reason: None,
+            deadman: None,
+            lots_yes: InventoryLots::default(),
+            lots_no: InventoryLots::default(),
+            correlated_inventory: Decimal::ZERO,
+            midpoint_samples: VecDeque::new(),
+            capital_limit: None,
+            confirm: None,
+            best_bid: None,
+            best_ask: None,
+            requote_scale: Decimal::ONE,
+            tier: Tier::Two,
+            last_band_sample: None,
+            round_trips: RoundTrips::default(),
+            flow: FlowMonitor::default(),
+            flow_limits: FlowLimits::default(),
+            flow_anomaly: None,
+            trade_throughs: TradeThroughs::default(),
+            throttled_until: None,
+            crowding: BandCrowding::default(),
+            competitor_score: None,
+            adverse: AdverseSelection::default(),
+            fills: FillNotices::default(),
+            database: None,
+            pending_split: Decimal::ZERO,
+            split_request: None,
+            warmup_started: None,
+            entered: false,
+            fill_curve: FillCurve::default(),
+            quoted_midpoint: None,
+            entry_retry_at: None,
+            ramp_ticks: 0,
+            levels_shed: 0,
+            jitter_seed: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64),
+            quote_epoch: 0,
+            quote_sides: Sides::Both,
+            book_state: BookState::Normal,
         }
     }
 
-    /// Fetch the current midpoint from the CLOB API.
-    pub async fn fetch_midpoint(
-        &self,
-        clob_client: &clob::Client<impl auth::state::State>,
-    ) -> Result<Decimal> {
-        let token_id =
-            U256::from_str(&self.market.token_yes_id).context("parsing YES token ID")?;
-        let req = MidpointRequest::builder().token_id(token_id).build();
-        let resp = clob_client
-            .midpoint(&req)
+    /// Use a different time source (e.g. `SimClock` in tests).
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_exit_limits(mut self, exit_limits: ExitLimits) -> Self {
+        self.exit_limits = exit_limits;
+        self
+    }
+
+    pub fn with_flow_limits(mut self, flow_limits: FlowLimits) -> Self {
+        self.flow_limits = flow_limits;
+        self
+    }
+
+    /// Write history to `database`.
+    pub fn with_database(mut self, database: Option<Arc<Database>>) -> Self {
+        self.database = database;
+        self
+    }
+
+    /// Write to the history database, if one is open. A failed write is
+    /// logged and never interrupts quoting.
+    fn journal(&self, write: impl FnOnce(&Database, &str) -> Result<()>) {
+        if let Some(db) = &self.database
+            && let Err(e) = write(db, &self.market.condition_id)
+        {
+            warn!(market = %self.market.question, error = %e, "History database write failed");
+        }
+    }
+
+    /// Journal the still-active orders among `orders` as cancelled.
+    fn journal_cancels(&self, orders: &[TrackedOrder]) {
+        let cancelled: Vec<TrackedOrder> = orders
+            .iter()
+            .filter(|o| o.is_active())
+            .map(|o| TrackedOrder {
+                status: OrderStatus::Cancelled,
+                ..o.clone()
+            })
+            .collect();
+        self.journal(|db, id| db.record_orders(id, &cancelled));
+    }
+
+    /// Replace orders before they expire when they're posted as GTD with
+    /// `secs` to live (0 = GTC, nothing to refresh).
+    pub fn with_deadman(mut self, secs: u64) -> Self {
+        self.deadman = (secs > 0).then(|| Duration::from_secs(secs));
+        self
+    }
+
+    /// Whether resting GTD orders are halfway to expiry and due to be
+    /// replaced, however quiet the market.
+    pub fn deadman_refresh_due(&self) -> bool {
+        let Some(ttl) = self.deadman else {
+            return false;
+        };
+        !self.halted
+            && !self.paused
+            && self.tracked_orders.iter().any(|o| o.is_active())
+            && self
+                .last_requote
+                .is_some_and(|at| self.clock.elapsed(at) >= ttl / 2)
+    }
+
+    /// Unrealized PnL of this market's inventory at `midpoint`.
+    pub fn unrealized_pnl(&self, midpoint: Decimal) -> Decimal {
+        MarketInventory {
+            yes_tokens: self.inventory_yes,
+            no_tokens: self.inventory_no,
+            total_bought_value: self.total_bought_value,
+            total_sold_value: self.total_sold_value,
+        }
+        .unrealized_pnl(midpoint)
+    }
+
+    /// Cost basis of held inventory.
+    pub fn cost_basis(&self) -> Decimal {
+        (self.total_bought_value - self.total_sold_value).max(Decimal::ZERO)
+    }
+
+    /// Capital tied up in this market: inventory cost basis plus USDC
+    /// locked in resting buy orders.
+    pub fn committed_capital(&self) -> Decimal {
+        self.cost_basis() + orders::resting_buy_notional(&self.tracked_orders)
+    }
+
+    /// Most quote levels per side this engine can post.
+    pub fn level_cap(&self) -> u32 {
+        if self.config.dynamic_levels {
+            self.config.max_levels
+        } else {
+            self.config.num_levels
+        }
+    }
+
+    /// Keep the innermost quote levels whose buy legs fit under
+    /// `capital_limit` on top of the current cost basis.
+    fn fit_to_capital(&self, quotes: Vec<Quote>) -> Vec<Quote> {
+        let Some(limit) = self.capital_limit else {
+            return quotes;
+        };
+        let mut committed = self.cost_basis();
+        let total = quotes.len();
+        let fitted: Vec<Quote> = quotes
+            .into_iter()
+            .take_while(|q| {
+                committed += quoter::quote_capital(q);
+                committed <= limit
+            })
+            .collect();
+        // Dynamic levels fill the band and expect capital to trim the ladder
+        if fitted.len() < total && self.config.dynamic_levels {
+            debug!(
+                market = %self.market.question,
+                limit = %limit,
+                levels = fitted.len(),
+                dropped = total - fitted.len(),
+                "Band levels trimmed to capital limit"
+            );
+        } else if fitted.len() < total {
+            warn!(
+                market = %self.market.question,
+                limit = %limit,
+                cost_basis = %self.cost_basis(),
+                levels = fitted.len(),
+                dropped = total - fitted.len(),
+                "Quote levels exceed capital limit"
+            );
+        }
+        fitted
+    }
+
+    /// Sample the midpoint (at most once per minute) and refresh the
+    /// market's daily volatility estimate.
+    pub fn record_midpoint(&mut self, midpoint: Decimal) {
+        self.update_quote_sides(midpoint);
+        let now = self.clock.now();
+        let horizon = Duration::from_secs(self.config.markout_secs);
+        self.adverse.mature(now, midpoint, horizon);
+        let matured = self.fills.mature(now, midpoint, horizon);
+        if matured > 0 {
+            let ready = self.fills.ready();
+            self.journal(|db, id| {
+                for notice in &ready[ready.len() - matured..] {
+                    let age = now.saturating_duration_since(notice.at);
+                    let filled_at =
+                        chrono::Utc::now() - chrono::Duration::from_std(age).unwrap_or_default();
+                    db.record_fill(id, notice, filled_at)?;
+                }
+                Ok(())
+            });
+        }
+        if let Some((last, _)) = self.midpoint_samples.back()
+            && now.saturating_duration_since(*last) < VOL_SAMPLE_INTERVAL
+        {
+            return;
+        }
+        self.midpoint_samples.push_back((now, midpoint));
+        if self.midpoint_samples.len() > VOL_MAX_SAMPLES {
+            self.midpoint_samples.pop_front();
+        }
+        self.metrics.daily_volatility =
+            risk::daily_volatility(self.midpoint_samples.make_contiguous());
+    }
+
+    /// Track which sides the extreme-price policy allows, logging each
+    /// switch so one-sided or suspended quoting is never silent.
+    fn update_quote_sides(&mut self, midpoint: Decimal) {
+        let sides = quoter::extreme_sides(
+            midpoint,
+            self.config.extreme_price,
+            self.config.extreme_policy,
+        );
+        if sides == self.quote_sides {
+            return;
+        }
+        if sides == Sides::Both {
+            info!(midpoint = %midpoint, from = %self.quote_sides, "Midpoint left extreme range, quoting two-sided");
+        } else {
+            warn!(midpoint = %midpoint, from = %self.quote_sides, to = %sides, "Midpoint at extreme price, switching quoting mode");
+        }
+        self.quote_sides = sides;
+    }
+
+    /// Reclassify the YES spread from the latest best bid and ask, logging
+    /// each change. A one-tick spread against our own resting order is left
+    /// as normal, since it's our quote that tightened it. Returns true when
+    /// the state changed and `collapse_policy` calls for a requote.
+    fn update_book_state(&mut self) -> bool {
+        let tick = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        let mut state = quoter::book_state(self.best_bid, self.best_ask, tick);
+        if state == BookState::Collapsed
+            && (self.best_bid.is_some_and(|p| self.rests_at(p, true))
+                || self.best_ask.is_some_and(|p| self.rests_at(p, false)))
+        {
+            state = BookState::Normal;
+        }
+        if state == self.book_state {
+            return false;
+        }
+        if state == BookState::Normal {
+            info!(market = %self.market.question, from = %self.book_state, "Book spread back to normal, resuming quotes");
+        } else {
+            warn!(
+                market = %self.market.question,
+                state = %state,
+                best_bid = ?self.best_bid,
+                best_ask = ?self.best_ask,
+                policy = ?self.config.collapse_policy,
+                "Book spread collapsed or crossed"
+            );
+        }
+        self.book_state = state;
+        self.config.collapse_policy != CollapsePolicy::Ignore
+    }
+
+    /// Whether one of our active orders rests at `yes_price` on the YES
+    /// book's bid (or ask) side, counting NO orders at their mirrored price.
+    fn rests_at(&self, yes_price: Decimal, bid: bool) -> bool {
+        self.tracked_orders
+            .iter()
+            .filter(|o| o.is_active())
+            .any(|o| {
+                let is_yes = o.token_id == self.market.token_yes_id;
+                let price = if is_yes {
+                    o.price
+                } else {
+                    Decimal::ONE - o.price
+                };
+                price == yes_price && (is_yes == (o.side == Side::Buy)) == bid
+            })
+    }
+
+    /// Seed the volatility estimate from the YES token's recent price
+    /// history, so VaR has something to go on from the first tick. Skipped
+    /// once samples exist; a failed fetch leaves the estimate to build live.
+    pub async fn seed_volatility(&mut self, exchange: &impl MarketData) {
+        let hours = self.config.volatility_seed_hours;
+        if hours == 0 || !self.midpoint_samples.is_empty() {
+            return;
+        }
+        let end = chrono::Utc::now().timestamp();
+        let start = end - (hours * 3600) as i64;
+        let fidelity = (hours * 60).div_ceil(VOL_MAX_SAMPLES as u64) as u32;
+        let history = match exchange
+            .price_history(&self.market.token_yes_id, start, end, fidelity)
             .await
-            .context("fetching midpoint")?;
-        Ok(resp.mid)
+        {
+            Ok(history) => history,
+            Err(e) => {
+                debug!(market = %self.market.question, error = %e, "Price history unavailable");
+                return;
+            }
+        };
+        let now = self.clock.now();
+        self.midpoint_samples = history
+            .into_iter()
+            .filter_map(|(t, price)| {
+                let age = Duration::from_secs(end.saturating_sub(t).max(0) as u64);
+                Some((now.checked_sub(age)?, price))
+            })
+            .collect();
+        while self.midpoint_samples.len() > VOL_MAX_SAMPLES {
+            self.midpoint_samples.pop_front();
+        }
+        self.metrics.daily_volatility =
+            risk::daily_volatility(self.midpoint_samples.make_contiguous());
+        info!(
+            market = %self.market.question,
+            samples = self.midpoint_samples.len(),
+            daily_volatility = ?self.metrics.daily_volatility,
+            "Seeded volatility from price history"
+        );
+    }
+
+    /// Record inventory changes as lots so their age is known.
+    pub fn sync_lots(&mut self) {
+        let now = self.clock.now();
+        self.lots_yes.sync_to(self.inventory_yes, now);
+        self.lots_no.sync_to(self.inventory_no, now);
+    }
+
+    fn max_inventory_age(&self) -> Duration {
+        Duration::from_secs(self.config.max_inventory_age_secs)
+    }
+
+    /// Signed skew from stale inventory: positive pushes out aged YES,
+    /// negative pushes out aged NO.
+    fn inventory_age_skew(&self) -> Decimal {
+        let now = self.clock.now();
+        let max_age = self.max_inventory_age();
+        let yes = self
+            .lots_yes
+            .oldest_age(now)
+            .map_or(Decimal::ZERO, |age| risk::age_skew(age, max_age));
+        let no = self
+            .lots_no
+            .oldest_age(now)
+            .map_or(Decimal::ZERO, |age| risk::age_skew(age, max_age));
+        yes - no
+    }
+
+    /// Whether any held lot is older than twice `max_inventory_age_secs`.
+    fn inventory_expired(&self) -> bool {
+        let max_age = self.max_inventory_age();
+        if max_age.is_zero() {
+            return false;
+        }
+        let now = self.clock.now();
+        [&self.lots_yes, &self.lots_no]
+            .iter()
+            .any(|lots| lots.oldest_age(now).is_some_and(|age| age >= max_age * 2))
+    }
+
+    /// Switch to unwind mode if this market's PnL crossed a stop-loss or
+    /// take-profit threshold, or its inventory has gone stale. Returns true
+    /// on the tick the exit fires.
+    pub fn check_exit(&mut self, midpoint: Decimal) -> bool {
+        if self.exit_reason.is_some() {
+            return false;
+        }
+        self.sync_lots();
+        let pnl = self.unrealized_pnl(midpoint);
+        let reason = match self.exit_limits.check(pnl) {
+            Some(reason) => reason,
+            None if self.inventory_expired() => ExitReason::InventoryAge,
+            None => return false,
+        };
+        warn!(
+            market = %self.market.question,
+            pnl = %pnl,
+            reason = ?reason,
+            "Exit threshold hit, unwinding position"
+        );
+        self.start_exit(Some(reason));
+        true
+    }
+
+    /// Switch to (or, with `None`, back out of) sell-only unwinding.
+    pub fn start_exit(&mut self, reason: Option<ExitReason>) {
+        if let Some(reason) = reason {
+            self.journal(|db, id| db.record_risk_event(Some(id), "exit", &format!("{reason:?}")));
+        }
+        self.exit_reason = reason;
+        self.metrics.exit_reason = reason;
+    }
+
+    /// Metrics snapshot with current inventory and midpoint filled in.
+    pub fn final_metrics(&self) -> MarketMetrics {
+        let mut metrics = self.metrics.clone();
+        metrics.inventory_yes = self.inventory_yes;
+        metrics.inventory_no = self.inventory_no;
+        metrics.last_midpoint = self.last_midpoint;
+        metrics.last_update = chrono::Utc::now();
+        metrics
+    }
+
+    /// Current midpoint from the first healthy source in the configured
+    /// chain. A failing source is skipped for `midpoint_retry_secs`, except
+    /// the last, which is always tried.
+    pub async fn fetch_midpoint(&mut self, exchange: &impl MarketData) -> Result<Decimal> {
+        let now = self.clock.now();
+        let retry = Duration::from_secs(self.config.midpoint_retry_secs);
+        let sources = self.config.midpoint_sources.clone();
+        let token = &self.market.token_yes_id;
+        let mut last_error = None;
+        for (i, &source) in sources.iter().enumerate() {
+            let last = i + 1 == sources.len();
+            let read = match source {
+                MidpointSource::Ws => match self.ws_midpoint(now) {
+                    Some(mid) => Ok(mid),
+                    None => continue,
+                },
+                _ if !last && !self.midpoint_health.ready(source, now, retry) => continue,
+                MidpointSource::Microprice => exchange.order_book(token).await.and_then(|book| {
+                    midpoint::microprice(&book).ok_or_else(|| anyhow!("order book is one-sided"))
+                }),
+                MidpointSource::Rest => exchange.midpoint(token).await,
+                MidpointSource::LastTrade => exchange.last_trade_price(token).await,
+            };
+            match read.and_then(midpoint::validate) {
+                Ok(mid) => {
+                    let previous = self.midpoint_health.current;
+                    if self.midpoint_health.mark_served(source) && previous.is_some() {
+                        info!(
+                            market = %self.market.question,
+                            from = ?previous,
+                            to = %source,
+                            "Midpoint source changed"
+                        );
+                    }
+                    return Ok(mid);
+                }
+                Err(e) => {
+                    warn!(
+                        market = %self.market.question,
+                        source = %source,
+                        error = %e,
+                        "Midpoint source failed"
+                    );
+                    self.midpoint_health.mark_failed(source, now);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("no midpoint source available")))
+    }
+
+    /// The WebSocket midpoint, if connected and it isn't stale.
+    fn ws_midpoint(&self, now: Instant) -> Option<Decimal> {
+        let stale = Duration::from_secs(self.config.midpoint_stale_secs);
+        self.ws_connected
+            .then(|| self.midpoint_health.ws_midpoint(now, stale))
+            .flatten()
+    }
+
+    /// How long to wait before the next REST tick: `rest_poll_interval_secs`,
+    /// tightened toward `rest_poll_min_secs` while the midpoint is moving.
+    pub fn poll_interval(&self) -> Duration {
+        let slowest = Duration::from_secs(self.config.rest_poll_interval_secs.max(1));
+        let fastest = Duration::from_secs(self.config.rest_poll_min_secs.max(1)).min(slowest);
+        self.poll_interval.clamp(fastest, slowest)
+    }
+
+    /// Halve the poll interval when the midpoint moved by at least half the
+    /// requote threshold since the last poll; otherwise ease back by
+    /// doubling.
+    fn adapt_poll_interval(&mut self, midpoint: Decimal) {
+        let moving = self.last_polled_midpoint.is_some_and(|last| {
+            (midpoint - last).abs() * dec!(100)
+                >= self.config.requote_threshold_cents / Decimal::TWO
+        });
+        self.last_polled_midpoint = Some(midpoint);
+        let current = self.poll_interval();
+        self.poll_interval = if moving { current / 2 } else { current * 2 };
+    }
+
+    /// Whether WebSocket updates drive this market's requotes: WS is ahead
+    /// of every other usable source in the chain. Otherwise requotes come
+    /// from REST ticks.
+    pub fn ws_anchored(&self) -> bool {
+        let now = self.clock.now();
+        let retry = Duration::from_secs(self.config.midpoint_retry_secs);
+        for &source in &self.config.midpoint_sources {
+            if source == MidpointSource::Ws {
+                return self.ws_midpoint(now).is_some();
+            }
+            if self.midpoint_health.ready(source, now, retry) {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Re-fetch the tick size when the midpoint crosses into or out of the
+    /// fine-tick regime near 0/1, so quotes stay on valid price increments.
+    /// Falls back to the regime's expected tick if the CLOB can't be reached.
+    pub async fn sync_tick_size(&mut self, exchange: &impl MarketData, midpoint: Decimal) {
+        let extreme = quoter::is_extreme_price(midpoint);
+        if self.extreme_regime == Some(extreme) {
+            return;
+        }
+
+        let tick_size = match exchange.tick_size(&self.market.token_yes_id).await {
+            Ok(tick) => tick,
+            Err(e) => {
+                let fallback = quoter::expected_tick_size(midpoint);
+                warn!(error = %e, fallback = %fallback, "Tick size fetch failed, using regime default");
+                fallback
+            }
+        };
+
+        if self.extreme_regime.is_some() || self.market.tick_size != tick_size.to_string() {
+            info!(
+                market = %self.market.question,
+                midpoint = %midpoint,
+                extreme,
+                old_tick = %self.market.tick_size,
+                new_tick = %tick_size,
+                "Tick size regime changed"
+            );
+        }
+        self.market.tick_size = tick_size.to_string();
+        self.extreme_regime = Some(extreme);
+        self.check_offset_ticks();
+    }
+
+    /// Warn when an offset set in cents isn't a whole number of ticks at the
+    /// current tick size. The minimum only matters above one tick, since the
+    /// quoter never goes tighter than that anyway.
+    fn check_offset_ticks(&self) {
+        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        let offsets = [
+            (
+                "base_offset_cents",
+                self.config.base_offset_cents,
+                self.config.base_offset_ticks,
+            ),
+            (
+                "min_offset_cents",
+                self.config.min_offset_cents,
+                self.config.min_offset_ticks,
+            ),
+        ];
+        for (setting, cents, ticks) in offsets {
+            if ticks.is_some()
+                || quoter::is_whole_ticks(cents, tick_size)
+                || (setting == "min_offset_cents" && cents / dec!(100) < tick_size)
+            {
+                continue;
+            }
+            warn!(
+                market = %self.market.question,
+                setting,
+                cents = %cents,
+                tick_size = %tick_size,
+                "Offset isn't a whole number of ticks and will round; set it in ticks instead"
+            );
+        }
+    }
+
+    /// Apply refreshed market parameters. Quoting-relevant fields (tick size,
+    /// reward band, fees) are read on every `compute_quotes`, so changes take
+    /// effect on the next requote. Returns true if any of them changed.
+    pub fn update_market_params(&mut self, fresh: &MarketInfo) -> bool {
+        let changed = self.market.tick_size != fresh.tick_size
+            || self.market.rewards_min_size != fresh.rewards_min_size
+            || self.market.rewards_max_spread != fresh.rewards_max_spread
+            || self.market.fee_rate_bps != fresh.fee_rate_bps;
+
+        if changed {
+            info!(
+                market = %self.market.question,
+                old_tick = %self.market.tick_size,
+                new_tick = %fresh.tick_size,
+                old_max_spread = ?self.market.rewards_max_spread,
+                new_max_spread = ?fresh.rewards_max_spread,
+                old_min_size = ?self.market.rewards_min_size,
+                new_min_size = ?fresh.rewards_min_size,
+                "Market parameters changed"
+            );
+        }
+
+        let tick_changed = self.market.tick_size != fresh.tick_size;
+        self.market.tick_size = fresh.tick_size.clone();
+        if tick_changed {
+            self.check_offset_ticks();
+        }
+        self.market.rewards_min_size = fresh.rewards_min_size;
+        self.market.rewards_max_spread = fresh.rewards_max_spread;
+        self.market.fee_rate_bps = fresh.fee_rate_bps;
+        self.market.liquidity = fresh.liquidity;
+        self.market.volume = fresh.volume;
+        self.market.reward_daily_estimate = fresh.reward_daily_estimate;
+        changed
     }
 
     /// Determine if we should requote based on midpoint shift or timer.
     pub fn should_requote(&self, new_midpoint: Decimal) -> bool {
-        let threshold = self.config.requote_threshold_cents / dec!(100);
+        if self.throttled_until.is_some_and(|t| self.clock.now() < t) {
+            return false;
+        }
+        let threshold = self.config.requote_threshold_cents / dec!(100)
+            * self.tier.threshold_factor()
+            * self.requote_scale;
+        let interval = Duration::from_secs(self.config.requote_interval_secs)
+            .mul_f64(self.requote_scale.to_f64().unwrap_or(1.0));
 
         if let Some(last_mid) = self.last_midpoint {
             if (new_midpoint - last_mid).abs() > threshold {
@@ -85,55 +851,130 @@ impl QuoteEngine {
             return true; // First quote
         }
 
-        if let Some(last_time) = self.last_requote {
-            if last_time.elapsed() > Duration::from_secs(self.config.requote_interval_secs) {
+        if self.deadman_refresh_due() {
+            debug!("Refreshing orders before they expire");
+            return true;
+        }
+
+        match self.last_requote {
+            Some(last_time) if self.clock.elapsed(last_time) > interval => {
                 debug!("Requote timer expired");
-                return true;
+                true
             }
+            Some(_) => false,
+            // Nothing quoted since a resume or config reload
+            None => true,
         }
+    }
 
-        false
+    /// Stop quoting and cancel this market's orders, or resume quoting on
+    /// the next tick.
+    pub async fn set_paused(&mut self, exchange: &impl ExchangeApi, paused: bool) -> Result<()> {
+        self.paused = paused;
+        if paused {
+            self.cancel_all(exchange).await
+        } else {
+            self.last_requote = None;
+            Ok(())
+        }
     }
 
-    /// Generate new quotes based on current midpoint.
-    pub fn compute_quotes(&self, midpoint: Decimal) -> Vec<Quote> {
-        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+    /// Swap in reloaded strategy and risk settings; the ladder is rebuilt
+    /// with them on the next tick.
+    pub fn reconfigure(&mut self, strategy: StrategyConfig, risk_config: &RiskConfig) {
+        self.config = strategy;
+        self.exit_limits = ExitLimits::from_config(risk_config);
+        self.flow_limits = FlowLimits::from_config(risk_config);
+        self.last_requote = None;
+    }
 
-        let net_inventory = self.inventory_yes - self.inventory_no;
+    /// Net YES exposure: this market's position plus what correlated markets
+    /// hold in its direction.
+    pub fn net_exposure(&self) -> Decimal {
+        self.inventory_yes - self.inventory_no + self.correlated_inventory
+    }
+
+    /// Skew in [-0.5, 0.5] from net inventory against the cap, plus the push
+    /// to work off stale inventory.
+    pub fn inventory_skew(&self) -> Decimal {
+        let net_inventory = self.net_exposure();
         let cap = self.config.inventory_cap;
-        let skew = if cap > Decimal::ZERO {
-            (net_inventory / cap).min(dec!(0.5)).max(dec!(-0.5))
+        let base_skew = if cap > Decimal::ZERO {
+            net_inventory / cap
         } else {
             Decimal::ZERO
         };
+        (base_skew + self.inventory_age_skew())
+            .min(dec!(0.5))
+            .max(dec!(-0.5))
+    }
+
+    /// Generate new quotes based on current midpoint.
+    pub fn compute_quotes(&self, midpoint: Decimal) -> Vec<Quote> {
+        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        let skew = self.inventory_skew();
 
-        let params = QuoteParams {
+        let mut params = QuoteParams {
             midpoint,
-            base_offset_cents: self.config.base_offset_cents,
-            min_offset_cents: self.config.min_offset_cents,
+            base_offset_cents: quoter::offset_cents(
+                self.config.base_offset_cents,
+                self.config.base_offset_ticks,
+                tick_size,
+            ),
+            min_offset_cents: quoter::offset_cents(
+                self.config.min_offset_cents,
+                self.config.min_offset_ticks,
+                tick_size,
+            ),
             tick_size,
-            order_size: self.config.order_size,
+            order_size: self.config.order_size * self.size_ramp(),
             num_levels: self.config.num_levels,
             fee_rate_bps: self.market.fee_rate_bps.map(|v| v as u32),
             max_incentive_spread: self.market.rewards_max_spread,
             min_incentive_size: self.market.rewards_min_size,
             inventory_skew: skew,
+            shade: self.shade_intensity(),
+            tick_spaced_levels: self.config.dynamic_levels,
+            size_skew: self.config.size_skew,
         };
+        let collapsed = self.book_state != BookState::Normal;
+        if collapsed && self.config.collapse_policy == CollapsePolicy::Widen {
+            let widen = Decimal::from(self.config.collapse_widen_ticks) * tick_size * dec!(100);
+            params.base_offset_cents += widen;
+            params.min_offset_cents += widen;
+        }
+        if self.config.dynamic_levels {
+            params.num_levels = quoter::band_levels(&params, self.config.max_levels);
+        }
 
-        let quotes = quoter::generate_quotes(&params);
+        let mut sides = quoter::extreme_sides(
+            midpoint,
+            self.config.extreme_price,
+            self.config.extreme_policy,
+        );
+        if collapsed && self.config.collapse_policy == CollapsePolicy::Suspend {
+            sides = Sides::None;
+        }
+        let mut quotes = quoter::generate_sided_quotes(&params, sides);
+        let jitter = Jitter {
+            size_pct: self.config.size_jitter_pct,
+            offset_ticks: self.config.offset_jitter_ticks,
+            seed: self.jitter_seed,
+        };
+        quoter::jitter_quotes(&mut quotes, &params, &jitter, self.quote_epoch);
 
         for q in &quotes {
             let bid_score = quoter::estimate_score(
                 midpoint,
                 q.bid_price,
-                q.size,
+                q.bid_size,
                 self.market.rewards_max_spread,
                 self.market.rewards_min_size,
             );
             let ask_score = quoter::estimate_score(
                 midpoint,
                 q.ask_price,
-                q.size,
+                q.ask_size,
                 self.market.rewards_max_spread,
                 self.market.rewards_min_size,
             );
@@ -152,135 +993,973 @@ impl QuoteEngine {
         quotes
     }
 
+    /// How far to shade quotes toward the reward-band edge given recent
+    /// adverse selection.
+    fn shade_intensity(&self) -> Decimal {
+        let toxicity = self.adverse.toxicity_cents();
+        let shade = risk::shade_intensity(
+            toxicity,
+            self.config.shade_start_cents,
+            self.config.shade_full_cents,
+            self.config.shade_curve,
+        );
+        if shade > Decimal::ZERO {
+            debug!(
+                market = %self.market.question,
+                toxicity_cents = %toxicity,
+                shade = %shade,
+                "Shading quotes toward reward-band edge"
+            );
+        }
+        shade
+    }
+
     /// Dry-run tick: fetch midpoint, compute quotes, log them.
-    pub async fn tick_dry_run(
-        &mut self,
-        clob_client: &clob::Client<impl auth::state::State>,
-    ) -> Result<()> {
-        let midpoint = self.fetch_midpoint(clob_client).await?;
+    #[instrument(
+        name = "tick",
+        skip_all,
+        fields(market = %self.market.question, condition_id = %self.market.condition_id)
+    )]
+    pub async fn tick_dry_run(&mut self, exchange: &impl MarketData) -> Result<()> {
+        let midpoint = self.fetch_midpoint(exchange).await?;
+        self.record_midpoint(midpoint);
+        self.adapt_poll_interval(midpoint);
 
-        if !self.should_requote(midpoint) {
+        if self.warming_up() || !self.should_requote(midpoint) {
             return Ok(());
         }
 
-        let quotes = self.compute_quotes(midpoint);
-        self.log_dry_run_quotes(&quotes, midpoint);
-
+        self.sync_tick_size(exchange, midpoint).await;
+        self.requote_dry_run(midpoint);
+        self.advance_ramp();
         self.last_midpoint = Some(midpoint);
-        self.last_requote = Some(Instant::now());
-        self.current_quotes = quotes;
         Ok(())
     }
 
-    /// Live tick: cancel stale orders, place new quotes, track fills.
-    pub async fn tick_live(
-        &mut self,
-        clob_client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-        signer: &impl Signer,
-    ) -> Result<()> {
-        let midpoint = self.fetch_midpoint(clob_client).await?;
+    /// Paper-trading tick for a dry-run market in a live session: quote as
+    /// `tick_dry_run` does, and book a simulated fill for every quote the
+    /// midpoint has moved through since the last tick.
+    pub async fn tick_paper(&mut self, exchange: &impl MarketData) -> Result<()> {
+        let midpoint = self.fetch_midpoint(exchange).await?;
+        self.fill_paper_quotes(midpoint);
+        self.record_midpoint(midpoint);
+        self.adapt_poll_interval(midpoint);
+        if !self.warming_up() && self.should_requote(midpoint) {
+            self.fill_paper_by_model();
+            self.sync_tick_size(exchange, midpoint).await;
+            self.requote_dry_run(midpoint);
+            self.advance_ramp();
+            self.last_midpoint = Some(midpoint);
+        }
+        self.record_metrics_tick();
+        Ok(())
+    }
 
-        // Reconcile existing orders to detect fills
-        if !self.tracked_orders.is_empty() {
-            orders::reconcile_orders(clob_client, &mut self.tracked_orders).await?;
-            self.update_inventory_from_fills();
+    /// Fill paper quotes the midpoint has reached: bids at or above it buy
+    /// YES, asks at or below it sell YES held or else buy its NO mirror.
+    fn fill_paper_quotes(&mut self, midpoint: Decimal) {
+        let yes = self.market.token_yes_id.clone();
+        let mut quotes = std::mem::take(&mut self.current_quotes);
+        for quote in &mut quotes {
+            if quote.bid_size > Decimal::ZERO && midpoint <= quote.bid_price {
+                self.apply_fill(&yes, Side::Buy, quote.bid_price, quote.bid_size);
+                quote.bid_size = Decimal::ZERO;
+            }
+            if quote.ask_size > Decimal::ZERO && midpoint >= quote.ask_price {
+                self.fill_paper_ask(quote.ask_price, quote.ask_size);
+                quote.ask_size = Decimal::ZERO;
+            }
         }
+        self.current_quotes = quotes;
+    }
 
-        if !self.should_requote(midpoint) {
-            return Ok(());
+    /// As a paper ladder is replaced, fill each side the midpoint never
+    /// reached with the probability the fill curve gives for its distance
+    /// and size. Nothing happens until the curve is calibrated.
+    fn fill_paper_by_model(&mut self) {
+        if !self.fill_curve.is_calibrated() {
+            return;
+        }
+        let Some(mid) = self.quoted_midpoint else {
+            return;
+        };
+        let yes = self.market.token_yes_id.clone();
+        let mut quotes = std::mem::take(&mut self.current_quotes);
+        for quote in &mut quotes {
+            let (bid, ask) = (quote.bid_price, quote.ask_price);
+            if quote.bid_size > Decimal::ZERO
+                && self.paper_fill_drawn(quote.level, 4, mid - bid, quote.bid_size)
+            {
+                self.apply_fill(&yes, Side::Buy, bid, quote.bid_size);
+                quote.bid_size = Decimal::ZERO;
+            }
+            if quote.ask_size > Decimal::ZERO
+                && self.paper_fill_drawn(quote.level, 5, ask - mid, quote.ask_size)
+            {
+                self.fill_paper_ask(ask, quote.ask_size);
+                quote.ask_size = Decimal::ZERO;
+            }
         }
+        self.current_quotes = quotes;
+    }
 
-        // Cancel stale orders before requoting
-        let stale_ids: Vec<String> = self
-            .tracked_orders
-            .iter()
-            .filter(|o| o.status == OrderStatus::Open || o.status == OrderStatus::PartiallyFilled)
-            .map(|o| o.order_id.clone())
-            .collect();
+    /// Whether the fill curve's odds come up for one side of one level of
+    /// the current paper ladder.
+    fn paper_fill_drawn(&self, level: u32, side: u8, distance: Decimal, size: Decimal) -> bool {
+        let draw = quoter::draw(self.jitter_seed, self.quote_epoch, level, side);
+        Decimal::from(draw % 10_000) / dec!(10000) < self.fill_curve.probability(distance, size)
+    }
 
-        if !stale_ids.is_empty() {
-            orders::cancel_orders(clob_client, &stale_ids).await?;
+    /// A paper YES ask fills by selling YES held, or else buying the NO
+    /// mirror.
+    fn fill_paper_ask(&mut self, price: Decimal, size: Decimal) {
+        if self.inventory_yes >= size {
+            let yes = self.market.token_yes_id.clone();
+            self.apply_fill(&yes, Side::Sell, price, size);
+        } else {
+            let no = self.market.token_no_id.clone();
+            self.apply_fill(&no, Side::Buy, Decimal::ONE - price, size);
+        }
+    }
+
+    /// Feed the ladder being replaced into the fill curve: each order's
+    /// distance from the midpoint it was quoted at, its size, and whether
+    /// any of it filled. Unwind orders aren't quotes and are left out.
+    fn calibrate_fill_curve(&mut self) {
+        let Some(mid) = self.quoted_midpoint.filter(|_| self.exit_reason.is_none()) else {
+            return;
+        };
+        for order in &self.tracked_orders {
+            let yes_price = if order.token_id == self.market.token_yes_id {
+                order.price
+            } else {
+                Decimal::ONE - order.price
+            };
+            self.fill_curve
+                .observe(yes_price - mid, order.size, order.filled > Decimal::ZERO);
         }
+    }
 
-        // Generate and place new quotes
+    /// Compute and log the ladder a live requote would post.
+    pub fn requote_dry_run(&mut self, midpoint: Decimal) {
         let quotes = self.compute_quotes(midpoint);
+        self.log_dry_run_quotes(&quotes, midpoint);
+        self.record_band_positions(&quotes, midpoint);
+        self.current_quotes = quotes;
+        self.quoted_midpoint = Some(midpoint);
+        self.last_requote = Some(self.clock.now());
+        self.quote_epoch += 1;
+    }
 
-        let new_orders = orders::place_quotes(
-            clob_client,
-            signer,
-            &self.market.token_yes_id,
-            &self.market.token_no_id,
-            &quotes,
-        )
-        .await?;
+    /// Live tick: cancel stale orders, place new quotes, track fills.
+    #[instrument(
+        name = "tick",
+        skip_all,
+        fields(market = %self.market.question, condition_id = %self.market.condition_id)
+    )]
+    pub async fn tick_live(&mut self, exchange: &impl ExchangeApi) -> Result<()> {
+        if self.halted {
+            return Ok(());
+        }
+        self.check_flow();
+        let midpoint = self.fetch_midpoint(exchange).await?;
+        self.record_midpoint(midpoint);
+        self.adapt_poll_interval(midpoint);
 
-        self.tracked_orders = new_orders;
-        self.last_midpoint = Some(midpoint);
-        self.last_requote = Some(Instant::now());
-        self.current_quotes = quotes;
+        // Reconcile existing orders to detect fills
+        if !self.tracked_orders.is_empty() {
+            let previous: Vec<Decimal> = self.tracked_orders.iter().map(|o| o.filled).collect();
+            orders::reconcile_orders(exchange, &mut self.tracked_orders).await?;
+            self.update_inventory_from_fills(&previous);
+        }
+        self.check_trade_through(exchange).await;
+        if self.paused || self.warming_up() || !self.ready_to_enter(exchange, midpoint).await {
+            self.record_metrics_tick();
+            return Ok(());
+        }
 
-        Ok(())
+        let exit_fired = self.check_exit(midpoint);
+        let result = if exit_fired || self.should_requote(midpoint) {
+            self.requote(exchange, midpoint).await
+        } else {
+            Ok(())
+        };
+        if result.is_ok() && self.tracked_orders.iter().any(|o| o.is_active()) {
+            self.advance_ramp();
+        }
+        self.record_metrics_tick();
+        result
     }
 
-    /// Update inventory based on detected fills.
-    fn update_inventory_from_fills(&mut self) {
-        for order in &self.tracked_orders {
-            if order.filled <= Decimal::ZERO {
-                continue;
-            }
-            let is_yes = order.token_id == self.market.token_yes_id;
-            match order.side {
-                Side::Buy => {
-                    if is_yes {
-                        self.inventory_yes += order.filled;
-                        self.total_bought_value += order.filled * order.price;
-                    } else {
-                        self.inventory_no += order.filled;
-                        self.total_bought_value += order.filled * order.price;
-                    }
-                }
-                Side::Sell => {
-                    if is_yes {
-                        self.inventory_yes -= order.filled;
-                        self.total_sold_value += order.filled * order.price;
-                    } else {
-                        self.inventory_no -= order.filled;
-                        self.total_sold_value += order.filled * order.price;
-                    }
-                }
-                _ => {}
-            }
+    /// Whether the engine is still only watching the market: for
+    /// `warmup_secs` after its first tick, midpoints seed the volatility
+    /// estimate and no orders go out.
+    fn warming_up(&mut self) -> bool {
+        let warmup = Duration::from_secs(self.config.warmup_secs);
+        if warmup.is_zero() {
+            return false;
         }
+        let now = self.clock.now();
+        let started = *self.warmup_started.get_or_insert_with(|| {
+            info!(
+                market = %self.market.question,
+                secs = warmup.as_secs(),
+                "Warming up before first quotes"
+            );
+            now
+        });
+        now.saturating_duration_since(started) < warmup
     }
 
-    /// Handle a WebSocket event. Returns true if a requote should be triggered.
-    pub fn handle_ws_event(&mut self, event: WsEvent) -> bool {
-        match event {
-            WsEvent::MidpointUpdate { midpoint, .. } => {
-                let should = self.should_requote(midpoint);
+    /// Whether the book is deep enough to post a market's first orders
+    /// into. A one-sided or thin book would leave us as the only resting
+    /// liquidity, so entry is deferred and the book checked again after
+    /// `entry_retry_secs`. Markets already holding orders or inventory
+    /// are past entry.
+    async fn ready_to_enter(&mut self, exchange: &impl MarketData, midpoint: Decimal) -> bool {
+        if self.entered
+            || self.config.entry_min_depth.is_zero()
+            || self.exit_reason.is_some()
+            || !self.tracked_orders.is_empty()
+            || !self.inventory_yes.is_zero()
+            || !self.inventory_no.is_zero()
+        {
+            self.entered = true;
+            return true;
+        }
+        let now = self.clock.now();
+        if self.entry_retry_at.is_some_and(|at| now < at) {
+            return false;
+        }
+        let within = self.config.max_price_deviation_cents / dec!(100);
+        let problem = match exchange.order_book(&self.market.token_yes_id).await {
+            Ok(book) => book.entry_problem(midpoint, within, self.config.entry_min_depth),
+            Err(e) => Some(format!("order book unavailable: {e:#}")),
+        };
+        match problem {
+            None => {
+                info!(market = %self.market.question, "Book deep enough, entering market");
+                self.entered = true;
+                self.entry_retry_at = None;
+                true
+            }
+            Some(problem) => {
+                info!(
+                    market = %self.market.question,
+                    %problem,
+                    retry_secs = self.config.entry_retry_secs,
+                    "Deferring entry into thin book"
+                );
+                self.entry_retry_at = Some(now + Duration::from_secs(self.config.entry_retry_secs));
+                false
+            }
+        }
+    }
+
+    fn advance_ramp(&mut self) {
+        if self.ramp_ticks < self.config.warmup_ramp_ticks {
+            self.ramp_ticks += 1;
+        }
+    }
+
+    /// Fraction of `order_size` to quote: ramps from 1/`warmup_ramp_ticks`
+    /// on the first quoting tick to full size once that many have succeeded.
+    pub fn size_ramp(&self) -> Decimal {
+        let ramp = self.config.warmup_ramp_ticks;
+        if ramp == 0 {
+            return Decimal::ONE;
+        }
+        (Decimal::from(self.ramp_ticks + 1) / Decimal::from(ramp)).min(Decimal::ONE)
+    }
+
+    /// Compare recent order flow with this market's baseline. A placement or
+    /// cancel spike pauses requotes for the throttle period.
+    pub fn check_flow(&mut self) {
+        let now = self.clock.now();
+        let quoting = self.tracked_orders.iter().any(|o| o.is_active());
+        let Some(anomaly) = self.flow.check(&self.flow_limits, now, quoting) else {
+            return;
+        };
+        warn!(
+            market = %self.market.question,
+            anomaly = %anomaly,
+            throttled = anomaly.throttles(),
+            "Order flow anomaly"
+        );
+        if anomaly.throttles() {
+            self.throttled_until = Some(now + self.flow_limits.throttle);
+        }
+        self.journal(|db, id| db.record_risk_event(Some(id), "flow_anomaly", &anomaly.to_string()));
+        self.flow_anomaly = Some(anomaly);
+    }
+
+    /// Every `trade_through_check`, compare the market's last trade with the
+    /// quotes that were resting, counting prints through our price that
+    /// didn't fill us and flagging a run of them.
+    async fn check_trade_through(&mut self, exchange: &impl MarketData) {
+        let now = self.clock.now();
+        if !self
+            .trade_throughs
+            .due(now, self.flow_limits.trade_through_check)
+        {
+            return;
+        }
+        let print = match exchange.last_trade_price(&self.market.token_yes_id).await {
+            Ok(price) => price,
+            Err(e) => {
+                debug!(error = %e, "Last trade unavailable for trade-through check");
+                return;
+            }
+        };
+        let yes_id = &self.market.token_yes_id;
+        if self
+            .trade_throughs
+            .check(now, print, yes_id, &self.tracked_orders)
+        {
+            warn!(
+                market = %self.market.question,
+                print = %print,
+                "Market traded through our quote without filling it"
+            );
+            self.metrics.record_trade_through();
+        }
+        if let Some(anomaly) = self
+            .trade_throughs
+            .anomaly(now, self.flow_limits.trade_through_alerts)
+        {
+            warn!(market = %self.market.question, anomaly = %anomaly, "Order flow anomaly");
+            self.journal(|db, id| {
+                db.record_risk_event(Some(id), "flow_anomaly", &anomaly.to_string())
+            });
+            self.flow_anomaly = Some(anomaly);
+        }
+    }
+
+    /// Count a tick toward uptime, which requires resting orders, and
+    /// sample reward-band uptime.
+    pub fn record_metrics_tick(&mut self) {
+        let quoting = if self.dry_run {
+            !self.current_quotes.is_empty()
+        } else {
+            self.tracked_orders.iter().any(|o| o.is_active())
+        };
+        self.metrics.record_tick(quoting);
+        self.sample_band_uptime();
+    }
+
+    /// Credit the whole minutes since the last sample as in or out of the
+    /// reward band, based on the orders resting now. Gaps are capped so a
+    /// stalled loop isn't booked as hours of either state.
+    fn sample_band_uptime(&mut self) {
+        let now = self.clock.now();
+        let Some(last) = self.last_band_sample else {
+            self.last_band_sample = Some(now);
+            return;
+        };
+        let minutes = (self.clock.elapsed(last).as_secs() / 60).min(BAND_MAX_GAP_MINUTES);
+        if minutes == 0 {
+            return;
+        }
+        self.last_band_sample = Some(now);
+        let qualifying = self
+            .last_midpoint
+            .is_some_and(|mid| self.quotes_in_band(mid));
+        self.metrics.record_band_minutes(minutes, qualifying);
+        if let Some(midpoint) = self.last_midpoint {
+            let (bid, ask) = self.our_side_scores(midpoint);
+            self.metrics.record_score_minutes(
+                minutes,
+                quoter::two_sided_score(bid, ask),
+                self.competitor_score.unwrap_or(Decimal::ZERO),
+                self.market.reward_daily_estimate,
+            );
+        }
+    }
+
+    /// Reward score of our resting orders on the YES book's bid and ask
+    /// sides, with NO orders mirrored to their YES price.
+    pub fn our_side_scores(&self, midpoint: Decimal) -> (Decimal, Decimal) {
+        let (mut bid, mut ask) = (Decimal::ZERO, Decimal::ZERO);
+        for o in self.tracked_orders.iter().filter(|o| o.is_active()) {
+            let is_yes = o.token_id == self.market.token_yes_id;
+            let yes_price = if is_yes {
+                o.price
+            } else {
+                Decimal::ONE - o.price
+            };
+            let score = quoter::estimate_score(
+                midpoint,
+                yes_price,
+                o.size - o.filled,
+                self.market.rewards_max_spread,
+                self.market.rewards_min_size,
+            );
+            if is_yes == (o.side == Side::Buy) {
+                bid += score;
+            } else {
+                ask += score;
+            }
+        }
+        (bid, ask)
+    }
+
+    /// Whether resting orders earn rewards: at least `rewards_min_size` left
+    /// on both sides of the YES book within `rewards_max_spread` of `midpoint`.
+    pub fn quotes_in_band(&self, midpoint: Decimal) -> bool {
+        let min_size = self.market.rewards_min_size.unwrap_or(Decimal::ZERO);
+        let qualifies = |o: &&TrackedOrder| {
+            let yes_price = if o.token_id == self.market.token_yes_id {
+                o.price
+            } else {
+                Decimal::ONE - o.price
+            };
+            o.is_active()
+                && o.size - o.filled >= min_size
+                && self
+                    .market
+                    .rewards_max_spread
+                    .is_none_or(|band| quoter::in_reward_band(midpoint, yes_price, band))
+        };
+        // A NO bid rests on the YES ask side and vice versa
+        let is_bid =
+            |o: &TrackedOrder| (o.token_id == self.market.token_yes_id) == (o.side == Side::Buy);
+        let active: Vec<&TrackedOrder> = self.tracked_orders.iter().filter(qualifies).collect();
+        active.iter().any(|o| is_bid(o)) && active.iter().any(|o| !is_bid(o))
+    }
+
+    /// Record how much of the reward band in `book` (the YES book) belongs
+    /// to other makers. Skipped until there is a midpoint to center on.
+    pub fn sample_crowding(&mut self, book: &BookSnapshot) {
+        let Some(midpoint) = self.last_midpoint else {
+            return;
+        };
+        let band = self.market.rewards_max_spread.unwrap_or(dec!(0.05));
+        let min_size = self.market.rewards_min_size.unwrap_or(Decimal::ZERO);
+        let total = book.band_size(midpoint, band, min_size);
+        // Our NO orders rest in the YES book at the complementary price
+        let ours: Decimal = self
+            .tracked_orders
+            .iter()
+            .filter(|o| o.is_active() && o.size - o.filled >= min_size)
+            .filter(|o| {
+                let yes_price = if o.token_id == self.market.token_yes_id {
+                    o.price
+                } else {
+                    Decimal::ONE - o.price
+                };
+                quoter::in_reward_band(midpoint, yes_price, band)
+            })
+            .map(|o| o.size - o.filled)
+            .sum();
+        let competitor = (total - ours).max(Decimal::ZERO);
+        self.crowding.record(self.clock.now(), competitor, ours);
+
+        let max_spread = self.market.rewards_max_spread;
+        let min_size = self.market.rewards_min_size;
+        let (our_bid, our_ask) = self.our_side_scores(midpoint);
+        let their_bid = quoter::book_side_score(midpoint, &book.bids, max_spread, min_size);
+        let their_ask = quoter::book_side_score(midpoint, &book.asks, max_spread, min_size);
+        let competitor_score = quoter::two_sided_score(
+            (their_bid - our_bid).max(Decimal::ZERO),
+            (their_ask - our_ask).max(Decimal::ZERO),
+        );
+        self.competitor_score = Some(competitor_score);
+        debug!(
+            market = %self.market.question,
+            competitor = %competitor,
+            ours = %ours,
+            competitor_score = %competitor_score,
+            share = ?self.crowding.share(),
+            trend = ?self.crowding.share_trend(),
+            "Band crowding sampled"
+        );
+    }
+
+    /// Replace resting orders with fresh quotes around `midpoint`, or with
+    /// sell-only unwind orders once an exit threshold has fired.
+    #[instrument(
+        name = "requote",
+        skip_all,
+        fields(market = %self.market.question, condition_id = %self.market.condition_id)
+    )]
+    pub async fn requote(&mut self, exchange: &impl ExchangeApi, midpoint: Decimal) -> Result<()> {
+        let started = Instant::now();
+        let mut timing = RequoteTiming::default();
+        exchange.take_signing_time();
+        self.check_exit(midpoint);
+        self.sync_lots();
+
+        // Cancel stale orders before requoting
+        let stale_ids: Vec<String> = self
+            .tracked_orders
+            .iter()
+            .filter(|o| o.status == OrderStatus::Open || o.status == OrderStatus::PartiallyFilled)
+            .map(|o| o.order_id.clone())
+            .collect();
+        let cancel = async {
+            let cancel_started = Instant::now();
+            orders::cancel_orders(exchange, &stale_ids)
+                .await
+                .map(|_| cancel_started.elapsed())
+        };
+
+        if self.exit_reason.is_some()
+            && self.inventory_yes <= Decimal::ZERO
+            && self.inventory_no <= Decimal::ZERO
+        {
+            cancel.await?;
+            self.record_cancels(stale_ids.len());
+            info!(
+                market = %self.market.question,
+                reason = ?self.exit_reason,
+                "Unwind complete, halting market"
+            );
+            self.halted = true;
+            self.journal_cancels(&self.tracked_orders);
+            self.tracked_orders.clear();
+            self.current_quotes.clear();
+            self.last_midpoint = Some(midpoint);
+            return Ok(());
+        }
+
+        // Build the new set while the old one is being cancelled
+        let (cancelled, built) = tokio::join!(cancel, self.build_ladder(exchange, midpoint));
+        timing.cancelling = cancelled?;
+        self.record_cancels(stale_ids.len());
+        let (ladder, quotes) = built;
+        // Time spent waiting on the operator doesn't count against the budget
+        let mut reviewing = Duration::ZERO;
+        if let Some(mode) = self.confirm.filter(|_| self.exit_reason.is_none()) {
+            let review_started = Instant::now();
+            if !self.confirm_ladder(mode, midpoint, &ladder).await? {
+                warn!(market = %self.market.question, "Ladder rejected, halting market");
+                self.halted = true;
+                return Ok(());
+            }
+            self.confirm = None;
+            reviewing = review_started.elapsed();
+        }
+        let post_started = Instant::now();
+        let new_orders = orders::submit_orders(exchange, &ladder).await?;
+        timing.signing = exchange.take_signing_time();
+        timing.posting = post_started.elapsed().saturating_sub(timing.signing);
+        timing.total = started.elapsed().saturating_sub(reviewing);
+        self.record_requote_timing(timing);
+
+        self.metrics.record_orders(new_orders.len() as u64);
+        self.record_band_positions(&quotes, midpoint);
+        self.flow
+            .record(FlowKind::Placements, new_orders.len(), self.clock.now());
+        self.journal_cancels(&self.tracked_orders);
+        self.journal(|db, id| {
+            db.record_orders(id, &new_orders)?;
+            db.record_quotes(id, midpoint, &quotes)
+        });
+        self.calibrate_fill_curve();
+        self.tracked_orders = new_orders;
+        self.last_midpoint = Some(midpoint);
+        self.quoted_midpoint = Some(midpoint);
+        self.last_requote = Some(self.clock.now());
+        self.current_quotes = quotes;
+        self.quote_epoch += 1;
+
+        Ok(())
+    }
+
+    fn record_cancels(&mut self, count: usize) {
+        if count > 0 {
+            self.flow.record(FlowKind::Cancels, count, self.clock.now());
+        }
+    }
+
+    /// The orders to post next and the quotes behind them: the unwind set
+    /// while exiting, otherwise the quote ladder less what already filled.
+    async fn build_ladder(
+        &mut self,
+        exchange: &impl ExchangeApi,
+        midpoint: Decimal,
+    ) -> (Vec<NewOrder>, Vec<Quote>) {
+        self.sync_tick_size(exchange, midpoint).await;
+        let guard = PriceGuard {
+            best_bid: self.best_bid,
+            best_ask: self.best_ask,
+            ..PriceGuard::new(midpoint, self.config.max_price_deviation_cents / dec!(100))
+        };
+        let yes_id = self.market.token_yes_id.clone();
+        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        if self.exit_reason.is_some() {
+            self.level_fills.clear();
+            let unwind = orders::normalize_orders(self.unwind_orders(midpoint), tick_size);
+            return (guard.filter(unwind, &yes_id), Vec::new());
+        }
+
+        let quotes = self.quote_levels(midpoint);
+        let ladder = orders::quote_orders(&yes_id, &self.market.token_no_id, &quotes);
+        let ladder = self.level_fills.remaining(ladder);
+        let ladder = orders::normalize_orders(self.back_asks(ladder), tick_size);
+        let ladder = self.cap_token_notional(ladder);
+        (guard.filter(ladder, &yes_id), quotes)
+    }
+
+    /// Most resting buy notional per token: `max_token_notional` when set,
+    /// never more than the capital limit.
+    pub fn token_notional_limit(&self) -> Option<Decimal> {
+        let configured =
+            Some(self.config.max_token_notional).filter(|limit| *limit > Decimal::ZERO);
+        match (configured, self.capital_limit) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn cap_token_notional(&self, ladder: Vec<NewOrder>) -> Vec<NewOrder> {
+        let Some(limit) = self.token_notional_limit() else {
+            return ladder;
+        };
+        let wanted = orders::buy_notional(&ladder);
+        let capped = orders::cap_buy_notional(ladder, limit);
+        let trimmed = wanted - orders::buy_notional(&capped);
+        if trimmed > Decimal::ZERO {
+            debug!(
+                market = %self.market.question,
+                limit = %limit,
+                trimmed = %trimmed,
+                "Buys trimmed to per-token notional limit"
+            );
+        }
+        capped
+    }
+
+    /// Quote levels to post around `midpoint`: as many as fit the capital
+    /// limit, less any shed after over-budget requotes.
+    fn quote_levels(&self, midpoint: Decimal) -> Vec<Quote> {
+        let mut quotes = self.fit_to_capital(self.compute_quotes(midpoint));
+        quotes.truncate(
+            quotes
+                .len()
+                .saturating_sub(self.levels_shed as usize)
+                .max(1),
+        );
+        quotes
+    }
+
+    /// Sign the ladders a move of up to `presign_ticks` from the last quoted
+    /// midpoint would post, so the requote that follows can skip signing.
+    /// Meant for the idle time between ticks; failures only cost the cache.
+    pub async fn presign_next(&self, exchange: &impl ExchangeApi) {
+        let ticks = self.config.presign_ticks;
+        if ticks == 0 || self.dry_run || self.halted || self.paused || self.exit_reason.is_some() {
+            return;
+        }
+        let Some(last) = self.last_midpoint else {
+            return;
+        };
+        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        let mut ladders = Vec::new();
+        for step in 1..=i64::from(ticks) {
+            for offset in [-step, step] {
+                let midpoint = last + tick_size * Decimal::from(offset);
+                if midpoint <= Decimal::ZERO || midpoint >= Decimal::ONE {
+                    continue;
+                }
+                let quotes = self.quote_levels(midpoint);
+                let ladder = orders::quote_orders(
+                    &self.market.token_yes_id,
+                    &self.market.token_no_id,
+                    &quotes,
+                );
+                ladders.extend(orders::normalize_orders(ladder, tick_size));
+            }
+        }
+        match exchange.presign(&ladders).await {
+            Ok(signed) => debug!(market = %self.market.question, signed, "Pre-signed next ladders"),
+            Err(e) => debug!(market = %self.market.question, error = %e, "Pre-signing failed"),
+        }
+    }
+
+    /// Check a requote against `requote_budget_ms`, counting overruns and,
+    /// with `shed_levels_on_overrun`, quoting one level fewer after each
+    /// and one more after each requote back under budget.
+    fn record_requote_timing(&mut self, timing: RequoteTiming) {
+        let budget = Duration::from_millis(self.config.requote_budget_ms);
+        if !self.config.shed_levels_on_overrun {
+            self.levels_shed = 0;
+        }
+        if budget.is_zero() {
+            return;
+        }
+        if timing.total <= budget {
+            if self.levels_shed > 0 {
+                self.levels_shed -= 1;
+                debug!(
+                    market = %self.market.question,
+                    levels_shed = self.levels_shed,
+                    "Requote back under budget, restoring a level"
+                );
+            }
+            return;
+        }
+        self.metrics.record_requote_overrun();
+        if self.config.shed_levels_on_overrun && self.levels_shed + 1 < self.level_cap() {
+            self.levels_shed += 1;
+        }
+        warn!(
+            market = %self.market.question,
+            total_ms = timing.total.as_millis() as u64,
+            budget_ms = budget.as_millis() as u64,
+            cancelling_ms = timing.cancelling.as_millis() as u64,
+            signing_ms = timing.signing.as_millis() as u64,
+            posting_ms = timing.posting.as_millis() as u64,
+            levels_shed = self.levels_shed,
+            "Requote over latency budget"
+        );
+    }
+
+    /// With `backed_asks`, trim the ladder's sells to the tokens held plus
+    /// any queued split, and ask for a split when asks come up short.
+    fn back_asks(&mut self, ladder: Vec<NewOrder>) -> Vec<NewOrder> {
+        if !self.config.backed_asks {
+            return ladder;
+        }
+        let sells = |orders: &[NewOrder]| -> Decimal {
+            orders
+                .iter()
+                .filter(|o| o.side == Side::Sell)
+                .map(|o| o.size)
+                .sum()
+        };
+        let wanted = sells(&ladder);
+        let yes = (self.inventory_yes + self.pending_split).max(Decimal::ZERO);
+        let no = (self.inventory_no + self.pending_split).max(Decimal::ZERO);
+        let backed = orders::back_sells(
+            ladder,
+            &[
+                (&self.market.token_yes_id, yes),
+                (&self.market.token_no_id, no),
+            ],
+        );
+        let short = wanted - sells(&backed);
+        if short > Decimal::ZERO {
+            debug!(
+                market = %self.market.question,
+                yes = %yes,
+                no = %no,
+                short = %short,
+                "Asks trimmed to token holdings"
+            );
+            let amount = self.config.auto_split_amount;
+            if amount > Decimal::ZERO
+                && self.pending_split.is_zero()
+                && self.split_request.is_none()
+            {
+                info!(
+                    market = %self.market.question,
+                    amount = %amount,
+                    "Requesting CTF split to back asks"
+                );
+                self.split_request = Some(amount);
+            }
+        }
+        backed
+    }
+
+    /// Credit tokens from a completed CTF split: `amount` each of YES and
+    /// NO, bought for `amount` USDC.
+    pub fn apply_split(&mut self, amount: Decimal) {
+        self.pending_split = (self.pending_split - amount).max(Decimal::ZERO);
+        self.inventory_yes += amount;
+        self.inventory_no += amount;
+        self.total_bought_value += amount;
+        self.sync_lots();
+    }
+
+    /// Show the intended ladder and ask the operator to approve it.
+    async fn confirm_ladder(
+        &self,
+        mode: ConfirmMode,
+        midpoint: Decimal,
+        ladder: &[NewOrder],
+    ) -> Result<bool> {
+        println!(
+            "\n{} (midpoint {midpoint}, {} orders)\n{}",
+            self.market.question,
+            ladder.len(),
+            orders::format_ladder(&self.market.token_yes_id, ladder)
+        );
+        if mode == ConfirmMode::AutoYes {
+            return Ok(true);
+        }
+
+        let answer = tokio::task::spawn_blocking(|| {
+            use std::io::Write;
+            print!("Place these orders? [y/N] ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            Ok::<_, std::io::Error>(line)
+        })
+        .await??;
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+
+    /// Sell-only orders that work held inventory off at the inside of the book.
+    fn unwind_orders(&self, midpoint: Decimal) -> Vec<NewOrder> {
+        let tick_size = Decimal::from_str(&self.market.tick_size).unwrap_or(dec!(0.01));
+        let mut unwind = Vec::new();
+        if self.inventory_yes > Decimal::ZERO {
+            unwind.push(NewOrder {
+                token_id: self.market.token_yes_id.clone(),
+                side: Side::Sell,
+                price: quoter::unwind_ask(midpoint, tick_size),
+                size: self.inventory_yes,
+            });
+        }
+        if self.inventory_no > Decimal::ZERO {
+            unwind.push(NewOrder {
+                token_id: self.market.token_no_id.clone(),
+                side: Side::Sell,
+                price: quoter::unwind_ask(Decimal::ONE - midpoint, tick_size),
+                size: self.inventory_no,
+            });
+        }
+        unwind
+    }
+
+    /// Update inventory from fills detected since `previous` (each tracked
+    /// order's filled size before reconciling).
+    fn update_inventory_from_fills(&mut self, previous: &[Decimal]) {
+        let fills: Vec<(String, Side, Decimal, Decimal)> = self
+            .tracked_orders
+            .iter()
+            .zip(previous)
+            .filter(|(order, before)| order.filled > **before)
+            .map(|(order, before)| {
+                (
+                    order.token_id.clone(),
+                    order.side,
+                    order.price,
+                    order.filled - before,
+                )
+            })
+            .collect();
+        let filled: Vec<TrackedOrder> = self
+            .tracked_orders
+            .iter()
+            .zip(previous)
+            .filter(|(order, before)| order.filled > **before)
+            .map(|(order, _)| order.clone())
+            .collect();
+        self.journal(|db, id| db.record_orders(id, &filled));
+        for (token_id, side, price, size) in fills {
+            self.level_fills.record(&token_id, side, price, size);
+            self.apply_fill(&token_id, side, price, size);
+        }
+    }
+
+    /// Book a fill into inventory, traded value, and metrics. Spread capture
+    /// comes from the FIFO round trips the fill completes.
+    fn apply_fill(&mut self, token_id: &str, side: Side, price: Decimal, size: Decimal) {
+        let is_yes = token_id == self.market.token_yes_id;
+        match side {
+            Side::Buy => {
+                if is_yes {
+                    self.inventory_yes += size;
+                } else {
+                    self.inventory_no += size;
+                }
+                self.total_bought_value += size * price;
+            }
+            Side::Sell => {
+                if is_yes {
+                    self.inventory_yes -= size;
+                } else {
+                    self.inventory_no -= size;
+                }
+                self.total_sold_value += size * price;
+            }
+            _ => return,
+        }
+        let captured = self
+            .round_trips
+            .record(token_id, side == Side::Buy, price, size);
+        if !captured.is_zero() {
+            debug!(market = %self.market.question, captured = %captured, "Round trip closed");
+        }
+        self.metrics.record_fill(captured);
+        self.flow.record_fill(self.clock.now());
+        // Buying YES or selling NO leaves us longer YES
+        let yes_price = if is_yes { price } else { Decimal::ONE - price };
+        self.adverse
+            .record_fill(self.clock.now(), yes_price, is_yes == (side == Side::Buy));
+        let midpoint = self
+            .fills
+            .yes_midpoint()
+            .map(|mid| if is_yes { mid } else { Decimal::ONE - mid });
+        self.fills.push(FillNotice {
+            market: self.market.question.clone(),
+            outcome: if is_yes { "YES" } else { "NO" },
+            side,
+            price,
+            size,
+            midpoint,
+            inventory_yes: self.inventory_yes,
+            inventory_no: self.inventory_no,
+            skew: self.inventory_skew(),
+            at: self.clock.now(),
+            markout_cents: None,
+        });
+    }
+
+    /// Handle a WebSocket event. Returns true if a requote should be triggered.
+    #[instrument(
+        name = "ws_event",
+        skip_all,
+        fields(market = %self.market.question, condition_id = %self.market.condition_id)
+    )]
+    pub fn handle_ws_event(&mut self, event: WsEvent) -> bool {
+        match event {
+            WsEvent::MidpointUpdate { asset_id, midpoint } => {
+                self.record_midpoint(midpoint);
+                if asset_id == self.market.token_yes_id {
+                    self.midpoint_health.record_ws(self.clock.now(), midpoint);
+                }
+                let should = self.ws_anchored() && self.should_requote(midpoint);
                 if should {
                     self.last_midpoint = Some(midpoint);
                 }
                 should
             }
             WsEvent::BookUpdate {
-                best_bid, best_ask, ..
+                asset_id,
+                best_bid,
+                best_ask,
             } => {
+                let mut book_changed = false;
+                if asset_id == self.market.token_yes_id {
+                    self.best_bid = best_bid;
+                    self.best_ask = best_ask;
+                    book_changed = self.update_book_state();
+                }
                 if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
                     let mid = (bid + ask) / Decimal::TWO;
-                    let should = self.should_requote(mid);
+                    if asset_id == self.market.token_yes_id {
+                        self.midpoint_health.record_ws(self.clock.now(), mid);
+                    }
+                    let should = self.ws_anchored() && self.should_requote(mid);
                     if should {
                         self.last_midpoint = Some(mid);
                     }
-                    should
+                    should || book_changed
                 } else {
-                    false
+                    book_changed
                 }
             }
             WsEvent::OrderFill {
                 order_id,
                 size,
                 price,
+                fee_rate_bps,
+                is_maker,
             } => {
                 // Update the matching tracked order
                 if let Some(order) = self
@@ -303,25 +1982,24 @@ impl QuoteEngine {
                     );
 
                     // Update inventory immediately
-                    let is_yes = order.token_id == self.market.token_yes_id;
-                    match order.side {
-                        Side::Buy => {
-                            if is_yes {
-                                self.inventory_yes += size;
-                            } else {
-                                self.inventory_no += size;
-                            }
-                            self.total_bought_value += size * price;
-                        }
-                        Side::Sell => {
-                            if is_yes {
-                                self.inventory_yes -= size;
-                            } else {
-                                self.inventory_no -= size;
-                            }
-                            self.total_sold_value += size * price;
+                    let (token_id, side) = (order.token_id.clone(), order.side);
+                    self.level_fills.record(&token_id, side, order.price, size);
+                    self.apply_fill(&token_id, side, price, size);
+
+                    // Prefer the fee rate reported on the trade over the scanned market rate
+                    let fee_bps = fee_rate_bps
+                        .or_else(|| self.market.fee_rate_bps.map(Decimal::from))
+                        .unwrap_or(Decimal::ZERO);
+                    if fee_bps > Decimal::ZERO {
+                        if is_maker {
+                            let rebate = quoter::estimate_maker_rebate(price, size, fee_bps);
+                            self.metrics.record_rebate(rebate);
+                            debug!(rebate = %rebate, "Maker rebate accrued");
+                        } else {
+                            let fee = quoter::taker_fee(price, size, fee_bps);
+                            self.metrics.record_fee(fee);
+                            warn!(fee = %fee, order_id = %order_id, "Taker fee paid on crossing fill");
                         }
-                        _ => {}
                     }
                 }
                 false // Don't requote just because of a fill
@@ -329,7 +2007,11 @@ impl QuoteEngine {
             WsEvent::Disconnected => {
                 self.ws_connected = false;
                 info!("WS disconnected, falling back to REST polling");
-                false
+                // The book stops updating; better no crossing check than a
+                // stale one, and a collapsed book mustn't hold quoting off
+                self.best_bid = None;
+                self.best_ask = None;
+                self.update_book_state()
             }
             WsEvent::Reconnected => {
                 self.ws_connected = true;
@@ -340,10 +2022,12 @@ impl QuoteEngine {
     }
 
     /// Cancel all active orders for this market.
-    pub async fn cancel_all(
-        &mut self,
-        clob_client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-    ) -> Result<()> {
+    #[instrument(
+        name = "cancel_all",
+        skip_all,
+        fields(market = %self.market.question, condition_id = %self.market.condition_id)
+    )]
+    pub async fn cancel_all(&mut self, exchange: &impl ExchangeApi) -> Result<()> {
         let active_ids: Vec<String> = self
             .tracked_orders
             .iter()
@@ -352,9 +2036,10 @@ impl QuoteEngine {
             .collect();
 
         if !active_ids.is_empty() {
-            orders::cancel_orders(clob_client, &active_ids).await?;
+            orders::cancel_orders(exchange, &active_ids).await?;
         }
 
+        self.journal_cancels(&self.tracked_orders);
         self.tracked_orders.clear();
         info!(
             market = %self.market.question,
@@ -363,6 +2048,64 @@ impl QuoteEngine {
         Ok(())
     }
 
+    /// Active orders and the midpoint they were quoted at, to leave resting
+    /// across a restart.
+    pub fn retained_orders(&self) -> RetainedMarket {
+        RetainedMarket {
+            last_midpoint: self.last_midpoint,
+            orders: self
+                .tracked_orders
+                .iter()
+                .filter(|o| o.is_active())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Take over orders a previous run left resting. Their fill state is
+    /// refreshed first and fills made while the bot was down are not booked
+    /// again (inventory comes from on-chain reconciliation); the requote
+    /// timer restarts so the orders aren't replaced on the first tick.
+    pub async fn adopt_orders(
+        &mut self,
+        exchange: &impl ExchangeApi,
+        retained: RetainedMarket,
+    ) -> Result<()> {
+        let mut adopted = retained.orders;
+        orders::reconcile_orders(exchange, &mut adopted).await?;
+        adopted.retain(|o| o.is_active());
+        info!(
+            market = %self.market.question,
+            orders = adopted.len(),
+            "Adopted orders left resting by the previous run"
+        );
+        if !adopted.is_empty() {
+            self.last_midpoint = retained.last_midpoint;
+            self.last_requote = Some(self.clock.now());
+        }
+        self.tracked_orders = adopted;
+        Ok(())
+    }
+
+    /// Log where each level sits in the reward band and add its distance
+    /// from the midpoint to the market's quote-distance histogram.
+    fn record_band_positions(&mut self, quotes: &[Quote], midpoint: Decimal) {
+        let band = self.market.rewards_max_spread;
+        let depth = |price| band.and_then(|b| quoter::band_depth_pct(midpoint, price, b));
+        for q in quotes {
+            debug!(
+                market = %self.market.question,
+                level = q.level,
+                bid_band_pct = ?depth(q.bid_price).map(|p| p.round()),
+                ask_band_pct = ?depth(q.ask_price).map(|p| p.round()),
+                "Level position in reward band"
+            );
+            self.metrics.record_quote_distance(midpoint - q.bid_price);
+            self.metrics.record_quote_distance(q.ask_price - midpoint);
+        }
+        self.metrics.band_cents = band.map(|b| b * dec!(100));
+    }
+
     pub fn log_dry_run_quotes(&self, quotes: &[Quote], midpoint: Decimal) {
         info!(
             market = %self.market.question,
@@ -375,10 +2118,673 @@ impl QuoteEngine {
                 level = q.level,
                 bid = %q.bid_price,
                 ask = %q.ask_price,
-                size = %q.size,
+                bid_size = %q.bid_size,
+                ask_size = %q.ask_size,
                 spread = %(q.ask_price - q.bid_price),
                 "[DRY-RUN] Quote"
             );
         }
     }
 }
+
+/// The `status` dashboard built from running engines rather than the
+/// metrics file, plus the markets not quoting and why.
+pub fn status_snapshot<'a>(engines: impl IntoIterator<Item = &'a QuoteEngine>) -> String {
+    let mut portfolio = PortfolioMetrics::new();
+    let mut market_data = Vec::new();
+    let mut idle = Vec::new();
+    for engine in engines {
+        let snapshot = engine.final_metrics();
+        portfolio.session_start = portfolio.session_start.min(snapshot.start_time);
+        portfolio
+            .markets
+            .insert(engine.market.condition_id.clone(), snapshot);
+        market_data.push((
+            engine.market.question.clone(),
+            engine.last_midpoint.unwrap_or(Decimal::ZERO),
+            engine.inventory_yes - engine.inventory_no,
+            engine
+                .tracked_orders
+                .iter()
+                .filter(|o| o.is_active())
+                .count(),
+        ));
+        let state = if engine.halted {
+            "halted"
+        } else if engine.paused {
+            "paused"
+        } else if engine.exit_reason.is_some() {
+            "unwinding"
+        } else {
+            continue;
+        };
+        idle.push(format!("{}: {state}\n", engine.market.question));
+    }
+
+    let mut out = metrics::format_dashboard(&portfolio, &market_data);
+    if !idle.is_empty() {
+        out.push_str("\n--- Not Quoting ---\n");
+        out.extend(idle);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimClock};
+    use crate::exchange::mock::{MockExchange, Op};
+    use std::sync::Arc;
+
+    fn test_market() -> MarketInfo {
+        MarketInfo {
+            condition_id: "cond".into(),
+            question: "Test market".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(10000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: Some(dec!(0.03)),
+            end_date: None,
+            score: dec!(100),
+        }
+    }
+
+    #[test]
+    fn test_requote_timer_uses_clock() {
+        let sim = Arc::new(SimClock::new());
+        let mut engine = QuoteEngine::new(test_market(), StrategyConfig::default(), true)
+            .with_clock(sim.clone());
+        assert!(engine.should_requote(dec!(0.50))); // first quote
+
+        engine.last_midpoint = Some(dec!(0.50));
+        engine.last_requote = Some(sim.now());
+        assert!(!engine.should_requote(dec!(0.50)));
+
+        sim.advance(Duration::from_secs(engine.config.requote_interval_secs + 1));
+        assert!(engine.should_requote(dec!(0.50)));
+    }
+
+    fn live_engine(sim: &Arc<SimClock>) -> QuoteEngine {
+        let config = StrategyConfig {
+            num_levels: 1,
+            order_size: dec!(100),
+            ..StrategyConfig::default()
+        };
+        QuoteEngine::new(test_market(), config, false).with_clock(sim.clone())
+    }
+
+    #[tokio::test]
+    async fn test_tick_live_places_and_requotes() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.tracked_orders.len(), 4);
+        let first_ids: Vec<String> =
+            engine.tracked_orders.iter().map(|o| o.order_id.clone()).collect();
+
+        // Fill the YES bid, then let the requote timer expire
+        let yes_bid = exchange.open_ids("1", Side::Buy)[0].clone();
+        exchange.fill(&yes_bid, dec!(100));
+        sim.advance(Duration::from_secs(engine.config.requote_interval_secs + 1));
+        engine.tick_live(&exchange).await.unwrap();
+
+        assert_eq!(engine.inventory_yes, dec!(100));
+        assert_eq!(engine.tracked_orders.len(), 4);
+        // The three unfilled orders were cancelled before requoting
+        let cancelled = exchange.state().cancelled.clone();
+        assert_eq!(cancelled.len(), 3);
+        assert!(cancelled.iter().all(|id| first_ids.contains(id)));
+    }
+
+    #[tokio::test]
+    async fn test_partial_fills_counted_once_in_metrics() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.metrics.total_orders, 4);
+        let yes_bid = exchange.open_ids("1", Side::Buy)[0].clone();
+
+        // The same partial fill is seen on two ticks but booked once
+        exchange.fill(&yes_bid, dec!(40));
+        engine.tick_live(&exchange).await.unwrap();
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.inventory_yes, dec!(40));
+        assert_eq!(engine.metrics.total_fills, 1);
+        assert_eq!(engine.round_trips.open_size("1"), dec!(40));
+        assert_eq!(engine.metrics.total_ticks, 3);
+        assert_eq!(engine.metrics.uptime_ticks, 3);
+    }
+
+    #[tokio::test]
+    async fn test_requote_posts_remainder_of_partially_filled_level() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        let yes_bid_size = |engine: &QuoteEngine| {
+            engine
+                .tracked_orders
+                .iter()
+                .find(|o| o.token_id == "1" && o.side == Side::Buy)
+                .map(|o| o.size)
+        };
+        let requote = Duration::from_secs(engine.config.requote_interval_secs + 1);
+
+        engine.tick_live(&exchange).await.unwrap();
+        let yes_bid = exchange.open_ids("1", Side::Buy)[0].clone();
+        exchange.fill(&yes_bid, dec!(40));
+        sim.advance(requote);
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(yes_bid_size(&engine), Some(dec!(60)));
+
+        // Filling the remainder completes the level; the next cycle is full size
+        let yes_bid = exchange.open_ids("1", Side::Buy)[0].clone();
+        exchange.fill(&yes_bid, dec!(60));
+        sim.advance(requote);
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.inventory_yes, dec!(100));
+        assert_eq!(yes_bid_size(&engine), Some(dec!(100)));
+    }
+
+    #[tokio::test]
+    async fn test_band_uptime_needs_both_sides() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.quotes_in_band(dec!(0.50)));
+        sim.advance(Duration::from_secs(120));
+        engine.record_metrics_tick();
+        assert_eq!(engine.metrics.band_minutes, 2);
+        assert_eq!(engine.metrics.band_minutes_qualifying, 2);
+
+        // With only YES-side bids resting, the quote is one-sided
+        engine
+            .tracked_orders
+            .retain(|o| (o.token_id == "1") == (o.side == Side::Buy));
+        assert!(!engine.quotes_in_band(dec!(0.50)));
+        sim.advance(Duration::from_secs(60));
+        engine.record_metrics_tick();
+        assert_eq!(engine.metrics.band_minutes, 3);
+        assert_eq!(engine.metrics.band_minutes_qualifying, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reward_score_projects_share_of_pool() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.tick_live(&exchange).await.unwrap();
+        engine.record_metrics_tick();
+
+        // Another maker mirrors our resting size, so the book holds twice ours
+        let mut book = BookSnapshot::default();
+        for o in &engine.tracked_orders {
+            let is_yes = o.token_id == "1";
+            let price = if is_yes {
+                o.price
+            } else {
+                Decimal::ONE - o.price
+            };
+            let side = if is_yes == (o.side == Side::Buy) {
+                &mut book.bids
+            } else {
+                &mut book.asks
+            };
+            side.push((price, o.size * dec!(2)));
+        }
+        engine.sample_crowding(&book);
+        sim.advance(Duration::from_secs(180));
+        engine.record_metrics_tick();
+
+        assert!(engine.metrics.score_today > Decimal::ZERO);
+        assert_eq!(engine.metrics.score_share_today(), dec!(0.5));
+        assert_eq!(engine.metrics.projected_reward_today(), dec!(5));
+    }
+
+    #[test]
+    fn test_crossed_book_suspends_quotes_until_normal() {
+        let mut engine = QuoteEngine::new(test_market(), StrategyConfig::default(), true);
+        let book = |bid, ask| WsEvent::BookUpdate {
+            asset_id: "1".into(),
+            best_bid: Some(bid),
+            best_ask: Some(ask),
+        };
+        assert!(!engine.compute_quotes(dec!(0.50)).is_empty());
+
+        assert!(engine.handle_ws_event(book(dec!(0.51), dec!(0.50))));
+        assert_eq!(engine.book_state, BookState::Crossed);
+        assert!(engine.compute_quotes(dec!(0.50)).is_empty());
+        engine.handle_ws_event(book(dec!(0.50), dec!(0.51)));
+        assert_eq!(engine.book_state, BookState::Collapsed);
+        assert!(engine.compute_quotes(dec!(0.50)).is_empty());
+
+        assert!(engine.handle_ws_event(book(dec!(0.47), dec!(0.53))));
+        assert_eq!(engine.book_state, BookState::Normal);
+        assert!(!engine.compute_quotes(dec!(0.50)).is_empty());
+
+        // A WS drop forgets the collapsed book instead of staying suspended
+        engine.handle_ws_event(book(dec!(0.50), dec!(0.51)));
+        assert!(engine.handle_ws_event(WsEvent::Disconnected));
+        assert_eq!(engine.book_state, BookState::Normal);
+
+        // Widening keeps quoting, further out
+        let normal = engine.compute_quotes(dec!(0.50));
+        engine.config.collapse_policy = CollapsePolicy::Widen;
+        engine.handle_ws_event(book(dec!(0.51), dec!(0.50)));
+        let widened = engine.compute_quotes(dec!(0.50));
+        assert!(widened[0].bid_price < normal[0].bid_price);
+    }
+
+    #[tokio::test]
+    async fn test_tick_live_midpoint_failure_places_nothing() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        exchange.fail(Op::Midpoint);
+        let mut engine = live_engine(&sim);
+
+        assert!(engine.tick_live(&exchange).await.is_err());
+        assert!(engine.tracked_orders.is_empty());
+        assert!(exchange.state().post_batches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_midpoint_chain_fails_over_and_back() {
+        let sim = Arc::new(SimClock::new());
+        let config = StrategyConfig {
+            midpoint_sources: vec![
+                MidpointSource::Ws,
+                MidpointSource::Rest,
+                MidpointSource::LastTrade,
+            ],
+            ..StrategyConfig::default()
+        };
+        let mut engine = QuoteEngine::new(test_market(), config, false).with_clock(sim.clone());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        exchange.state().last_trades.insert("1".into(), dec!(0.47));
+
+        // Fresh WS midpoint anchors; a stale one falls through to REST
+        engine.ws_connected = true;
+        engine.midpoint_health.record_ws(sim.now(), dec!(0.52));
+        assert!(engine.ws_anchored());
+        assert_eq!(engine.fetch_midpoint(&exchange).await.unwrap(), dec!(0.52));
+        sim.advance(Duration::from_secs(engine.config.midpoint_stale_secs + 1));
+        assert!(!engine.ws_anchored());
+        assert_eq!(engine.fetch_midpoint(&exchange).await.unwrap(), dec!(0.50));
+
+        // REST failing drops to the last trade until its retry wait is over
+        exchange.fail(Op::Midpoint);
+        assert_eq!(engine.fetch_midpoint(&exchange).await.unwrap(), dec!(0.47));
+        exchange.heal(Op::Midpoint);
+        assert_eq!(engine.fetch_midpoint(&exchange).await.unwrap(), dec!(0.47));
+        sim.advance(Duration::from_secs(engine.config.midpoint_retry_secs));
+        assert_eq!(engine.fetch_midpoint(&exchange).await.unwrap(), dec!(0.50));
+        assert_eq!(engine.midpoint_health.current, Some(MidpointSource::Rest));
+    }
+
+    #[tokio::test]
+    async fn test_poll_interval_tightens_while_moving() {
+        let mut engine = QuoteEngine::new(test_market(), StrategyConfig::default(), true);
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let slowest = Duration::from_secs(engine.config.rest_poll_interval_secs);
+        engine.tick_dry_run(&exchange).await.unwrap();
+        assert_eq!(engine.poll_interval(), slowest);
+
+        // Each move of at least half the requote threshold halves the wait
+        for mid in [dec!(0.52), dec!(0.54), dec!(0.56), dec!(0.58)] {
+            exchange.state().midpoints.insert("1".into(), mid);
+            engine.tick_dry_run(&exchange).await.unwrap();
+        }
+        assert_eq!(
+            engine.poll_interval(),
+            Duration::from_secs(engine.config.rest_poll_min_secs)
+        );
+
+        // Then eases back once the market is quiet
+        for _ in 0..4 {
+            engine.tick_dry_run(&exchange).await.unwrap();
+        }
+        assert_eq!(engine.poll_interval(), slowest);
+    }
+
+    #[tokio::test]
+    async fn test_entry_deferred_until_book_has_depth() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.entry_min_depth = dec!(200);
+        let thin = BookSnapshot {
+            bids: vec![(dec!(0.49), dec!(500))],
+            asks: vec![],
+        };
+        exchange.state().books.insert("1".into(), thin);
+
+        // One-sided book: no orders, and no re-check until the retry is due
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.tracked_orders.is_empty());
+        let deep = BookSnapshot {
+            bids: vec![(dec!(0.49), dec!(500))],
+            asks: vec![(dec!(0.51), dec!(300))],
+        };
+        exchange.state().books.insert("1".into(), deep);
+        sim.advance(Duration::from_secs(engine.config.entry_retry_secs - 1));
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.tracked_orders.is_empty());
+
+        sim.advance(Duration::from_secs(1));
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.entered);
+        assert_eq!(engine.tracked_orders.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_paper_quotes_fill_when_midpoint_crosses() {
+        let mut engine = QuoteEngine::new(test_market(), StrategyConfig::default(), true);
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        engine.tick_paper(&exchange).await.unwrap();
+        let bid = engine.current_quotes[0].bid_price;
+        let crossed: Vec<&Quote> = engine
+            .current_quotes
+            .iter()
+            .filter(|q| q.bid_price >= bid)
+            .collect();
+        let size: Decimal = crossed.iter().map(|q| q.bid_size).sum();
+        let fills = crossed.len() as u64;
+        assert_eq!(engine.inventory_yes, Decimal::ZERO);
+
+        // Midpoint drops to our inner bid: bought YES there, nothing else
+        exchange.state().midpoints.insert("1".into(), bid);
+        engine.tick_paper(&exchange).await.unwrap();
+        assert_eq!(engine.inventory_yes, size);
+        assert_eq!(engine.inventory_no, Decimal::ZERO);
+        assert_eq!(engine.total_bought_value, bid * size);
+        assert_eq!(engine.metrics.total_fills, fills);
+        assert!(engine.tracked_orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deadman_orders_replaced_at_half_life() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim).with_deadman(120);
+        engine.config.requote_interval_secs = 3600;
+        engine.tick_live(&exchange).await.unwrap();
+
+        // Quiet market: nothing to requote for until the orders near expiry
+        sim.advance(Duration::from_secs(59));
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 1);
+        assert!(!engine.deadman_refresh_due());
+
+        sim.advance(Duration::from_secs(1));
+        assert!(engine.deadman_refresh_due());
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 2);
+        assert!(!engine.deadman_refresh_due());
+    }
+
+    #[tokio::test]
+    async fn test_tick_live_cancel_failure_keeps_tracked_orders() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.tick_live(&exchange).await.unwrap();
+
+        exchange.fail(Op::Cancel);
+        exchange.state().midpoints.insert("1".into(), dec!(0.55));
+        assert!(engine.tick_live(&exchange).await.is_err());
+        // No new quotes stacked on top of orders we couldn't cancel
+        assert_eq!(exchange.state().post_batches.len(), 1);
+        assert_eq!(engine.tracked_orders.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_tick_size_fetch_failure_falls_back_to_regime() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.05));
+        exchange.fail(Op::TickSize);
+        let mut engine = live_engine(&sim);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.market.tick_size, "0.001");
+        assert!(!engine.tracked_orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_loss_switches_to_unwind() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.40));
+        let mut engine = live_engine(&sim).with_exit_limits(ExitLimits {
+            stop_loss: dec!(20),
+            take_profit: Decimal::ZERO,
+        });
+        // Bought 200 YES at 0.50; worth 0.40 now -> -$20
+        engine.inventory_yes = dec!(200);
+        engine.total_bought_value = dec!(100);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.exit_reason, Some(ExitReason::StopLoss));
+        assert_eq!(engine.metrics.exit_reason, Some(ExitReason::StopLoss));
+        assert!(engine.current_quotes.is_empty());
+        assert_eq!(engine.tracked_orders.len(), 1);
+        let order = &engine.tracked_orders[0];
+        assert_eq!(order.side, Side::Sell);
+        assert_eq!(order.token_id, "1");
+        assert_eq!(order.price, dec!(0.40));
+        assert_eq!(order.size, dec!(200));
+
+        // Once flat, the market halts instead of requoting
+        engine.inventory_yes = Decimal::ZERO;
+        engine.requote(&exchange, dec!(0.40)).await.unwrap();
+        assert!(engine.halted);
+        assert!(engine.tracked_orders.is_empty());
+        assert_eq!(exchange.state().post_batches.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_inventory_skews_then_unwinds() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.max_inventory_age_secs = 3600;
+        engine.config.inventory_cap = Decimal::ZERO; // isolate the age skew
+        engine.market.tick_size = "0.001".into();
+        engine.inventory_yes = dec!(100);
+        engine.total_bought_value = dec!(50);
+        engine.sync_lots();
+
+        let fresh = engine.compute_quotes(dec!(0.50));
+        sim.advance(Duration::from_secs(5400));
+        let aged = engine.compute_quotes(dec!(0.50));
+        // Aged YES: bid backs off, ask moves toward mid
+        assert!(aged[0].bid_price <= fresh[0].bid_price);
+        assert!(aged[0].ask_price < fresh[0].ask_price);
+
+        sim.advance(Duration::from_secs(1800));
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.exit_reason, Some(ExitReason::InventoryAge));
+        assert!(engine.tracked_orders.iter().all(|o| o.side == Side::Sell));
+    }
+
+    #[tokio::test]
+    async fn test_capital_limit_drops_outer_levels() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.num_levels = 3;
+        // Each level locks ~100 USDC of buys; 50 is already in inventory
+        engine.total_bought_value = dec!(50);
+        engine.capital_limit = Some(dec!(260));
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.current_quotes.len(), 2);
+        assert!(engine.committed_capital() <= dec!(260));
+
+        // No room at all: nothing is posted
+        engine.capital_limit = Some(dec!(40));
+        engine.requote(&exchange, dec!(0.50)).await.unwrap();
+        assert!(engine.current_quotes.is_empty());
+        assert!(engine.tracked_orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backed_asks_trim_and_request_split() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.backed_asks = true;
+        engine.config.auto_split_amount = dec!(200);
+        engine.inventory_yes = dec!(60);
+        engine.total_bought_value = dec!(30);
+
+        // 60 YES backs part of the YES ask; no NO means no NO ask
+        engine.tick_live(&exchange).await.unwrap();
+        let sells: Vec<(String, Decimal)> = engine
+            .tracked_orders
+            .iter()
+            .filter(|o| o.side == Side::Sell)
+            .map(|o| (o.token_id.clone(), o.size))
+            .collect();
+        assert_eq!(sells, vec![("1".to_string(), dec!(60))]);
+        assert_eq!(engine.split_request, Some(dec!(200)));
+
+        // Once queued, the split backs full asks before it lands
+        engine.split_request = None;
+        engine.pending_split = dec!(200);
+        engine.requote(&exchange, dec!(0.50)).await.unwrap();
+        assert_eq!(engine.tracked_orders.len(), 4);
+        assert_eq!(engine.split_request, None);
+
+        engine.apply_split(dec!(200));
+        assert_eq!(engine.pending_split, Decimal::ZERO);
+        assert_eq!(
+            (engine.inventory_yes, engine.inventory_no),
+            (dec!(260), dec!(200))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requote_after_one_tick_move_posts_presigned_orders() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.presign_ticks = 2;
+
+        engine.tick_live(&exchange).await.unwrap();
+        engine.presign_next(&exchange).await;
+        // Four ladders (±1, ±2 ticks) of up to four orders, some shared
+        let presigned = exchange.state().presigned.len();
+        assert!(presigned > 4 && presigned <= 16, "{presigned}");
+
+        engine.requote(&exchange, dec!(0.51)).await.unwrap();
+        assert_eq!(exchange.state().presign_hits, 4);
+    }
+
+    #[tokio::test]
+    async fn test_requote_overruns_shed_and_restore_levels() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.num_levels = 3;
+        engine.config.shed_levels_on_overrun = true;
+        let took = |ms| RequoteTiming {
+            total: Duration::from_millis(ms),
+            ..RequoteTiming::default()
+        };
+
+        // Shedding stops at one level
+        for _ in 0..3 {
+            engine.record_requote_timing(took(1500));
+        }
+        assert_eq!(engine.metrics.requote_overruns, 3);
+        assert_eq!(engine.levels_shed, 2);
+        engine.requote(&exchange, dec!(0.50)).await.unwrap();
+        assert_eq!(engine.current_quotes.len(), 1);
+
+        // The mock answers instantly, so that requote was under budget
+        assert_eq!(engine.levels_shed, 1);
+        engine.record_requote_timing(took(100));
+        assert_eq!(engine.levels_shed, 0);
+        assert_eq!(engine.metrics.requote_overruns, 3);
+    }
+
+    #[tokio::test]
+    async fn test_seed_volatility_from_price_history() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new();
+        let now = chrono::Utc::now().timestamp();
+        exchange.state().price_history.insert(
+            "1".into(),
+            (0..10)
+                .map(|i| (now - 600 + i * 60, dec!(0.50) + Decimal::new(i % 2, 2)))
+                .collect(),
+        );
+        let mut engine = live_engine(&sim);
+        engine.seed_volatility(&exchange).await;
+        assert_eq!(engine.midpoint_samples.len(), 10);
+        assert!(engine.metrics.daily_volatility.is_some());
+
+        // Live samples carry on from the seeded ones
+        engine.record_midpoint(dec!(0.50));
+        assert_eq!(engine.midpoint_samples.len(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_then_size_ramp() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.config.warmup_secs = 120;
+        engine.config.warmup_ramp_ticks = 4;
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.tracked_orders.is_empty());
+        assert_eq!(engine.midpoint_samples.len(), 1);
+
+        // First quotes go out at a quarter size, then step up
+        sim.advance(Duration::from_secs(121));
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.tracked_orders.iter().all(|o| o.size == dec!(25)));
+        sim.advance(Duration::from_secs(engine.config.requote_interval_secs + 1));
+        engine.tick_live(&exchange).await.unwrap();
+        assert!(engine.tracked_orders.iter().all(|o| o.size == dec!(50)));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_only_gates_first_requote() {
+        let sim = Arc::new(SimClock::new());
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let mut engine = live_engine(&sim);
+        engine.confirm = Some(ConfirmMode::AutoYes);
+
+        engine.tick_live(&exchange).await.unwrap();
+        assert_eq!(engine.confirm, None);
+        assert_eq!(engine.tracked_orders.len(), 4);
+    }
+
+    #[test]
+    fn test_ws_disconnect_forgets_top_of_book() {
+        let mut engine = QuoteEngine::new(test_market(), StrategyConfig::default(), false);
+        engine.handle_ws_event(WsEvent::BookUpdate {
+            asset_id: "1".into(),
+            best_bid: Some(dec!(0.48)),
+            best_ask: Some(dec!(0.52)),
+        });
+        assert_eq!(engine.best_bid, Some(dec!(0.48)));
+
+        engine.handle_ws_event(WsEvent::Disconnected);
+        assert_eq!((engine.best_bid, engine.best_ask), (None, None));
+    }
+}
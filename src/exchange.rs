@@ -0,0 +1,1045 @@
+use anyhow::{Context, Result};
+use futures::{StreamExt, TryStreamExt, stream};
+use polymarket_client_sdk::auth;
+use polymarket_client_sdk::auth::Signer;
+use polymarket_client_sdk::clob::types::request::{
+    BalanceAllowanceRequest, LastTradePriceRequest, MidpointRequest, OrderBookSummaryRequest,
+    PriceHistoryRequest,
+};
+use polymarket_client_sdk::clob;
+use polymarket_client_sdk::clob::types::{
+    AssetType, OrderType, Side, SignatureType, SignedOrder, TimeRange,
+};
+use polymarket_client_sdk::types::U256;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::alerts::{self, Severity};
+use crate::client::{self, AuthRecovery, CredentialMode, Timed};
+use crate::clock::ClockSkew;
+use crate::config::Config;
+use crate::deadman;
+use crate::orders;
+use crate::quoter;
+
+/// Most orders held signed ahead of time, across all markets.
+const PRESIGN_CACHE_MAX: usize = 500;
+
+/// Orders signed at once; signing is CPU-bound and deep ladders need
+/// dozens of signatures per requote.
+const SIGNING_CONCURRENCY: usize = 8;
+
+/// A limit order to build, sign, and post.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NewOrder {
+    pub token_id: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+/// Exchange response for one posted order.
+#[derive(Debug, Clone)]
+pub struct PostResult {
+    pub order_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Fill progress of an order on the exchange.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderState {
+    pub original_size: Decimal,
+    pub size_matched: Decimal,
+}
+
+/// An order resting on the exchange for this wallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrder {
+    pub order_id: String,
+    /// Condition ID of the order's market
+    pub market: String,
+    pub token_id: String,
+}
+
+/// Order book levels as (price, size) pairs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookSnapshot {
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+impl BookSnapshot {
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.iter().map(|(price, _)| *price).max()
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.iter().map(|(price, _)| *price).min()
+    }
+
+    /// Resting size on both sides within `max_spread` of `midpoint`,
+    /// counting only levels of at least `min_size`.
+    pub fn band_size(&self, midpoint: Decimal, max_spread: Decimal, min_size: Decimal) -> Decimal {
+        self.bids
+            .iter()
+            .chain(&self.asks)
+            .filter(|(price, size)| {
+                *size >= min_size && quoter::in_reward_band(midpoint, *price, max_spread)
+            })
+            .map(|(_, size)| *size)
+            .sum()
+    }
+
+    /// Why this book is too thin to start quoting into at `midpoint`: a
+    /// missing side, a midpoint outside the touch, or less than `min_depth`
+    /// resting within `within` of the midpoint on either side.
+    pub fn entry_problem(
+        &self,
+        midpoint: Decimal,
+        within: Decimal,
+        min_depth: Decimal,
+    ) -> Option<String> {
+        let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) else {
+            return Some("one-sided book".into());
+        };
+        if midpoint < bid || midpoint > ask {
+            return Some(format!("midpoint {midpoint} outside {bid}/{ask}"));
+        }
+        let depth = |levels: &[(Decimal, Decimal)]| -> Decimal {
+            levels
+                .iter()
+                .filter(|(price, _)| (*price - midpoint).abs() <= within)
+                .map(|(_, size)| *size)
+                .sum()
+        };
+        let (bids, asks) = (depth(&self.bids), depth(&self.asks));
+        (bids < min_depth || asks < min_depth)
+            .then(|| format!("depth {bids} bid / {asks} ask, need {min_depth} each"))
+    }
+}
+
+/// Read-only market data. Implemented directly by the CLOB client in any
+/// auth state, so dry-run engines can quote without a wallet.
+pub trait MarketData: Sync {
+    fn midpoint(&self, token_id: &str) -> impl Future<Output = Result<Decimal>> + Send;
+    fn tick_size(&self, token_id: &str) -> impl Future<Output = Result<Decimal>> + Send;
+    fn order_book(&self, token_id: &str) -> impl Future<Output = Result<BookSnapshot>> + Send;
+    fn last_trade_price(&self, token_id: &str) -> impl Future<Output = Result<Decimal>> + Send;
+    /// Unix seconds on the exchange's clock.
+    fn server_time(&self) -> impl Future<Output = Result<i64>> + Send;
+    /// Price points `(unix seconds, price)` between `start_ts` and `end_ts`,
+    /// one every `fidelity_mins` minutes, oldest first.
+    fn price_history(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity_mins: u32,
+    ) -> impl Future<Output = Result<Vec<(i64, Decimal)>>> + Send;
+}
+
+/// Order management for an authenticated wallet. Engine, order, and manager
+/// logic goes through this trait so it can run against `MockExchange` in tests.
+pub trait ExchangeApi: MarketData {
+    /// Sign and post a batch of orders. Results are returned in request order.
+    fn post_orders(
+        &self,
+        orders: &[NewOrder],
+    ) -> impl Future<Output = Result<Vec<PostResult>>> + Send;
+    /// Cancel orders by ID, returning how many were actually cancelled.
+    fn cancel_orders(&self, order_ids: &[String]) -> impl Future<Output = Result<usize>> + Send;
+    fn cancel_all(&self) -> impl Future<Output = Result<()>> + Send;
+    fn order_state(&self, order_id: &str) -> impl Future<Output = Result<OrderState>> + Send;
+    fn usdc_balance(&self) -> impl Future<Output = Result<Decimal>> + Send;
+    /// Every open order for the wallet, across all markets.
+    fn open_orders(&self) -> impl Future<Output = Result<Vec<OpenOrder>>> + Send;
+    /// Time spent signing orders since the last call, resetting the count.
+    /// Zero where orders aren't signed locally.
+    fn take_signing_time(&self) -> Duration {
+        Duration::ZERO
+    }
+    /// Latest measured offset of the exchange's clock from ours, for order
+    /// expirations. Ignored where orders don't expire.
+    fn set_clock_skew(&self, skew: ClockSkew) {
+        let _ = skew;
+    }
+    /// Sign orders ahead of time so a later `post_orders` of the same orders
+    /// can skip signing. Replaces anything signed earlier for the same
+    /// tokens and returns how many orders were newly signed; a no-op where
+    /// orders aren't signed locally.
+    fn presign(&self, orders: &[NewOrder]) -> impl Future<Output = Result<usize>> + Send {
+        let _ = orders;
+        async { Ok(0) }
+    }
+}
+
+/// Requests made per endpoint class, for rate limiting. Posts and cancels
+/// count orders; GETs count calls.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApiUsage {
+    pub posts: usize,
+    pub cancels: usize,
+    pub gets: usize,
+}
+
+/// Wraps an exchange and counts the requests that go through it.
+pub struct Metered<'a, E> {
+    inner: &'a E,
+    usage: Mutex<ApiUsage>,
+}
+
+impl<'a, E> Metered<'a, E> {
+    pub fn new(inner: &'a E) -> Self {
+        Self {
+            inner,
+            usage: Mutex::new(ApiUsage::default()),
+        }
+    }
+
+    /// Usage since the last call, resetting the counters.
+    pub fn take_usage(&self) -> ApiUsage {
+        std::mem::take(&mut *self.usage.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn count(&self, f: impl FnOnce(&mut ApiUsage)) {
+        f(&mut self.usage.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+impl<E: MarketData> MarketData for Metered<'_, E> {
+    async fn midpoint(&self, token_id: &str) -> Result<Decimal> {
+        self.count(|u| u.gets += 1);
+        self.inner.midpoint(token_id).await
+    }
+
+    async fn tick_size(&self, token_id: &str) -> Result<Decimal> {
+        self.count(|u| u.gets += 1);
+        self.inner.tick_size(token_id).await
+    }
+
+    async fn order_book(&self, token_id: &str) -> Result<BookSnapshot> {
+        self.count(|u| u.gets += 1);
+        self.inner.order_book(token_id).await
+    }
+
+    async fn last_trade_price(&self, token_id: &str) -> Result<Decimal> {
+        self.count(|u| u.gets += 1);
+        self.inner.last_trade_price(token_id).await
+    }
+
+    async fn server_time(&self) -> Result<i64> {
+        self.count(|u| u.gets += 1);
+        self.inner.server_time().await
+    }
+
+    async fn price_history(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity_mins: u32,
+    ) -> Result<Vec<(i64, Decimal)>> {
+        self.count(|u| u.gets += 1);
+        self.inner
+            .price_history(token_id, start_ts, end_ts, fidelity_mins)
+            .await
+    }
+}
+
+impl<E: ExchangeApi> ExchangeApi for Metered<'_, E> {
+    async fn post_orders(&self, orders: &[NewOrder]) -> Result<Vec<PostResult>> {
+        self.count(|u| u.posts += orders.len());
+        self.inner.post_orders(orders).await
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<usize> {
+        self.count(|u| u.cancels += order_ids.len());
+        self.inner.cancel_orders(order_ids).await
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        self.count(|u| u.cancels += 1);
+        self.inner.cancel_all().await
+    }
+
+    async fn order_state(&self, order_id: &str) -> Result<OrderState> {
+        self.count(|u| u.gets += 1);
+        self.inner.order_state(order_id).await
+    }
+
+    async fn usdc_balance(&self) -> Result<Decimal> {
+        self.count(|u| u.gets += 1);
+        self.inner.usdc_balance().await
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        self.count(|u| u.gets += 1);
+        self.inner.open_orders().await
+    }
+
+    fn take_signing_time(&self) -> Duration {
+        self.inner.take_signing_time()
+    }
+
+    // Signing is local, so there's nothing to count against rate limits
+    fn set_clock_skew(&self, skew: ClockSkew) {
+        self.inner.set_clock_skew(skew);
+    }
+
+    async fn presign(&self, orders: &[NewOrder]) -> Result<usize> {
+        self.inner.presign(orders).await
+    }
+}
+
+impl<E: MarketData> MarketData for Timed<E> {
+    async fn midpoint(&self, token_id: &str) -> Result<Decimal> {
+        self.send(self.client().midpoint(token_id)).await
+    }
+
+    async fn tick_size(&self, token_id: &str) -> Result<Decimal> {
+        self.send(self.client().tick_size(token_id)).await
+    }
+
+    async fn order_book(&self, token_id: &str) -> Result<BookSnapshot> {
+        self.send(self.client().order_book(token_id)).await
+    }
+
+    async fn last_trade_price(&self, token_id: &str) -> Result<Decimal> {
+        self.send(self.client().last_trade_price(token_id)).await
+    }
+
+    async fn server_time(&self) -> Result<i64> {
+        self.send(self.client().server_time()).await
+    }
+
+    async fn price_history(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity_mins: u32,
+    ) -> Result<Vec<(i64, Decimal)>> {
+        self.send(
+            self.client()
+                .price_history(token_id, start_ts, end_ts, fidelity_mins),
+        )
+        .await
+    }
+}
+
+impl<E: ExchangeApi> ExchangeApi for Timed<E> {
+    async fn post_orders(&self, orders: &[NewOrder]) -> Result<Vec<PostResult>> {
+        self.send(self.client().post_orders(orders)).await
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<usize> {
+        self.send(self.client().cancel_orders(order_ids)).await
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        self.send(self.client().cancel_all()).await
+    }
+
+    async fn order_state(&self, order_id: &str) -> Result<OrderState> {
+        self.send(self.client().order_state(order_id)).await
+    }
+
+    async fn usdc_balance(&self) -> Result<Decimal> {
+        self.send(self.client().usdc_balance()).await
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        self.send(self.client().open_orders()).await
+    }
+
+    fn take_signing_time(&self) -> Duration {
+        self.client().take_signing_time()
+    }
+
+    fn set_clock_skew(&self, skew: ClockSkew) {
+        self.client().set_clock_skew(skew);
+    }
+
+    async fn presign(&self, orders: &[NewOrder]) -> Result<usize> {
+        self.send(self.client().presign(orders)).await
+    }
+}
+
+fn parse_token_id(token_id: &str) -> Result<U256> {
+    U256::from_str(token_id).context("parsing token ID")
+}
+
+impl<S: auth::state::State + Send + Sync> MarketData for clob::Client<S> {
+    async fn midpoint(&self, token_id: &str) -> Result<Decimal> {
+        let req = MidpointRequest::builder()
+            .token_id(parse_token_id(token_id)?)
+            .build();
+        let resp = clob::Client::midpoint(self, &req)
+            .await
+            .context("fetching midpoint")?;
+        Ok(resp.mid)
+    }
+
+    async fn tick_size(&self, token_id: &str) -> Result<Decimal> {
+        let resp = clob::Client::tick_size(self, parse_token_id(token_id)?)
+            .await
+            .context("fetching tick size")?;
+        Ok(resp.minimum_tick_size.as_decimal())
+    }
+
+    async fn order_book(&self, token_id: &str) -> Result<BookSnapshot> {
+        let req = OrderBookSummaryRequest::builder()
+            .token_id(parse_token_id(token_id)?)
+            .build();
+        let book = clob::Client::order_book(self, &req)
+            .await
+            .context("fetching order book")?;
+        Ok(BookSnapshot {
+            bids: book.bids.iter().map(|o| (o.price, o.size)).collect(),
+            asks: book.asks.iter().map(|o| (o.price, o.size)).collect(),
+        })
+    }
+
+    async fn last_trade_price(&self, token_id: &str) -> Result<Decimal> {
+        let req = LastTradePriceRequest::builder()
+            .token_id(parse_token_id(token_id)?)
+            .build();
+        let resp = clob::Client::last_trade_price(self, &req)
+            .await
+            .context("fetching last trade price")?;
+        Ok(resp.price)
+    }
+
+    async fn server_time(&self) -> Result<i64> {
+        clob::Client::server_time(self)
+            .await
+            .context("fetching server time")
+    }
+
+    async fn price_history(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity_mins: u32,
+    ) -> Result<Vec<(i64, Decimal)>> {
+        let req = PriceHistoryRequest::builder()
+            .market(parse_token_id(token_id)?)
+            .time_range(TimeRange::Range { start_ts, end_ts })
+            .fidelity(fidelity_mins)
+            .build();
+        let resp = clob::Client::price_history(self, &req)
+            .await
+            .context("fetching price history")?;
+        Ok(resp.history.iter().map(|p| (p.t, p.p)).collect())
+    }
+}
+
+type AuthClient = clob::Client<auth::state::Authenticated<auth::Normal>>;
+
+/// `ExchangeApi` backed by the authenticated SDK client and a signer.
+pub struct SdkExchange<'a, T> {
+    /// Swapped for a freshly authenticated client after auth failures
+    client: RwLock<Arc<AuthClient>>,
+    signer: &'a T,
+    signature_type: SignatureType,
+    /// Config to re-authenticate from, when recovery is enabled
+    reauth: Option<(&'a Config, Mutex<AuthRecovery>)>,
+    /// Signing time since `take_signing_time` was last called
+    signing: Mutex<Duration>,
+    /// Orders signed ahead of the requote expected to post them
+    presigned: Mutex<HashMap<NewOrder, SignedOrder>>,
+    /// Lifetime of GTD orders; GTC when unset
+    deadman: Option<Duration>,
+    skew: Mutex<ClockSkew>,
+}
+
+impl<'a, T: Signer + Sync> SdkExchange<'a, T> {
+    pub fn new(client: AuthClient, signer: &'a T, signature_type: SignatureType) -> Self {
+        Self {
+            client: RwLock::new(Arc::new(client)),
+            signer,
+            signature_type,
+            reauth: None,
+            signing: Mutex::new(Duration::ZERO),
+            presigned: Mutex::new(HashMap::new()),
+            deadman: None,
+            skew: Mutex::new(ClockSkew::default()),
+        }
+    }
+
+    /// Post GTD orders that expire `secs` after signing, so they lapse on
+    /// their own if the bot stops replacing them (0 keeps GTC).
+    pub fn with_deadman(mut self, secs: u64) -> Self {
+        self.deadman = (secs > 0).then(|| Duration::from_secs(secs));
+        self
+    }
+
+    /// Re-derive API credentials and re-authenticate when order requests
+    /// start failing auth, alerting if that keeps not helping.
+    pub fn with_reauth(mut self, config: &'a Config) -> Self {
+        let recovery = AuthRecovery::new(
+            config.wallet.reauth_max_attempts,
+            config.wallet.reauth_cooldown_secs,
+        );
+        self.reauth = Some((config, Mutex::new(recovery)));
+        self
+    }
+
+    /// The current authenticated client.
+    pub fn auth_client(&self) -> Arc<AuthClient> {
+        self.client
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Pass a result through, re-authenticating first if it failed on auth.
+    async fn check_auth<R>(&self, result: Result<R>) -> Result<R> {
+        let Some((config, recovery)) = &self.reauth else {
+            return result;
+        };
+        match &result {
+            Ok(_) => {
+                recovery
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .recovered();
+            }
+            Err(e) if client::is_auth_error(e) => self.reauthenticate(config, recovery, e).await,
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn reauthenticate(
+        &self,
+        config: &Config,
+        recovery: &Mutex<AuthRecovery>,
+        cause: &anyhow::Error,
+    ) {
+        let lock = || recovery.lock().unwrap_or_else(|e| e.into_inner());
+        if !lock().begin_attempt(Instant::now()) {
+            return;
+        }
+        warn!(
+            error = format!("{cause:#}"),
+            "Auth failure, re-deriving API credentials"
+        );
+        match client::authenticate(config, CredentialMode::Rederive).await {
+            Ok(fresh) => {
+                *self.client.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(fresh);
+                info!("Re-authenticated CLOB client");
+            }
+            Err(e) => warn!(error = format!("{e:#}"), "Re-authentication failed"),
+        }
+        let exhausted = lock().exhausted();
+        if let Some(attempts) = exhausted {
+            let message = format!(
+                "Exchange auth still failing after {attempts} re-authentication attempts: {cause:#}"
+            );
+            alerts::send(&config.monitoring, Severity::Critical, &message).await;
+        }
+    }
+}
+
+impl<T: Signer + Sync> MarketData for SdkExchange<'_, T> {
+    async fn midpoint(&self, token_id: &str) -> Result<Decimal> {
+        MarketData::midpoint(&*self.auth_client(), token_id).await
+    }
+
+    async fn tick_size(&self, token_id: &str) -> Result<Decimal> {
+        MarketData::tick_size(&*self.auth_client(), token_id).await
+    }
+
+    async fn order_book(&self, token_id: &str) -> Result<BookSnapshot> {
+        MarketData::order_book(&*self.auth_client(), token_id).await
+    }
+
+    async fn last_trade_price(&self, token_id: &str) -> Result<Decimal> {
+        MarketData::last_trade_price(&*self.auth_client(), token_id).await
+    }
+
+    async fn server_time(&self) -> Result<i64> {
+        MarketData::server_time(&*self.auth_client()).await
+    }
+
+    async fn price_history(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity_mins: u32,
+    ) -> Result<Vec<(i64, Decimal)>> {
+        MarketData::price_history(
+            &*self.auth_client(),
+            token_id,
+            start_ts,
+            end_ts,
+            fidelity_mins,
+        )
+        .await
+    }
+}
+
+impl<T: Signer + Sync> SdkExchange<'_, T> {
+    async fn sign_order(&self, client: &AuthClient, order: &NewOrder) -> Result<SignedOrder> {
+        let unsigned = client
+            .limit_order()
+            .token_id(parse_token_id(&order.token_id)?)
+            .side(order.side)
+            .price(order.price)
+            .size(order.size);
+        let unsigned = match self.deadman {
+            Some(ttl) => {
+                let skew = *self.skew.lock().unwrap_or_else(|e| e.into_inner());
+                unsigned
+                    .order_type(OrderType::GTD)
+                    .expiration(deadman::gtd_expiration(skew, ttl))
+            }
+            None => unsigned.order_type(OrderType::GTC),
+        }
+        .build()
+        .await
+        .context("building order")?;
+        client
+            .sign(self.signer, unsigned)
+            .await
+            .context("signing order")
+    }
+
+    fn take_presigned(&self, order: &NewOrder) -> Option<SignedOrder> {
+        self.presigned
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(order)
+    }
+}
+
+impl<T: Signer + Sync> ExchangeApi for SdkExchange<'_, T> {
+    async fn post_orders(&self, orders: &[NewOrder]) -> Result<Vec<PostResult>> {
+        let client = self.auth_client();
+        let result = async {
+            let started = Instant::now();
+            let client = &client;
+            let signing = orders.iter().map(|order| async move {
+                match self.take_presigned(order) {
+                    Some(signed) => Ok::<_, anyhow::Error>((signed, true)),
+                    None => Ok((self.sign_order(client, order).await?, false)),
+                }
+            });
+            let signed: Vec<(SignedOrder, bool)> = stream::iter(signing.collect::<Vec<_>>())
+                .buffered(SIGNING_CONCURRENCY)
+                .try_collect()
+                .await?;
+            *self.signing.lock().unwrap_or_else(|e| e.into_inner()) += started.elapsed();
+            let cached = signed.iter().filter(|(_, cached)| *cached).count();
+            let signed: Vec<SignedOrder> = signed.into_iter().map(|(order, _)| order).collect();
+            if cached > 0 {
+                debug!(cached, total = orders.len(), "Posting pre-signed orders");
+            }
+
+            let responses = client
+                .post_orders(signed)
+                .await
+                .context("posting order batch")?;
+            Ok(responses
+                .into_iter()
+                .map(|r| PostResult {
+                    order_id: r.order_id,
+                    success: r.success,
+                    error: r.error_msg,
+                })
+                .collect())
+        }
+        .await;
+        self.check_auth(result).await
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> Result<usize> {
+        let ids: Vec<&str> = order_ids.iter().map(|s| s.as_str()).collect();
+        let result = self
+            .auth_client()
+            .cancel_orders(&ids)
+            .await
+            .context("cancelling orders")
+            .map(|resp| resp.canceled.len());
+        self.check_auth(result).await
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        let result = self
+            .auth_client()
+            .cancel_all_orders()
+            .await
+            .context("cancelling all orders")
+            .map(|_| ());
+        self.check_auth(result).await
+    }
+
+    async fn order_state(&self, order_id: &str) -> Result<OrderState> {
+        let result = self
+            .auth_client()
+            .order(order_id)
+            .await
+            .context("fetching order status")
+            .map(|resp| OrderState {
+                original_size: resp.original_size,
+                size_matched: resp.size_matched,
+            });
+        self.check_auth(result).await
+    }
+
+    async fn usdc_balance(&self) -> Result<Decimal> {
+        let req = BalanceAllowanceRequest::builder()
+            .asset_type(AssetType::Collateral)
+            .signature_type(self.signature_type)
+            .build();
+        let result = self
+            .auth_client()
+            .balance_allowance(req)
+            .await
+            .context("checking USDC balance")
+            .map(|resp| resp.balance);
+        self.check_auth(result).await
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+        let result = orders::fetch_open_orders(&self.auth_client())
+            .await
+            .map(|open| {
+                open.into_iter()
+                    .map(|o| OpenOrder {
+                        order_id: o.id,
+                        market: o.market.to_string(),
+                        token_id: o.asset_id.to_string(),
+                    })
+                    .collect()
+            });
+        self.check_auth(result).await
+    }
+
+    fn take_signing_time(&self) -> Duration {
+        std::mem::take(&mut *self.signing.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn set_clock_skew(&self, skew: ClockSkew) {
+        *self.skew.lock().unwrap_or_else(|e| e.into_inner()) = skew;
+    }
+
+    async fn presign(&self, orders: &[NewOrder]) -> Result<usize> {
+        // A cached order's expiry would date from when it was signed
+        if self.deadman.is_some() {
+            return Ok(0);
+        }
+        let client = self.auth_client();
+        let tokens: HashSet<&str> = orders.iter().map(|o| o.token_id.as_str()).collect();
+        let wanted: HashSet<&NewOrder> = orders.iter().collect();
+        // Drop what the new set no longer expects for these tokens
+        self.presigned
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|o, _| !tokens.contains(o.token_id.as_str()) || wanted.contains(o));
+
+        let missing: Vec<&NewOrder> = {
+            let cache = self.presigned.lock().unwrap_or_else(|e| e.into_inner());
+            let room = PRESIGN_CACHE_MAX.saturating_sub(cache.len());
+            wanted
+                .into_iter()
+                .filter(|o| !cache.contains_key(*o))
+                .take(room)
+                .collect()
+        };
+
+        let client = &client;
+        let signing: Vec<_> = missing
+            .into_iter()
+            .map(|order| async move { (order, self.sign_order(client, order).await) })
+            .collect();
+        let mut signing = stream::iter(signing).buffer_unordered(SIGNING_CONCURRENCY);
+        let mut signed = 0;
+        while let Some((order, result)) = signing.next().await {
+            let order_signed = self.check_auth(result).await?;
+            self.presigned
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(order.clone(), order_signed);
+            signed += 1;
+        }
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use anyhow::bail;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Operations that can be made to fail.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Op {
+        Midpoint,
+        TickSize,
+        OrderBook,
+        LastTrade,
+        ServerTime,
+        PriceHistory,
+        Post,
+        Cancel,
+        CancelAll,
+        OrderState,
+        Balance,
+        OpenOrders,
+    }
+
+    /// In-memory exchange with failure injection.
+    #[derive(Default)]
+    pub struct MockExchange {
+        state: Mutex<MockState>,
+    }
+
+    #[derive(Default)]
+    pub struct MockState {
+        pub midpoints: HashMap<String, Decimal>,
+        /// Books by token; missing tokens have an empty book
+        pub books: HashMap<String, BookSnapshot>,
+        pub tick_size: Option<Decimal>,
+        pub last_trades: HashMap<String, Decimal>,
+        /// Price points by token, oldest first
+        pub price_history: HashMap<String, Vec<(i64, Decimal)>>,
+        /// Condition ID of each token's market, for listing open orders
+        pub token_markets: HashMap<String, String>,
+        /// How far the exchange's clock runs ahead of ours
+        pub server_offset_secs: i64,
+        /// Live orders: ID -> (order, fill progress)
+        pub open: HashMap<String, (NewOrder, OrderState)>,
+        /// Size of each `post_orders` call, in call order
+        pub post_batches: Vec<usize>,
+        pub cancelled: Vec<String>,
+        pub balance: Decimal,
+        /// Operations that currently return an error
+        pub failing: HashSet<Op>,
+        /// Post succeeds at the transport level but every order is rejected
+        pub reject_orders: bool,
+        /// Orders signed ahead, and how many posts used one
+        pub presigned: HashSet<NewOrder>,
+        pub presign_hits: usize,
+        next_id: u64,
+    }
+
+    impl MockExchange {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn with_midpoint(self, token_id: &str, midpoint: Decimal) -> Self {
+            self.state().midpoints.insert(token_id.to_string(), midpoint);
+            self
+        }
+
+        pub fn state(&self) -> MutexGuard<'_, MockState> {
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        pub fn fail(&self, op: Op) {
+            self.state().failing.insert(op);
+        }
+
+        pub fn heal(&self, op: Op) {
+            self.state().failing.remove(&op);
+        }
+
+        /// Simulate a (partial) fill on a live order.
+        pub fn fill(&self, order_id: &str, amount: Decimal) {
+            if let Some((_, state)) = self.state().open.get_mut(order_id) {
+                state.size_matched = (state.size_matched + amount).min(state.original_size);
+            }
+        }
+
+        /// IDs of live orders matching a side and token.
+        pub fn open_ids(&self, token_id: &str, side: Side) -> Vec<String> {
+            let mut ids: Vec<String> = self
+                .state()
+                .open
+                .iter()
+                .filter(|(_, (o, _))| o.token_id == token_id && o.side == side)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.sort();
+            ids
+        }
+
+        fn check(&self, op: Op) -> Result<()> {
+            if self.state().failing.contains(&op) {
+                bail!("injected {op:?} failure");
+            }
+            Ok(())
+        }
+    }
+
+    impl MarketData for MockExchange {
+        async fn midpoint(&self, token_id: &str) -> Result<Decimal> {
+            self.check(Op::Midpoint)?;
+            match self.state().midpoints.get(token_id) {
+                Some(mid) => Ok(*mid),
+                None => bail!("no midpoint for {token_id}"),
+            }
+        }
+
+        async fn tick_size(&self, _token_id: &str) -> Result<Decimal> {
+            self.check(Op::TickSize)?;
+            Ok(self.state().tick_size.unwrap_or(Decimal::new(1, 2)))
+        }
+
+        async fn order_book(&self, token_id: &str) -> Result<BookSnapshot> {
+            self.check(Op::OrderBook)?;
+            Ok(self
+                .state()
+                .books
+                .get(token_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn last_trade_price(&self, token_id: &str) -> Result<Decimal> {
+            self.check(Op::LastTrade)?;
+            match self.state().last_trades.get(token_id) {
+                Some(price) => Ok(*price),
+                None => bail!("no trades for {token_id}"),
+            }
+        }
+
+        async fn server_time(&self) -> Result<i64> {
+            self.check(Op::ServerTime)?;
+            Ok(chrono::Utc::now().timestamp() + self.state().server_offset_secs)
+        }
+
+        async fn price_history(
+            &self,
+            token_id: &str,
+            start_ts: i64,
+            end_ts: i64,
+            _fidelity_mins: u32,
+        ) -> Result<Vec<(i64, Decimal)>> {
+            self.check(Op::PriceHistory)?;
+            Ok(self
+                .state()
+                .price_history
+                .get(token_id)
+                .into_iter()
+                .flatten()
+                .filter(|(t, _)| (start_ts..=end_ts).contains(t))
+                .copied()
+                .collect())
+        }
+    }
+
+    impl ExchangeApi for MockExchange {
+        async fn post_orders(&self, orders: &[NewOrder]) -> Result<Vec<PostResult>> {
+            self.check(Op::Post)?;
+            let mut state = self.state();
+            state.post_batches.push(orders.len());
+            let mut results = Vec::with_capacity(orders.len());
+            for order in orders {
+                if state.presigned.remove(order) {
+                    state.presign_hits += 1;
+                }
+                if state.reject_orders {
+                    results.push(PostResult {
+                        order_id: String::new(),
+                        success: false,
+                        error: Some("rejected".into()),
+                    });
+                    continue;
+                }
+                state.next_id += 1;
+                let id = format!("order-{:04}", state.next_id);
+                let progress = OrderState {
+                    original_size: order.size,
+                    size_matched: Decimal::ZERO,
+                };
+                state.open.insert(id.clone(), (order.clone(), progress));
+                results.push(PostResult {
+                    order_id: id,
+                    success: true,
+                    error: None,
+                });
+            }
+            Ok(results)
+        }
+
+        async fn cancel_orders(&self, order_ids: &[String]) -> Result<usize> {
+            self.check(Op::Cancel)?;
+            let mut state = self.state();
+            let mut cancelled = 0;
+            for id in order_ids {
+                if state.open.remove(id).is_some() {
+                    state.cancelled.push(id.clone());
+                    cancelled += 1;
+                }
+            }
+            Ok(cancelled)
+        }
+
+        async fn presign(&self, orders: &[NewOrder]) -> Result<usize> {
+            let mut state = self.state();
+            let tokens: HashSet<&str> = orders.iter().map(|o| o.token_id.as_str()).collect();
+            state
+                .presigned
+                .retain(|o| !tokens.contains(o.token_id.as_str()) || orders.contains(o));
+            Ok(orders
+                .iter()
+                .filter(|o| state.presigned.insert((*o).clone()))
+                .count())
+        }
+
+        async fn cancel_all(&self) -> Result<()> {
+            self.check(Op::CancelAll)?;
+            let mut state = self.state();
+            let ids: Vec<String> = state.open.drain().map(|(id, _)| id).collect();
+            state.cancelled.extend(ids);
+            Ok(())
+        }
+
+        async fn order_state(&self, order_id: &str) -> Result<OrderState> {
+            self.check(Op::OrderState)?;
+            match self.state().open.get(order_id) {
+                Some((_, progress)) => Ok(*progress),
+                None => bail!("unknown order {order_id}"),
+            }
+        }
+
+        async fn usdc_balance(&self) -> Result<Decimal> {
+            self.check(Op::Balance)?;
+            Ok(self.state().balance)
+        }
+
+        async fn open_orders(&self) -> Result<Vec<OpenOrder>> {
+            self.check(Op::OpenOrders)?;
+            let state = self.state();
+            let mut open: Vec<OpenOrder> = state
+                .open
+                .iter()
+                .map(|(id, (order, _))| OpenOrder {
+                    order_id: id.clone(),
+                    market: state
+                        .token_markets
+                        .get(&order.token_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    token_id: order.token_id.clone(),
+                })
+                .collect();
+            open.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+            Ok(open)
+        }
+    }
+}
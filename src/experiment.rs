@@ -0,0 +1,234 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use comfy_table::Table;
+use comfy_table::presets::UTF8_FULL;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal_macros::dec;
+use std::fmt;
+
+use crate::config::{ExperimentConfig, StrategyConfig};
+use crate::metrics::{MarketMetrics, MetricFn};
+
+/// Label shuffles behind the permutation test's p-value.
+const PERMUTATIONS: usize = 10_000;
+/// Markets each arm needs before the arms are compared at all.
+const MIN_MARKETS_PER_ARM: usize = 2;
+
+/// Which parameter set a market runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arm {
+    /// The `[strategy]` section as configured
+    A,
+    /// `[strategy]` with `[experiment.strategy_b]` laid over it
+    B,
+}
+
+impl fmt::Display for Arm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arm::A => write!(f, "A"),
+            Arm::B => write!(f, "B"),
+        }
+    }
+}
+
+/// A running A/B experiment: seeded arm assignment plus arm B's strategy.
+#[derive(Debug, Clone)]
+pub struct Experiment {
+    pub seed: u64,
+    pub share_b: Decimal,
+    pub strategy_b: StrategyConfig,
+}
+
+impl Experiment {
+    /// Build from config, or None when no experiment is enabled. Fails if
+    /// `strategy_b` names settings `[strategy]` doesn't have.
+    pub fn from_config(config: &ExperimentConfig, base: &StrategyConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            seed: config.seed,
+            share_b: config.share_b,
+            strategy_b: overlay_strategy(base, &config.strategy_b)?,
+        }))
+    }
+
+    /// The arm for a market. Depends only on the seed and condition ID, so a
+    /// market keeps its arm across rescans and restarts.
+    pub fn assign(&self, condition_id: &str) -> Arm {
+        let bucket = fnv1a(self.seed, condition_id.as_bytes()) % 10_000;
+        if Decimal::from(bucket) < self.share_b * dec!(10000) {
+            Arm::B
+        } else {
+            Arm::A
+        }
+    }
+}
+
+/// `base` with each key of `overrides` replaced.
+pub fn overlay_strategy(base: &StrategyConfig, overrides: &toml::Table) -> Result<StrategyConfig> {
+    let mut table = toml::Table::try_from(base).context("serializing [strategy]")?;
+    for (key, value) in overrides {
+        if !table.contains_key(key) {
+            bail!("experiment.strategy_b: unknown strategy setting `{key}`");
+        }
+        table.insert(key.clone(), value.clone());
+    }
+    table
+        .try_into()
+        .context("parsing [experiment.strategy_b] over [strategy]")
+}
+
+fn fnv1a(seed: u64, bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.to_le_bytes().iter().chain(bytes) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Net PnL per hour since the market's metrics started, so markets added
+/// mid-session compare fairly with ones that ran all along.
+fn pnl_per_hour(metrics: &MarketMetrics, now: DateTime<Utc>) -> f64 {
+    let hours = (now - metrics.start_time).num_seconds().max(60) as f64 / 3600.0;
+    metrics.net_pnl().to_f64().unwrap_or(0.0) / hours
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// Two-sided permutation test on the difference in means: the share of
+/// random relabellings whose |mean(b) - mean(a)| is at least the observed.
+pub fn permutation_p_value(a: &[f64], b: &[f64], seed: u64) -> f64 {
+    let observed = (mean(b) - mean(a)).abs();
+    let mut pooled: Vec<f64> = a.iter().chain(b).copied().collect();
+    let mut state = seed | 1;
+    let mut extreme = 0;
+    for _ in 0..PERMUTATIONS {
+        // Fisher-Yates with xorshift64
+        for i in (1..pooled.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            pooled.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+        let (pa, pb) = pooled.split_at(a.len());
+        // Tolerance so ties with the observed split count as extreme
+        if (mean(pb) - mean(pa)).abs() >= observed - 1e-12 {
+            extreme += 1;
+        }
+    }
+    (extreme + 1) as f64 / (PERMUTATIONS + 1) as f64
+}
+
+/// Per-arm averages and a permutation test on net PnL per hour.
+pub fn format_report(
+    experiment: &Experiment,
+    a: &[&MarketMetrics],
+    b: &[&MarketMetrics],
+    now: DateTime<Utc>,
+) -> String {
+    let avg = |markets: &[&MarketMetrics], f: MetricFn| -> Decimal {
+        markets.iter().map(|m| f(m)).sum::<Decimal>() / Decimal::from(markets.len().max(1))
+    };
+    let hourly = |markets: &[&MarketMetrics]| -> Vec<f64> {
+        markets.iter().map(|m| pnl_per_hour(m, now)).collect()
+    };
+    let (hourly_a, hourly_b) = (hourly(a), hourly(b));
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Metric", "Arm A", "Arm B", "B - A"]);
+    table.add_row(vec![
+        "Markets".to_string(),
+        a.len().to_string(),
+        b.len().to_string(),
+        String::new(),
+    ]);
+    let (ma, mb) = (mean(&hourly_a), mean(&hourly_b));
+    table.add_row(vec![
+        "Net PnL / hour ($)".to_string(),
+        format!("{ma:.4}"),
+        format!("{mb:.4}"),
+        format!("{:+.4}", mb - ma),
+    ]);
+    let rows: [(&str, MetricFn); 4] = [
+        ("Net PnL ($)", |m| m.net_pnl()),
+        ("Rewards ($)", |m| m.reward_pnl),
+        ("Fill rate (%)", |m| m.fill_rate() * dec!(100)),
+        ("Band uptime (%)", |m| m.band_uptime_pct()),
+    ];
+    for (name, f) in rows {
+        let (va, vb) = (avg(a, f), avg(b, f));
+        table.add_row(vec![
+            name.to_string(),
+            format!("{va:.2}"),
+            format!("{vb:.2}"),
+            format!("{:+.2}", vb - va),
+        ]);
+    }
+
+    let mut out = format!(
+        "A/B experiment (seed {}, {}% of markets on B)\n{table}\n",
+        experiment.seed,
+        (experiment.share_b * dec!(100)).round_dp(0)
+    );
+    if a.len() < MIN_MARKETS_PER_ARM || b.len() < MIN_MARKETS_PER_ARM {
+        out.push_str(&format!(
+            "Too few markets for a significance test (need {MIN_MARKETS_PER_ARM} per arm)"
+        ));
+    } else {
+        let p = permutation_p_value(&hourly_a, &hourly_b, experiment.seed);
+        out.push_str(&format!(
+            "Net PnL / hour difference: permutation p = {p:.3} ({PERMUTATIONS} shuffles)"
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignment_and_overlay() {
+        let config = ExperimentConfig {
+            enabled: true,
+            seed: 7,
+            share_b: dec!(0.5),
+            strategy_b: toml::from_str("num_levels = 3\nbase_offset_cents = 1.5").unwrap(),
+        };
+        let base = StrategyConfig::default();
+        let experiment = Experiment::from_config(&config, &base).unwrap().unwrap();
+        assert_eq!(experiment.strategy_b.num_levels, 3);
+        assert_eq!(experiment.strategy_b.base_offset_cents, dec!(1.5));
+        assert_eq!(experiment.strategy_b.order_size, base.order_size);
+
+        // Close to the configured split
+        let ids: Vec<String> = (0..1000).map(|i| format!("0x{i:04x}")).collect();
+        let on_b = ids
+            .iter()
+            .filter(|id| experiment.assign(id) == Arm::B)
+            .count();
+        assert!((400..600).contains(&on_b), "{on_b} of 1000 on B");
+
+        let typo = ExperimentConfig {
+            strategy_b: toml::from_str("num_level = 3").unwrap(),
+            ..config
+        };
+        assert!(Experiment::from_config(&typo, &base).is_err());
+    }
+
+    #[test]
+    fn test_permutation_p_value() {
+        let a = [1.0, 1.1, 0.9, 1.0, 1.05, 0.95];
+        let b = [2.0, 2.1, 1.9, 2.0, 2.05, 1.95];
+        assert!(permutation_p_value(&a, &b, 1) < 0.01);
+        let same = [1.0, 2.0, 1.5, 1.2, 1.8, 1.4];
+        assert!(permutation_p_value(&same, &same, 1) > 0.5);
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Upper bounds of the size tiers in shares; larger orders share the last
+const SIZE_TIERS: [Decimal; 3] = [dec!(50), dec!(200), dec!(1000)];
+/// A bucket's counts are halved past this many quotes, so the curve follows
+/// recent conditions rather than the whole history
+const MAX_WEIGHT: f64 = 500.0;
+/// Quotes' worth of the tier's pooled fill rate blended into each bucket
+const PRIOR_WEIGHT: f64 = 5.0;
+/// Quotes observed before a curve is trusted
+const MIN_OBSERVATIONS: f64 = 50.0;
+
+/// Quotes seen in one distance and size bucket, and how many filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub quoted: f64,
+    pub filled: f64,
+}
+
+impl Bucket {
+    fn record(&mut self, filled: bool) {
+        self.quoted += 1.0;
+        if filled {
+            self.filled += 1.0;
+        }
+        if self.quoted > MAX_WEIGHT {
+            self.quoted /= 2.0;
+            self.filled /= 2.0;
+        }
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        self.quoted += other.quoted;
+        self.filled += other.filled;
+    }
+}
+
+/// One market's chance that a quote fills at all before it is replaced,
+/// by distance from the midpoint (half-cent buckets, as in the `status`
+/// histogram) and size tier. Calibrated from our own live ladders.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FillCurve {
+    buckets: BTreeMap<u32, [Bucket; 4]>,
+}
+
+impl FillCurve {
+    /// Record one replaced quote: its distance (in price) from the midpoint
+    /// it was quoted at, its size, and whether any of it filled.
+    pub fn observe(&mut self, distance: Decimal, size: Decimal, filled: bool) {
+        let tiers = self.buckets.entry(distance_bucket(distance)).or_default();
+        tiers[size_tier(size)].record(filled);
+    }
+
+    pub fn observations(&self) -> f64 {
+        self.buckets.values().flatten().map(|b| b.quoted).sum()
+    }
+
+    pub fn is_calibrated(&self) -> bool {
+        self.observations() >= MIN_OBSERVATIONS
+    }
+
+    /// Probability that a quote `distance` from the midpoint for `size`
+    /// shares fills before the next requote. Each bucket is shrunk toward
+    /// its size tier's pooled rate, and capped by every closer bucket with
+    /// data, so a farther quote never looks likelier to fill.
+    pub fn probability(&self, distance: Decimal, size: Decimal) -> Decimal {
+        let tier = size_tier(size);
+        let target = distance_bucket(distance);
+        let pooled = self.pooled_rate(tier);
+        let rate = |b: Bucket| (b.filled + PRIOR_WEIGHT * pooled) / (b.quoted + PRIOR_WEIGHT);
+        let own = self.buckets.get(&target).map(|t| t[tier]);
+        let p = self
+            .buckets
+            .range(..target)
+            .map(|(_, t)| t[tier])
+            .filter(|b| b.quoted > 0.0)
+            .map(rate)
+            .fold(rate(own.unwrap_or_default()), f64::min);
+        Decimal::from_f64(p).unwrap_or_default().round_dp(4)
+    }
+
+    /// Fill rate across all distances for one size tier, or across every
+    /// tier when that one has no data.
+    fn pooled_rate(&self, tier: usize) -> f64 {
+        let sum = |tiers: &[usize]| {
+            let mut total = Bucket::default();
+            for t in self.buckets.values() {
+                for &i in tiers {
+                    total.merge(&t[i]);
+                }
+            }
+            total
+        };
+        let own = sum(&[tier]);
+        let total = if own.quoted > 0.0 {
+            own
+        } else {
+            sum(&[0, 1, 2, 3])
+        };
+        if total.quoted > 0.0 {
+            total.filled / total.quoted
+        } else {
+            0.0
+        }
+    }
+
+    fn merge(&mut self, other: &FillCurve) {
+        for (bucket, tiers) in &other.buckets {
+            let mine = self.buckets.entry(*bucket).or_default();
+            for (m, o) in mine.iter_mut().zip(tiers) {
+                m.merge(o);
+            }
+        }
+    }
+}
+
+fn distance_bucket(distance: Decimal) -> u32 {
+    (distance.abs() * dec!(200))
+        .floor()
+        .to_u32()
+        .unwrap_or(u32::MAX)
+}
+
+fn size_tier(size: Decimal) -> usize {
+    SIZE_TIERS
+        .iter()
+        .position(|bound| size < *bound)
+        .unwrap_or(SIZE_TIERS.len())
+}
+
+/// Fill curves for every market we have quoted live, by condition ID.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FillModel {
+    pub markets: HashMap<String, FillCurve>,
+}
+
+impl FillModel {
+    /// Load from disk; a missing file means nothing calibrated yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading fill model {}", path.display()))?;
+        serde_json::from_str(&content).context("parsing fill model")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        crate::metrics::write_atomic(path, &json)
+    }
+
+    /// The market's own curve once calibrated, else every market's history
+    /// pooled, which is the best guess for a market we haven't quoted.
+    pub fn curve_for(&self, condition_id: &str) -> FillCurve {
+        if let Some(curve) = self.markets.get(condition_id)
+            && curve.is_calibrated()
+        {
+            return curve.clone();
+        }
+        let mut pooled = FillCurve::default();
+        for curve in self.markets.values() {
+            pooled.merge(curve);
+        }
+        pooled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_curve_calibrates_and_stays_monotone() {
+        let mut curve = FillCurve::default();
+        // 1c away fills 40% of the time, 3c away 10%, 2c away looks
+        // better than 1c by chance
+        for i in 0..50 {
+            curve.observe(dec!(0.011), dec!(100), i % 5 < 2);
+            curve.observe(dec!(-0.031), dec!(100), i % 10 == 0);
+            curve.observe(dec!(0.021), dec!(100), i % 2 == 0);
+        }
+        assert!(curve.is_calibrated());
+        let near = curve.probability(dec!(0.01), dec!(100));
+        let far = curve.probability(dec!(0.03), dec!(100));
+        assert!(near > dec!(0.35) && near < dec!(0.45), "{near}");
+        assert!(far > dec!(0.05) && far < dec!(0.15), "{far}");
+        assert!(curve.probability(dec!(0.02), dec!(100)) <= near);
+        // Another size tier with no data falls back to the pooled rate
+        assert!(curve.probability(dec!(0.01), dec!(5000)) > Decimal::ZERO);
+
+        let mut model = FillModel::default();
+        assert_eq!(model.curve_for("a"), FillCurve::default());
+        model.markets.insert("a".into(), curve.clone());
+        assert_eq!(model.curve_for("a"), curve);
+        assert_eq!(model.curve_for("b").observations(), curve.observations());
+    }
+}
@@ -0,0 +1,181 @@
+use polymarket_client_sdk::clob::types::Side;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::alerts::{Alert, AlertRule, Severity};
+
+/// One fill with the context needed to judge its quality: where it sat
+/// against the midpoint, what it did to inventory, and where the price
+/// went shortly after.
+#[derive(Debug, Clone)]
+pub struct FillNotice {
+    pub market: String,
+    /// "YES" or "NO"
+    pub outcome: &'static str,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    /// The token's midpoint when the fill landed, if one had been seen
+    pub midpoint: Option<Decimal>,
+    pub inventory_yes: Decimal,
+    pub inventory_no: Decimal,
+    /// Inventory skew in [-0.5, 0.5] right after the fill
+    pub skew: Decimal,
+    pub at: Instant,
+    /// Move of the token's midpoint over the markout horizon, in cents,
+    /// positive when it went our way; set once the horizon has passed
+    pub markout_cents: Option<Decimal>,
+}
+
+impl FillNotice {
+    /// Info alert valued at the fill's notional, for `[alerts.rules.fill]`.
+    pub fn alert(&self) -> Alert {
+        Alert::new(AlertRule::Fill, Severity::Info, self.to_string())
+            .with_value(self.price * self.size)
+    }
+
+    /// Fill price minus the token's midpoint at the time, in cents.
+    pub fn vs_mid_cents(&self) -> Option<Decimal> {
+        self.midpoint.map(|mid| (self.price - mid) * dec!(100))
+    }
+
+    fn direction(&self) -> Decimal {
+        if self.side == Side::Buy {
+            Decimal::ONE
+        } else {
+            -Decimal::ONE
+        }
+    }
+}
+
+impl fmt::Display for FillNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cents =
+            |c: Option<Decimal>| c.map_or_else(|| "n/a".to_string(), |c| format!("{c:+.1}¢"));
+        writeln!(
+            f,
+            "💱 Fill on {}: {:?} {} {} @ {} ({} vs mid)",
+            self.market,
+            self.side,
+            self.size,
+            self.outcome,
+            self.price,
+            cents(self.vs_mid_cents())
+        )?;
+        writeln!(
+            f,
+            "Inventory {} YES / {} NO, skew {:.2}",
+            self.inventory_yes, self.inventory_no, self.skew
+        )?;
+        write!(f, "Markout: {}", cents(self.markout_cents))
+    }
+}
+
+/// Fills waiting out the markout horizon, and those ready to report.
+#[derive(Debug, Default)]
+pub struct FillNotices {
+    pending: VecDeque<FillNotice>,
+    ready: Vec<FillNotice>,
+    /// Latest YES midpoint seen, stamped on new fills
+    yes_midpoint: Option<Decimal>,
+}
+
+impl FillNotices {
+    pub fn yes_midpoint(&self) -> Option<Decimal> {
+        self.yes_midpoint
+    }
+
+    pub fn push(&mut self, notice: FillNotice) {
+        self.pending.push_back(notice);
+    }
+
+    /// Mark fills at least `horizon` old against the current YES midpoint,
+    /// logging each and queueing it for alerting. Returns how many matured.
+    pub fn mature(&mut self, now: Instant, yes_midpoint: Decimal, horizon: Duration) -> usize {
+        self.yes_midpoint = Some(yes_midpoint);
+        let before = self.ready.len();
+        while let Some(mut notice) = self.pending.pop_front() {
+            if now.saturating_duration_since(notice.at) < horizon {
+                self.pending.push_front(notice);
+                break;
+            }
+            let midpoint = if notice.outcome == "YES" {
+                yes_midpoint
+            } else {
+                Decimal::ONE - yes_midpoint
+            };
+            notice.markout_cents = notice
+                .midpoint
+                .map(|at_fill| (midpoint - at_fill) * dec!(100) * notice.direction());
+            info!(
+                market = %notice.market,
+                outcome = notice.outcome,
+                side = ?notice.side,
+                price = %notice.price,
+                size = %notice.size,
+                vs_mid_cents = ?notice.vs_mid_cents(),
+                inventory_yes = %notice.inventory_yes,
+                inventory_no = %notice.inventory_no,
+                skew = %notice.skew,
+                markout_cents = ?notice.markout_cents,
+                "Fill report"
+            );
+            self.ready.push(notice);
+        }
+        self.ready.len() - before
+    }
+
+    /// Matured notices not yet picked up, oldest first.
+    pub fn ready(&self) -> &[FillNotice] {
+        &self.ready
+    }
+
+    /// Matured notices not yet picked up for alerting.
+    pub fn take_ready(&mut self) -> Vec<FillNotice> {
+        std::mem::take(&mut self.ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_notice_matures_with_markout() {
+        let start = Instant::now();
+        let horizon = Duration::from_secs(30);
+        let notice = |outcome, side, price| FillNotice {
+            market: "Test".into(),
+            outcome,
+            side,
+            price,
+            size: dec!(50),
+            midpoint: Some(dec!(0.50)),
+            inventory_yes: dec!(50),
+            inventory_no: Decimal::ZERO,
+            skew: dec!(0.1),
+            at: start,
+            markout_cents: None,
+        };
+        let mut notices = FillNotices::default();
+        notices.push(notice("YES", Side::Buy, dec!(0.49)));
+        notices.push(notice("NO", Side::Buy, dec!(0.49)));
+
+        notices.mature(start + Duration::from_secs(10), dec!(0.48), horizon);
+        assert!(notices.take_ready().is_empty());
+
+        // YES fell 2c: the YES buy lost, the NO buy (NO rose 2c) gained
+        notices.mature(start + horizon, dec!(0.48), horizon);
+        let ready = notices.take_ready();
+        assert_eq!(ready[0].markout_cents, Some(dec!(-2)));
+        assert_eq!(ready[1].markout_cents, Some(dec!(2)));
+        assert_eq!(ready[0].vs_mid_cents(), Some(dec!(-1)));
+        let message = ready[0].to_string();
+        assert!(message.contains("Buy 50 YES @ 0.49 (-1.0¢ vs mid)"));
+        assert!(message.contains("Markout: -2.0¢"));
+    }
+}
@@ -3,17 +3,30 @@ use polymarket_client_sdk::auth;
 use polymarket_client_sdk::clob;
 use polymarket_client_sdk::clob::types::{AssetType, SignatureType};
 use polymarket_client_sdk::clob::types::request::BalanceAllowanceRequest;
+use polymarket_client_sdk::data;
+use polymarket_client_sdk::data::types::request::PositionsRequest;
+use polymarket_client_sdk::gamma;
+use polymarket_client_sdk::gamma::types::request::MarketsRequest;
+use polymarket_client_sdk::types::{Address, B256};
 use rust_decimal::Decimal;
-use tracing::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::client::Timed;
+use crate::config::CtfConfig;
 
 /// Check USDC balance and token balances for a given asset.
 pub async fn check_balances(
     client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
+    signature_type: SignatureType,
 ) -> Result<BalanceInfo> {
-    // Check collateral (USDC) balance
+    // Check collateral (USDC) balance held by the EOA or proxy/Safe funder
     let usdc_req = BalanceAllowanceRequest::builder()
         .asset_type(AssetType::Collateral)
-        .signature_type(SignatureType::Eoa)
+        .signature_type(signature_type)
         .build();
 
     let usdc_resp = client
@@ -26,14 +39,69 @@ pub async fn check_balances(
         "USDC balance"
     );
 
+    let mut allowances = BTreeMap::new();
+    for (spender, raw) in usdc_resp.allowances {
+        match Decimal::from_str(&raw) {
+            Ok(allowance) => {
+                allowances.insert(spender.to_string(), allowance);
+            }
+            Err(e) => warn!(%spender, allowance = %raw, error = %e, "Unparseable USDC allowance"),
+        }
+    }
+
     Ok(BalanceInfo {
         usdc_balance: usdc_resp.balance,
+        allowances,
     })
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BalanceInfo {
     pub usdc_balance: Decimal,
+    /// USDC allowance granted to each exchange contract, keyed by spender address
+    pub allowances: BTreeMap<String, Decimal>,
+}
+
+/// YES/NO token holdings for one market, as reported by the Data API.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketPosition {
+    pub condition_id: String,
+    pub title: String,
+    pub yes: Decimal,
+    pub no: Decimal,
+}
+
+/// Fetch the wallet's token positions grouped by market.
+pub async fn fetch_positions(
+    data_client: &Timed<data::Client>,
+    owner: Address,
+) -> Result<Vec<MarketPosition>> {
+    let req = PositionsRequest::builder().user(owner).build();
+    let positions = data_client
+        .send(async { Ok(data_client.client().positions(&req).await?) })
+        .await
+        .context("fetching positions from Data API")?;
+
+    let mut by_market: BTreeMap<String, MarketPosition> = BTreeMap::new();
+    for p in positions {
+        let condition_id = p.condition_id.to_string();
+        let entry = by_market
+            .entry(condition_id.clone())
+            .or_insert_with(|| MarketPosition {
+                condition_id,
+                title: p.title.clone(),
+                yes: Decimal::ZERO,
+                no: Decimal::ZERO,
+            });
+        // Binary markets: anything but the "No" outcome counts as YES
+        if p.outcome.eq_ignore_ascii_case("no") {
+            entry.no += p.size;
+        } else {
+            entry.yes += p.size;
+        }
+    }
+
+    Ok(by_market.into_values().collect())
 }
 
 /// Split USDC into YES + NO token pairs.
@@ -86,17 +154,197 @@ pub async fn redeem_winning_tokens(
     Ok(())
 }
 
-/// Detect if a market has been resolved.
-/// Returns the winning outcome index if resolved, None if still active.
-pub fn check_resolution(market_closed: bool) -> Option<ResolutionResult> {
-    if market_closed {
-        Some(ResolutionResult { resolved: true })
-    } else {
-        None
+/// An on-chain CTF operation waiting to be executed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CtfOperation {
+    Split { condition_id: String, amount: Decimal },
+    Merge { condition_id: String, amount: Decimal },
+    Redeem { condition_id: String, amount: Decimal },
+}
+
+impl CtfOperation {
+    /// Splits fund asks we want to quote now; merges and redemptions can wait for cheap gas.
+    pub fn is_urgent(&self) -> bool {
+        matches!(self, CtfOperation::Split { .. })
+    }
+}
+
+/// Execute a single CTF operation.
+pub async fn execute_ctf_operation(
+    client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
+    op: &CtfOperation,
+) -> Result<()> {
+    match op {
+        CtfOperation::Split { condition_id, amount } => {
+            split_usdc_to_tokens(client, condition_id, *amount).await
+        }
+        CtfOperation::Merge { condition_id, amount } => {
+            merge_tokens_to_usdc(client, condition_id, *amount).await
+        }
+        CtfOperation::Redeem { condition_id, amount } => {
+            redeem_winning_tokens(client, condition_id, *amount).await
+        }
+    }
+}
+
+/// Queues CTF operations and releases non-urgent ones only when gas is below
+/// the configured ceiling (or they've waited longer than `max_defer`).
+pub struct CtfScheduler {
+    queue: Vec<(CtfOperation, Instant)>,
+    max_gas_gwei: Decimal,
+    max_defer: Duration,
+}
+
+impl CtfScheduler {
+    pub fn new(config: &CtfConfig) -> Self {
+        Self {
+            queue: Vec::new(),
+            max_gas_gwei: config.max_gas_gwei,
+            max_defer: Duration::from_secs(config.max_defer_secs),
+        }
+    }
+
+    pub fn enqueue(&mut self, op: CtfOperation) {
+        info!(op = ?op, "CTF operation queued");
+        self.queue.push((op, Instant::now()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Remove and return operations that should run now.
+    /// `gas_price_gwei` of None means gas isn't monitored, so everything runs.
+    pub fn take_due(&mut self, gas_price_gwei: Option<Decimal>, now: Instant) -> Vec<CtfOperation> {
+        let gas_ok = gas_price_gwei.is_none_or(|gas| gas <= self.max_gas_gwei);
+        let max_defer = self.max_defer;
+
+        let (due, deferred): (Vec<_>, Vec<_>) = self.queue.drain(..).partition(|(op, queued)| {
+            gas_ok || op.is_urgent() || now.duration_since(*queued) >= max_defer
+        });
+        self.queue = deferred;
+
+        if !self.queue.is_empty() {
+            debug!(
+                deferred = self.queue.len(),
+                gas = ?gas_price_gwei,
+                ceiling = %self.max_gas_gwei,
+                "Deferring CTF operations until gas drops"
+            );
+        }
+        due.into_iter().map(|(op, _)| op).collect()
+    }
+}
+
+/// Resolution lifecycle of a market as reported by Gamma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionStatus {
+    /// Trading open, no outcome proposed
+    Open,
+    /// Trading closed or outcome proposed, awaiting finalization
+    Closed,
+    /// Proposed outcome has been disputed on UMA
+    Disputed,
+    /// Outcome finalized — winning tokens can be redeemed
+    Resolved,
+}
+
+/// Classify a market from Gamma's `closed` flag and `umaResolutionStatus` field.
+pub fn check_resolution(closed: bool, uma_status: Option<&str>) -> ResolutionStatus {
+    match uma_status.map(|s| s.to_ascii_lowercase()).as_deref() {
+        Some("resolved") => ResolutionStatus::Resolved,
+        Some("disputed") => ResolutionStatus::Disputed,
+        Some("proposed") => ResolutionStatus::Closed,
+        _ if closed => ResolutionStatus::Closed,
+        _ => ResolutionStatus::Open,
+    }
+}
+
+/// Poll Gamma for the resolution status of each given market.
+pub async fn fetch_resolution_statuses(
+    gamma_client: &Timed<gamma::Client>,
+    condition_ids: &[String],
+) -> Result<HashMap<String, ResolutionStatus>> {
+    let ids: Vec<B256> = condition_ids
+        .iter()
+        .filter_map(|id| B256::from_str(id).ok())
+        .collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
     }
+
+    let request = MarketsRequest::builder().condition_ids(ids).build();
+    let markets = gamma_client
+        .send(async { Ok(gamma_client.client().markets(&request).await?) })
+        .await
+        .context("fetching market statuses from Gamma API")?;
+
+    let statuses = markets
+        .iter()
+        .filter_map(|m| {
+            let id = m.condition_id.as_ref()?.to_string();
+            let status = check_resolution(
+                m.closed.unwrap_or(false),
+                m.uma_resolution_status.as_deref(),
+            );
+            Some((id, status))
+        })
+        .collect();
+    Ok(statuses)
 }
 
-#[derive(Debug, Clone)]
-pub struct ResolutionResult {
-    pub resolved: bool,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_resolution() {
+        assert_eq!(check_resolution(false, None), ResolutionStatus::Open);
+        assert_eq!(check_resolution(true, None), ResolutionStatus::Closed);
+        assert_eq!(check_resolution(true, Some("proposed")), ResolutionStatus::Closed);
+        assert_eq!(check_resolution(true, Some("disputed")), ResolutionStatus::Disputed);
+        assert_eq!(check_resolution(true, Some("Resolved")), ResolutionStatus::Resolved);
+    }
+
+    fn scheduler() -> CtfScheduler {
+        CtfScheduler::new(&CtfConfig {
+            max_gas_gwei: Decimal::new(100, 0),
+            max_defer_secs: 3600,
+        })
+    }
+
+    #[test]
+    fn test_ctf_scheduler_defers_on_high_gas() {
+        let mut sched = scheduler();
+        sched.enqueue(CtfOperation::Merge {
+            condition_id: "a".into(),
+            amount: Decimal::ONE,
+        });
+        sched.enqueue(CtfOperation::Split {
+            condition_id: "b".into(),
+            amount: Decimal::ONE,
+        });
+
+        // High gas: only the urgent split runs
+        let due = sched.take_due(Some(Decimal::new(500, 0)), Instant::now());
+        assert_eq!(due.len(), 1);
+        assert!(due[0].is_urgent());
+        assert!(!sched.is_empty());
+
+        // Gas drops: the merge runs
+        let due = sched.take_due(Some(Decimal::new(50, 0)), Instant::now());
+        assert_eq!(due.len(), 1);
+        assert!(sched.is_empty());
+    }
+
+    #[test]
+    fn test_ctf_scheduler_runs_overdue() {
+        let mut sched = scheduler();
+        sched.enqueue(CtfOperation::Redeem {
+            condition_id: "a".into(),
+            amount: Decimal::ONE,
+        });
+        let later = Instant::now() + Duration::from_secs(3601);
+        assert_eq!(sched.take_due(Some(Decimal::new(500, 0)), later).len(), 1);
+    }
 }
@@ -0,0 +1,47 @@
+//! Polymarket liquidity provider engine.
+//!
+//! The `polymarket-lp` binary is a thin CLI over this crate. To embed the
+//! quoting engine, build a [`engine::QuoteEngine`] from a
+//! [`scanner::MarketInfo`] and [`config::StrategyConfig`], then drive it with
+//! `tick_live` against any [`exchange::ExchangeApi`] implementation (or
+//! `tick_dry_run` against any [`exchange::MarketData`]). [`manager::MarketManager`]
+//! does the same across many markets with capital allocation and rate limiting.
+//! [`runner::Runner`] is the single-market event loop the CLI drives an
+//! engine with, live or dry-run.
+//!
+//! Pure quoting math lives in [`quoter`] and [`risk`] and needs no I/O.
+
+pub mod alerts;
+pub mod anomaly;
+pub mod canary;
+pub mod client;
+pub mod clock;
+pub mod config;
+pub mod credentials;
+pub mod crowding;
+pub mod db;
+pub mod deadman;
+pub mod engine;
+pub mod exchange;
+pub mod experiment;
+pub mod fill_model;
+pub mod fills;
+pub mod inventory;
+pub mod manager;
+pub mod metrics;
+pub mod midpoint;
+pub mod orders;
+pub mod prices;
+pub mod prometheus;
+pub mod quoter;
+pub mod risk;
+pub mod rpc;
+pub mod runner;
+pub mod scanner;
+pub mod secrets;
+pub mod signals;
+pub mod snapshots;
+pub mod states;
+pub mod supervisor;
+pub mod telemetry;
+pub mod ws;
@@ -1,26 +1,23 @@
-mod client;
-mod config;
-mod engine;
-mod inventory;
-mod manager;
-mod metrics;
-mod orders;
-mod quoter;
-mod risk;
-mod scanner;
-mod ws;
-
-use anyhow::{bail, Result};
+use polymarket_lp::exchange::{self, ExchangeApi, MarketData};
+use polymarket_lp::{
+    anomaly, canary, client, config, db, deadman, engine, experiment, fill_model, inventory,
+    manager, metrics, orders, prices, quoter, risk, runner, scanner, secrets, signals, snapshots,
+    states, supervisor, telemetry, ws,
+};
+use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand};
 use comfy_table::{presets::UTF8_FULL, Table};
 use polymarket_client_sdk::auth::{LocalSigner, Signer};
+use polymarket_client_sdk::clob::types::Side;
+use polymarket_client_sdk::clob::types::request::{MidpointRequest, OrderBookSummaryRequest};
 use polymarket_client_sdk::POLYGON;
 use rust_decimal::Decimal;
-use std::path::PathBuf;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use tokio::signal;
 use tracing::{info, warn};
-use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
 #[command(name = "polymarket-lp", about = "Polymarket liquidity provider bot")]
@@ -58,9 +55,219 @@ enum Commands {
         /// Run across multiple markets (auto-select based on config)
         #[arg(long)]
         multi: bool,
+        /// Show each market's first order ladder and wait for confirmation
+        #[arg(long, requires = "live")]
+        confirm: bool,
+        /// Show each market's first order ladder without waiting
+        #[arg(long, requires = "live")]
+        yes: bool,
+        /// Leave orders resting at shutdown for the next run to adopt
+        #[arg(long, requires = "live")]
+        keep_orders_on_exit: bool,
+        /// Multi-market run where only this market gets the configured
+        /// strategy; the rest keep the last recorded profile
+        #[arg(long, requires = "live")]
+        canary: Option<String>,
     },
     /// Show current status, positions, and PnL
-    Status,
+    Status {
+        /// Compare against the latest snapshot at least this old (e.g. 1h)
+        /// instead of the previous `status` run
+        #[arg(long, value_parser = prices::parse_span)]
+        since: Option<std::time::Duration>,
+    },
+    /// Simulate the quote ladder for given parameters (no API calls)
+    Simulate {
+        /// Midpoint price of the YES token
+        #[arg(long)]
+        midpoint: Decimal,
+        /// Tick size
+        #[arg(long, default_value = "0.01")]
+        tick: Decimal,
+        /// Taker fee rate in basis points (omit for fee-free markets)
+        #[arg(long)]
+        fee_bps: Option<u32>,
+        /// Base offset from midpoint in cents [default: strategy.base_offset_ticks
+        /// at --tick, else strategy.base_offset_cents]
+        #[arg(long)]
+        offset: Option<Decimal>,
+        /// Minimum offset in cents [default: strategy.min_offset_ticks at
+        /// --tick, else strategy.min_offset_cents]
+        #[arg(long)]
+        min_offset: Option<Decimal>,
+        /// Order size per level [default: strategy.order_size]
+        #[arg(long)]
+        size: Option<Decimal>,
+        /// Number of levels [default: strategy.num_levels, or as many as fit
+        /// the band with strategy.dynamic_levels]
+        #[arg(long)]
+        levels: Option<u32>,
+        /// Reward max spread from midpoint (price units, e.g. 0.03)
+        #[arg(long)]
+        max_spread: Option<Decimal>,
+        /// Reward minimum order size
+        #[arg(long)]
+        min_size: Option<Decimal>,
+        /// Inventory skew in [-0.5, 0.5] (positive = long)
+        #[arg(long, default_value = "0", allow_hyphen_values = true)]
+        skew: Decimal,
+    },
+    /// Reward calculators
+    Rewards {
+        #[command(subcommand)]
+        action: RewardsCommand,
+    },
+    /// Show USDC balance, exchange allowances, and token positions
+    Balances {
+        /// Print machine-readable JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+    /// List open orders on the exchange grouped by market
+    Orders,
+    /// Show past trades with realized PnL, volume, and fees per market
+    History {
+        /// Start date (YYYY-MM-DD, UTC). Defaults to 7 days ago
+        #[arg(long)]
+        from: Option<chrono::NaiveDate>,
+        /// End date (YYYY-MM-DD, UTC, inclusive). Defaults to today
+        #[arg(long)]
+        to: Option<chrono::NaiveDate>,
+    },
+    /// Cancel open orders on the exchange (e.g. after a crashed session)
+    Cancel {
+        /// Cancel every open order for the wallet
+        #[arg(long, conflicts_with_all = ["market", "order"])]
+        all: bool,
+        /// Cancel all orders in a market (condition ID)
+        #[arg(short, long, conflicts_with = "order")]
+        market: Option<String>,
+        /// Cancel specific orders by ID (repeatable)
+        #[arg(short, long)]
+        order: Vec<String>,
+    },
+    /// Cancel open orders in markets the bot doesn't track
+    Cleanup {
+        /// Report orphan orders without cancelling them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show what's known about one market: Gamma details, reward params,
+    /// book stats, and our orders, inventory, and PnL if we trade it
+    Market {
+        /// Market condition ID
+        condition_id: String,
+        /// Print machine-readable JSON instead of tables
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a market's order book for both tokens, marking our orders
+    /// and the reward band
+    Book {
+        /// Market condition ID
+        condition_id: String,
+        /// Book levels to show on each side (ours are always shown)
+        #[arg(short, long, default_value = "10")]
+        depth: usize,
+    },
+    /// Show a market's YES price history as a table and sparkline, to judge
+    /// how steady it is before quoting it
+    Prices {
+        /// Market condition ID
+        condition_id: String,
+        /// Time between points (e.g. 5m, 1h, 1d)
+        #[arg(long, default_value = "1h", value_parser = prices::parse_span)]
+        interval: std::time::Duration,
+        /// How far back to go (e.g. 1d, 7d, 4w)
+        #[arg(long, default_value = "7d", value_parser = prices::parse_span)]
+        range: std::time::Duration,
+    },
+    /// Pause, unwind, or retire one market in the multi-market bot without
+    /// a restart, or list the markets not active
+    State {
+        /// Market condition ID
+        condition_id: Option<String>,
+        /// active, paused, unwinding, removing, or retired
+        #[arg(requires = "condition_id")]
+        state: Option<states::EngineState>,
+    },
+    /// Quote a specific market in the multi-market bot alongside the ranked
+    /// ones, funded from unallocated capital, without a restart
+    AddMarket {
+        /// Market condition ID
+        condition_id: String,
+    },
+    /// Stop quoting a market in the multi-market bot: cancel its orders,
+    /// record its final PnL, and free its allocation
+    RemoveMarket {
+        /// Market condition ID
+        condition_id: String,
+        /// Sell down its inventory before removing it
+        #[arg(long)]
+        unwind: bool,
+    },
+    /// Clear a tripped kill switch so quoting can restart
+    Resume {
+        /// Acknowledge the loss that tripped the kill switch
+        #[arg(long)]
+        ack_loss: bool,
+    },
+    /// Apply `[retention]` to the history database and reclaim the space, and
+    /// compact metrics archives past `monitoring.metrics_retention_days`
+    Compact,
+    /// Run read-only SQL over the history database (tables: orders, fills,
+    /// quotes, risk_events)
+    Query {
+        /// e.g. "SELECT * FROM fills WHERE markout_cents < -2"
+        sql: String,
+    },
+    /// Manage cached API credentials
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+    /// Create or check the encrypted secrets file
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Create API credentials under the next nonce and cache them in place
+    /// of the current ones
+    Refresh,
+}
+
+#[derive(Subcommand)]
+enum SecretsCommand {
+    /// Encrypt a plaintext TOML secrets file with a passphrase
+    Encrypt {
+        /// Plaintext file with private_key, [api], and telegram_bot_token
+        input: PathBuf,
+        /// Where to write the encrypted file (default: wallet.secrets_file)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Decrypt the configured secrets file and list what it holds
+    Check,
+}
+
+#[derive(Subcommand)]
+enum RewardsCommand {
+    /// Estimate our score share and $/day for a quote configuration
+    Estimate {
+        /// Market condition ID
+        #[arg(short, long)]
+        market: String,
+        /// Order size on each side
+        #[arg(short, long)]
+        size: Decimal,
+        /// Distance from midpoint in cents
+        #[arg(long)]
+        spread: Decimal,
+    },
 }
 
 #[tokio::main]
@@ -70,24 +277,14 @@ async fn main() -> Result<()> {
     let config = if cli.config.exists() {
         config::Config::load(&cli.config)?
     } else {
-        config::Config {
-            wallet: config::WalletConfig {
-                private_key_env: "POLYMARKET_PRIVATE_KEY".into(),
-                signature_type: "eoa".into(),
-            },
-            strategy: config::StrategyConfig::default(),
-            markets: config::MarketsConfig::default(),
-            risk: config::RiskConfig::default(),
-            monitoring: config::MonitoringConfig::default(),
-        }
+        config::Config::default()
     };
+    if let Commands::Secrets { action } = &cli.command {
+        return cmd_secrets(&config, action);
+    }
+    let config = config.unlock_secrets()?;
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new(&config.monitoring.log_level)),
-        )
-        .init();
+    let _telemetry = telemetry::init(&config.monitoring)?;
 
     match cli.command {
         Commands::Scan { min_reward, limit } => {
@@ -98,30 +295,172 @@ async fn main() -> Result<()> {
             market,
             no_ws,
             multi,
+            confirm,
+            yes,
+            keep_orders_on_exit,
+            canary,
         } => {
-            if multi {
-                cmd_run_multi(&config, live).await?;
+            let keep_orders = keep_orders_on_exit || config.monitoring.keep_orders_on_exit;
+            let confirm = if yes {
+                Some(engine::ConfirmMode::AutoYes)
+            } else if confirm {
+                Some(engine::ConfirmMode::Prompt)
+            } else {
+                None
+            };
+            if multi || canary.is_some() {
+                cmd_run_multi(&config, &cli.config, live, confirm, canary, keep_orders).await?;
             } else {
-                cmd_run(&config, live, market, no_ws).await?;
+                cmd_run(
+                    &config,
+                    &cli.config,
+                    live,
+                    market,
+                    no_ws,
+                    confirm,
+                    keep_orders,
+                )
+                .await?;
+            }
+        }
+        Commands::Status { since } => {
+            cmd_status(&config, since).await?;
+        }
+        Commands::Simulate {
+            midpoint,
+            tick,
+            fee_bps,
+            offset,
+            min_offset,
+            size,
+            levels,
+            max_spread,
+            min_size,
+            skew,
+        } => {
+            let dynamic = levels.is_none() && config.strategy.dynamic_levels;
+            let mut params = quoter::QuoteParams {
+                midpoint,
+                base_offset_cents: offset.unwrap_or_else(|| {
+                    quoter::offset_cents(
+                        config.strategy.base_offset_cents,
+                        config.strategy.base_offset_ticks,
+                        tick,
+                    )
+                }),
+                min_offset_cents: min_offset.unwrap_or_else(|| {
+                    quoter::offset_cents(
+                        config.strategy.min_offset_cents,
+                        config.strategy.min_offset_ticks,
+                        tick,
+                    )
+                }),
+                tick_size: tick,
+                order_size: size.unwrap_or(config.strategy.order_size),
+                num_levels: levels.unwrap_or(config.strategy.num_levels),
+                fee_rate_bps: fee_bps,
+                max_incentive_spread: max_spread,
+                min_incentive_size: min_size,
+                inventory_skew: skew,
+                shade: Decimal::ZERO,
+                tick_spaced_levels: dynamic,
+                size_skew: config.strategy.size_skew,
+            };
+            if dynamic {
+                params.num_levels = quoter::band_levels(&params, config.strategy.max_levels);
             }
+            cmd_simulate(&params);
+        }
+        Commands::Rewards {
+            action: RewardsCommand::Estimate {
+                market,
+                size,
+                spread,
+            },
+        } => {
+            cmd_rewards_estimate(&config, &market, size, spread).await?;
+        }
+        Commands::Balances { json } => {
+            cmd_balances(&config, json).await?;
+        }
+        Commands::Orders => {
+            cmd_orders(&config).await?;
+        }
+        Commands::History { from, to } => {
+            cmd_history(&config, from, to).await?;
         }
-        Commands::Status => {
-            cmd_status(&config).await?;
+        Commands::Cancel { all, market, order } => {
+            cmd_cancel(&config, all, market, order).await?;
         }
+        Commands::Cleanup { dry_run } => {
+            cmd_cleanup(&config, dry_run).await?;
+        }
+        Commands::Market { condition_id, json } => {
+            cmd_market(&config, &condition_id, json).await?;
+        }
+        Commands::Book {
+            condition_id,
+            depth,
+        } => {
+            cmd_book(&config, &condition_id, depth).await?;
+        }
+        Commands::Prices {
+            condition_id,
+            interval,
+            range,
+        } => {
+            cmd_prices(&config, &condition_id, interval, range).await?;
+        }
+        Commands::State {
+            condition_id,
+            state,
+        } => {
+            cmd_state(&config, condition_id, state)?;
+        }
+        Commands::AddMarket { condition_id } => {
+            cmd_add_market(&config, &condition_id)?;
+        }
+        Commands::RemoveMarket {
+            condition_id,
+            unwind,
+        } => {
+            cmd_remove_market(&config, &condition_id, unwind)?;
+        }
+        Commands::Resume { ack_loss } => {
+            cmd_resume(&config, ack_loss)?;
+        }
+        Commands::Compact => {
+            cmd_compact(&config)?;
+        }
+        Commands::Query { sql } => {
+            cmd_query(&config, &sql)?;
+        }
+        Commands::Auth {
+            action: AuthCommand::Refresh,
+        } => {
+            cmd_auth_refresh(&config).await?;
+        }
+        Commands::Secrets { .. } => unreachable!("handled before unlocking secrets"),
     }
 
     Ok(())
 }
 
 async fn cmd_scan(config: &config::Config, min_reward: Option<f64>, limit: usize) -> Result<()> {
-    let gamma_client = client::create_gamma_client()?;
-    let all_markets = scanner::scan_markets(&gamma_client).await?;
+    let gamma_client = client::create_gamma_client(config)?;
+    let all_markets =
+        scanner::scan_markets(&gamma_client, config.markets.score_weights.as_ref()).await?;
 
     let min_reward_dec = min_reward
         .map(|v| Decimal::try_from(v).unwrap_or(config.markets.min_reward_daily))
         .unwrap_or(config.markets.min_reward_daily);
 
-    let ranked = scanner::rank_markets(&all_markets, min_reward_dec, limit);
+    let ranked = scanner::rank_markets(
+        &all_markets,
+        min_reward_dec,
+        limit,
+        config.markets.prefer_fee_enabled,
+    );
 
     if ranked.is_empty() {
         println!("No markets found matching criteria (min_reward=${min_reward_dec}/day)");
@@ -135,11 +474,7 @@ async fn cmd_scan(config: &config::Config, min_reward: Option<f64>, limit: usize
     ]);
 
     for (i, m) in ranked.iter().enumerate() {
-        let question = if m.question.len() > 50 {
-            format!("{}...", &m.question[..47])
-        } else {
-            m.question.clone()
-        };
+        let question = metrics::short_question(&m.question, 50);
         table.add_row(vec![
             format!("{}", i + 1),
             question,
@@ -166,18 +501,34 @@ async fn cmd_scan(config: &config::Config, min_reward: Option<f64>, limit: usize
 
 async fn cmd_run(
     config: &config::Config,
+    config_path: &Path,
     live: bool,
     market: Option<String>,
     no_ws: bool,
+    confirm: Option<engine::ConfirmMode>,
+    keep_orders: bool,
 ) -> Result<()> {
     let dry_run = !live;
     if dry_run {
         info!("DRY-RUN mode (use --live to place real orders)");
+    } else {
+        // The latch is shared with multi-market runs, so a single-market run
+        // can't be used to trade around it
+        let risk_state = risk::RiskState::load(std::path::Path::new(&config.risk.state_file))?;
+        if let Some(latch) = risk_state.kill_switch {
+            bail!(
+                "Kill switch latched since {} (PnL ${:.2}, limit -${:.2}) — run `resume --ack-loss` before trading live",
+                latch.tripped_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                latch.total_pnl,
+                latch.threshold
+            );
+        }
     }
 
     // Find the target market
-    let gamma_client = client::create_gamma_client()?;
-    let markets = scanner::scan_markets(&gamma_client).await?;
+    let gamma_client = client::create_gamma_client(config)?;
+    let markets =
+        scanner::scan_markets(&gamma_client, config.markets.score_weights.as_ref()).await?;
 
     let target = if let Some(ref cond_id) = market {
         markets
@@ -185,9 +536,14 @@ async fn cmd_run(
             .find(|m| m.condition_id.starts_with(cond_id))
             .cloned()
     } else {
-        scanner::rank_markets(&markets, config.markets.min_reward_daily, 1)
-            .into_iter()
-            .next()
+        scanner::rank_markets(
+            &markets,
+            config.markets.min_reward_daily,
+            1,
+            config.markets.prefer_fee_enabled,
+        )
+        .into_iter()
+        .next()
     };
 
     let target = match target {
@@ -201,28 +557,80 @@ async fn cmd_run(
         "Selected market"
     );
 
-    let tick_interval = std::time::Duration::from_secs(config.strategy.requote_interval_secs);
+    let mut strategy = config.strategy.clone();
+    config
+        .markets
+        .apply_overrides(&target.condition_id, &mut strategy);
 
     if live {
         let auth_client = client::create_authenticated_client(config).await?;
         let private_key = config.private_key()?;
         let signer = LocalSigner::from_str(&private_key)?.with_chain_id(Some(POLYGON));
 
-        let mut engine_inst =
-            engine::QuoteEngine::new(target.clone(), config.strategy.clone(), false);
+        orders::validate_wallet_setup(&auth_client, &signer, &target.token_yes_id).await?;
+        let owner = client::wallet_address(config, &signer)?;
+        let exchange = client::Timed::new(
+            exchange::SdkExchange::new(
+                auth_client.clone(),
+                &signer,
+                client::signature_type(config),
+            )
+            .with_reauth(config)
+            .with_deadman(config.risk.deadman_secs),
+            config.endpoints.clob_timeout_secs,
+        );
+        if config.risk.cancel_on_panic {
+            deadman::install_panic_cancel(config);
+        }
+
+        let mut engine_inst = engine::QuoteEngine::new(target.clone(), strategy, false)
+            .with_exit_limits(risk::ExitLimits::from_config(&config.risk))
+            .with_flow_limits(anomaly::FlowLimits::from_config(&config.risk))
+            .with_deadman(config.risk.deadman_secs)
+            .with_database(db::Database::open_configured(&config.monitoring)?);
+        engine_inst.capital_limit =
+            Some(config.risk.max_per_market.min(config.risk.max_total_capital));
+        engine_inst.confirm = confirm;
+        engine_inst.seed_volatility(&exchange).await;
+
+        // Take over whatever the last run left resting in this market;
+        // anything it left elsewhere is cancelled
+        let retained_path = std::path::Path::new(&config.monitoring.retained_orders_file);
+        if let Some(mut retained) = orders::RetainedOrders::take(retained_path)? {
+            let market = retained.markets.remove(&target.condition_id);
+            let elsewhere = retained.active_ids();
+            if !elsewhere.is_empty() {
+                orders::cancel_orders(&exchange, &elsewhere).await?;
+            }
+            if let Some(market) = market {
+                engine_inst.adopt_orders(&exchange, market).await?;
+            }
+        }
+        cleanup_orphans_on_start(
+            &exchange,
+            HashSet::from([target.condition_id.clone()]),
+            config,
+        )
+        .await;
 
         // Start WebSocket if not disabled
+        let mut watchdog =
+            supervisor::Supervisor::new(supervisor::RestartPolicy::from_config(&config.monitoring));
         let ws_manager = if !no_ws {
             let token_ids = vec![target.token_yes_id.clone(), target.token_no_id.clone()];
-            let creds = Some((
-                auth_client.credentials().clone(),
-                auth_client.address(),
-            ));
-            match ws::WsManager::start(token_ids, Some(target.condition_id.clone()), creds).await {
-                Ok((mgr, rx)) => {
-                    engine_inst.ws_connected = true;
+            let creds = Some((auth_client.credentials().clone(), owner));
+            match ws::WsManager::start(
+                &config.endpoints,
+                token_ids,
+                Some(target.condition_id.clone()),
+                creds,
+                &mut watchdog,
+            )
+            .await
+            {
+                Ok(ws) => {
                     info!("WebSocket connected");
-                    Some((mgr, rx))
+                    Some(ws)
                 }
                 Err(e) => {
                     warn!(error = %e, "Failed to start WebSocket, falling back to REST");
@@ -233,85 +641,35 @@ async fn cmd_run(
             None
         };
 
-        info!("Starting LIVE quoting loop (Ctrl+C to stop)...");
-
-        if let Some((mgr, mut ws_rx)) = ws_manager {
-            // WS-driven loop: react to WS events, fallback to REST on disconnect
-            loop {
-                tokio::select! {
-                    _ = signal::ctrl_c() => {
-                        info!("Shutdown signal received, cancelling all orders...");
-                        mgr.shutdown();
-                        if let Err(e) = engine_inst.cancel_all(&auth_client).await {
-                            warn!(error = %e, "Error cancelling orders during shutdown");
-                        }
-                        break;
-                    }
-                    Some(event) = ws_rx.recv() => {
-                        let should_requote = engine_inst.handle_ws_event(event);
-                        if should_requote {
-                            if let Some(mid) = engine_inst.last_midpoint {
-                                let quotes = engine_inst.compute_quotes(mid);
-                                // Cancel stale + place new
-                                let stale: Vec<String> = engine_inst.tracked_orders.iter()
-                                    .filter(|o| o.status == orders::OrderStatus::Open || o.status == orders::OrderStatus::PartiallyFilled)
-                                    .map(|o| o.order_id.clone())
-                                    .collect();
-                                if !stale.is_empty() {
-                                    let _ = orders::cancel_orders(&auth_client, &stale).await;
-                                }
-                                match orders::place_quotes(&auth_client, &signer, &engine_inst.market.token_yes_id, &engine_inst.market.token_no_id, &quotes).await {
-                                    Ok(new_orders) => {
-                                        engine_inst.tracked_orders = new_orders;
-                                        engine_inst.current_quotes = quotes;
-                                        engine_inst.last_requote = Some(std::time::Instant::now());
-                                    }
-                                    Err(e) => warn!(error = %e, "Failed to place orders"),
-                                }
-                            }
-                        }
-                    }
-                    // Fallback REST tick when WS is disconnected
-                    _ = tokio::time::sleep(tick_interval), if !engine_inst.ws_connected => {
-                        if let Err(e) = engine_inst.tick_live(&auth_client, &signer).await {
-                            warn!(error = %e, "REST fallback tick error");
-                        }
-                    }
-                }
-            }
-        } else {
-            // Pure REST loop (no WS)
-            loop {
-                tokio::select! {
-                    _ = signal::ctrl_c() => {
-                        info!("Shutdown signal received, cancelling all orders...");
-                        if let Err(e) = engine_inst.cancel_all(&auth_client).await {
-                            warn!(error = %e, "Error cancelling orders during shutdown");
-                        }
-                        break;
-                    }
-                    result = engine_inst.tick_live(&auth_client, &signer) => {
-                        if let Err(e) = result {
-                            warn!(error = %e, "Engine tick error");
-                        }
-                    }
-                }
-                tokio::time::sleep(tick_interval).await;
-            }
+        let mut executor = runner::LiveExecutor::new(&exchange, config);
+        executor.check_clock_skew().await;
+        let mut run = runner::Runner::new(engine_inst, executor, config, config_path)
+            .with_watchdog(watchdog)
+            .with_keep_orders(keep_orders);
+        if let Some((mgr, rx)) = ws_manager {
+            run = run.with_ws(mgr, rx);
         }
+        info!("Starting LIVE quoting loop (Ctrl+C to stop)...");
+        run.run().await?;
     } else {
         // Dry-run mode with optional WS for midpoint
-        let clob_client = client::create_unauthenticated_client()?;
-        let mut engine_inst =
-            engine::QuoteEngine::new(target.clone(), config.strategy.clone(), true);
+        let clob_client = client::Timed::new(
+            client::create_unauthenticated_client(config)?,
+            config.endpoints.clob_timeout_secs,
+        );
+        let mut engine_inst = engine::QuoteEngine::new(target.clone(), strategy, true);
+        engine_inst.seed_volatility(&clob_client).await;
 
+        let mut watchdog =
+            supervisor::Supervisor::new(supervisor::RestartPolicy::from_config(&config.monitoring));
         let ws_manager = if !no_ws {
             let token_ids = vec![target.token_yes_id.clone(), target.token_no_id.clone()];
-            match ws::WsManager::start(token_ids, None, None).await {
-                Ok((mgr, rx)) => {
-                    engine_inst.ws_connected = true;
+            match ws::WsManager::start(&config.endpoints, token_ids, None, None, &mut watchdog)
+                .await
+            {
+                Ok(ws) => {
                     info!("WebSocket connected (dry-run)");
-                    Some((mgr, rx))
+                    Some(ws)
                 }
                 Err(e) => {
                     warn!(error = %e, "Failed to start WebSocket, using REST polling");
@@ -322,79 +680,206 @@ async fn cmd_run(
             None
         };
 
+        let executor = runner::DryRunExecutor::new(&clob_client);
+        let mut run =
+            runner::Runner::new(engine_inst, executor, config, config_path).with_watchdog(watchdog);
+        if let Some((mgr, rx)) = ws_manager {
+            run = run.with_ws(mgr, rx);
+        }
         info!("Starting DRY-RUN quoting loop (Ctrl+C to stop)...");
+        run.run().await?;
+    }
 
-        if let Some((mgr, mut ws_rx)) = ws_manager {
-            loop {
-                tokio::select! {
-                    _ = signal::ctrl_c() => {
-                        mgr.shutdown();
-                        info!("Shutdown signal received");
-                        break;
-                    }
-                    Some(event) = ws_rx.recv() => {
-                        let should_requote = engine_inst.handle_ws_event(event);
-                        if should_requote {
-                            if let Some(mid) = engine_inst.last_midpoint {
-                                let quotes = engine_inst.compute_quotes(mid);
-                                engine_inst.log_dry_run_quotes(&quotes, mid);
-                                engine_inst.current_quotes = quotes;
-                                engine_inst.last_requote = Some(std::time::Instant::now());
-                            }
-                        }
-                    }
-                    _ = tokio::time::sleep(tick_interval), if !engine_inst.ws_connected => {
-                        if let Err(e) = engine_inst.tick_dry_run(&clob_client).await {
-                            warn!(error = %e, "REST fallback tick error");
-                        }
-                    }
-                }
+    info!("Quoting engine stopped");
+    Ok(())
+}
+
+/// Markets the bot manages, per its persisted metrics, retained orders,
+/// and `markets.manual_markets`; orders anywhere else are orphans.
+fn tracked_markets(config: &config::Config) -> Result<HashSet<String>> {
+    let mut tracked: HashSet<String> = config.markets.manual_markets.iter().cloned().collect();
+    let metrics_path = Path::new(&config.monitoring.metrics_file);
+    if metrics_path.exists() {
+        tracked.extend(
+            metrics::PortfolioMetrics::load(metrics_path)?
+                .markets
+                .into_keys(),
+        );
+    }
+    let retained_path = Path::new(&config.monitoring.retained_orders_file);
+    if let Some(retained) = orders::RetainedOrders::load(retained_path)? {
+        tracked.extend(retained.markets.into_keys());
+    }
+    Ok(tracked)
+}
+
+/// Cancel open orders in markets this run won't manage, if enabled.
+async fn cleanup_orphans_on_start(
+    exchange: &impl ExchangeApi,
+    managed: HashSet<String>,
+    config: &config::Config,
+) {
+    if !config.monitoring.cleanup_orphans_on_start {
+        return;
+    }
+    match orders::cleanup_orphans(exchange, &managed, false).await {
+        Ok(report) if report.orphans.is_empty() => info!("No orphan orders found"),
+        Ok(report) => info!(
+            markets = report.orphans.len(),
+            orders = report.orphan_count(),
+            cancelled = report.cancelled,
+            "Cleaned up orphan orders"
+        ),
+        Err(e) => warn!(error = %e, "Orphan order cleanup failed"),
+    }
+}
+
+/// Act on a runtime control signal in a multi-market run.
+async fn apply_control(
+    control: signals::Control,
+    mgr: &mut manager::MarketManager,
+    exchange: &impl ExchangeApi,
+    config_path: &Path,
+) {
+    match control {
+        signals::Control::TogglePause => {
+            let paused = !mgr.paused;
+            if let Err(e) = mgr.set_paused(exchange, paused).await {
+                warn!(error = %e, "Failed to cancel orders on pause");
             }
-        } else {
-            loop {
-                tokio::select! {
-                    _ = signal::ctrl_c() => {
-                        info!("Shutdown signal received");
-                        break;
-                    }
-                    result = engine_inst.tick_dry_run(&clob_client) => {
-                        if let Err(e) = result {
-                            warn!(error = %e, "Engine tick error");
-                        }
-                    }
-                }
-                tokio::time::sleep(tick_interval).await;
+        }
+        signals::Control::DumpStatus => {
+            info!("Status snapshot\n{}", mgr.status_snapshot());
+            mgr.save_metrics().await;
+        }
+        signals::Control::Reload => {
+            let reloaded =
+                config::Config::load(config_path).and_then(|config| mgr.reload_config(config));
+            if let Err(e) = reloaded {
+                warn!(error = %e, "Config reload failed, keeping current settings");
             }
         }
     }
-
-    info!("Quoting engine stopped");
-    Ok(())
 }
 
-async fn cmd_run_multi(config: &config::Config, live: bool) -> Result<()> {
+async fn cmd_run_multi(
+    config: &config::Config,
+    config_path: &Path,
+    live: bool,
+    confirm: Option<engine::ConfirmMode>,
+    canary: Option<String>,
+    keep_orders: bool,
+) -> Result<()> {
     if !live {
         bail!("Multi-market mode requires --live flag");
     }
 
+    // A canary run compares against the profile of the last normal run;
+    // normal runs record theirs
+    let profile_path = std::path::Path::new(&config.monitoring.strategy_profile_file);
+    let canary = match canary {
+        Some(condition_id) => {
+            let Some(baseline) = canary::load_profile(profile_path)? else {
+                bail!(
+                    "No strategy profile at {}; run once without --canary to record the baseline",
+                    profile_path.display()
+                );
+            };
+            let changes = canary::changed_settings(&baseline, &config.strategy);
+            info!(
+                market = %condition_id,
+                changes = %changes.join(", "),
+                "Canary run: new strategy on one market only"
+            );
+            Some(canary::CanaryRun {
+                condition_id,
+                baseline,
+            })
+        }
+        None => {
+            canary::save_profile(profile_path, &config.strategy)?;
+            None
+        }
+    };
+
     let auth_client = client::create_authenticated_client(config).await?;
     let private_key = config.private_key()?;
     let signer = LocalSigner::from_str(&private_key)?.with_chain_id(Some(POLYGON));
 
-    let gamma_client = client::create_gamma_client()?;
-    let markets = scanner::scan_markets(&gamma_client).await?;
-    let ranked = scanner::rank_markets(
+    let gamma_client = client::create_gamma_client(config)?;
+    let markets =
+        scanner::scan_markets(&gamma_client, config.markets.score_weights.as_ref()).await?;
+    let mut ranked = scanner::rank_markets(
         &markets,
         config.markets.min_reward_daily,
         config.markets.max_markets,
+        config.markets.prefer_fee_enabled,
     );
 
+    // The canary is quoted even if it didn't make the ranking
+    if let Some(run) = &canary
+        && !ranked.iter().any(|m| m.condition_id == run.condition_id)
+    {
+        match scanner::fetch_markets(&gamma_client, std::slice::from_ref(&run.condition_id))
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(market) => ranked.push(market),
+            None => bail!("Canary market {} not found or not active", run.condition_id),
+        }
+    }
+
     if ranked.is_empty() {
         bail!("No suitable markets found");
     }
+    orders::validate_wallet_setup(&auth_client, &signer, &ranked[0].token_yes_id).await?;
+    let exchange = client::Timed::new(
+        exchange::SdkExchange::new(auth_client.clone(), &signer, client::signature_type(config))
+            .with_reauth(config)
+            .with_deadman(config.risk.deadman_secs),
+        config.endpoints.clob_timeout_secs,
+    );
+    if config.risk.cancel_on_panic {
+        deadman::install_panic_cancel(config);
+    }
 
     let mut mgr = manager::MarketManager::new(config.clone());
+    mgr.confirm = confirm;
+    mgr.database = db::Database::open_configured(&config.monitoring)?;
+    mgr.canary = canary;
+    mgr.experiment = experiment::Experiment::from_config(&config.experiment, &config.strategy)?;
+    mgr.load_risk_state()?;
+    mgr.load_market_states()?;
+    mgr.load_fill_model()?;
     mgr.initialize_markets(ranked);
+    mgr.seed_volatility(&exchange).await;
+
+    // On-chain balances are the source of truth for inventory when an RPC is configured
+    let rpc = client::create_rpc_client(config);
+    let owner = client::wallet_address(config, &signer)?;
+    if let Some(ref rpc) = rpc
+        && let Err(e) = mgr.reconcile_onchain(rpc, owner).await
+    {
+        warn!(error = %e, "On-chain inventory reconciliation failed");
+    }
+
+    // Take over whatever the last run left resting
+    let retained_path = std::path::Path::new(&config.monitoring.retained_orders_file);
+    if let Some(retained) = orders::RetainedOrders::take(retained_path)? {
+        mgr.adopt_orders(&exchange, retained).await?;
+    }
+    cleanup_orphans_on_start(&exchange, mgr.engines.keys().cloned().collect(), config).await;
+
+    match exchange.usdc_balance().await {
+        Ok(balance) if balance < config.risk.max_total_capital => warn!(
+            balance = %balance,
+            max_total_capital = %config.risk.max_total_capital,
+            "USDC balance below configured capital"
+        ),
+        Ok(balance) => info!(balance = %balance, "USDC balance"),
+        Err(e) => warn!(error = %e, "USDC balance check failed"),
+    }
 
     info!(
         markets = mgr.engines.len(),
@@ -402,41 +887,161 @@ async fn cmd_run_multi(config: &config::Config, live: bool) -> Result<()> {
     );
 
     let tick_interval = std::time::Duration::from_secs(config.strategy.requote_interval_secs);
+    let mut controls = signals::Controls::install()?;
+
+    let mut watchdog =
+        supervisor::Supervisor::new(supervisor::RestartPolicy::from_config(&config.monitoring));
+    // Tier-1 markets get WebSocket midpoints; the rest are polled over REST
+    let ws_token_ids = mgr.ws_token_ids();
+    let (ws_manager, mut ws_rx) = if ws_token_ids.is_empty() {
+        (None, None)
+    } else {
+        match ws::WsManager::start(&config.endpoints, ws_token_ids, None, None, &mut watchdog).await
+        {
+            Ok((ws_mgr, rx)) => {
+                info!("WebSocket connected for tier-1 markets");
+                for engine in mgr.engines.values_mut() {
+                    engine.ws_connected = engine.tier == engine::Tier::One;
+                }
+                (Some(ws_mgr), Some(rx))
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to start WebSocket, using REST polling");
+                (None, None)
+            }
+        }
+    };
 
     loop {
         tokio::select! {
             _ = signal::ctrl_c() => {
-                info!("Shutdown signal received, cancelling all orders...");
-                if let Err(e) = mgr.cancel_all_markets(&auth_client).await {
-                    warn!(error = %e, "Error cancelling orders during shutdown");
+                if let Some(ref ws_mgr) = ws_manager {
+                    ws_mgr.shutdown();
+                }
+                info!("Shutdown signal received");
+                if !(keep_orders && runner::keep_retained(&mgr.retained_orders(), config)) {
+                    info!("Cancelling all orders...");
+                    if let Err(e) = mgr.cancel_all_markets(&exchange).await {
+                        warn!(error = %e, "Error cancelling orders during shutdown");
+                    }
+                }
+                mgr.save_metrics().await;
+                for report in [mgr.canary_report(), mgr.experiment_report()]
+                    .into_iter()
+                    .flatten()
+                {
+                    println!("\n{report}");
                 }
                 break;
             }
-            _ = async {
+            task_failure = async {
                 // Periodic rescan
                 if mgr.needs_rescan() {
-                    if let Err(e) = mgr.rescan(&gamma_client).await {
+                    if let Err(e) = mgr.rescan(&gamma_client, &exchange).await {
                         warn!(error = %e, "Market rescan failed");
                     }
+                    if let Some(ref rpc) = rpc
+                        && let Err(e) = mgr.reconcile_onchain(rpc, owner).await
+                    {
+                        warn!(error = %e, "On-chain inventory reconciliation failed");
+                    }
+                }
+
+                // Catch local clock drift before the exchange rejects us for it
+                if mgr.needs_skew_check() {
+                    mgr.check_clock_skew(&exchange).await;
+                }
+
+                // Track how much reward-band size other makers hold
+                if mgr.needs_crowding_sample() {
+                    mgr.sample_crowding(&exchange).await;
+                }
+
+                // Pick up tick size / reward band changes
+                if mgr.needs_param_refresh()
+                    && let Err(e) = mgr.refresh_market_params(&gamma_client).await
+                {
+                    warn!(error = %e, "Market parameter refresh failed");
+                }
+
+                // Stop quoting closed/disputed markets, redeem resolved ones
+                if mgr.needs_resolution_check() {
+                    if let Err(e) = mgr.check_resolutions(&gamma_client, &exchange).await {
+                        warn!(error = %e, "Resolution check failed");
+                    }
+                    // Run queued merges/redemptions once gas allows
+                    mgr.process_ctf_queue(&exchange.client().auth_client(), rpc.as_ref())
+                        .await;
+                }
+
+                // Pick up markets paused, unwound, or retired with `state`
+                mgr.sync_market_states(&exchange).await;
+                mgr.add_requested_markets(&gamma_client, &exchange).await;
+                // Follow markets added or removed since the last loop
+                if let Some(ref ws_mgr) = ws_manager {
+                    ws_mgr.set_tokens(&mgr.ws_token_ids());
                 }
 
                 // Tick all markets
-                if let Err(e) = mgr.tick_all(&auth_client, &signer).await {
+                if let Err(e) = mgr.tick_all(&exchange).await {
                     warn!(error = %e, "Multi-market tick error");
                 }
+                if mgr.needs_metrics_save() {
+                    mgr.save_metrics().await;
+                }
+                if mgr.needs_history_prune() {
+                    mgr.prune_history().await;
+                }
 
                 // Log portfolio stats periodically
                 let stats = mgr.portfolio_stats();
+                let var = mgr.check_var().await;
+                let util: Vec<Decimal> = mgr
+                    .rate_limiter
+                    .utilization()
+                    .iter()
+                    .map(|u| u.pct().round_dp(1))
+                    .collect();
                 info!(
                     markets = stats.total_markets,
                     active = stats.active_markets,
                     capital = %stats.total_capital_deployed,
+                    committed = %stats.committed_capital,
                     pnl = %stats.total_unrealized_pnl,
+                    var_95 = %var.portfolio_var.round_dp(2),
+                    post_util_pct = %util[0],
+                    cancel_util_pct = %util[1],
+                    get_util_pct = %util[2],
                     "Portfolio status"
                 );
 
-                tokio::time::sleep(tick_interval).await;
-            } => {}
+                // Handle tier-1 WS updates until the next REST tick is due
+                let next_tick = tokio::time::sleep(tick_interval);
+                tokio::pin!(next_tick);
+                loop {
+                    tokio::select! {
+                        _ = &mut next_tick => break,
+                        Some(event) = runner::next_ws_event(&mut ws_rx) => {
+                            mgr.handle_ws_event(event, &exchange).await;
+                        }
+                        control = controls.recv() => {
+                            apply_control(control, &mut mgr, &exchange, config_path).await;
+                        }
+                    }
+                }
+                runner::supervise(&mut watchdog, &mut mgr.alerter, config).await
+            } => {
+                if let Some(failure) = task_failure {
+                    if let Some(ref ws_mgr) = ws_manager {
+                        ws_mgr.shutdown();
+                    }
+                    if let Err(e) = mgr.cancel_all_markets(&exchange).await {
+                        warn!(error = %e, "Error cancelling orders during safe stop");
+                    }
+                    mgr.save_metrics().await;
+                    bail!("{failure}; stopped with all orders cancelled");
+                }
+            }
         }
     }
 
@@ -444,46 +1049,1188 @@ async fn cmd_run_multi(config: &config::Config, live: bool) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_status(config: &config::Config) -> Result<()> {
-    // Load persisted metrics if available
-    let metrics_path = std::path::Path::new("metrics.json");
-    let portfolio = if metrics_path.exists() {
-        metrics::PortfolioMetrics::load(metrics_path)?
-    } else {
-        println!("No metrics data found. Run the bot first to generate metrics.");
-        println!("Showing live market overview instead.\n");
+fn cmd_simulate(params: &quoter::QuoteParams) {
+    let offset = quoter::compute_offset(params);
+    let quotes = quoter::generate_quotes(params);
 
-        // Show a live scan as fallback
-        let gamma_client = client::create_gamma_client()?;
-        let markets = scanner::scan_markets(&gamma_client).await?;
-        let ranked = scanner::rank_markets(&markets, config.markets.min_reward_daily, 10);
+    println!(
+        "Midpoint {} | tick {} | offset {} | fee {} bps | skew {}",
+        params.midpoint,
+        params.tick_size,
+        offset,
+        params.fee_rate_bps.unwrap_or(0),
+        params.inventory_skew
+    );
 
-        let market_data: Vec<(String, Decimal, Decimal, usize)> = ranked
-            .iter()
-            .map(|m| (m.question.clone(), Decimal::ZERO, Decimal::ZERO, 0))
-            .collect();
+    if quotes.is_empty() {
+        println!("No valid quotes for these parameters");
+        return;
+    }
 
-        let empty_portfolio = metrics::PortfolioMetrics::new();
-        let dashboard = metrics::format_dashboard(&empty_portfolio, &market_data);
-        println!("{dashboard}");
-        return Ok(());
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        "Level",
+        "Bid",
+        "Ask",
+        "Spread",
+        "Bid Size",
+        "Ask Size",
+        "Bid Score",
+        "Ask Score",
+        "Score",
+        "Capital",
+    ]);
+
+    let mut total_score = Decimal::ZERO;
+    let mut total_capital = Decimal::ZERO;
+    for q in &quotes {
+        let bid_score = quoter::estimate_score(
+            params.midpoint,
+            q.bid_price,
+            q.bid_size,
+            params.max_incentive_spread,
+            params.min_incentive_size,
+        );
+        let ask_score = quoter::estimate_score(
+            params.midpoint,
+            q.ask_price,
+            q.ask_size,
+            params.max_incentive_spread,
+            params.min_incentive_size,
+        );
+        let score = quoter::two_sided_score(bid_score, ask_score);
+        let capital = quoter::quote_capital(q);
+        total_score += score;
+        total_capital += capital;
+
+        table.add_row(vec![
+            q.level.to_string(),
+            q.bid_price.to_string(),
+            q.ask_price.to_string(),
+            (q.ask_price - q.bid_price).to_string(),
+            q.bid_size.to_string(),
+            q.ask_size.to_string(),
+            format!("{bid_score:.2}"),
+            format!("{ask_score:.2}"),
+            format!("{score:.2}"),
+            format!("${capital:.2}"),
+        ]);
+    }
+
+    // Each level sells its ask size of YES and its bid size of NO
+    let yes_tokens: Decimal = quotes.iter().map(|q| q.ask_size).sum();
+    let no_tokens: Decimal = quotes.iter().map(|q| q.bid_size).sum();
+    println!("{table}");
+    println!(
+        "\nTotal score {total_score:.2} | USDC for buys ${total_capital:.2} | tokens for sells {yes_tokens} YES + {no_tokens} NO"
+    );
+}
+
+async fn cmd_rewards_estimate(
+    config: &config::Config,
+    condition_id: &str,
+    size: Decimal,
+    spread_cents: Decimal,
+) -> Result<()> {
+    let gamma_client = client::create_gamma_client(config)?;
+    let market = match scanner::fetch_markets(&gamma_client, &[condition_id.to_string()])
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(m) => m,
+        None => bail!("Market {condition_id} not found or not active"),
     };
 
-    let market_data: Vec<(String, Decimal, Decimal, usize)> = portfolio
-        .markets
-        .values()
-        .map(|m| {
-            (
-                m.question.clone(),
-                m.last_midpoint.unwrap_or(Decimal::ZERO),
-                m.inventory_yes - m.inventory_no,
-                0,
-            )
-        })
-        .collect();
+    let clob_client = client::create_unauthenticated_client(config)?;
+    let token_id = polymarket_client_sdk::types::U256::from_str(&market.token_yes_id)?;
+    let book = clob_client
+        .order_book(&OrderBookSummaryRequest::builder().token_id(token_id).build())
+        .await?;
+    let midpoint = clob_client
+        .midpoint(&MidpointRequest::builder().token_id(token_id).build())
+        .await?
+        .mid;
 
-    let dashboard = metrics::format_dashboard(&portfolio, &market_data);
-    println!("{dashboard}");
+    let max_spread = market.rewards_max_spread;
+    let min_size = market.rewards_min_size;
+    let bids: Vec<_> = book.bids.iter().map(|o| (o.price, o.size)).collect();
+    let asks: Vec<_> = book.asks.iter().map(|o| (o.price, o.size)).collect();
+    let their_bid = quoter::book_side_score(midpoint, &bids, max_spread, min_size);
+    let their_ask = quoter::book_side_score(midpoint, &asks, max_spread, min_size);
+    let their_score = quoter::two_sided_score(their_bid, their_ask);
+
+    let offset = spread_cents / Decimal::ONE_HUNDRED;
+    let our_bid = quoter::estimate_score(midpoint, midpoint - offset, size, max_spread, min_size);
+    let our_ask = quoter::estimate_score(midpoint, midpoint + offset, size, max_spread, min_size);
+    let our_score = quoter::two_sided_score(our_bid, our_ask);
+
+    let share = quoter::reward_share(our_score, their_score);
+    let daily = market.reward_daily_estimate * share;
+
+    println!("{}", market.question);
+    println!("Midpoint:          {midpoint}");
+    println!(
+        "Reward band:       max spread {}, min size {}",
+        max_spread.map(|v| v.to_string()).unwrap_or_else(|| "-".into()),
+        min_size.map(|v| v.to_string()).unwrap_or_else(|| "-".into())
+    );
+    println!("Daily pool:        ${:.2}", market.reward_daily_estimate);
+    println!("Our quotes:        {size} @ ±{spread_cents}¢");
+    println!("Our score:         {our_score:.2} (bid {our_bid:.2}, ask {our_ask:.2})");
+    println!("Competitor score:  {their_score:.2} (bid {their_bid:.2}, ask {their_ask:.2})");
+    println!("Score share:       {:.2}%", share * Decimal::ONE_HUNDRED);
+    println!("Projected rewards: ${daily:.2}/day");
+    let fill_model = fill_model::FillModel::load(Path::new(&config.monitoring.fill_model_file))?;
+    let curve = fill_model.curve_for(condition_id);
+    if curve.is_calibrated() {
+        println!(
+            "Fill chance:       {:.1}% per ladder (from {:.0} of our quotes)",
+            curve.probability(offset, size) * Decimal::ONE_HUNDRED,
+            curve.observations()
+        );
+    }
+    Ok(())
+}
+
+async fn cmd_balances(config: &config::Config, json: bool) -> Result<()> {
+    let auth_client = client::create_authenticated_client(config).await?;
+    let private_key = config.private_key()?;
+    let signer = LocalSigner::from_str(&private_key)?;
+    let owner = client::wallet_address(config, &signer)?;
+
+    let balances = inventory::check_balances(&auth_client, client::signature_type(config)).await?;
+    let data_client = client::create_data_client(config)?;
+    let positions = inventory::fetch_positions(&data_client, owner).await?;
+
+    let onchain_usdc = match client::create_rpc_client(config) {
+        Some(rpc) => match rpc.usdc_balance(owner).await {
+            Ok(balance) => Some(balance),
+            Err(e) => {
+                warn!(error = %e, "On-chain USDC balance lookup failed");
+                None
+            }
+        },
+        None => None,
+    };
+
+    if json {
+        let out = serde_json::json!({
+            "wallet": owner.to_string(),
+            "usdc_balance": balances.usdc_balance,
+            "onchain_usdc": onchain_usdc,
+            "allowances": balances.allowances,
+            "positions": positions,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    println!("Wallet: {owner}");
+    println!("USDC balance: ${:.2}", balances.usdc_balance);
+    if let Some(balance) = onchain_usdc {
+        println!("On-chain USDC.e: ${balance:.2}");
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Spender", "Allowance"]);
+    for (spender, allowance) in &balances.allowances {
+        table.add_row(vec![spender.clone(), format!("{allowance:.2}")]);
+    }
+    println!("\n{table}");
+
+    if positions.is_empty() {
+        println!("\nNo token positions");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Market", "YES", "NO", "Net", "Condition ID"]);
+    for p in &positions {
+        let title = metrics::short_question(&p.title, 50);
+        table.add_row(vec![
+            title,
+            format!("{:.2}", p.yes),
+            format!("{:.2}", p.no),
+            format!("{:.2}", p.yes - p.no),
+            p.condition_id[..12.min(p.condition_id.len())].to_string(),
+        ]);
+    }
+    println!("\n{table}");
+
+    Ok(())
+}
+
+async fn cmd_orders(config: &config::Config) -> Result<()> {
+    let auth_client = client::create_authenticated_client(config).await?;
+    let open_orders = orders::fetch_open_orders(&auth_client).await?;
+    if open_orders.is_empty() {
+        println!("No open orders");
+        return Ok(());
+    }
+
+    let tracked = tracked_markets(config)?;
+
+    let mut by_market: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for order in open_orders {
+        by_market
+            .entry(order.market.to_string())
+            .or_default()
+            .push(order);
+    }
+
+    let gamma_client = client::create_gamma_client(config)?;
+    let ids: Vec<String> = by_market.keys().cloned().collect();
+    let market_info: HashMap<String, scanner::MarketInfo> =
+        scanner::fetch_markets(&gamma_client, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Market details unavailable");
+                Vec::new()
+            })
+            .into_iter()
+            .map(|m| (m.condition_id.clone(), m))
+            .collect();
+
+    let now = chrono::Utc::now();
+    let mut orphans = 0;
+    for (condition_id, market_orders) in &by_market {
+        let info = market_info.get(condition_id);
+        let question = info.map(|m| m.question.as_str()).unwrap_or("Unknown market");
+        let orphan = !tracked.contains(condition_id);
+        if orphan {
+            orphans += market_orders.len();
+        }
+
+        let mut mids: HashMap<String, Option<Decimal>> = HashMap::new();
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Order ID", "Side", "Price", "Size", "Filled", "Age", "In Band"]);
+        for o in market_orders {
+            let asset = o.asset_id.to_string();
+            if !mids.contains_key(&asset) {
+                let req = MidpointRequest::builder().token_id(o.asset_id).build();
+                let mid = auth_client.midpoint(&req).await.ok().map(|r| r.mid);
+                mids.insert(asset.clone(), mid);
+            }
+            let in_band = match (mids[&asset], info.and_then(|m| m.rewards_max_spread)) {
+                (Some(mid), Some(max_spread)) => {
+                    if quoter::in_reward_band(mid, o.price, max_spread) {
+                        "yes"
+                    } else {
+                        "NO"
+                    }
+                }
+                _ => "-",
+            };
+            let age = now - o.created_at;
+            table.add_row(vec![
+                o.id[..12.min(o.id.len())].to_string(),
+                format!("{:?}", o.side),
+                format!("{}", o.price),
+                format!("{:.2}", o.original_size),
+                format!("{:.2}", o.size_matched),
+                format!("{}h{:02}m", age.num_hours(), age.num_minutes() % 60),
+                in_band.to_string(),
+            ]);
+        }
+
+        let tag = if orphan { " [ORPHAN: not managed by bot]" } else { "" };
+        println!("\n{question} ({}){tag}", &condition_id[..12.min(condition_id.len())]);
+        println!("{table}");
+
+        if let Some(m) = info {
+            let resting = |token: &str| -> Decimal {
+                market_orders
+                    .iter()
+                    .filter(|o| o.side == Side::Buy && o.asset_id.to_string() == token)
+                    .map(|o| (o.original_size - o.size_matched) * o.price)
+                    .sum()
+            };
+            let limit = if config.strategy.max_token_notional > Decimal::ZERO {
+                format!(" (limit ${:.2} each)", config.strategy.max_token_notional)
+            } else {
+                String::new()
+            };
+            println!(
+                "Resting buys: YES ${:.2}, NO ${:.2}{limit}",
+                resting(&m.token_yes_id),
+                resting(&m.token_no_id)
+            );
+        }
+    }
+
+    let total: usize = by_market.values().map(|v| v.len()).sum();
+    println!(
+        "\n{total} open orders across {} markets ({orphans} orphaned)",
+        by_market.len()
+    );
+    Ok(())
+}
+
+async fn cmd_history(
+    config: &config::Config,
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+) -> Result<()> {
+    let today = chrono::Utc::now().date_naive();
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or(to - chrono::Duration::days(7));
+    if from > to {
+        bail!("--from ({from}) is after --to ({to})");
+    }
+    let after = from.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp();
+    let before = (to + chrono::Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+        .timestamp();
+
+    let auth_client = client::create_authenticated_client(config).await?;
+    let trades = orders::fetch_trades(&auth_client, after, before).await?;
+    let (our_key, our_address) = (auth_client.credentials().key(), auth_client.address());
+    let fills: Vec<metrics::Fill> = trades
+        .iter()
+        .flat_map(|t| orders::trade_fills(t, our_key, our_address))
+        .collect();
+    let summaries = metrics::summarize_fills(&fills);
+
+    if summaries.is_empty() {
+        println!("No trades between {from} and {to}");
+        return Ok(());
+    }
+
+    // Join with the local journal for market names and accrued rewards
+    let metrics_path = std::path::Path::new(&config.monitoring.metrics_file);
+    let journal = if metrics_path.exists() {
+        Some(metrics::PortfolioMetrics::load(metrics_path)?)
+    } else {
+        None
+    };
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        "Market", "Trades", "Volume", "Fees", "Rebates", "Realized PnL", "Rewards",
+    ]);
+
+    let mut total = metrics::TradeSummary::default();
+    for (condition_id, s) in &summaries {
+        let journaled = journal.as_ref().and_then(|j| j.markets.get(condition_id));
+        let name = match journaled {
+            Some(m) => metrics::short_question(&m.question, 40),
+            None => condition_id[..12.min(condition_id.len())].to_string(),
+        };
+        table.add_row(vec![
+            name,
+            s.trades.to_string(),
+            format!("${:.2}", s.volume),
+            format!("${:.4}", s.fees_paid),
+            format!("${:.4}", s.rebates),
+            format!("${:.4}", s.realized_pnl),
+            journaled
+                .map(|m| format!("${:.4}", m.reward_pnl))
+                .unwrap_or_else(|| "-".into()),
+        ]);
+
+        total.trades += s.trades;
+        total.volume += s.volume;
+        total.fees_paid += s.fees_paid;
+        total.rebates += s.rebates;
+        total.realized_pnl += s.realized_pnl;
+    }
+
+    println!("Trade history {from} to {to}");
+    println!("{table}");
+    println!(
+        "\n{} trades, volume ${:.2}, fees ${:.4}, rebates ${:.4}, realized PnL ${:.4}",
+        total.trades, total.volume, total.fees_paid, total.rebates, total.realized_pnl
+    );
+    Ok(())
+}
+
+async fn cmd_cancel(
+    config: &config::Config,
+    all: bool,
+    market: Option<String>,
+    order_ids: Vec<String>,
+) -> Result<()> {
+    if !all && market.is_none() && order_ids.is_empty() {
+        bail!("Specify one of --all, --market <condition_id>, or --order <id>");
+    }
+
+    let auth_client = client::create_authenticated_client(config).await?;
+    let signer = LocalSigner::from_str(&config.private_key()?)?.with_chain_id(Some(POLYGON));
+    let exchange = client::Timed::new(
+        exchange::SdkExchange::new(auth_client.clone(), &signer, client::signature_type(config)),
+        config.endpoints.clob_timeout_secs,
+    );
+
+    if all {
+        orders::cancel_all(&exchange).await?;
+        println!("Cancelled all open orders");
+    } else if let Some(condition_id) = market {
+        let cancelled = orders::cancel_market(&auth_client, &condition_id).await?;
+        println!("Cancelled {cancelled} orders in market {condition_id}");
+    } else {
+        let cancelled = orders::cancel_orders(&exchange, &order_ids).await?;
+        println!("Cancelled {cancelled}/{} orders", order_ids.len());
+    }
+
+    Ok(())
+}
+
+async fn cmd_cleanup(config: &config::Config, dry_run: bool) -> Result<()> {
+    let auth_client = client::create_authenticated_client(config).await?;
+    let signer = LocalSigner::from_str(&config.private_key()?)?.with_chain_id(Some(POLYGON));
+    let exchange = client::Timed::new(
+        exchange::SdkExchange::new(auth_client, &signer, client::signature_type(config)),
+        config.endpoints.clob_timeout_secs,
+    );
+
+    let managed = tracked_markets(config)?;
+    let report = orders::cleanup_orphans(&exchange, &managed, dry_run).await?;
+    if report.orphans.is_empty() {
+        println!("No orphan orders");
+        return Ok(());
+    }
+    println!("{}", report.format());
+    if dry_run {
+        println!(
+            "Would cancel {} orphan orders in {} markets",
+            report.orphan_count(),
+            report.orphans.len()
+        );
+    } else {
+        println!(
+            "Cancelled {} orphan orders in {} markets",
+            report.cancelled,
+            report.orphans.len()
+        );
+    }
+    Ok(())
+}
+
+async fn cmd_market(config: &config::Config, condition_id: &str, json: bool) -> Result<()> {
+    let gamma_client = client::create_gamma_client(config)?;
+    let Some(details) = scanner::fetch_market_details(&gamma_client, condition_id).await? else {
+        bail!("Market {condition_id} not found or not active");
+    };
+    let market = &details.info;
+    let clob_client = client::Timed::new(
+        client::create_unauthenticated_client(config)?,
+        config.endpoints.clob_timeout_secs,
+    );
+    let midpoint = clob_client.midpoint(&market.token_yes_id).await?;
+    let book = clob_client.order_book(&market.token_yes_id).await?;
+    let last_trade = clob_client
+        .last_trade_price(&market.token_yes_id)
+        .await
+        .inspect_err(|e| warn!(error = %e, "Last trade price unavailable"))
+        .ok();
+    let (best_bid, best_ask) = (book.best_bid(), book.best_ask());
+    let spread = best_bid.zip(best_ask).map(|(bid, ask)| ask - bid);
+    let (max_spread, min_size) = (market.rewards_max_spread, market.rewards_min_size);
+    let band_depth =
+        max_spread.map(|s| book.band_size(midpoint, s, min_size.unwrap_or(Decimal::ZERO)));
+    let book_score = quoter::two_sided_score(
+        quoter::book_side_score(midpoint, &book.bids, max_spread, min_size),
+        quoter::book_side_score(midpoint, &book.asks, max_spread, min_size),
+    );
+
+    // Our side: recorded metrics if the bot has run here, and open orders if
+    // the wallet is configured
+    let metrics_path = Path::new(&config.monitoring.metrics_file);
+    let ours = if metrics_path.exists() {
+        metrics::PortfolioMetrics::load(metrics_path)?
+            .markets
+            .remove(condition_id)
+    } else {
+        None
+    };
+    let open_orders = match client::create_authenticated_client(config).await {
+        Ok(auth_client) => orders::fetch_open_orders(&auth_client)
+            .await?
+            .into_iter()
+            .filter(|o| o.market.to_string().eq_ignore_ascii_case(condition_id))
+            .collect(),
+        Err(e) => {
+            warn!(error = %e, "Wallet unavailable, skipping open orders");
+            Vec::new()
+        }
+    };
+    let outcome = |asset_id: String| {
+        if asset_id == market.token_yes_id {
+            "YES"
+        } else {
+            "NO"
+        }
+    };
+
+    if json {
+        let orders: Vec<_> = open_orders
+            .iter()
+            .map(|o| {
+                serde_json::json!({
+                    "order_id": o.id,
+                    "outcome": outcome(o.asset_id.to_string()),
+                    "side": format!("{:?}", o.side),
+                    "price": o.price,
+                    "remaining": o.original_size - o.size_matched,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "condition_id": market.condition_id,
+            "question": market.question,
+            "end_date": details.end_date,
+            "token_yes_id": market.token_yes_id,
+            "token_no_id": market.token_no_id,
+            "tick_size": market.tick_size,
+            "order_min_size": details.order_min_size,
+            "taker_fee_bps": market.fee_rate_bps,
+            "maker_fee_bps": details.maker_fee_bps,
+            "neg_risk": details.neg_risk,
+            "liquidity": market.liquidity,
+            "volume": market.volume,
+            "volume_24hr": details.volume_24hr,
+            "rewards": {
+                "daily_estimate": market.reward_daily_estimate,
+                "max_spread": max_spread,
+                "min_size": min_size,
+            },
+            "book": {
+                "midpoint": midpoint,
+                "best_bid": best_bid,
+                "best_ask": best_ask,
+                "spread": spread,
+                "last_trade": last_trade,
+                "band_depth": band_depth,
+                "score": book_score,
+            },
+            "managed": ours.is_some(),
+            "metrics": ours,
+            "open_orders": orders,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    let or_dash = |v: Option<String>| v.unwrap_or_else(|| "-".into());
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Field", "Value"]);
+    let end_date = details.end_date.map(|end| {
+        let left = end - chrono::Utc::now();
+        format!(
+            "{} ({}d {}h left)",
+            end.format("%Y-%m-%d %H:%M UTC"),
+            left.num_days(),
+            left.num_hours() % 24
+        )
+    });
+    let fee = |bps: Option<i32>| bps.map(|b| format!("{b} bps"));
+    let price = |p: Option<Decimal>| p.map(|p| p.to_string());
+    let rows = [
+        ("Question", market.question.clone()),
+        ("Condition ID", market.condition_id.clone()),
+        ("End date", or_dash(end_date)),
+        ("YES token", market.token_yes_id.clone()),
+        ("NO token", market.token_no_id.clone()),
+        ("Tick size", market.tick_size.clone()),
+        ("Min order size", or_dash(price(details.order_min_size))),
+        ("Taker fee", or_dash(fee(market.fee_rate_bps))),
+        ("Maker fee", or_dash(fee(details.maker_fee_bps))),
+        ("Neg risk", details.neg_risk.to_string()),
+        ("Liquidity", format!("${:.2}", market.liquidity)),
+        (
+            "Volume",
+            format!(
+                "${:.2} (24h {})",
+                market.volume,
+                or_dash(details.volume_24hr.map(|v| format!("${v:.2}")))
+            ),
+        ),
+        (
+            "Daily rewards",
+            format!("${:.2}", market.reward_daily_estimate),
+        ),
+        ("Reward max spread", or_dash(price(max_spread))),
+        ("Reward min size", or_dash(price(min_size))),
+        ("Midpoint", midpoint.to_string()),
+        (
+            "Best bid / ask",
+            format!(
+                "{} / {}",
+                or_dash(price(best_bid)),
+                or_dash(price(best_ask))
+            ),
+        ),
+        ("Spread", or_dash(price(spread))),
+        ("Last trade", or_dash(price(last_trade))),
+        ("Depth in band", or_dash(price(band_depth))),
+        ("Book score", format!("{book_score:.2}")),
+    ];
+    for (field, value) in rows {
+        table.add_row(vec![field.to_string(), value]);
+    }
+    println!("{table}");
+
+    match &ours {
+        Some(m) => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+            table.set_header(vec!["Ours", "Value"]);
+            let rows = [
+                (
+                    "Inventory YES / NO",
+                    format!("{:.2} / {:.2}", m.inventory_yes, m.inventory_no),
+                ),
+                ("Spread PnL", format!("${:.2}", m.spread_pnl)),
+                ("Reward PnL", format!("${:.2}", m.reward_pnl)),
+                ("Rebate PnL", format!("${:.2}", m.rebate_pnl)),
+                ("Fees paid", format!("${:.2}", m.fees_paid)),
+                ("Net PnL", format!("${:.2}", m.net_pnl())),
+                (
+                    "Fills / orders",
+                    format!("{} / {}", m.total_fills, m.total_orders),
+                ),
+                (
+                    "Last update",
+                    m.last_update.format("%Y-%m-%d %H:%M UTC").to_string(),
+                ),
+            ];
+            for (field, value) in rows {
+                table.add_row(vec![field.to_string(), value]);
+            }
+            println!("\n{table}");
+        }
+        None => println!("\nNot managed by the bot (no recorded metrics)"),
+    }
+
+    if open_orders.is_empty() {
+        println!("No open orders");
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Order ID", "Outcome", "Side", "Price", "Remaining"]);
+    for o in &open_orders {
+        table.add_row(vec![
+            o.id[..12.min(o.id.len())].to_string(),
+            outcome(o.asset_id.to_string()).to_string(),
+            format!("{:?}", o.side),
+            o.price.to_string(),
+            format!("{:.2}", o.original_size - o.size_matched),
+        ]);
+    }
+    println!("\n{table}");
+    Ok(())
+}
+
+async fn cmd_prices(
+    config: &config::Config,
+    condition_id: &str,
+    interval: std::time::Duration,
+    range: std::time::Duration,
+) -> Result<()> {
+    let gamma_client = client::create_gamma_client(config)?;
+    let market = match scanner::fetch_markets(&gamma_client, &[condition_id.to_string()])
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(m) => m,
+        None => bail!("Market {condition_id} not found or not active"),
+    };
+    let clob_client = client::Timed::new(
+        client::create_unauthenticated_client(config)?,
+        config.endpoints.clob_timeout_secs,
+    );
+    let history =
+        prices::fetch_history(&clob_client, &market.token_yes_id, range, interval).await?;
+    println!("{}", market.question);
+    let (Some((_, first)), Some((_, last))) = (history.first(), history.last()) else {
+        println!("No price history in range");
+        return Ok(());
+    };
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Time (UTC)", "YES", "Change"]);
+    let mut previous = None;
+    for (t, price) in &history {
+        let time = chrono::DateTime::from_timestamp(*t, 0)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| t.to_string());
+        let change = previous.map_or(String::new(), |p: Decimal| format!("{:+}", price - p));
+        table.add_row(vec![time, price.to_string(), change]);
+        previous = Some(*price);
+    }
+    println!("{table}");
+
+    let series: Vec<Decimal> = history.iter().map(|(_, p)| *p).collect();
+    let low = series.iter().copied().min().unwrap_or_default();
+    let high = series.iter().copied().max().unwrap_or_default();
+    println!("\n{}", prices::sparkline(&series, 60));
+    println!("Points:      {}", history.len());
+    println!("Range:       {low} - {high} ({} wide)", high - low);
+    println!("Change:      {first} -> {last} ({:+})", last - first);
+    match prices::daily_volatility(&history) {
+        Some(vol) => println!("Daily vol:   {:.4}", vol),
+        None => println!("Daily vol:   -"),
+    }
+    Ok(())
+}
+
+async fn cmd_book(config: &config::Config, condition_id: &str, depth: usize) -> Result<()> {
+    let gamma_client = client::create_gamma_client(config)?;
+    let market = match scanner::fetch_markets(&gamma_client, &[condition_id.to_string()])
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(m) => m,
+        None => bail!("Market {condition_id} not found or not active"),
+    };
+    let clob_client = client::Timed::new(
+        client::create_unauthenticated_client(config)?,
+        config.endpoints.clob_timeout_secs,
+    );
+    let midpoint = clob_client.midpoint(&market.token_yes_id).await?;
+
+    // Remaining size of our orders per token and level; the book is still
+    // worth showing when the wallet isn't configured
+    let mut own: HashMap<String, HashMap<(Side, Decimal), Decimal>> = HashMap::new();
+    match client::create_authenticated_client(config).await {
+        Ok(auth_client) => {
+            for o in orders::fetch_open_orders(&auth_client).await? {
+                if !o.market.to_string().eq_ignore_ascii_case(condition_id) {
+                    continue;
+                }
+                *own.entry(o.asset_id.to_string())
+                    .or_default()
+                    .entry((o.side, o.price))
+                    .or_default() += o.original_size - o.size_matched;
+            }
+        }
+        Err(e) => warn!(error = %e, "Wallet unavailable, our orders won't be marked"),
+    }
+
+    let max_spread = market.rewards_max_spread;
+    println!("{}", market.question);
+    println!("Tick size:   {}", market.tick_size);
+    println!(
+        "Midpoint:    {midpoint} YES / {} NO",
+        Decimal::ONE - midpoint
+    );
+    match max_spread {
+        Some(spread) => println!(
+            "Reward band: ±{spread} (YES {} – {}, NO {} – {}), min size {}",
+            midpoint - spread,
+            midpoint + spread,
+            Decimal::ONE - midpoint - spread,
+            Decimal::ONE - midpoint + spread,
+            market
+                .rewards_min_size
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".into())
+        ),
+        None => println!("Reward band: -"),
+    }
+
+    let no_ours = HashMap::new();
+    for (outcome, token_id, token_mid) in [
+        ("YES", &market.token_yes_id, midpoint),
+        ("NO", &market.token_no_id, Decimal::ONE - midpoint),
+    ] {
+        let book = clob_client.order_book(token_id).await?;
+        let ours = own.get(token_id).unwrap_or(&no_ours);
+        let in_band = ours
+            .keys()
+            .filter(|(_, price)| {
+                max_spread.is_some_and(|s| quoter::in_reward_band(token_mid, *price, s))
+            })
+            .count();
+        println!("\n{outcome} ({token_id})");
+        println!(
+            "{}",
+            orders::format_book(&book, ours, token_mid, max_spread, depth)
+        );
+        println!("Our levels: {} ({in_band} in band)", ours.len());
+    }
+    Ok(())
+}
+
+/// Print what moved since the previous `status` run (or the `since` window)
+/// and record this run's snapshot for the next one.
+fn print_status_changes(
+    config: &config::Config,
+    portfolio: &metrics::PortfolioMetrics,
+    since: Option<std::time::Duration>,
+) -> Result<()> {
+    let path = Path::new(&config.monitoring.status_history_file);
+    let mut history = snapshots::StatusHistory::load(path)?;
+    let now = chrono::Utc::now();
+    let current = snapshots::StatusSnapshot::capture(portfolio, now);
+    let window = since.map(chrono::Duration::from_std).transpose()?;
+
+    match history.baseline(now, window) {
+        Some(baseline) => {
+            println!(
+                "--- Changes since {} ---",
+                baseline.taken_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            println!("Net PnL: {:+.4}", current.net_pnl() - baseline.net_pnl());
+            let deltas = snapshots::diff(baseline, &current);
+            if deltas.is_empty() {
+                println!("No market moved\n");
+            } else {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["Question", "PnL", "New Fills", "Inventory"]);
+                for d in &deltas {
+                    let question = metrics::short_question(&d.question, 50);
+                    table.add_row(vec![
+                        question,
+                        format!("{:+.4}", d.pnl),
+                        d.fills.to_string(),
+                        format!("{:+.1}", d.inventory),
+                    ]);
+                }
+                println!("{table}\n");
+            }
+        }
+        None if since.is_some() => println!("No status snapshot old enough to compare against\n"),
+        None => {}
+    }
+
+    history.record(current);
+    history.save(path)
+}
+
+fn print_var_report(report: &risk::VarReport) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Market", "Net YES", "Daily Vol", "VaR 95%"]);
+    for m in &report.markets {
+        let question = metrics::short_question(&m.market, 40);
+        table.add_row(vec![
+            question,
+            format!("{:.1}", m.net_yes),
+            m.daily_vol
+                .map_or_else(|| "n/a".to_string(), |v| format!("{:.2}¢", v * dec!(100))),
+            format!("${:.2}", m.var),
+        ]);
+    }
+    println!("\n--- Risk (1-day 95% VaR) ---\n{table}");
+    println!("Portfolio VaR: ${:.2}", report.portfolio_var);
+    if report.breached() {
+        println!(
+            "WARNING: VaR exceeds limit of ${:.2} (risk.var_limit_fraction of capital)",
+            report.limit
+        );
+    }
+}
+
+fn cmd_resume(config: &config::Config, ack_loss: bool) -> Result<()> {
+    let path = std::path::Path::new(&config.risk.state_file);
+    let mut state = risk::RiskState::load(path)?;
+    let Some(latch) = state.kill_switch.take() else {
+        println!("Kill switch is not latched");
+        return Ok(());
+    };
+
+    println!(
+        "Kill switch tripped at {} with PnL ${:.2} (limit -${:.2})",
+        latch.tripped_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        latch.total_pnl,
+        latch.threshold
+    );
+    if !ack_loss {
+        bail!("Re-run with --ack-loss to acknowledge the loss and clear the kill switch");
+    }
+
+    state.save(path)?;
+    info!(total_pnl = %latch.total_pnl, "Kill switch cleared by operator");
+    println!("Kill switch cleared — quoting resumes on next start");
+    Ok(())
+}
+
+fn cmd_state(
+    config: &config::Config,
+    condition_id: Option<String>,
+    state: Option<states::EngineState>,
+) -> Result<()> {
+    let path = std::path::Path::new(&config.monitoring.market_states_file);
+    let mut market_states = states::MarketStates::load(path)?;
+    match (condition_id, state) {
+        (Some(condition_id), Some(state)) => {
+            let from = market_states.get(&condition_id);
+            market_states.set(&condition_id, state);
+            market_states.save(path)?;
+            info!(market = %condition_id, from = %from, to = %state, "Market state set by operator");
+            println!("{condition_id}: {from} → {state}");
+            println!("A running multi-market bot applies this on its next loop");
+        }
+        (Some(condition_id), None) => {
+            println!("{condition_id}: {}", market_states.get(&condition_id));
+        }
+        (None, _) if market_states.markets.is_empty() => println!("All markets active"),
+        (None, _) => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+            table.set_header(vec!["Market", "State"]);
+            for (condition_id, state) in &market_states.markets {
+                table.add_row(vec![condition_id.clone(), state.to_string()]);
+            }
+            println!("{table}");
+        }
+    }
+    Ok(())
+}
+
+async fn cmd_auth_refresh(config: &config::Config) -> Result<()> {
+    if config.secrets.api.is_some() {
+        bail!("API credentials come from the secrets file; update them there instead");
+    }
+    if config.wallet.credentials_cache.is_empty() {
+        bail!("wallet.credentials_cache is not set, so there is nothing to refresh");
+    }
+    let client = client::authenticate(config, client::CredentialMode::Rotate).await?;
+    println!(
+        "Rotated API credentials for {}; restart running bots to pick them up",
+        client.address()
+    );
+    Ok(())
+}
+
+fn cmd_secrets(config: &config::Config, action: &SecretsCommand) -> Result<()> {
+    match action {
+        SecretsCommand::Encrypt { input, output } => {
+            let output = match output {
+                Some(path) => path.clone(),
+                None if !config.wallet.secrets_file.is_empty() => {
+                    PathBuf::from(&config.wallet.secrets_file)
+                }
+                None => bail!("no --output given and wallet.secrets_file is not set"),
+            };
+            let plaintext = std::fs::read_to_string(input)
+                .with_context(|| format!("reading {}", input.display()))?;
+            // Refuse to seal something the bot could not read back
+            secrets::Secrets::parse(&plaintext)?;
+            let passphrase = secrets::new_passphrase(&config.wallet.passphrase_env)?;
+            std::fs::write(&output, secrets::encrypt(&plaintext, passphrase)?)
+                .with_context(|| format!("writing {}", output.display()))?;
+            println!("Encrypted secrets written to {}", output.display());
+            println!("Delete the plaintext {} once verified", input.display());
+        }
+        SecretsCommand::Check => {
+            if config.wallet.secrets_file.is_empty() {
+                bail!("wallet.secrets_file is not set");
+            }
+            let unlocked = config.clone().unlock_secrets()?;
+            let present = unlocked.secrets.present();
+            if present.is_empty() {
+                println!(
+                    "{}: decrypted, but holds no secrets",
+                    config.wallet.secrets_file
+                );
+            } else {
+                println!(
+                    "{}: decrypted, holds {}",
+                    config.wallet.secrets_file,
+                    present.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_compact(config: &config::Config) -> Result<()> {
+    let path = std::path::Path::new(&config.monitoring.database_file);
+    if !config.monitoring.database_file.is_empty() && path.exists() {
+        let size = |p: &Path| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+        let before = size(path);
+        let database = db::Database::open(path)?;
+        let removed = database.prune(&config.retention, chrono::Utc::now())?;
+        database.vacuum()?;
+        if removed.is_empty() {
+            println!("History: nothing past retention");
+        }
+        for (table, rows) in removed {
+            println!("History: removed {rows} rows from {table}");
+        }
+        println!(
+            "History database: {:.1} MB → {:.1} MB",
+            before as f64 / 1e6,
+            size(path) as f64 / 1e6
+        );
+    }
+
+    let metrics_path = std::path::Path::new(&config.monitoring.metrics_file);
+    let days = metrics::PortfolioMetrics::compact_archives(
+        metrics_path,
+        chrono::Utc::now().date_naive(),
+        config.monitoring.metrics_retention_days,
+    )?;
+    println!("Metrics: compacted {days} daily archives");
+    Ok(())
+}
+
+fn cmd_query(config: &config::Config, sql: &str) -> Result<()> {
+    let path = std::path::Path::new(&config.monitoring.database_file);
+    if config.monitoring.database_file.is_empty() || !path.exists() {
+        bail!("No history database; set monitoring.database_file and run the bot live first");
+    }
+    let result = db::Database::open_read_only(path)?.query(sql)?;
+    if result.rows.is_empty() {
+        println!("No rows");
+        return Ok(());
+    }
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(result.columns);
+    let count = result.rows.len();
+    for row in result.rows {
+        table.add_row(row);
+    }
+    println!("{table}");
+    println!("{count} rows");
+    Ok(())
+}
+
+fn cmd_add_market(config: &config::Config, condition_id: &str) -> Result<()> {
+    let path = std::path::Path::new(&config.monitoring.market_states_file);
+    let mut market_states = states::MarketStates::load(path)?;
+    if !market_states.add(condition_id) {
+        println!("{condition_id} is already added");
+        return Ok(());
+    }
+    market_states.save(path)?;
+    info!(market = %condition_id, "Market added by operator");
+    println!("{condition_id}: added");
+    println!("A running multi-market bot scans and starts it on its next loop");
+    Ok(())
+}
+
+fn cmd_remove_market(config: &config::Config, condition_id: &str, unwind: bool) -> Result<()> {
+    let path = std::path::Path::new(&config.monitoring.market_states_file);
+    let mut market_states = states::MarketStates::load(path)?;
+    let state = if unwind {
+        states::EngineState::Removing
+    } else {
+        states::EngineState::Retired
+    };
+    let from = market_states.get(condition_id);
+    market_states.set(condition_id, state);
+    market_states.save(path)?;
+    info!(market = %condition_id, from = %from, to = %state, "Market removed by operator");
+    println!("{condition_id}: {from} → {state}");
+    println!("A running multi-market bot applies this on its next loop");
+    Ok(())
+}
+
+async fn cmd_status(config: &config::Config, since: Option<std::time::Duration>) -> Result<()> {
+    let risk_state = risk::RiskState::load(std::path::Path::new(&config.risk.state_file))?;
+    if let Some(latch) = &risk_state.kill_switch {
+        println!(
+            "KILL SWITCH LATCHED since {} (PnL ${:.2}, limit -${:.2}) — run `resume --ack-loss` to clear\n",
+            latch.tripped_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            latch.total_pnl,
+            latch.threshold
+        );
+    }
+
+    // Load persisted metrics if available
+    let metrics_path = std::path::Path::new(&config.monitoring.metrics_file);
+    let portfolio = if metrics_path.exists() {
+        metrics::PortfolioMetrics::load(metrics_path)?
+    } else {
+        println!("No metrics data found. Run the bot first to generate metrics.");
+        println!("Showing live market overview instead.\n");
+
+        // Show a live scan as fallback
+        let gamma_client = client::create_gamma_client(config)?;
+        let markets =
+            scanner::scan_markets(&gamma_client, config.markets.score_weights.as_ref()).await?;
+        let ranked = scanner::rank_markets(
+            &markets,
+            config.markets.min_reward_daily,
+            10,
+            config.markets.prefer_fee_enabled,
+        );
+
+        let market_data: Vec<(String, Decimal, Decimal, usize)> = ranked
+            .iter()
+            .map(|m| (m.question.clone(), Decimal::ZERO, Decimal::ZERO, 0))
+            .collect();
+
+        let empty_portfolio = metrics::PortfolioMetrics::new();
+        let dashboard = metrics::format_dashboard(&empty_portfolio, &market_data);
+        println!("{dashboard}");
+        return Ok(());
+    };
+
+    // Prefer on-chain positions over the persisted inventory when an RPC is configured
+    let onchain = match (client::create_rpc_client(config), config.private_key()) {
+        (Some(rpc), Ok(key)) => {
+            let signer = LocalSigner::from_str(&key)?;
+            Some((rpc, client::wallet_address(config, &signer)?))
+        }
+        _ => None,
+    };
+
+    let mut market_data: Vec<(String, Decimal, Decimal, usize)> = Vec::new();
+    for m in portfolio.markets.values() {
+        let mut inventory = m.inventory_yes - m.inventory_no;
+        if let Some((ref rpc, owner)) = onchain {
+            match rpc.position(owner, &m.token_yes_id, &m.token_no_id).await {
+                Ok(position) => inventory = position.yes - position.no,
+                Err(e) => warn!(market = %m.question, error = %e, "On-chain position lookup failed"),
+            }
+        }
+        market_data.push((
+            m.question.clone(),
+            m.last_midpoint.unwrap_or(Decimal::ZERO),
+            inventory,
+            0,
+        ));
+    }
+
+    let dashboard = metrics::format_dashboard(&portfolio, &market_data);
+    println!("{dashboard}");
+    if let Some(report) =
+        metrics::performance_report(metrics_path, config.monitoring.performance_window_days)?
+    {
+        println!("{report}");
+    }
+    print_status_changes(config, &portfolio, since)?;
+
+    let positions = portfolio
+        .markets
+        .values()
+        .map(|m| {
+            (
+                m.question.clone(),
+                m.inventory_yes - m.inventory_no,
+                m.last_midpoint.unwrap_or(dec!(0.5)),
+                m.daily_volatility,
+            )
+        })
+        .collect();
+    print_var_report(&risk::var_report(positions, &config.risk));
+
+    if let Some((ref rpc, owner)) = onchain {
+        match rpc.usdc_balance(owner).await {
+            Ok(balance) => println!("On-chain USDC.e: ${balance:.2}"),
+            Err(e) => warn!(error = %e, "On-chain USDC balance lookup failed"),
+        }
+    }
 
     Ok(())
 }
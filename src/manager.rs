@@ -1,66 +1,179 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use futures::future::join_all;
 use polymarket_client_sdk::auth;
-use polymarket_client_sdk::auth::Signer;
 use polymarket_client_sdk::clob;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal_macros::dec;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::{debug, info, instrument, warn};
 
-use crate::config::Config;
-use crate::engine::QuoteEngine;
-use crate::orders;
-use crate::risk::{self, MarketInventory};
+use crate::alerts::{Alert, AlertRule, Alerter, Severity};
+use crate::anomaly::FlowLimits;
+use crate::canary::{self, CanaryRun};
+use crate::client::Timed;
+use crate::clock::{self, SharedClock, SkewMonitor};
+use crate::config::{Config, RateLimitConfig, StrategyConfig};
+use crate::db::{self, Database};
+use crate::engine::{self, ConfirmMode, QuoteEngine, Tier};
+use crate::exchange::{ApiUsage, ExchangeApi, MarketData, Metered};
+use crate::experiment::{self, Arm, Experiment};
+use crate::fill_model::FillModel;
+use crate::inventory::{self, CtfOperation, CtfScheduler, ResolutionStatus};
+use crate::metrics::{self, MarketMetrics};
+use crate::orders::{self, RetainedOrders};
+use crate::prometheus;
+use crate::risk::{self, ExitLimits, ExitReason, MarketInventory, RiskState};
+use crate::rpc::PolygonRpc;
 use crate::scanner::{self, MarketInfo};
+use crate::states::{EngineState, MarketStates};
+use crate::ws::WsEvent;
 
-/// Rate limiter to stay within Polymarket's API limits.
-pub struct RateLimiter {
-    /// Timestamps of recent order submissions
-    order_timestamps: Vec<Instant>,
-    /// Max orders per 10s burst
+/// API endpoint classes with separate rate limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    Post,
+    Cancel,
+    Get,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 3] = [Endpoint::Post, Endpoint::Cancel, Endpoint::Get];
+}
+
+const BURST_WINDOW: Duration = Duration::from_secs(10);
+const SUSTAINED_WINDOW: Duration = Duration::from_secs(600);
+
+/// Sliding burst (10s) and sustained (10min) windows for one endpoint.
+struct Window {
+    /// Recent requests as (time, count)
+    events: VecDeque<(Instant, usize)>,
     burst_limit: usize,
-    /// Max orders per 10min sustained
     sustained_limit: usize,
 }
 
-impl RateLimiter {
-    pub fn new() -> Self {
+impl Window {
+    fn new(burst_limit: usize, sustained_limit: usize) -> Self {
         Self {
-            order_timestamps: Vec::new(),
-            burst_limit: 3500,
-            sustained_limit: 36000,
+            events: VecDeque::new(),
+            burst_limit,
+            sustained_limit,
         }
     }
 
-    /// Check if we can place `count` orders right now.
-    pub fn can_place(&mut self, count: usize) -> bool {
-        let now = Instant::now();
-        // Clean old timestamps
-        self.order_timestamps
-            .retain(|t| now.duration_since(*t) < Duration::from_secs(600));
-
-        let burst_window = Duration::from_secs(10);
-        let burst_count = self
-            .order_timestamps
+    /// Drop expired events and return (burst, sustained) usage.
+    fn usage(&mut self, now: Instant) -> (usize, usize) {
+        while let Some((t, _)) = self.events.front() {
+            if now.saturating_duration_since(*t) < SUSTAINED_WINDOW {
+                break;
+            }
+            self.events.pop_front();
+        }
+        let sustained = self.events.iter().map(|(_, n)| n).sum();
+        let burst = self
+            .events
             .iter()
-            .filter(|t| now.duration_since(**t) < burst_window)
-            .count();
+            .filter(|(t, _)| now.saturating_duration_since(*t) < BURST_WINDOW)
+            .map(|(_, n)| n)
+            .sum();
+        (burst, sustained)
+    }
+}
+
+/// Current usage of one endpoint's limits.
+#[derive(Debug, Clone, Copy)]
+pub struct Utilization {
+    pub endpoint: Endpoint,
+    pub burst_used: usize,
+    pub burst_limit: usize,
+    pub sustained_used: usize,
+    pub sustained_limit: usize,
+}
+
+impl Utilization {
+    /// The tighter of the two windows, as a percentage.
+    pub fn pct(&self) -> Decimal {
+        let ratio = |used: usize, limit: usize| {
+            if limit == 0 {
+                Decimal::ZERO
+            } else {
+                Decimal::from(used) / Decimal::from(limit)
+            }
+        };
+        ratio(self.burst_used, self.burst_limit).max(ratio(self.sustained_used, self.sustained_limit))
+            * dec!(100)
+    }
+}
+
+/// Rate limiter to stay within Polymarket's API limits, tracked separately
+/// for order posts, cancels, and GET requests.
+pub struct RateLimiter {
+    windows: HashMap<Endpoint, Window>,
+    clock: SharedClock,
+}
 
-        if burst_count + count > self.burst_limit {
+impl RateLimiter {
+    pub fn with_clock(clock: SharedClock) -> Self {
+        Self::from_config(&RateLimitConfig::default(), clock)
+    }
+
+    pub fn from_config(config: &RateLimitConfig, clock: SharedClock) -> Self {
+        let windows = HashMap::from([
+            (
+                Endpoint::Post,
+                Window::new(config.post_burst, config.post_sustained),
+            ),
+            (
+                Endpoint::Cancel,
+                Window::new(config.cancel_burst, config.cancel_sustained),
+            ),
+            (Endpoint::Get, Window::new(config.get_burst, config.get_sustained)),
+        ]);
+        Self { windows, clock }
+    }
+
+    fn window(&mut self, endpoint: Endpoint) -> &mut Window {
+        self.windows
+            .get_mut(&endpoint)
+            .expect("every endpoint has a window")
+    }
+
+    /// Check if `count` more requests to `endpoint` fit right now.
+    pub fn can_call(&mut self, endpoint: Endpoint, count: usize) -> bool {
+        self.can_call_within(endpoint, count, Decimal::ONE)
+    }
+
+    /// Like `can_call`, but only `share` (0-1) of each limit is usable.
+    pub fn can_call_within(&mut self, endpoint: Endpoint, count: usize, share: Decimal) -> bool {
+        let now = self.clock.now();
+        let window = self.window(endpoint);
+        let (burst, sustained) = window.usage(now);
+        let scaled = |limit: usize| {
+            (Decimal::from(limit) * share)
+                .floor()
+                .to_usize()
+                .unwrap_or(0)
+        };
+        let burst_limit = scaled(window.burst_limit);
+        let sustained_limit = scaled(window.sustained_limit);
+
+        if burst + count > burst_limit {
             warn!(
-                current = burst_count,
+                endpoint = ?endpoint,
+                current = burst,
                 requested = count,
                 "Rate limit: burst limit would be exceeded"
             );
             return false;
         }
 
-        if self.order_timestamps.len() + count > self.sustained_limit {
+        if sustained + count > sustained_limit {
             warn!(
-                current = self.order_timestamps.len(),
+                endpoint = ?endpoint,
+                current = sustained,
                 requested = count,
                 "Rate limit: sustained limit would be exceeded"
             );
@@ -70,12 +183,60 @@ impl RateLimiter {
         true
     }
 
+    /// Record that `count` requests were made to `endpoint`.
+    pub fn record_calls(&mut self, endpoint: Endpoint, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let now = self.clock.now();
+        self.window(endpoint).events.push_back((now, count));
+    }
+
+    /// Check if we can place `count` orders right now.
+    pub fn can_place(&mut self, count: usize) -> bool {
+        self.can_call(Endpoint::Post, count)
+    }
+
     /// Record that `count` orders were placed.
     pub fn record(&mut self, count: usize) {
-        let now = Instant::now();
-        for _ in 0..count {
-            self.order_timestamps.push(now);
-        }
+        self.record_calls(Endpoint::Post, count);
+    }
+
+    /// Whether a whole tick's worth of requests fits in every budget.
+    pub fn can_afford(&mut self, usage: &ApiUsage) -> bool {
+        self.can_afford_within(usage, Decimal::ONE)
+    }
+
+    /// Whether a tick fits in `share` (0-1) of every budget.
+    pub fn can_afford_within(&mut self, usage: &ApiUsage, share: Decimal) -> bool {
+        self.can_call_within(Endpoint::Post, usage.posts, share)
+            && self.can_call_within(Endpoint::Cancel, usage.cancels, share)
+            && self.can_call_within(Endpoint::Get, usage.gets, share)
+    }
+
+    pub fn record_usage(&mut self, usage: &ApiUsage) {
+        self.record_calls(Endpoint::Post, usage.posts);
+        self.record_calls(Endpoint::Cancel, usage.cancels);
+        self.record_calls(Endpoint::Get, usage.gets);
+    }
+
+    /// Headroom per endpoint.
+    pub fn utilization(&mut self) -> Vec<Utilization> {
+        let now = self.clock.now();
+        Endpoint::ALL
+            .iter()
+            .map(|&endpoint| {
+                let window = self.window(endpoint);
+                let (burst_used, sustained_used) = window.usage(now);
+                Utilization {
+                    endpoint,
+                    burst_used,
+                    burst_limit: window.burst_limit,
+                    sustained_used,
+                    sustained_limit: window.sustained_limit,
+                }
+            })
+            .collect()
     }
 }
 
@@ -87,150 +248,1384 @@ pub struct MarketManager {
     pub last_rescan: Instant,
     pub rescan_interval: Duration,
     pub capital_allocations: HashMap<String, Decimal>,
+    /// Intraday shifts on top of `capital_allocations`, reverted at rescan
+    pub rebalanced: HashMap<String, Decimal>,
+    pub last_resolution_check: Instant,
+    pub resolution_check_interval: Duration,
+    /// Last known resolution status per market, to act only on transitions
+    pub resolution_statuses: HashMap<String, ResolutionStatus>,
+    /// Pending split/merge/redeem operations, released when gas allows
+    pub ctf_scheduler: CtfScheduler,
+    pub last_param_refresh: Instant,
+    pub param_refresh_interval: Duration,
+    /// Time source for rescan/refresh intervals, shared with engines and the rate limiter
+    pub clock: SharedClock,
+    /// Persisted kill-switch latch
+    pub risk_state: RiskState,
+    /// Whether portfolio VaR was over its limit at the last check
+    pub var_breached: bool,
+    /// Review mode for each new market's first ladder (`--confirm` / `--yes`)
+    pub confirm: Option<ConfirmMode>,
+    /// Loop iterations run by `tick_all`, for slow tier-3 polling
+    pub tick_count: u64,
+    /// Markets dropped from the ranking that are still unwinding inventory
+    pub retiring: HashSet<String>,
+    /// Consecutive rescans each unmanaged market has ranked
+    pub ranked_streaks: HashMap<String, u32>,
+    /// Consecutive rescans each managed market has missed the ranking
+    pub unranked_streaks: HashMap<String, u32>,
+    /// When each market was last removed, for the re-add cooldown
+    pub removed_at: HashMap<String, Instant>,
+    pub last_metrics_save: Instant,
+    pub metrics_save_interval: Duration,
+    pub last_history_prune: Instant,
+    pub last_crowding_sample: Instant,
+    /// Market trialling the configured strategy while the rest keep the
+    /// persisted profile (`run --canary`)
+    pub canary: Option<CanaryRun>,
+    /// A/B assignment of markets to two strategy parameter sets
+    pub experiment: Option<Experiment>,
+    /// Quoting suspended everywhere by the operator (SIGUSR1)
+    pub paused: bool,
+    /// Per-market states set with the `state` command, as last applied
+    pub market_states: MarketStates,
+    /// Local clock drift from the exchange's server time
+    pub skew_monitor: SkewMonitor,
+    /// Applies `[alerts]` thresholds, cooldowns, and quiet hours
+    pub alerter: Alerter,
+    /// Markets queued with `add-market` awaiting a scan
+    pub pending_additions: Vec<String>,
+    /// History database handed to every engine, if one is open
+    pub database: Option<Arc<Database>>,
+    /// Fill curves of every market quoted live, saved with the metrics
+    pub fill_model: FillModel,
 }
 
 impl MarketManager {
     pub fn new(config: Config) -> Self {
+        Self::with_clock(config, clock::system())
+    }
+
+    pub fn with_clock(config: Config, clock: SharedClock) -> Self {
+        let now = clock.now();
         Self {
             engines: HashMap::new(),
-            config,
-            rate_limiter: RateLimiter::new(),
-            last_rescan: Instant::now(),
-            rescan_interval: Duration::from_secs(3600), // Rescan hourly
+            rate_limiter: RateLimiter::from_config(&config.rate_limits, clock.clone()),
+            last_rescan: now,
+            rescan_interval: Duration::from_secs(config.markets.rescan_interval_secs),
             capital_allocations: HashMap::new(),
+            rebalanced: HashMap::new(),
+            last_resolution_check: now,
+            resolution_check_interval: Duration::from_secs(config.markets.resolution_check_secs),
+            resolution_statuses: HashMap::new(),
+            ctf_scheduler: CtfScheduler::new(&config.ctf),
+            last_param_refresh: now,
+            param_refresh_interval: Duration::from_secs(config.markets.param_refresh_secs),
+            clock,
+            risk_state: RiskState::default(),
+            var_breached: false,
+            confirm: None,
+            tick_count: 0,
+            retiring: HashSet::new(),
+            ranked_streaks: HashMap::new(),
+            unranked_streaks: HashMap::new(),
+            removed_at: HashMap::new(),
+            last_metrics_save: now,
+            metrics_save_interval: Duration::from_secs(config.monitoring.metrics_save_secs),
+            last_history_prune: now,
+            last_crowding_sample: now,
+            canary: None,
+            experiment: None,
+            paused: false,
+            market_states: MarketStates::default(),
+            skew_monitor: SkewMonitor::new(
+                config.monitoring.clock_skew_check_secs,
+                config.monitoring.max_clock_skew_ms,
+            ),
+            alerter: Alerter::new(config.alerts.clone()),
+            pending_additions: Vec::new(),
+            database: None,
+            fill_model: FillModel::default(),
+            config,
+        }
+    }
+
+    /// Load the persisted kill-switch latch. A latched kill switch keeps all
+    /// quoting stopped until cleared with `resume --ack-loss`.
+    pub fn load_risk_state(&mut self) -> Result<()> {
+        self.risk_state = RiskState::load(std::path::Path::new(&self.config.risk.state_file))?;
+        if let Some(latch) = &self.risk_state.kill_switch {
+            warn!(
+                tripped_at = %latch.tripped_at,
+                total_pnl = %latch.total_pnl,
+                "Kill switch is latched — not quoting until `resume --ack-loss`"
+            );
+        }
+        Ok(())
+    }
+
+    /// Load the fill curves calibrated by earlier runs. Call before
+    /// `initialize_markets` so engines start from them.
+    pub fn load_fill_model(&mut self) -> Result<()> {
+        let path = &self.config.monitoring.fill_model_file;
+        if !path.is_empty() {
+            self.fill_model = FillModel::load(std::path::Path::new(path))?;
+        }
+        Ok(())
+    }
+
+    /// Load the persisted per-market states. Call before
+    /// `initialize_markets` so retired markets are never added.
+    pub fn load_market_states(&mut self) -> Result<()> {
+        let path = std::path::Path::new(&self.config.monitoring.market_states_file);
+        self.market_states = MarketStates::load(path)?;
+        for (id, state) in &self.market_states.markets {
+            info!(market = %id, state = %state, "Market state set by operator");
         }
+        self.pending_additions = self.market_states.added.iter().cloned().collect();
+        Ok(())
     }
 
     /// Initialize engines for the given markets with capital allocation.
     pub fn initialize_markets(&mut self, markets: Vec<MarketInfo>) {
+        let markets: Vec<MarketInfo> = markets
+            .into_iter()
+            .filter(|m| !self.market_states.get(&m.condition_id).is_removed())
+            .collect();
         // Calculate capital allocation
-        let scores: Vec<(String, Decimal)> = markets
+        let scores: Vec<(String, Decimal, Decimal)> = markets
+            .iter()
+            .map(|m| {
+                let min = self.min_viable_allocation(m);
+                (m.condition_id.clone(), m.score, min)
+            })
+            .collect();
+        let allocations = risk::allocate_viable_capital(
+            &scores,
+            self.config.risk.max_total_capital,
+            self.config.risk.max_per_market,
+        );
+        let viable: HashSet<String> = allocations.iter().map(|(id, _)| id.clone()).collect();
+        self.capital_allocations.extend(allocations);
+
+        for market in markets {
+            if self.engines.contains_key(&market.condition_id)
+                || !viable.contains(&market.condition_id)
+            {
+                continue;
+            }
+            self.add_engine(market);
+        }
+
+        info!(total_markets = self.engines.len(), "Markets initialized");
+    }
+
+    /// Start an engine for `market`, sized to its capital allocation.
+    fn add_engine(&mut self, market: MarketInfo) {
+        let cond_id = market.condition_id.clone();
+        let allocation = self
+            .capital_allocations
+            .get(&cond_id)
+            .copied()
+            .unwrap_or(Decimal::ZERO);
+
+        // Adjust order size based on allocation
+        let mut strategy = self.strategy_for(&cond_id).clone();
+        strategy.order_size = self.allocated_order_size(&market, allocation);
+        self.config.markets.apply_overrides(&cond_id, &mut strategy);
+        strategy.inventory_cap = risk::scaled_inventory_cap(&market, &strategy);
+        let dry_run = self.config.markets.dry_run_markets.contains(&cond_id);
+
+        info!(
+            market = %market.question,
+            allocation = %allocation,
+            order_size = %strategy.order_size,
+            reward_min = ?market.rewards_min_size,
+            arm = ?self.arm_of(&cond_id),
+            dry_run,
+            "Adding market to manager"
+        );
+
+        // Paper-traded markets stay out of the history database, which
+        // records what happened on the exchange
+        let database = self.database.clone().filter(|_| !dry_run);
+        let mut engine = QuoteEngine::new(market, strategy, dry_run)
+            .with_clock(self.clock.clone())
+            .with_exit_limits(ExitLimits::from_config(&self.config.risk))
+            .with_flow_limits(FlowLimits::from_config(&self.config.risk))
+            .with_deadman(self.config.risk.deadman_secs)
+            .with_database(database);
+        engine.metrics.dry_run = dry_run;
+        engine.fill_curve = self.fill_model.curve_for(&cond_id);
+        engine.confirm = self.confirm;
+        engine.paused = self.paused;
+        match self.market_states.get(&cond_id) {
+            EngineState::Paused => engine.paused = true,
+            EngineState::Unwinding => engine.start_exit(Some(ExitReason::Operator)),
+            EngineState::Active | EngineState::Removing | EngineState::Retired => {}
+        }
+        engine.tier = self.classify_tier(&engine.market);
+        self.engines.insert(cond_id, engine);
+    }
+
+    /// Capital not allocated to any managed market.
+    pub fn unallocated_capital(&self) -> Decimal {
+        let allocated: Decimal = self.engines.keys().map(|id| self.allocation_for(id)).sum();
+        (self.config.risk.max_total_capital - allocated).max(Decimal::ZERO)
+    }
+
+    /// Start quoting a market added by hand, funded from unallocated capital
+    /// up to `max_per_market`. Errors if what's left can't fund it.
+    pub fn add_manual_market(&mut self, market: MarketInfo) -> Result<Decimal> {
+        let allocation = self
+            .unallocated_capital()
+            .min(self.config.risk.max_per_market);
+        let min = self.min_viable_allocation(&market);
+        if allocation <= Decimal::ZERO || allocation < min {
+            bail!("only ${allocation} unallocated, market needs ${min}");
+        }
+        self.capital_allocations
+            .insert(market.condition_id.clone(), allocation);
+        self.add_engine(market);
+        Ok(allocation)
+    }
+
+    /// Scan and start markets queued with `add-market`. Markets that can't
+    /// be found or funded are reported once and dropped from the queue;
+    /// a failed scan keeps them queued for the next loop.
+    pub async fn add_requested_markets(
+        &mut self,
+        gamma_client: &Timed<polymarket_client_sdk::gamma::Client>,
+        exchange: &impl ExchangeApi,
+    ) {
+        let pending: Vec<String> = std::mem::take(&mut self.pending_additions)
+            .into_iter()
+            .filter(|id| !self.engines.contains_key(id) && !self.market_states.get(id).is_removed())
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+        let scanned = match scanner::fetch_markets(gamma_client, &pending).await {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                warn!(error = %e, "Failed to scan added markets, retrying next loop");
+                self.pending_additions = pending;
+                return;
+            }
+        };
+
+        let mut messages = Vec::new();
+        for id in &pending {
+            let Some(market) = scanned.iter().find(|m| m.condition_id == *id) else {
+                warn!(market = %id, "Added market not found or not active");
+                messages.push(format!("⚠️ Could not add {id}: not found or not active"));
+                continue;
+            };
+            let question = market.question.clone();
+            match self.add_manual_market(market.clone()) {
+                Ok(allocation) => {
+                    info!(market = %question, allocation = %allocation, "Market added by operator");
+                    messages.push(format!("➕ Added {question} with ${allocation:.2}"));
+                }
+                Err(e) => {
+                    warn!(market = %question, error = %e, "Not enough capital to add market");
+                    messages.push(format!("⚠️ Could not add {question}: {e}"));
+                }
+            }
+        }
+        self.seed_volatility(exchange).await;
+        for message in messages {
+            self.send_alert(Alert::new(AlertRule::MarketChange, Severity::Info, message))
+                .await;
+        }
+    }
+
+    /// Seed volatility from price history for markets without samples yet.
+    pub async fn seed_volatility(&mut self, exchange: &impl MarketData) {
+        join_all(
+            self.engines
+                .values_mut()
+                .map(|engine| engine.seed_volatility(exchange)),
+        )
+        .await;
+    }
+
+    /// Strategy for a market: the configured one, unless a canary run keeps
+    /// this market on the baseline profile or it is in experiment arm B.
+    pub fn strategy_for(&self, condition_id: &str) -> &StrategyConfig {
+        match (&self.canary, &self.experiment) {
+            (Some(canary), _) if canary.condition_id != condition_id => &canary.baseline,
+            (_, Some(experiment)) if experiment.assign(condition_id) == Arm::B => {
+                &experiment.strategy_b
+            }
+            _ => &self.config.strategy,
+        }
+    }
+
+    /// Experiment arm for a market, if an A/B experiment is running.
+    pub fn arm_of(&self, condition_id: &str) -> Option<Arm> {
+        self.experiment.as_ref().map(|e| e.assign(condition_id))
+    }
+
+    /// Per-arm comparison across managed markets, if an experiment is running.
+    pub fn experiment_report(&self) -> Option<String> {
+        let run = self.experiment.as_ref()?;
+        let (b, a): (Vec<&MarketMetrics>, Vec<&MarketMetrics>) = self
+            .engines
+            .values()
+            .map(|e| &e.metrics)
+            .partition(|m| run.assign(&m.condition_id) == Arm::B);
+        Some(experiment::format_report(run, &a, &b, chrono::Utc::now()))
+    }
+
+    /// Canary market against the baseline markets, if this is a canary run
+    /// and the canary is still managed.
+    pub fn canary_report(&self) -> Option<String> {
+        let run = self.canary.as_ref()?;
+        let canary = self.engines.get(&run.condition_id)?;
+        let baseline: Vec<&MarketMetrics> = self
+            .engines
+            .iter()
+            .filter(|(id, _)| **id != run.condition_id)
+            .map(|(_, e)| &e.metrics)
+            .collect();
+        let changes = canary::changed_settings(&run.baseline, &self.config.strategy);
+        Some(canary::format_comparison(
+            &canary.metrics,
+            &baseline,
+            &changes,
+        ))
+    }
+
+    /// Least capital worth giving a market: enough that its scaled order
+    /// size reaches the reward minimum, and for a minimum-size order on
+    /// every level (about a dollar of buys per share pair). Zero for markets
+    /// without a reward minimum.
+    fn min_viable_allocation(&self, market: &MarketInfo) -> Decimal {
+        let Some(min_size) = market.rewards_min_size.filter(|s| *s > Decimal::ZERO) else {
+            return Decimal::ZERO;
+        };
+        let strategy = self.strategy_for(&market.condition_id);
+        let levels = if strategy.dynamic_levels {
+            1
+        } else {
+            strategy.num_levels.max(1)
+        };
+        let for_levels = min_size * Decimal::from(levels);
+        if strategy.order_size <= Decimal::ZERO {
+            return for_levels;
+        }
+        let for_size = min_size * self.config.risk.max_per_market / strategy.order_size;
+        for_levels.max(for_size)
+    }
+
+    /// Order size scaled proportionally to a market's capital allocation,
+    /// never below the market's reward minimum so its orders stay eligible.
+    fn allocated_order_size(&self, market: &MarketInfo, allocation: Decimal) -> Decimal {
+        let order_size = self.strategy_for(&market.condition_id).order_size;
+        let base_capital = self.config.risk.max_per_market;
+        if allocation <= Decimal::ZERO || base_capital <= Decimal::ZERO {
+            return order_size;
+        }
+        let scaled = (order_size * allocation / base_capital)
+            .round()
+            .max(Decimal::ONE);
+        match market.rewards_min_size {
+            Some(min_size) if scaled < min_size => {
+                info!(
+                    market = %market.question,
+                    allocation = %allocation,
+                    scaled = %scaled,
+                    reward_min = %min_size,
+                    "Scaled order size below reward minimum, using the minimum"
+                );
+                min_size
+            }
+            _ => scaled,
+        }
+    }
+
+    /// Recompute capital allocations across every quoting market from fresh
+    /// scores, resizing orders to match. Returns the markets whose share
+    /// fell below their minimum viable allocation; they get none and should
+    /// be wound down.
+    pub fn reallocate_capital(&mut self, fresh: &[MarketInfo]) -> Vec<String> {
+        let scores: Vec<(String, Decimal, Decimal)> = self
+            .engines
             .iter()
-            .map(|m| (m.condition_id.clone(), m.score))
+            .filter(|(id, _)| !self.retiring.contains(*id))
+            .map(|(id, engine)| {
+                let crowding = engine
+                    .crowding
+                    .score_factor(self.config.markets.crowding_min_factor);
+                let score = fresh
+                    .iter()
+                    .find(|m| m.condition_id == *id)
+                    .map_or(engine.market.score * crowding, |m| m.score);
+                let min = self.min_viable_allocation(&engine.market);
+                (id.clone(), score, min)
+            })
             .collect();
 
-        self.capital_allocations = risk::allocate_capital(
+        self.capital_allocations = risk::allocate_viable_capital(
             &scores,
             self.config.risk.max_total_capital,
             self.config.risk.max_per_market,
         )
         .into_iter()
         .collect();
+        let unviable: Vec<String> = scores
+            .into_iter()
+            .map(|(id, _, _)| id)
+            .filter(|id| !self.capital_allocations.contains_key(id))
+            .collect();
 
-        for market in markets {
-            let cond_id = market.condition_id.clone();
-            if self.engines.contains_key(&cond_id) {
+        for (id, allocation) in &self.capital_allocations {
+            let Some(engine) = self.engines.get(id) else {
+                continue;
+            };
+            let order_size = self.allocated_order_size(&engine.market, *allocation);
+            if let Some(engine) = self.engines.get_mut(id)
+                && engine.config.order_size != order_size
+            {
+                info!(
+                    market = %engine.market.question,
+                    allocation = %allocation,
+                    from = %engine.config.order_size,
+                    to = %order_size,
+                    reward_min = ?engine.market.rewards_min_size,
+                    "Order size reallocated"
+                );
+                engine.config.order_size = order_size;
+            }
+        }
+        unviable
+    }
+
+    /// Allocation a market quotes against: its rescan share plus any
+    /// intraday shift.
+    fn allocation_for(&self, id: &str) -> Decimal {
+        self.capital_allocations
+            .get(id)
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+            + self.rebalanced.get(id).copied().unwrap_or(Decimal::ZERO)
+    }
+
+    /// Resize a market's orders to its current allocation.
+    fn resize_to_allocation(&mut self, id: &str) {
+        let allocation = self.allocation_for(id);
+        let Some(engine) = self.engines.get(id) else {
+            return;
+        };
+        let order_size = self.allocated_order_size(&engine.market, allocation);
+        if let Some(engine) = self.engines.get_mut(id)
+            && engine.config.order_size != order_size
+        {
+            info!(
+                market = %engine.market.question,
+                allocation = %allocation,
+                from = %engine.config.order_size,
+                to = %order_size,
+                "Order size rebalanced"
+            );
+            engine.config.order_size = order_size;
+        }
+    }
+
+    /// Lend the unused allocation of markets sitting at their inventory cap
+    /// to markets whose committed capital has reached their allocation.
+    /// Lenders keep what they have committed; borrowers are topped up to at
+    /// most `max_per_market`. Shifts hold until the next rescan.
+    pub fn rebalance_intraday(&mut self) {
+        let mut lenders = Vec::new();
+        let mut starved = Vec::new();
+        for (id, engine) in &self.engines {
+            let allocation = self.allocation_for(id);
+            if self.retiring.contains(id) || allocation <= Decimal::ZERO {
+                continue;
+            }
+            let committed = engine.committed_capital();
+            // The same netted exposure the quoter skews against the cap
+            let net = engine.net_exposure().abs();
+            let cap = engine.config.inventory_cap;
+            if cap > Decimal::ZERO && net >= cap {
+                // Each market lends once per rescan, never below its commitment
+                if !self.rebalanced.contains_key(id)
+                    && committed > Decimal::ZERO
+                    && committed < allocation
+                {
+                    lenders.push((id.clone(), allocation - committed));
+                }
+            } else if committed >= allocation {
+                starved.push(id.clone());
+            }
+        }
+        if lenders.is_empty() || starved.is_empty() {
+            return;
+        }
+        lenders.sort();
+        starved.sort();
+
+        let spare: Decimal = lenders.iter().map(|(_, amount)| *amount).sum();
+        let share = spare / Decimal::from(starved.len());
+        let mut moved = Decimal::ZERO;
+        for id in &starved {
+            let headroom = self.config.risk.max_per_market - self.allocation_for(id);
+            let amount = share.min(headroom).max(Decimal::ZERO);
+            if amount > Decimal::ZERO {
+                *self.rebalanced.entry(id.clone()).or_default() += amount;
+                moved += amount;
+            }
+        }
+        if moved.is_zero() {
+            return;
+        }
+        info!(
+            amount = %moved,
+            lenders = lenders.len(),
+            borrowers = starved.len(),
+            "Rebalancing capital from inventory-capped markets"
+        );
+        let mut remaining = moved;
+        for (id, amount) in &lenders {
+            let lent = (*amount).min(remaining);
+            *self.rebalanced.entry(id.clone()).or_default() -= lent;
+            remaining -= lent;
+        }
+        let shifted: Vec<String> = lenders
+            .into_iter()
+            .map(|(id, _)| id)
+            .chain(starved)
+            .collect();
+        for id in shifted {
+            self.resize_to_allocation(&id);
+        }
+    }
+
+    /// Undo intraday shifts, restoring each market's rescan allocation and
+    /// order size.
+    pub fn revert_rebalancing(&mut self) {
+        let shifted: Vec<String> = self.rebalanced.drain().map(|(id, _)| id).collect();
+        if shifted.is_empty() {
+            return;
+        }
+        info!(markets = shifted.len(), "Reverting intraday rebalancing");
+        for id in shifted {
+            self.resize_to_allocation(&id);
+        }
+    }
+
+    /// Recompute each managed market's inventory cap from freshly scanned
+    /// volume and depth. A no-op under the `fixed` basis.
+    pub fn rescale_inventory_caps(&mut self, scanned: &[MarketInfo]) {
+        for market in scanned {
+            let id = &market.condition_id;
+            if !self.engines.contains_key(id) {
+                continue;
+            }
+            let mut strategy = self.strategy_for(id).clone();
+            self.config.markets.apply_overrides(id, &mut strategy);
+            if let Some(engine) = self.engines.get_mut(id) {
+                strategy.order_size = engine.config.order_size;
+                let cap = risk::scaled_inventory_cap(market, &strategy);
+                if engine.config.inventory_cap != cap {
+                    info!(
+                        market = %market.question,
+                        from = %engine.config.inventory_cap,
+                        to = %cap,
+                        basis = ?strategy.inventory_cap_basis,
+                        "Inventory cap rescaled"
+                    );
+                    engine.config.inventory_cap = cap;
+                }
+            }
+        }
+    }
+
+    /// Wind down markets that fell out of the ranking: cancel their orders,
+    /// then drop flat markets and unwind the rest before dropping them.
+    pub async fn remove_stale_markets(
+        &mut self,
+        active_ids: &[String],
+        exchange: &impl ExchangeApi,
+    ) {
+        // Markets that ranked again before finishing their unwind resume quoting
+        let returning: Vec<String> = self
+            .retiring
+            .iter()
+            .filter(|id| active_ids.contains(id))
+            .cloned()
+            .collect();
+        for id in returning {
+            self.retiring.remove(&id);
+            if let Some(engine) = self.engines.get_mut(&id)
+                && engine.exit_reason == Some(ExitReason::Delisted)
+            {
+                info!(market = %engine.market.question, "Market back in ranking, resuming quotes");
+                engine.start_exit(None);
+            }
+        }
+
+        let stale: Vec<String> = self
+            .engines
+            .keys()
+            .filter(|id| !active_ids.contains(id) && !self.retiring.contains(*id))
+            .cloned()
+            .collect();
+
+        let mut messages = Vec::new();
+        for id in stale {
+            let Some(engine) = self.engines.get_mut(&id) else {
+                continue;
+            };
+            if let Err(e) = engine.cancel_all(exchange).await {
+                // Keep the engine so its orders stay tracked; retried next rescan
+                warn!(market = %engine.market.question, error = %e, "Failed to cancel orders for stale market");
+                continue;
+            }
+            if engine.inventory_yes <= Decimal::ZERO && engine.inventory_no <= Decimal::ZERO {
+                self.retire_engine(&id);
+                continue;
+            }
+
+            info!(
+                market = %engine.market.question,
+                yes = %engine.inventory_yes,
+                no = %engine.inventory_no,
+                "Stale market holds inventory, unwinding before removal"
+            );
+            if engine.exit_reason.is_none() {
+                engine.start_exit(Some(ExitReason::Delisted));
+            }
+            messages.push(format!(
+                "📤 Dropped from rotation: {}\nUnwinding {} YES / {} NO",
+                engine.market.question, engine.inventory_yes, engine.inventory_no
+            ));
+            self.retiring.insert(id);
+        }
+
+        for message in messages {
+            self.send_alert(Alert::new(AlertRule::MarketChange, Severity::Info, message))
+                .await;
+        }
+    }
+
+    /// Drop retiring markets whose unwind has finished.
+    fn finish_retirements(&mut self) {
+        self.retiring.retain(|id| self.engines.contains_key(id));
+        let done: Vec<String> = self
+            .retiring
+            .iter()
+            .filter(|id| self.engines[*id].halted)
+            .cloned()
+            .collect();
+        for id in done {
+            self.retire_engine(&id);
+        }
+    }
+
+    /// Remove an engine, persisting its final PnL and inventory to the
+    /// metrics file and freeing its allocation.
+    fn retire_engine(&mut self, condition_id: &str) {
+        self.retiring.remove(condition_id);
+        self.unranked_streaks.remove(condition_id);
+        self.capital_allocations.remove(condition_id);
+        self.rebalanced.remove(condition_id);
+        let Some(engine) = self.engines.remove(condition_id) else {
+            return;
+        };
+        self.removed_at
+            .insert(condition_id.to_string(), self.clock.now());
+        if !engine.dry_run && engine.fill_curve.observations() > 0.0 {
+            self.fill_model
+                .markets
+                .insert(condition_id.to_string(), engine.fill_curve.clone());
+        }
+        let final_metrics = engine.final_metrics();
+        info!(
+            market = %engine.market.question,
+            pnl = %final_metrics.net_pnl(),
+            yes = %final_metrics.inventory_yes,
+            no = %final_metrics.inventory_no,
+            "Removing market"
+        );
+
+        let path = std::path::Path::new(&self.config.monitoring.metrics_file);
+        if let Err(e) = metrics::PortfolioMetrics::persist_markets(path, [final_metrics]) {
+            warn!(market = %engine.market.question, error = %e, "Failed to persist final market metrics");
+        }
+    }
+
+    /// Re-read `market_states_file` and move every market whose state
+    /// changed there. A transition whose cancel fails is retried on the
+    /// next sync.
+    pub async fn sync_market_states(&mut self, exchange: &impl ExchangeApi) {
+        let path = std::path::Path::new(&self.config.monitoring.market_states_file);
+        let next = match MarketStates::load(path) {
+            Ok(next) => next,
+            Err(e) => {
+                warn!(error = %e, "Failed to read market states, keeping current ones");
+                return;
+            }
+        };
+        let changes = self.market_states.changes(&next);
+        self.pending_additions
+            .extend(self.market_states.additions(&next));
+        self.market_states = next;
+
+        let mut messages = Vec::new();
+        for (id, from, to) in changes {
+            let Some(engine) = self.engines.get_mut(&id) else {
+                if from.is_removed() && !to.is_removed() {
+                    info!(market = %id, state = %to, "Removed market eligible again at next rescan");
+                }
+                continue;
+            };
+            let question = engine.market.question.clone();
+            // Leaving an operator unwind restarts quoting even if it finished
+            if engine.exit_reason == Some(ExitReason::Operator) {
+                engine.start_exit(None);
+                engine.halted = false;
+            }
+            let result = match to {
+                EngineState::Active => engine.set_paused(exchange, self.paused).await,
+                EngineState::Paused => engine.set_paused(exchange, true).await,
+                EngineState::Unwinding => {
+                    if engine.exit_reason.is_none() {
+                        engine.start_exit(Some(ExitReason::Operator));
+                    }
+                    engine.set_paused(exchange, self.paused).await
+                }
+                EngineState::Removing => {
+                    if engine.exit_reason.is_none() {
+                        engine.start_exit(Some(ExitReason::Operator));
+                    }
+                    engine.paused = self.paused;
+                    engine.cancel_all(exchange).await
+                }
+                EngineState::Retired => engine.cancel_all(exchange).await,
+            };
+            if let Err(e) = result {
+                warn!(market = %question, state = %to, error = %e, "Failed to apply market state");
+                self.market_states.set(&id, from);
+                continue;
+            }
+            match to {
+                EngineState::Retired => self.retire_engine(&id),
+                // Dropped by `finish_retirements` once the unwind is done
+                EngineState::Removing => {
+                    self.retiring.insert(id.clone());
+                }
+                _ if from == EngineState::Removing => {
+                    self.retiring.remove(&id);
+                }
+                _ => {}
+            }
+            info!(market = %question, from = %from, to = %to, "Market state changed");
+            messages.push(format!("🎛️ {question}: {from} → {to}"));
+        }
+        for message in messages {
+            self.send_alert(Alert::new(AlertRule::MarketChange, Severity::Info, message))
+                .await;
+        }
+    }
+
+    /// Check if a periodic metrics write is due.
+    pub fn needs_metrics_save(&self) -> bool {
+        self.clock.elapsed(self.last_metrics_save) > self.metrics_save_interval
+    }
+
+    /// Check if the history database is due for pruning.
+    pub fn needs_history_prune(&self) -> bool {
+        let interval = self.config.retention.prune_interval_secs;
+        self.database.is_some()
+            && interval > 0
+            && self.clock.elapsed(self.last_history_prune) > Duration::from_secs(interval)
+    }
+
+    /// Delete history past `[retention]`.
+    pub async fn prune_history(&mut self) {
+        self.last_history_prune = self.clock.now();
+        if let Some(db) = &self.database {
+            db::prune_in_background(db.clone(), self.config.retention.clone()).await;
+        }
+    }
+
+    /// Write every engine's metrics to the metrics file. When the file rotates
+    /// at UTC midnight, send the daily summary and start a new day's counters.
+    pub async fn save_metrics(&mut self) {
+        self.last_metrics_save = self.clock.now();
+        self.save_fill_model();
+        let path = std::path::PathBuf::from(&self.config.monitoring.metrics_file);
+        let retention = self.config.monitoring.metrics_retention_days;
+        if let Some(textfile) = &self.config.monitoring.prometheus_textfile {
+            let snapshots: Vec<MarketMetrics> =
+                self.engines.values().map(|e| e.final_metrics()).collect();
+            if let Err(e) = prometheus::write_textfile(std::path::Path::new(textfile), &snapshots) {
+                warn!(error = %e, "Failed to write Prometheus textfile");
+            }
+        }
+        for _ in 0..2 {
+            let snapshots = self.engines.values().map(|e| e.final_metrics()).collect();
+            match metrics::autosave(path.clone(), snapshots, retention).await {
+                Ok(Some(finished)) => {
+                    self.send_daily_summary(&finished).await;
+                    for engine in self.engines.values_mut() {
+                        engine.metrics.roll_day();
+                    }
+                }
+                Ok(None) => return,
+                Err(e) => {
+                    warn!(error = %e, "Failed to save metrics");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Persist live engines' fill curves. Paper engines only borrow a curve
+    /// and never add to it.
+    fn save_fill_model(&mut self) {
+        for (id, engine) in &self.engines {
+            if !engine.dry_run && engine.fill_curve.observations() > 0.0 {
+                self.fill_model
+                    .markets
+                    .insert(id.clone(), engine.fill_curve.clone());
+            }
+        }
+        let path = &self.config.monitoring.fill_model_file;
+        if !path.is_empty()
+            && let Err(e) = self.fill_model.save(std::path::Path::new(path))
+        {
+            warn!(error = %e, "Failed to save fill model");
+        }
+    }
+
+    /// Push the finished day's summary to Telegram.
+    async fn send_daily_summary(&self, finished: &metrics::PortfolioMetrics) {
+        let monitoring = &self.config.monitoring;
+        if !monitoring.daily_summary {
+            return;
+        }
+        let estimates: HashMap<String, Decimal> = self
+            .engines
+            .iter()
+            .map(|(id, e)| (id.clone(), e.market.reward_daily_estimate))
+            .collect();
+        let mut risk_events = Vec::new();
+        if let Some(latch) = &self.risk_state.kill_switch {
+            risk_events.push(format!(
+                "Kill switch latched since {} (PnL ${:.2})",
+                latch.tripped_at.format("%Y-%m-%d %H:%M UTC"),
+                latch.total_pnl
+            ));
+        }
+        if self.var_breached {
+            risk_events.push("Portfolio VaR above limit".to_string());
+        }
+        let mut summary = metrics::format_daily_summary(finished, &estimates, &risk_events);
+        match metrics::performance_report(
+            std::path::Path::new(&monitoring.metrics_file),
+            monitoring.performance_window_days,
+        ) {
+            Ok(Some(report)) => summary.push_str(&format!("\n{report}")),
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, "Failed to compute performance statistics"),
+        }
+        for report in [self.canary_report(), self.experiment_report()]
+            .into_iter()
+            .flatten()
+        {
+            summary.push_str(&format!("\n\n{report}"));
+        }
+        if let Err(e) = metrics::send_telegram_alert(
+            &monitoring.telegram_bot_token,
+            &monitoring.telegram_chat_id,
+            &summary,
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to send daily summary");
+        }
+    }
+
+    /// Update rank streaks from a rescan's ranked IDs. Returns the unmanaged
+    /// markets that have ranked long enough to add and the managed markets to
+    /// keep; anything managed and not kept has missed enough rescans to remove.
+    pub fn apply_hysteresis(&mut self, ranked_ids: &[String]) -> (Vec<String>, Vec<String>) {
+        let markets = &self.config.markets;
+        let now = self.clock.now();
+        let cooldown = Duration::from_secs(markets.readd_cooldown_secs);
+        self.removed_at
+            .retain(|_, at| now.saturating_duration_since(*at) < cooldown);
+
+        self.ranked_streaks.retain(|id, _| ranked_ids.contains(id));
+        let mut add = Vec::new();
+        for id in ranked_ids {
+            if self.engines.contains_key(id) {
+                self.ranked_streaks.remove(id);
+                continue;
+            }
+            let streak = self.ranked_streaks.entry(id.clone()).or_insert(0);
+            *streak += 1;
+            if *streak >= markets.add_after_rescans && !self.removed_at.contains_key(id) {
+                add.push(id.clone());
+            }
+        }
+
+        // The canary stays for the whole run so the comparison holds
+        let canary = self.canary.as_ref().map(|c| c.condition_id.as_str());
+        let mut keep = Vec::new();
+        for id in self.engines.keys() {
+            if ranked_ids.contains(id) || canary == Some(id.as_str()) {
+                self.unranked_streaks.remove(id);
+                keep.push(id.clone());
+                continue;
+            }
+            let misses = self.unranked_streaks.entry(id.clone()).or_insert(0);
+            *misses += 1;
+            if *misses < markets.remove_after_rescans {
+                keep.push(id.clone());
+            }
+        }
+
+        (add, keep)
+    }
+
+    /// Check if a rescan is due.
+    pub fn needs_rescan(&self) -> bool {
+        self.clock.elapsed(self.last_rescan) > self.rescan_interval
+    }
+
+    /// Perform a rescan: fetch fresh markets, add new ones, wind down stale ones.
+    pub async fn rescan(
+        &mut self,
+        gamma_client: &Timed<polymarket_client_sdk::gamma::Client>,
+        exchange: &impl ExchangeApi,
+    ) -> Result<()> {
+        info!("Rescanning markets...");
+        self.revert_rebalancing();
+
+        let mut all_markets =
+            scanner::scan_markets(gamma_client, self.config.markets.score_weights.as_ref()).await?;
+        self.apply_crowding(&mut all_markets);
+        self.rescale_inventory_caps(&all_markets);
+        let ranked = scanner::rank_markets(
+            &all_markets,
+            self.config.markets.min_reward_daily,
+            self.config.markets.max_markets,
+            self.config.markets.prefer_fee_enabled,
+        );
+
+        let ranked_ids: Vec<String> = ranked.iter().map(|m| m.condition_id.clone()).collect();
+        let (add_ids, keep_ids) = self.apply_hysteresis(&ranked_ids);
+
+        let markets = &self.config.markets;
+
+        // Add markets that have ranked for enough rescans, up to the market cap
+        if markets.rescan_add_new {
+            let quoting = keep_ids
+                .iter()
+                .filter(|id| !self.retiring.contains(*id))
+                .count();
+            let slots = markets.max_markets.saturating_sub(quoting);
+            let new_markets: Vec<MarketInfo> = ranked
+                .iter()
+                .filter(|m| add_ids.contains(&m.condition_id))
+                .take(slots)
+                .cloned()
+                .collect();
+
+            if !new_markets.is_empty() {
+                info!(count = new_markets.len(), "New markets discovered");
+                for market in &new_markets {
+                    self.ranked_streaks.remove(&market.condition_id);
+                }
+                self.initialize_markets(new_markets);
+                self.seed_volatility(exchange).await;
+            }
+        }
+
+        // Remove markets that have missed enough rescans; markets added by
+        // hand stay until retired
+        if self.config.markets.rescan_remove_stale {
+            let mut active_ids = keep_ids;
+            active_ids.extend(
+                self.engines
+                    .keys()
+                    .filter(|id| add_ids.contains(id) || self.market_states.added.contains(*id))
+                    .cloned(),
+            );
+            self.remove_stale_markets(&active_ids, exchange).await;
+        }
+
+        if self.config.markets.rescan_reallocate {
+            let unviable = self.reallocate_capital(&ranked);
+            if !unviable.is_empty() {
+                // Wind down only the unviable markets
+                let keep: Vec<String> = self
+                    .engines
+                    .keys()
+                    .filter(|id| !unviable.contains(id) && !self.retiring.contains(*id))
+                    .cloned()
+                    .collect();
+                self.remove_stale_markets(&keep, exchange).await;
+            }
+        }
+
+        // Check for sponsored markets (high reward/competition)
+        for (_, engine) in &self.engines {
+            if engine.market.reward_daily_estimate > dec!(50) {
+                info!(
+                    market = %engine.market.question,
+                    reward = %engine.market.reward_daily_estimate,
+                    "Sponsored market detected — high reward opportunity"
+                );
+            }
+        }
+
+        self.last_rescan = self.clock.now();
+        info!(total_markets = self.engines.len(), "Rescan complete");
+        Ok(())
+    }
+
+    /// Check if a band-crowding snapshot is due.
+    pub fn needs_skew_check(&self) -> bool {
+        self.skew_monitor.due(self.clock.now())
+    }
+
+    /// Compare the local clock with the exchange's, alerting when drift
+    /// crosses `monitoring.max_clock_skew_ms`.
+    pub async fn check_clock_skew(&mut self, exchange: &impl ExchangeApi) {
+        let now = self.clock.now();
+        let alert = self.skew_monitor.check(now, exchange).await;
+        exchange.set_clock_skew(self.skew_monitor.skew);
+        if let Some((severity, message)) = alert {
+            self.send_alert(Alert::new(AlertRule::ClockSkew, severity, message))
+                .await;
+        }
+    }
+
+    pub fn needs_crowding_sample(&self) -> bool {
+        let secs = self.config.markets.crowding_sample_secs;
+        secs > 0 && self.clock.elapsed(self.last_crowding_sample) > Duration::from_secs(secs)
+    }
+
+    /// Snapshot each quoting market's book to track how much reward-band
+    /// size other makers hold. Uses only the shared (non-tier-1) GET budget.
+    pub async fn sample_crowding(&mut self, exchange: &impl ExchangeApi) {
+        self.last_crowding_sample = self.clock.now();
+        let share = Decimal::ONE - self.config.rate_limits.tier1_reserve_pct / dec!(100);
+        let book_usage = ApiUsage {
+            gets: 1,
+            ..ApiUsage::default()
+        };
+        for engine in self.engines.values_mut().filter(|e| !e.halted) {
+            if !self.rate_limiter.can_afford_within(&book_usage, share) {
+                debug!("Rate limit reached, deferring remaining crowding samples");
+                break;
+            }
+            self.rate_limiter.record_usage(&book_usage);
+            match exchange.order_book(&engine.market.token_yes_id).await {
+                Ok(book) => engine.sample_crowding(&book),
+                Err(e) => warn!(
+                    market = %engine.market.question,
+                    error = %e,
+                    "Failed to fetch book for crowding"
+                ),
+            }
+        }
+    }
+
+    /// Scale scanned scores of managed markets by their crowding factor, so
+    /// markets where our band share is collapsing rank lower and draw less
+    /// capital. Re-sorts by score, as `rank_markets` expects.
+    fn apply_crowding(&self, markets: &mut [MarketInfo]) {
+        let floor = self.config.markets.crowding_min_factor;
+        for market in markets.iter_mut() {
+            let Some(engine) = self.engines.get(&market.condition_id) else {
                 continue;
+            };
+            let factor = engine.crowding.score_factor(floor);
+            if factor < Decimal::ONE {
+                info!(
+                    market = %market.question,
+                    factor = %factor,
+                    competitor_size = ?engine.crowding.persistent_competitor(),
+                    "Band crowding lowers market score"
+                );
+                market.score *= factor;
             }
+        }
+        markets.sort_by_key(|m| std::cmp::Reverse(m.score));
+    }
+
+    /// Check if a market parameter refresh is due.
+    pub fn needs_param_refresh(&self) -> bool {
+        self.clock.elapsed(self.last_param_refresh) > self.param_refresh_interval
+    }
+
+    /// Re-fetch tick size, reward band, and fees for managed markets so
+    /// engines don't quote on stale parameters during long sessions.
+    pub async fn refresh_market_params(
+        &mut self,
+        gamma_client: &Timed<polymarket_client_sdk::gamma::Client>,
+    ) -> Result<()> {
+        let ids: Vec<String> = self.engines.keys().cloned().collect();
+        let fresh = scanner::fetch_markets(gamma_client, &ids).await?;
+        self.last_param_refresh = self.clock.now();
+
+        let mut changed = 0;
+        for market in &fresh {
+            let tier = self.classify_tier(market);
+            if let Some(engine) = self.engines.get_mut(&market.condition_id) {
+                if engine.update_market_params(market) {
+                    changed += 1;
+                }
+                if engine.tier != tier {
+                    info!(market = %market.question, from = ?engine.tier, to = ?tier, "Market tier changed");
+                    engine.tier = tier;
+                }
+            }
+        }
+        info!(refreshed = fresh.len(), changed, "Market parameters refreshed");
+        Ok(())
+    }
+
+    /// Check if a resolution status poll is due.
+    pub fn needs_resolution_check(&self) -> bool {
+        self.clock.elapsed(self.last_resolution_check) > self.resolution_check_interval
+    }
+
+    /// Poll managed markets for closure, disputes, and finalized outcomes.
+    /// Closed or disputed markets stop quoting; resolved markets are redeemed and dropped.
+    pub async fn check_resolutions(
+        &mut self,
+        gamma_client: &Timed<polymarket_client_sdk::gamma::Client>,
+        exchange: &impl ExchangeApi,
+    ) -> Result<()> {
+        let ids: Vec<String> = self.engines.keys().cloned().collect();
+        let statuses = inventory::fetch_resolution_statuses(gamma_client, &ids).await?;
+        self.last_resolution_check = self.clock.now();
 
-            let allocation = self
-                .capital_allocations
+        for (cond_id, status) in statuses {
+            let previous = self
+                .resolution_statuses
                 .get(&cond_id)
                 .copied()
-                .unwrap_or(Decimal::ZERO);
-
-            // Adjust order size based on allocation
-            let mut strategy = self.config.strategy.clone();
-            if allocation > Decimal::ZERO {
-                // Scale order size proportionally to allocation
-                let base_capital = self.config.risk.max_per_market;
-                if base_capital > Decimal::ZERO {
-                    let scale = allocation / base_capital;
-                    strategy.order_size = (strategy.order_size * scale).round();
-                    strategy.order_size = strategy.order_size.max(Decimal::ONE);
-                }
+                .unwrap_or(ResolutionStatus::Open);
+            if previous == status {
+                continue;
             }
+            let engine = match self.engines.get_mut(&cond_id) {
+                Some(e) => e,
+                None => continue,
+            };
 
-            info!(
-                market = %market.question,
-                allocation = %allocation,
-                order_size = %strategy.order_size,
-                "Adding market to manager"
-            );
+            match status {
+                ResolutionStatus::Open => {
+                    engine.halted = false;
+                }
+                ResolutionStatus::Closed | ResolutionStatus::Disputed => {
+                    info!(
+                        market = %engine.market.question,
+                        status = ?status,
+                        "Market no longer tradeable, halting quotes"
+                    );
+                    engine.halted = true;
+                    if let Err(e) = engine.cancel_all(exchange).await {
+                        warn!(
+                            market = %engine.market.question,
+                            error = %e,
+                            "Failed to cancel orders, retrying next check"
+                        );
+                        continue;
+                    }
+
+                    if status == ResolutionStatus::Disputed {
+                        let message = format!(
+                            "⚠️ Resolution disputed: {}\nInventory: {} YES / {} NO",
+                            engine.market.question, engine.inventory_yes, engine.inventory_no
+                        );
+                        self.send_alert(Alert::new(
+                            AlertRule::Resolution,
+                            Severity::Warning,
+                            message,
+                        ))
+                        .await;
+                    }
+                }
+                ResolutionStatus::Resolved => {
+                    engine.halted = true;
+                    if let Err(e) = engine.cancel_all(exchange).await {
+                        warn!(
+                            market = %engine.market.question,
+                            error = %e,
+                            "Failed to cancel orders, retrying next check"
+                        );
+                        continue;
+                    }
 
-            let engine = QuoteEngine::new(market, strategy, false);
-            self.engines.insert(cond_id, engine);
+                    let held = engine.inventory_yes + engine.inventory_no;
+                    let mut alert = None;
+                    if held > Decimal::ZERO {
+                        self.ctf_scheduler.enqueue(CtfOperation::Redeem {
+                            condition_id: cond_id.clone(),
+                            amount: held,
+                        });
+                        alert = Some(format!(
+                            "🏁 Market resolved with inventory: {}\n{} YES / {} NO queued for redemption",
+                            engine.market.question, engine.inventory_yes, engine.inventory_no
+                        ));
+                    }
+                    info!(
+                        market = %engine.market.question,
+                        "Market resolved, removing from manager"
+                    );
+                    self.retire_engine(&cond_id);
+                    self.resolution_statuses.remove(&cond_id);
+                    if let Some(message) = alert {
+                        self.send_alert(Alert::new(
+                            AlertRule::Resolution,
+                            Severity::Critical,
+                            message,
+                        ))
+                        .await;
+                    }
+                    continue;
+                }
+            }
+            // Recorded only once acted on, so a failed cancel is retried
+            self.resolution_statuses.insert(cond_id, status);
         }
 
-        info!(total_markets = self.engines.len(), "Markets initialized");
+        Ok(())
     }
 
-    /// Remove markets that are no longer rewarded or have been resolved.
-    pub fn remove_stale_markets(&mut self, active_ids: &[String]) {
-        let stale: Vec<String> = self
-            .engines
-            .keys()
-            .filter(|id| !active_ids.contains(id))
-            .cloned()
-            .collect();
-
-        for id in &stale {
-            info!(condition_id = %id, "Removing stale market");
-            self.engines.remove(id);
+    /// Orders every engine would leave resting at shutdown.
+    pub fn retained_orders(&self) -> RetainedOrders {
+        RetainedOrders {
+            saved_at: Some(chrono::Utc::now()),
+            markets: self
+                .engines
+                .iter()
+                .map(|(id, e)| (id.clone(), e.retained_orders()))
+                .filter(|(_, m)| !m.orders.is_empty())
+                .collect(),
         }
     }
 
-    /// Check if hourly rescan is due.
-    pub fn needs_rescan(&self) -> bool {
-        self.last_rescan.elapsed() > self.rescan_interval
-    }
-
-    /// Perform a rescan: fetch fresh markets, add new ones, remove stale ones.
-    pub async fn rescan(
+    /// Hand orders left resting by the previous run to their engines, and
+    /// cancel those in markets this run doesn't manage.
+    pub async fn adopt_orders(
         &mut self,
-        gamma_client: &polymarket_client_sdk::gamma::Client,
+        exchange: &impl ExchangeApi,
+        retained: RetainedOrders,
     ) -> Result<()> {
-        info!("Rescanning markets...");
-
-        let all_markets = scanner::scan_markets(gamma_client).await?;
-        let ranked = scanner::rank_markets(
-            &all_markets,
-            self.config.markets.min_reward_daily,
-            self.config.markets.max_markets,
-        );
+        let mut unmanaged = Vec::new();
+        for (cond_id, market) in retained.markets {
+            match self.engines.get_mut(&cond_id) {
+                Some(engine) => engine.adopt_orders(exchange, market).await?,
+                None => unmanaged.extend(
+                    market
+                        .orders
+                        .iter()
+                        .filter(|o| o.is_active())
+                        .map(|o| o.order_id.clone()),
+                ),
+            }
+        }
+        if !unmanaged.is_empty() {
+            info!(
+                orders = unmanaged.len(),
+                "Cancelling retained orders in markets no longer managed"
+            );
+            orders::cancel_orders(exchange, &unmanaged).await?;
+        }
+        Ok(())
+    }
 
-        let active_ids: Vec<String> = ranked.iter().map(|m| m.condition_id.clone()).collect();
+    /// Execute queued CTF operations that are due at the current gas price.
+    pub async fn process_ctf_queue(
+        &mut self,
+        clob_client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
+        rpc: Option<&PolygonRpc>,
+    ) {
+        if self.ctf_scheduler.is_empty() {
+            return;
+        }
 
-        // Add new markets
-        let new_markets: Vec<MarketInfo> = ranked
-            .into_iter()
-            .filter(|m| !self.engines.contains_key(&m.condition_id))
-            .collect();
+        let gas = match rpc {
+            Some(rpc) => match rpc.gas_price_gwei().await {
+                Ok(gas) => Some(gas),
+                Err(e) => {
+                    warn!(error = %e, "Gas price lookup failed, deferring CTF operations");
+                    return;
+                }
+            },
+            None => None,
+        };
 
-        if !new_markets.is_empty() {
-            info!(count = new_markets.len(), "New markets discovered");
-            self.initialize_markets(new_markets);
+        for op in self.ctf_scheduler.take_due(gas, self.clock.now()) {
+            if let Err(e) = inventory::execute_ctf_operation(clob_client, &op).await {
+                warn!(op = ?op, error = %e, "CTF operation failed, requeueing");
+                self.ctf_scheduler.enqueue(op);
+            } else if let CtfOperation::Split {
+                condition_id,
+                amount,
+            } = &op
+                && let Some(engine) = self.engines.get_mut(condition_id)
+            {
+                engine.apply_split(*amount);
+            }
         }
+    }
 
-        // Remove stale
-        self.remove_stale_markets(&active_ids);
-
-        // Check for sponsored markets (high reward/competition)
-        for (_, engine) in &self.engines {
-            if engine.market.reward_daily_estimate > dec!(50) {
-                info!(
+    /// Replace engine-tracked inventory with on-chain balances, logging any drift.
+    pub async fn reconcile_onchain(
+        &mut self,
+        rpc: &PolygonRpc,
+        owner: polymarket_client_sdk::types::Address,
+    ) -> Result<()> {
+        for engine in self.engines.values_mut() {
+            let position = rpc
+                .position(owner, &engine.market.token_yes_id, &engine.market.token_no_id)
+                .await?;
+            if position.yes != engine.inventory_yes || position.no != engine.inventory_no {
+                warn!(
                     market = %engine.market.question,
-                    reward = %engine.market.reward_daily_estimate,
-                    "Sponsored market detected — high reward opportunity"
+                    tracked_yes = %engine.inventory_yes,
+                    tracked_no = %engine.inventory_no,
+                    onchain_yes = %position.yes,
+                    onchain_no = %position.no,
+                    "Inventory drift vs on-chain balances, using on-chain"
                 );
+                engine.inventory_yes = position.yes;
+                engine.inventory_no = position.no;
             }
         }
-
-        self.last_rescan = Instant::now();
-        info!(total_markets = self.engines.len(), "Rescan complete");
         Ok(())
     }
 
+    /// Send an alert through the configured channels, subject to the
+    /// `[alerts]` rules. Failures are logged.
+    async fn send_alert(&mut self, alert: Alert) {
+        self.alerter.send(&self.config.monitoring, alert).await;
+    }
+
     /// Run one tick across all managed markets with rate limiting.
-    pub async fn tick_all(
-        &mut self,
-        clob_client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-        signer: &impl Signer,
-    ) -> Result<()> {
+    #[instrument(name = "tick_all", skip_all, fields(markets = self.engines.len()))]
+    pub async fn tick_all(&mut self, exchange: &impl ExchangeApi) -> Result<()> {
+        if self.risk_state.is_latched() {
+            return Ok(());
+        }
+
         // Check kill switch across all markets
         let inventories: Vec<(&str, MarketInventory, Decimal)> = self
             .engines
             .values()
+            .filter(|e| !e.dry_run)
             .map(|e| {
                 let inv = MarketInventory {
                     yes_tokens: e.inventory_yes,
@@ -249,54 +1644,371 @@ impl MarketManager {
             .collect();
 
         if risk::should_kill_switch(&inv_refs, &self.config.risk) {
-            warn!("Kill switch activated — cancelling all orders");
-            self.cancel_all_markets(clob_client).await?;
+            let total_pnl = risk::total_unrealized_pnl(&inv_refs);
+            warn!("Kill switch activated — cancelling all orders and latching");
+            self.risk_state.trip(total_pnl, self.config.risk.kill_switch_loss);
+            if let Err(e) = self
+                .risk_state
+                .save(std::path::Path::new(&self.config.risk.state_file))
+            {
+                warn!(error = %e, "Failed to persist kill-switch latch");
+            }
+            if let Some(db) = &self.database
+                && let Err(e) = db.record_risk_event(None, "kill_switch", &total_pnl.to_string())
+            {
+                warn!(error = %e, "History database write failed");
+            }
+            self.cancel_all_markets(exchange).await?;
+            self.send_alert(Alert::new(
+                AlertRule::KillSwitch,
+                Severity::Critical,
+                format!(
+                    "🛑 Kill switch tripped: PnL ${total_pnl:.2} (limit -${}). \
+                     Quoting stopped until `resume --ack-loss`.",
+                    self.config.risk.kill_switch_loss
+                ),
+            ))
+            .await;
             return Ok(());
         }
 
+        self.apply_correlated_exposure();
+        self.apply_requote_pressure();
+        if self.config.markets.intraday_rebalance {
+            self.rebalance_intraday();
+        }
+
         // Tick each engine, respecting rate limits
+        let mut exit_alerts = Vec::new();
+        let mut anomaly_alerts = Vec::new();
+        let mut fill_alerts = Vec::new();
+        let mut splits = Vec::new();
+        let mut committed: HashMap<String, Decimal> = self
+            .engines
+            .iter()
+            .filter(|(_, e)| !e.dry_run)
+            .map(|(id, e)| (id.clone(), e.committed_capital()))
+            .collect();
+        let metered = Metered::new(exchange);
+        self.tick_count += 1;
+        let tier3_due = self
+            .tick_count
+            .is_multiple_of(self.config.markets.tier3_poll_every.max(1));
+        let shared_budget = Decimal::ONE - self.config.rate_limits.tier1_reserve_pct / dec!(100);
         let condition_ids: Vec<String> = self.engines.keys().cloned().collect();
         for cond_id in condition_ids {
             let engine = match self.engines.get_mut(&cond_id) {
                 Some(e) => e,
                 None => continue,
             };
+            if engine.halted || (engine.tier == Tier::Three && !tier3_due) {
+                continue;
+            }
 
-            // Estimate orders needed for this tick (4 per level * num_levels)
-            let estimated_orders = (engine.config.num_levels * 4) as usize;
-            if !self.rate_limiter.can_place(estimated_orders) {
+            // Worst case for this tick: a full ladder (4 orders per level),
+            // cancelling and polling every tracked order, plus the midpoint
+            let tracked = engine.tracked_orders.len();
+            let estimated = ApiUsage {
+                posts: (engine.level_cap() * 4) as usize,
+                cancels: tracked,
+                gets: tracked + 1,
+            };
+            // Only tier 1 may dip into the reserved share of each budget
+            let share = if engine.tier == Tier::One {
+                Decimal::ONE
+            } else {
+                shared_budget
+            };
+            if !self.rate_limiter.can_afford_within(&estimated, share) {
                 warn!(
                     market = %engine.market.question,
+                    tier = ?engine.tier,
                     "Skipping tick due to rate limit"
                 );
                 continue;
             }
 
-            match engine.tick_live(clob_client, signer).await {
-                Ok(()) => {
-                    let actual_orders = engine.tracked_orders.len();
-                    self.rate_limiter.record(actual_orders);
-                }
-                Err(e) => {
-                    warn!(
-                        market = %engine.market.question,
-                        error = %e,
-                        "Engine tick failed"
-                    );
-                }
+            // Live capital cap: this market's allocation, bounded by what the
+            // other markets haven't already committed
+            let others: Decimal = committed
+                .iter()
+                .filter(|(id, _)| **id != cond_id)
+                .map(|(_, c)| *c)
+                .sum();
+            let allocation = self
+                .capital_allocations
+                .get(&cond_id)
+                .copied()
+                .map(|a| a + self.rebalanced.get(&cond_id).copied().unwrap_or_default())
+                .filter(|a| *a > Decimal::ZERO)
+                .unwrap_or(self.config.risk.max_per_market);
+            let remaining = (self.config.risk.max_total_capital - others).max(Decimal::ZERO);
+            engine.capital_limit = Some(allocation.min(remaining));
+
+            let was_exiting = engine.exit_reason.is_some();
+            let result = if engine.dry_run {
+                engine.tick_paper(&metered).await
+            } else {
+                let result = engine.tick_live(&metered).await;
+                engine.presign_next(&metered).await;
+                result
+            };
+            self.rate_limiter.record_usage(&metered.take_usage());
+            if let Err(e) = result {
+                warn!(
+                    market = %engine.market.question,
+                    error = %e,
+                    "Engine tick failed"
+                );
+            }
+            if !engine.dry_run {
+                committed.insert(cond_id.clone(), engine.committed_capital());
+            }
+            if let Some(reason) = engine.exit_reason
+                && !was_exiting
+            {
+                exit_alerts.push(format!(
+                    "🚪 {reason:?} hit on {}: unwinding {} YES / {} NO",
+                    engine.market.question, engine.inventory_yes, engine.inventory_no
+                ));
+            }
+            if let Some(anomaly) = engine.flow_anomaly.take() {
+                let action = if anomaly.throttles() {
+                    "; requotes paused"
+                } else {
+                    ""
+                };
+                anomaly_alerts.push(format!(
+                    "⚠️ Order-flow anomaly on {}: {anomaly}{action}",
+                    engine.market.question
+                ));
+            }
+            let ready = engine.fills.take_ready();
+            if !engine.dry_run {
+                fill_alerts.extend(ready);
+            }
+            if let Some(amount) = engine.split_request.take() {
+                engine.pending_split += amount;
+                splits.push(CtfOperation::Split {
+                    condition_id: cond_id.clone(),
+                    amount,
+                });
+            }
+        }
+
+        for op in splits {
+            self.ctf_scheduler.enqueue(op);
+        }
+
+        for message in exit_alerts {
+            self.send_alert(Alert::new(AlertRule::Exit, Severity::Warning, message))
+                .await;
+        }
+        for message in anomaly_alerts {
+            self.send_alert(Alert::new(
+                AlertRule::FlowAnomaly,
+                Severity::Warning,
+                message,
+            ))
+            .await;
+        }
+        if self.config.monitoring.fill_alerts {
+            for notice in fill_alerts {
+                self.send_alert(notice.alert()).await;
             }
         }
+        self.finish_retirements();
 
         Ok(())
     }
 
+    /// Tier from config overrides, else from the market's daily reward.
+    pub fn classify_tier(&self, market: &MarketInfo) -> Tier {
+        let markets = &self.config.markets;
+        if let Some(tier) = markets
+            .tiers
+            .get(&market.condition_id)
+            .and_then(|n| Tier::from_number(*n))
+        {
+            return tier;
+        }
+        if market.reward_daily_estimate >= markets.tier1_min_reward {
+            Tier::One
+        } else if market.reward_daily_estimate >= markets.tier2_min_reward {
+            Tier::Two
+        } else {
+            Tier::Three
+        }
+    }
+
+    /// YES token IDs of tier-1 markets, which get WebSocket midpoint updates.
+    pub fn ws_token_ids(&self) -> Vec<String> {
+        self.engines
+            .values()
+            .filter(|e| e.tier == Tier::One)
+            .map(|e| e.market.token_yes_id.clone())
+            .collect()
+    }
+
+    /// Apply a market-data WS event to the engine it belongs to, requoting
+    /// immediately when the midpoint moved enough.
+    #[instrument(name = "ws_dispatch", skip_all)]
+    pub async fn handle_ws_event(&mut self, event: WsEvent, exchange: &impl ExchangeApi) {
+        let asset_id = match &event {
+            WsEvent::MidpointUpdate { asset_id, .. } | WsEvent::BookUpdate { asset_id, .. } => {
+                asset_id.clone()
+            }
+            WsEvent::Disconnected | WsEvent::Reconnected => {
+                let connected = matches!(event, WsEvent::Reconnected);
+                for engine in self.engines.values_mut().filter(|e| e.tier == Tier::One) {
+                    engine.ws_connected = connected;
+                }
+                return;
+            }
+            WsEvent::OrderFill { .. } => return,
+        };
+        if self.risk_state.is_latched() {
+            return;
+        }
+        let Some(engine) = self
+            .engines
+            .values_mut()
+            .find(|e| e.market.token_yes_id == asset_id)
+        else {
+            return;
+        };
+        if engine.halted || !engine.handle_ws_event(event) || engine.paused {
+            return;
+        }
+        let Some(mid) = engine.last_midpoint else {
+            return;
+        };
+
+        let tracked = engine.tracked_orders.len();
+        let estimated = ApiUsage {
+            posts: (engine.level_cap() * 4) as usize,
+            cancels: tracked,
+            gets: 1,
+        };
+        if !self.rate_limiter.can_afford(&estimated) {
+            return;
+        }
+        let metered = Metered::new(exchange);
+        if let Err(e) = engine.requote(&metered, mid).await {
+            warn!(market = %engine.market.question, error = %e, "WS-triggered requote failed");
+        }
+        self.rate_limiter.record_usage(&metered.take_usage());
+    }
+
+    /// Stretch requote intervals as rate-limit utilization climbs past
+    /// `adaptive_start_pct`. Markets are ranked by daily reward and the
+    /// best ones are stretched least, so coverage of the valuable markets
+    /// is the last to degrade.
+    fn apply_requote_pressure(&mut self) {
+        let limits = &self.config.rate_limits;
+        let busiest = self
+            .rate_limiter
+            .utilization()
+            .iter()
+            .map(|u| u.pct())
+            .max()
+            .unwrap_or(Decimal::ZERO);
+        let headroom = dec!(100) - limits.adaptive_start_pct;
+        let pressure = if headroom > Decimal::ZERO {
+            ((busiest - limits.adaptive_start_pct) / headroom)
+                .max(Decimal::ZERO)
+                .min(Decimal::ONE)
+        } else {
+            Decimal::ZERO
+        };
+        let max_extra = (limits.max_interval_scale - Decimal::ONE).max(Decimal::ZERO);
+
+        let mut ranked: Vec<&mut QuoteEngine> = self.engines.values_mut().collect();
+        ranked.sort_by(|a, b| {
+            a.tier.cmp(&b.tier).then(
+                b.market
+                    .reward_daily_estimate
+                    .cmp(&a.market.reward_daily_estimate),
+            )
+        });
+        let n = Decimal::from(ranked.len().max(1));
+        for (rank, engine) in ranked.into_iter().enumerate() {
+            let weight = Decimal::from(rank + 1) / n;
+            let scale = Decimal::ONE + max_extra * pressure * weight;
+            if scale != engine.requote_scale {
+                debug!(
+                    market = %engine.market.question,
+                    scale = %scale,
+                    utilization = %busiest,
+                    "Requote interval scaled for rate limits"
+                );
+            }
+            engine.requote_scale = scale;
+        }
+    }
+
+    /// Share positions across correlated markets so each engine skews on the
+    /// group's net exposure rather than its own inventory alone.
+    fn apply_correlated_exposure(&mut self) {
+        if self.config.risk.correlations.is_empty() {
+            return;
+        }
+        let positions: HashMap<String, Decimal> = self
+            .engines
+            .iter()
+            .map(|(id, e)| (id.clone(), e.inventory_yes - e.inventory_no))
+            .collect();
+        let exposure = risk::correlated_exposure(&positions, &self.config.risk.correlations);
+        for (id, engine) in self.engines.iter_mut() {
+            engine.correlated_inventory = exposure[id] - positions[id];
+        }
+    }
+
+    /// 1-day 95% VaR across managed markets.
+    pub fn var_report(&self) -> risk::VarReport {
+        let positions = self
+            .engines
+            .values()
+            .map(|e| {
+                (
+                    e.market.question.clone(),
+                    e.inventory_yes - e.inventory_no,
+                    e.last_midpoint.unwrap_or(dec!(0.5)),
+                    e.metrics.daily_volatility,
+                )
+            })
+            .collect();
+        risk::var_report(positions, &self.config.risk)
+    }
+
+    /// Compute VaR and alert when it first goes over the configured limit.
+    pub async fn check_var(&mut self) -> risk::VarReport {
+        let report = self.var_report();
+        let breached = report.breached();
+        if breached && !self.var_breached {
+            warn!(
+                var = %report.portfolio_var,
+                limit = %report.limit,
+                "Portfolio VaR above limit"
+            );
+            let message = format!(
+                "⚠️ 1-day 95% VaR ${:.2} exceeds limit ${:.2}",
+                report.portfolio_var, report.limit
+            );
+            self.send_alert(
+                Alert::new(AlertRule::Var, Severity::Warning, message)
+                    .with_value(report.portfolio_var),
+            )
+            .await;
+        }
+        self.var_breached = breached;
+        report
+    }
+
     /// Cancel all orders across all markets.
-    pub async fn cancel_all_markets(
-        &mut self,
-        clob_client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-    ) -> Result<()> {
+    pub async fn cancel_all_markets(&mut self, exchange: &impl ExchangeApi) -> Result<()> {
         // Use the bulk cancel endpoint for efficiency
-        orders::cancel_all(clob_client).await?;
+        orders::cancel_all(exchange).await?;
 
         // Clear local state
         for engine in self.engines.values_mut() {
@@ -307,6 +2019,74 @@ impl MarketManager {
         Ok(())
     }
 
+    /// Stop quoting and cancel every order, or resume. Fills, rescans, and
+    /// resolution checks carry on while paused.
+    pub async fn set_paused(&mut self, exchange: &impl ExchangeApi, paused: bool) -> Result<()> {
+        self.paused = paused;
+        for (id, engine) in &mut self.engines {
+            engine.paused = paused || self.market_states.get(id) == EngineState::Paused;
+            if !engine.paused {
+                engine.last_requote = None;
+            }
+        }
+        let message = if paused {
+            self.cancel_all_markets(exchange).await?;
+            "⏸️ Quoting paused by operator"
+        } else {
+            "▶️ Quoting resumed by operator"
+        };
+        info!(paused, "{message}");
+        self.send_alert(Alert::new(AlertRule::Operator, Severity::Info, message))
+            .await;
+        Ok(())
+    }
+
+    /// Status dashboard across managed markets, headed by anything holding
+    /// back quoting portfolio-wide.
+    pub fn status_snapshot(&self) -> String {
+        let mut out = String::new();
+        if let Some(latch) = &self.risk_state.kill_switch {
+            out.push_str(&format!(
+                "KILL SWITCH LATCHED since {} (PnL ${:.2})\n",
+                latch.tripped_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                latch.total_pnl
+            ));
+        }
+        if self.paused {
+            out.push_str("PAUSED (send SIGUSR1 to resume)\n");
+        }
+        out.push_str(&engine::status_snapshot(self.engines.values()));
+        out
+    }
+
+    /// Apply a re-read config file. Strategy and risk settings reach every
+    /// market on its next requote, with order sizes re-derived from the
+    /// current allocations; intervals, rate limits, and credentials keep
+    /// their startup values until a restart.
+    pub fn reload_config(&mut self, config: Config) -> Result<()> {
+        // Secrets are decrypted once at startup; keep them across reloads
+        let config = config.with_secrets(self.config.secrets.clone());
+        self.experiment = Experiment::from_config(&config.experiment, &config.strategy)?;
+        self.alerter.reconfigure(config.alerts.clone());
+        self.config = config;
+        let ids: Vec<String> = self.engines.keys().cloned().collect();
+        for id in ids {
+            let allocation = self.allocation_for(&id);
+            let Some(engine) = self.engines.get(&id) else {
+                continue;
+            };
+            let mut strategy = self.strategy_for(&id).clone();
+            strategy.order_size = self.allocated_order_size(&engine.market, allocation);
+            self.config.markets.apply_overrides(&id, &mut strategy);
+            strategy.inventory_cap = risk::scaled_inventory_cap(&engine.market, &strategy);
+            if let Some(engine) = self.engines.get_mut(&id) {
+                engine.reconfigure(strategy, &self.config.risk);
+            }
+        }
+        info!(markets = self.engines.len(), "Config reloaded");
+        Ok(())
+    }
+
     /// Get aggregate portfolio stats.
     pub fn portfolio_stats(&self) -> PortfolioStats {
         let mut total_capital = Decimal::ZERO;
@@ -342,6 +2122,7 @@ impl MarketManager {
             total_yes_tokens: total_yes,
             total_no_tokens: total_no,
             total_unrealized_pnl: total_pnl,
+            committed_capital: self.engines.values().map(|e| e.committed_capital()).sum(),
         }
     }
 }
@@ -354,15 +2135,18 @@ pub struct PortfolioStats {
     pub total_yes_tokens: Decimal,
     pub total_no_tokens: Decimal,
     pub total_unrealized_pnl: Decimal,
+    /// Inventory cost basis plus USDC locked in resting buys
+    pub committed_capital: Decimal,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::SimClock;
 
     #[test]
     fn test_rate_limiter_basic() {
-        let mut limiter = RateLimiter::new();
+        let mut limiter = RateLimiter::with_clock(clock::system());
         assert!(limiter.can_place(100));
         limiter.record(100);
         assert!(limiter.can_place(100));
@@ -370,10 +2154,564 @@ mod tests {
 
     #[test]
     fn test_rate_limiter_burst_limit() {
-        let mut limiter = RateLimiter::new();
-        limiter.burst_limit = 10;
+        let mut limiter = RateLimiter::with_clock(clock::system());
+        limiter.window(Endpoint::Post).burst_limit = 10;
         assert!(limiter.can_place(10));
         limiter.record(10);
         assert!(!limiter.can_place(1));
     }
+
+    #[test]
+    fn test_rate_limiter_burst_window_expires() {
+        let sim = Arc::new(SimClock::new());
+        let mut limiter = RateLimiter::with_clock(sim.clone());
+        limiter.window(Endpoint::Post).burst_limit = 10;
+        limiter.record(10);
+        assert!(!limiter.can_place(1));
+        sim.advance(Duration::from_secs(11));
+        assert!(limiter.can_place(10));
+    }
+
+    #[test]
+    fn test_rate_limiter_budgets_are_per_endpoint() {
+        let config = RateLimitConfig {
+            cancel_burst: 5,
+            ..RateLimitConfig::default()
+        };
+        let mut limiter = RateLimiter::from_config(&config, clock::system());
+        limiter.record_usage(&ApiUsage {
+            posts: 8,
+            cancels: 5,
+            gets: 3,
+        });
+        assert!(limiter.can_place(100));
+        assert!(!limiter.can_call(Endpoint::Cancel, 1));
+        assert!(!limiter.can_afford(&ApiUsage {
+            posts: 1,
+            cancels: 1,
+            gets: 0,
+        }));
+
+        let cancel = limiter
+            .utilization()
+            .into_iter()
+            .find(|u| u.endpoint == Endpoint::Cancel)
+            .unwrap();
+        assert_eq!(cancel.burst_used, 5);
+        assert_eq!(cancel.pct(), dec!(100));
+    }
+
+    #[test]
+    fn test_requote_pressure_spares_high_reward_markets() {
+        let mut mgr = MarketManager::with_clock(Config::default(), Arc::new(SimClock::new()));
+        let market = |id: &str, reward| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: reward,
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("rich", dec!(100)), market("poor", dec!(5))]);
+
+        mgr.apply_requote_pressure();
+        assert!(mgr.engines.values().all(|e| e.requote_scale == Decimal::ONE));
+
+        // 75% of the post burst budget: halfway between 50% and 100%
+        mgr.rate_limiter.record(2625);
+        mgr.apply_requote_pressure();
+        let rich = mgr.engines["rich"].requote_scale;
+        let poor = mgr.engines["poor"].requote_scale;
+        assert_eq!(poor, dec!(2.5)); // 1 + 3 * 0.5 * 1
+        assert!(rich > Decimal::ONE && rich < poor);
+    }
+
+    #[test]
+    fn test_tier_classification() {
+        let mut config = Config::default();
+        config.markets.tiers.insert("pinned".into(), 3);
+        let mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        let market = |id: &str, reward| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: reward,
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        assert_eq!(mgr.classify_tier(&market("a", dec!(80))), Tier::One);
+        assert_eq!(mgr.classify_tier(&market("b", dec!(10))), Tier::Two);
+        assert_eq!(mgr.classify_tier(&market("c", dec!(9.99))), Tier::Three);
+        assert_eq!(mgr.classify_tier(&market("pinned", dec!(80))), Tier::Three);
+    }
+
+    #[test]
+    fn test_manager_intervals_follow_clock() {
+        let sim = Arc::new(SimClock::new());
+        let mgr = MarketManager::with_clock(Config::default(), sim.clone());
+        assert!(!mgr.needs_rescan());
+        assert!(!mgr.needs_resolution_check());
+
+        sim.advance(mgr.resolution_check_interval + Duration::from_secs(1));
+        assert!(mgr.needs_resolution_check());
+        assert!(!mgr.needs_rescan());
+
+        sim.advance(mgr.rescan_interval);
+        assert!(mgr.needs_rescan());
+    }
+
+    #[tokio::test]
+    async fn test_tick_all_isolates_engine_failures() {
+        use crate::exchange::mock::{MockExchange, Op};
+
+        let sim = Arc::new(SimClock::new());
+        let mut mgr = MarketManager::with_clock(Config::default(), sim.clone());
+        let market = |id: &str, yes: &str, no: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: yes.into(),
+            token_no_id: no.into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("a", "1", "2"), market("b", "3", "4")]);
+
+        // Market "b" has no midpoint, so its tick fails; "a" still quotes
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert!(!mgr.engines["a"].tracked_orders.is_empty());
+        assert!(mgr.engines["b"].tracked_orders.is_empty());
+
+        // Post failures are logged per engine, not propagated
+        exchange.fail(Op::Post);
+        sim.advance(Duration::from_secs(3600));
+        mgr.tick_all(&exchange).await.unwrap();
+    }
+
+    #[test]
+    fn test_rescan_hysteresis_and_cooldown() {
+        let metrics_file = std::env::temp_dir().join("polymarket_lp_test_hysteresis_metrics.json");
+        let mut config = Config::default();
+        config.monitoring.metrics_file = metrics_file.to_string_lossy().into_owned();
+        let sim = Arc::new(SimClock::new());
+        let mut mgr = MarketManager::with_clock(config, sim.clone());
+        mgr.initialize_markets(vec![MarketInfo {
+            condition_id: "held".into(),
+            question: "held".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        }]);
+        let ids = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // One rescan in or out isn't enough to add or remove
+        let (add, keep) = mgr.apply_hysteresis(&ids(&["new"]));
+        assert!(add.is_empty());
+        assert_eq!(keep, ids(&["held"]));
+
+        // A second consecutive rescan is
+        let (add, keep) = mgr.apply_hysteresis(&ids(&["new"]));
+        assert_eq!(add, ids(&["new"]));
+        assert!(keep.is_empty());
+
+        // A removed market waits out the cooldown before coming back
+        mgr.retire_engine("held");
+        mgr.apply_hysteresis(&ids(&["held"]));
+        let (add, _) = mgr.apply_hysteresis(&ids(&["held"]));
+        assert!(add.is_empty());
+        sim.advance(Duration::from_secs(mgr.config.markets.readd_cooldown_secs));
+        let (add, _) = mgr.apply_hysteresis(&ids(&["held"]));
+        assert_eq!(add, ids(&["held"]));
+        std::fs::remove_file(&metrics_file).ok();
+    }
+
+    #[test]
+    fn test_reallocate_capital_resizes_existing_markets() {
+        let mut mgr = MarketManager::with_clock(Config::default(), Arc::new(SimClock::new()));
+        let market = |id: &str, score| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score,
+        };
+        mgr.initialize_markets(vec![market("a", dec!(100))]);
+        let before = mgr.capital_allocations["a"];
+
+        // A second market joins with the same score: "a" keeps its allocation
+        // until reallocation splits capital between them
+        mgr.initialize_markets(vec![market("b", dec!(100))]);
+        assert_eq!(mgr.capital_allocations["a"], before);
+        mgr.reallocate_capital(&[market("a", dec!(100)), market("b", dec!(100))]);
+        assert_eq!(mgr.capital_allocations["a"], mgr.capital_allocations["b"]);
+        assert_eq!(
+            mgr.engines["a"].config.order_size,
+            mgr.allocated_order_size(&market("a", dec!(100)), mgr.capital_allocations["a"])
+        );
+
+        // Scaling never drops a market below its reward minimum
+        let mut small = market("a", dec!(100));
+        small.rewards_min_size = Some(dec!(90));
+        assert_eq!(mgr.allocated_order_size(&small, dec!(1)), dec!(90));
+    }
+
+    #[test]
+    fn test_rebalance_lends_capped_capital_until_rescan() {
+        let mut config = Config::default();
+        config.risk.max_total_capital = dec!(600);
+        let mut mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        let market = |id: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("a"), market("b")]);
+        assert_eq!(mgr.capital_allocations["a"], dec!(300));
+
+        // "a" sits at its inventory cap with $100 committed; "b" has used
+        // its whole allocation
+        let capped = mgr.engines.get_mut("a").unwrap();
+        capped.inventory_yes = capped.config.inventory_cap;
+        capped.total_bought_value = dec!(100);
+        mgr.engines.get_mut("b").unwrap().total_bought_value = dec!(300);
+
+        mgr.rebalance_intraday();
+        assert_eq!(mgr.allocation_for("a"), dec!(100));
+        assert_eq!(mgr.allocation_for("b"), dec!(500));
+        assert_eq!(mgr.engines["b"].config.order_size, dec!(500));
+        // A lender only lends once per rescan
+        mgr.rebalance_intraday();
+        assert_eq!(mgr.allocation_for("b"), dec!(500));
+
+        mgr.revert_rebalancing();
+        assert_eq!(mgr.allocation_for("b"), dec!(300));
+        assert_eq!(mgr.engines["a"].config.order_size, dec!(300));
+        assert_eq!(mgr.engines["b"].config.order_size, dec!(300));
+    }
+
+    #[test]
+    fn test_add_manual_market_funds_from_unallocated_capital() {
+        let mut config = Config::default();
+        config.risk.max_total_capital = dec!(800);
+        let mut mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        let market = |id: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("a")]);
+        assert_eq!(mgr.unallocated_capital(), dec!(300));
+
+        assert_eq!(mgr.add_manual_market(market("b")).unwrap(), dec!(300));
+        assert_eq!(mgr.engines["b"].config.order_size, dec!(300));
+        assert!(mgr.unallocated_capital().is_zero());
+        assert!(mgr.add_manual_market(market("c")).is_err());
+        assert!(!mgr.engines.contains_key("c"));
+    }
+
+    #[test]
+    fn test_canary_gets_new_strategy_only() {
+        let mut config = Config::default();
+        config.strategy.num_levels = 3;
+        let mut mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        mgr.canary = Some(CanaryRun {
+            condition_id: "canary".into(),
+            baseline: StrategyConfig::default(),
+        });
+        let market = |id: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("canary"), market("other")]);
+        assert_eq!(mgr.engines["canary"].config.num_levels, 3);
+        assert_eq!(mgr.engines["other"].config.num_levels, 2);
+
+        // The canary survives rescans it doesn't rank in
+        let (_, keep) = mgr.apply_hysteresis(&[]);
+        let (_, keep_again) = mgr.apply_hysteresis(&[]);
+        assert!(keep.contains(&"canary".to_string()));
+        assert_eq!(keep_again, vec!["canary".to_string()]);
+
+        let report = mgr.canary_report().unwrap();
+        assert!(report.contains("num_levels: 2 -> 3"));
+        assert!(report.contains("Baseline avg (1 markets)"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_market_unwinds_before_removal() {
+        use crate::exchange::mock::MockExchange;
+
+        let metrics_file = std::env::temp_dir().join("polymarket_lp_test_retired_metrics.json");
+        let _ = std::fs::remove_file(&metrics_file);
+        let mut config = Config::default();
+        config.monitoring.metrics_file = metrics_file.to_string_lossy().into_owned();
+        let mut mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        let market = |id: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: format!("{id}-yes"),
+            token_no_id: format!("{id}-no"),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        mgr.initialize_markets(vec![market("flat"), market("held")]);
+        mgr.engines.get_mut("held").unwrap().inventory_yes = dec!(20);
+
+        let exchange = MockExchange::new();
+        mgr.remove_stale_markets(&[], &exchange).await;
+        assert!(!mgr.engines.contains_key("flat"));
+        assert_eq!(mgr.engines["held"].exit_reason, Some(ExitReason::Delisted));
+        let saved = metrics::PortfolioMetrics::load(&metrics_file).unwrap();
+        assert!(saved.markets.contains_key("flat"));
+
+        // Unwind finished: the engine is dropped and its inventory recorded
+        let held = mgr.engines.get_mut("held").unwrap();
+        held.halted = true;
+        mgr.finish_retirements();
+        assert!(mgr.engines.is_empty());
+        let saved = metrics::PortfolioMetrics::load(&metrics_file).unwrap();
+        assert_eq!(saved.markets["held"].inventory_yes, dec!(20));
+        std::fs::remove_file(&metrics_file).ok();
+    }
+
+    #[tokio::test]
+    async fn test_latched_kill_switch_blocks_quoting() {
+        use crate::exchange::mock::MockExchange;
+
+        let mut mgr = MarketManager::with_clock(Config::default(), Arc::new(SimClock::new()));
+        mgr.initialize_markets(vec![MarketInfo {
+            condition_id: "a".into(),
+            question: "a".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        }]);
+        mgr.risk_state.trip(dec!(-150), dec!(100));
+
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert!(exchange.state().post_batches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pause_cancels_and_resume_requotes() {
+        use crate::exchange::mock::MockExchange;
+
+        let mut mgr = MarketManager::with_clock(Config::default(), Arc::new(SimClock::new()));
+        mgr.initialize_markets(vec![MarketInfo {
+            condition_id: "a".into(),
+            question: "a".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        }]);
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 1);
+
+        mgr.set_paused(&exchange, true).await.unwrap();
+        assert!(mgr.engines["a"].tracked_orders.is_empty());
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 1);
+        assert!(mgr.status_snapshot().contains("PAUSED"));
+
+        // Requotes straight away on resume, without waiting out the timer
+        mgr.set_paused(&exchange, false).await.unwrap();
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_market_states_pause_one_market_and_retire_another() {
+        use crate::exchange::mock::MockExchange;
+
+        let dir = std::env::temp_dir();
+        let states_path = dir.join("polymarket_lp_test_manager_states.json");
+        let mut config = Config::default();
+        config.monitoring.market_states_file = states_path.to_string_lossy().into();
+        config.monitoring.metrics_file = dir
+            .join("polymarket_lp_test_manager_states_metrics.json")
+            .to_string_lossy()
+            .into();
+        let market = |id: &str, yes: &str, no: &str| MarketInfo {
+            condition_id: id.into(),
+            question: id.into(),
+            token_yes_id: yes.into(),
+            token_no_id: no.into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(1000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: dec!(100),
+        };
+        let mut mgr = MarketManager::with_clock(config, Arc::new(SimClock::new()));
+        mgr.initialize_markets(vec![market("a", "1", "2"), market("b", "3", "4")]);
+        let exchange = MockExchange::new()
+            .with_midpoint("1", dec!(0.50))
+            .with_midpoint("3", dec!(0.50));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 2);
+
+        let mut states = MarketStates::default();
+        states.set("a", EngineState::Paused);
+        states.set("b", EngineState::Retired);
+        states.save(&states_path).unwrap();
+        mgr.sync_market_states(&exchange).await;
+        assert!(mgr.engines["a"].paused);
+        assert!(mgr.engines["a"].tracked_orders.is_empty());
+        assert!(!mgr.engines.contains_key("b"));
+        assert!(!mgr.capital_allocations.contains_key("b"));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 2);
+
+        // Resumed market requotes; the retired one stays out of rescans
+        states.set("a", EngineState::Active);
+        states.save(&states_path).unwrap();
+        mgr.sync_market_states(&exchange).await;
+        mgr.initialize_markets(vec![market("b", "3", "4")]);
+        assert!(!mgr.engines.contains_key("b"));
+        mgr.tick_all(&exchange).await.unwrap();
+        assert_eq!(exchange.state().post_batches.len(), 3);
+
+        // Removing with inventory unwinds before the market is dropped
+        mgr.engines.get_mut("a").unwrap().inventory_yes = dec!(10);
+        states.set("a", EngineState::Removing);
+        states.save(&states_path).unwrap();
+        mgr.sync_market_states(&exchange).await;
+        assert!(mgr.retiring.contains("a"));
+        assert_eq!(mgr.engines["a"].exit_reason, Some(ExitReason::Operator));
+        assert!(mgr.engines["a"].tracked_orders.is_empty());
+        std::fs::remove_file(&states_path).unwrap();
+    }
 }
@@ -1,20 +1,30 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, MathematicalOps};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
-use tracing::info;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::risk::ExitReason;
 
 /// Tracks PnL, fill rates, and other metrics for a single market.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketMetrics {
     pub condition_id: String,
     pub question: String,
+    #[serde(default)]
+    pub token_yes_id: String,
+    #[serde(default)]
+    pub token_no_id: String,
     pub spread_pnl: Decimal,
     pub reward_pnl: Decimal,
     pub rebate_pnl: Decimal,
+    /// Taker fees paid on fills where our order crossed the book
+    #[serde(default)]
+    pub fees_paid: Decimal,
     pub total_fills: u64,
     pub total_orders: u64,
     pub uptime_ticks: u64,
@@ -24,6 +34,46 @@ pub struct MarketMetrics {
     pub last_midpoint: Option<Decimal>,
     pub start_time: DateTime<Utc>,
     pub last_update: DateTime<Utc>,
+    /// Set when a stop-loss or take-profit ended quoting in this market
+    #[serde(default)]
+    pub exit_reason: Option<ExitReason>,
+    /// Recent midpoint volatility scaled to one day, for VaR
+    #[serde(default)]
+    pub daily_volatility: Option<Decimal>,
+    /// Minutes sampled for reward-band uptime this UTC day
+    #[serde(default)]
+    pub band_minutes: u64,
+    /// Sampled minutes with two-sided quotes of at least `rewards_min_size`
+    /// within `rewards_max_spread` of the midpoint
+    #[serde(default)]
+    pub band_minutes_qualifying: u64,
+    /// Trades printed through our quoted price without filling us
+    #[serde(default)]
+    pub trade_throughs: u64,
+    /// Requotes that took longer than `requote_budget_ms`
+    #[serde(default)]
+    pub requote_overruns: u64,
+    /// Our reward score this UTC day, summed over sampled minutes
+    #[serde(default)]
+    pub score_today: Decimal,
+    /// Estimated score of every maker in the band (ours included) over the
+    /// same minutes
+    #[serde(default)]
+    pub market_score_today: Decimal,
+    /// The market's daily reward pool, for projecting our payout
+    #[serde(default)]
+    pub reward_pool: Decimal,
+    /// Paper-traded in a live session; kept out of portfolio totals
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Quoted prices by distance from the midpoint in half-cent buckets
+    /// (bucket `n` covers `n/2` up to `(n+1)/2` cents), one per side and
+    /// level at each requote
+    #[serde(default)]
+    pub quote_distances: BTreeMap<u32, u64>,
+    /// Reward band half-width in cents when last quoted
+    #[serde(default)]
+    pub band_cents: Option<Decimal>,
 }
 
 impl MarketMetrics {
@@ -32,9 +82,12 @@ impl MarketMetrics {
         Self {
             condition_id,
             question,
+            token_yes_id: String::new(),
+            token_no_id: String::new(),
             spread_pnl: Decimal::ZERO,
             reward_pnl: Decimal::ZERO,
             rebate_pnl: Decimal::ZERO,
+            fees_paid: Decimal::ZERO,
             total_fills: 0,
             total_orders: 0,
             uptime_ticks: 0,
@@ -44,6 +97,18 @@ impl MarketMetrics {
             last_midpoint: None,
             start_time: now,
             last_update: now,
+            exit_reason: None,
+            daily_volatility: None,
+            band_minutes: 0,
+            band_minutes_qualifying: 0,
+            trade_throughs: 0,
+            requote_overruns: 0,
+            score_today: Decimal::ZERO,
+            market_score_today: Decimal::ZERO,
+            reward_pool: Decimal::ZERO,
+            dry_run: false,
+            quote_distances: BTreeMap::new(),
+            band_cents: None,
         }
     }
 
@@ -62,10 +127,23 @@ impl MarketMetrics {
             * dec!(100)
     }
 
+    /// Share of sampled minutes that qualified for liquidity rewards.
+    pub fn band_uptime_pct(&self) -> Decimal {
+        if self.band_minutes == 0 {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.band_minutes_qualifying) / Decimal::from(self.band_minutes) * dec!(100)
+    }
+
     pub fn total_pnl(&self) -> Decimal {
         self.spread_pnl + self.reward_pnl + self.rebate_pnl
     }
 
+    /// Total PnL after taker fees.
+    pub fn net_pnl(&self) -> Decimal {
+        self.total_pnl() - self.fees_paid
+    }
+
     pub fn record_tick(&mut self, had_orders: bool) {
         self.total_ticks += 1;
         if had_orders {
@@ -79,10 +157,53 @@ impl MarketMetrics {
         self.spread_pnl += spread_capture;
     }
 
+    pub fn record_band_minutes(&mut self, minutes: u64, qualifying: bool) {
+        self.band_minutes += minutes;
+        if qualifying {
+            self.band_minutes_qualifying += minutes;
+        }
+    }
+
+    /// Add `minutes` of reward scoring at the given per-minute scores.
+    pub fn record_score_minutes(
+        &mut self,
+        minutes: u64,
+        ours: Decimal,
+        competitor: Decimal,
+        pool: Decimal,
+    ) {
+        let minutes = Decimal::from(minutes);
+        self.score_today += ours * minutes;
+        self.market_score_today += (ours + competitor) * minutes;
+        self.reward_pool = pool;
+    }
+
+    /// Our average share of the market's score so far today.
+    pub fn score_share_today(&self) -> Decimal {
+        if self.market_score_today.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.score_today / self.market_score_today
+    }
+
+    /// Today's reward if our share so far holds for the rest of the day.
+    pub fn projected_reward_today(&self) -> Decimal {
+        self.score_share_today() * self.reward_pool
+    }
+
     pub fn record_orders(&mut self, count: u64) {
         self.total_orders += count;
     }
 
+    /// Count one quote `distance` (in price) from the midpoint.
+    pub fn record_quote_distance(&mut self, distance: Decimal) {
+        let bucket = (distance.abs() * dec!(200))
+            .floor()
+            .to_u32()
+            .unwrap_or(u32::MAX);
+        *self.quote_distances.entry(bucket).or_default() += 1;
+    }
+
     pub fn record_reward(&mut self, amount: Decimal) {
         self.reward_pnl += amount;
     }
@@ -90,6 +211,35 @@ impl MarketMetrics {
     pub fn record_rebate(&mut self, amount: Decimal) {
         self.rebate_pnl += amount;
     }
+
+    pub fn record_requote_overrun(&mut self) {
+        self.requote_overruns += 1;
+    }
+
+    pub fn record_trade_through(&mut self) {
+        self.trade_throughs += 1;
+    }
+
+    pub fn record_fee(&mut self, amount: Decimal) {
+        self.fees_paid += amount;
+    }
+
+    /// Zero the daily counters after the metrics file rotates. Identity,
+    /// inventory, and volatility carry over.
+    pub fn roll_day(&mut self) {
+        let fresh = Self::new(self.condition_id.clone(), self.question.clone());
+        *self = Self {
+            token_yes_id: std::mem::take(&mut self.token_yes_id),
+            token_no_id: std::mem::take(&mut self.token_no_id),
+            inventory_yes: self.inventory_yes,
+            inventory_no: self.inventory_no,
+            last_midpoint: self.last_midpoint,
+            exit_reason: self.exit_reason,
+            daily_volatility: self.daily_volatility,
+            reward_pool: self.reward_pool,
+            ..fresh
+        };
+    }
 }
 
 /// Aggregate metrics across all markets.
@@ -98,6 +248,22 @@ pub struct PortfolioMetrics {
     pub markets: HashMap<String, MarketMetrics>,
     pub daily_rewards: Vec<DailyReward>,
     pub session_start: DateTime<Utc>,
+    /// UTC day this file covers; it is archived once the day is over
+    #[serde(default)]
+    pub day: Option<NaiveDate>,
+}
+
+/// Totals for one archived day, kept after its detailed file is compacted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub markets: usize,
+    pub spread_pnl: Decimal,
+    pub reward_pnl: Decimal,
+    pub rebate_pnl: Decimal,
+    pub fees_paid: Decimal,
+    pub total_fills: u64,
+    pub total_orders: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,29 +279,53 @@ impl PortfolioMetrics {
             markets: HashMap::new(),
             daily_rewards: Vec::new(),
             session_start: Utc::now(),
+            day: Some(Utc::now().date_naive()),
         }
     }
 
+    /// Markets trading for real, leaving out paper-traded ones.
+    pub fn live_markets(&self) -> impl Iterator<Item = &MarketMetrics> {
+        self.markets.values().filter(|m| !m.dry_run)
+    }
+
+    /// Paper-traded markets, by question.
+    pub fn paper_markets(&self) -> Vec<&MarketMetrics> {
+        let mut paper: Vec<&MarketMetrics> = self.markets.values().filter(|m| m.dry_run).collect();
+        paper.sort_by(|a, b| a.question.cmp(&b.question));
+        paper
+    }
+
     pub fn total_pnl(&self) -> Decimal {
-        self.markets.values().map(|m| m.total_pnl()).sum()
+        self.live_markets().map(|m| m.total_pnl()).sum()
     }
 
     pub fn total_spread_pnl(&self) -> Decimal {
-        self.markets.values().map(|m| m.spread_pnl).sum()
+        self.live_markets().map(|m| m.spread_pnl).sum()
     }
 
     pub fn total_reward_pnl(&self) -> Decimal {
-        self.markets.values().map(|m| m.reward_pnl).sum()
+        self.live_markets().map(|m| m.reward_pnl).sum()
+    }
+
+    pub fn total_rebate_pnl(&self) -> Decimal {
+        self.live_markets().map(|m| m.rebate_pnl).sum()
+    }
+
+    pub fn total_fees_paid(&self) -> Decimal {
+        self.live_markets().map(|m| m.fees_paid).sum()
+    }
+
+    pub fn net_pnl(&self) -> Decimal {
+        self.live_markets().map(|m| m.net_pnl()).sum()
     }
 
     pub fn total_fills(&self) -> u64 {
-        self.markets.values().map(|m| m.total_fills).sum()
+        self.live_markets().map(|m| m.total_fills).sum()
     }
 
     pub fn avg_fill_rate(&self) -> Decimal {
         let rates: Vec<Decimal> = self
-            .markets
-            .values()
+            .live_markets()
             .filter(|m| m.total_orders > 0)
             .map(|m| m.fill_rate())
             .collect();
@@ -148,8 +338,7 @@ impl PortfolioMetrics {
 
     pub fn avg_uptime(&self) -> Decimal {
         let uptimes: Vec<Decimal> = self
-            .markets
-            .values()
+            .live_markets()
             .filter(|m| m.total_ticks > 0)
             .map(|m| m.uptime_pct())
             .collect();
@@ -160,13 +349,33 @@ impl PortfolioMetrics {
         sum / Decimal::new(uptimes.len() as i64, 0)
     }
 
-    /// Save metrics to a JSON file for persistence.
+    /// Sum of every market's projected reward for today.
+    pub fn projected_reward_today(&self) -> Decimal {
+        self.live_markets()
+            .map(|m| m.projected_reward_today())
+            .sum()
+    }
+
+    pub fn avg_band_uptime(&self) -> Decimal {
+        let uptimes: Vec<Decimal> = self
+            .live_markets()
+            .filter(|m| m.band_minutes > 0)
+            .map(|m| m.band_uptime_pct())
+            .collect();
+        if uptimes.is_empty() {
+            return Decimal::ZERO;
+        }
+        let sum: Decimal = uptimes.iter().sum();
+        sum / Decimal::from(uptimes.len())
+    }
+
+    /// Save metrics to a JSON file for persistence. The file is replaced
+    /// atomically, so a crash mid-write leaves the previous version intact.
     pub fn save(&self, path: &Path) -> Result<()> {
         let json = serde_json::to_string_pretty(self)
             .context("serializing metrics")?;
-        std::fs::write(path, json)
-            .context("writing metrics file")?;
-        info!(path = ?path, "Metrics saved");
+        write_atomic(path, &json)?;
+        debug!(path = ?path, "Metrics saved");
         Ok(())
     }
 
@@ -178,6 +387,401 @@ impl PortfolioMetrics {
             .context("parsing metrics file")?;
         Ok(metrics)
     }
+
+    /// Write market snapshots into the metrics file, keeping entries for
+    /// markets no longer running.
+    pub fn persist_markets(
+        path: &Path,
+        snapshots: impl IntoIterator<Item = MarketMetrics>,
+    ) -> Result<()> {
+        let mut portfolio = if path.exists() {
+            Self::load(path)?
+        } else {
+            Self::new()
+        };
+        portfolio.day.get_or_insert_with(|| Utc::now().date_naive());
+        for snapshot in snapshots {
+            portfolio
+                .markets
+                .insert(snapshot.condition_id.clone(), snapshot);
+        }
+        portfolio.save(path)
+    }
+
+    /// Move the metrics file to its dated archive if it covers a day before
+    /// `today`. Returns the archive path if it was rotated.
+    pub fn rotate(path: &Path, today: NaiveDate) -> Result<Option<PathBuf>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let day = match Self::load(path)?.day {
+            Some(day) if day < today => day,
+            _ => return Ok(None),
+        };
+        let archive = archive_path(path, day);
+        std::fs::rename(path, &archive)
+            .with_context(|| format!("archiving metrics to {archive:?}"))?;
+        info!(archive = ?archive, "Metrics rotated");
+        Ok(Some(archive))
+    }
+
+    /// Fold dated archives older than `retention_days` into the history file
+    /// as one `DaySummary` each, deleting the detailed files. Returns how
+    /// many days were compacted.
+    pub fn compact_archives(path: &Path, today: NaiveDate, retention_days: u32) -> Result<usize> {
+        let cutoff = today - chrono::Duration::days(i64::from(retention_days));
+        let expired: Vec<(NaiveDate, PathBuf)> = list_archives(path)?
+            .into_iter()
+            .filter(|(date, _)| *date < cutoff)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let history_file = history_path(path);
+        let mut history = load_history(path)?;
+        for (date, archive) in &expired {
+            let day = Self::load(archive)?;
+            history.retain(|s| s.date != *date);
+            history.push(DaySummary {
+                date: *date,
+                markets: day.markets.len(),
+                spread_pnl: day.total_spread_pnl(),
+                reward_pnl: day.total_reward_pnl(),
+                rebate_pnl: day.total_rebate_pnl(),
+                fees_paid: day.total_fees_paid(),
+                total_fills: day.total_fills(),
+                total_orders: day.markets.values().map(|m| m.total_orders).sum(),
+            });
+        }
+        history.sort_by_key(|s| s.date);
+        let json = serde_json::to_string_pretty(&history)
+            .context("serializing metrics history")?;
+        write_atomic(&history_file, &json)?;
+
+        for (_, archive) in &expired {
+            std::fs::remove_file(archive)
+                .with_context(|| format!("removing compacted archive {archive:?}"))?;
+        }
+        info!(days = expired.len(), history = ?history_file, "Compacted old metrics archives");
+        Ok(expired.len())
+    }
+}
+
+/// Flush `snapshots` into the metrics file, then rotate it if its day is
+/// over and compact old archives. Runs off the async runtime. Returns the
+/// finished day's metrics if the file rotated, in which case callers should
+/// `roll_day` their metrics and save again.
+pub async fn autosave(
+    path: PathBuf,
+    snapshots: Vec<MarketMetrics>,
+    retention_days: u32,
+) -> Result<Option<PortfolioMetrics>> {
+    tokio::task::spawn_blocking(move || {
+        PortfolioMetrics::persist_markets(&path, snapshots)?;
+        let today = Utc::now().date_naive();
+        let Some(archive) = PortfolioMetrics::rotate(&path, today)? else {
+            return Ok(None);
+        };
+        let finished = PortfolioMetrics::load(&archive)?;
+        PortfolioMetrics::compact_archives(&path, today, retention_days)?;
+        Ok(Some(finished))
+    })
+    .await
+    .context("metrics autosave task")?
+}
+
+/// Message summarizing a finished UTC day: PnL by component, fills,
+/// estimated vs booked rewards, per-market uptime, the best and worst
+/// markets, and any risk events. `reward_estimates` maps condition ID to
+/// the scanner's daily reward estimate.
+pub fn format_daily_summary(
+    day: &PortfolioMetrics,
+    reward_estimates: &HashMap<String, Decimal>,
+    risk_events: &[String],
+) -> String {
+    let date = day
+        .day
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| "previous day".into());
+    let mut out = format!("📊 Daily summary {date} (UTC)\n\n");
+
+    out.push_str(&format!("Net PnL: ${:.2}\n", day.net_pnl()));
+    out.push_str(&format!("  Spread:  ${:.2}\n", day.total_spread_pnl()));
+    out.push_str(&format!("  Rewards: ${:.2}\n", day.total_reward_pnl()));
+    out.push_str(&format!("  Rebates: ${:.2}\n", day.total_rebate_pnl()));
+    out.push_str(&format!("  Fees:   -${:.2}\n", day.total_fees_paid()));
+    out.push_str(&format!("Fills: {}\n", day.total_fills()));
+
+    // Rewards accrue only for in-band minutes, so scale each estimate by them
+    let expected: Decimal = day
+        .markets
+        .values()
+        .filter_map(|m| {
+            let estimate = reward_estimates.get(&m.condition_id)?;
+            Some(*estimate * m.band_uptime_pct() / dec!(100))
+        })
+        .sum();
+    out.push_str(&format!(
+        "Rewards est ${:.2} vs booked ${:.2}\n",
+        expected,
+        day.total_reward_pnl()
+    ));
+
+    let mut markets: Vec<&MarketMetrics> = day.markets.values().collect();
+    markets.sort_by_key(|m| std::cmp::Reverse(m.net_pnl()));
+    if !markets.is_empty() {
+        out.push_str("\nUptime (quoting / in band):\n");
+        for m in &markets {
+            out.push_str(&format!(
+                "  {}: {:.0}% / {:.0}%\n",
+                m.question,
+                m.uptime_pct(),
+                m.band_uptime_pct()
+            ));
+        }
+
+        let top = 3.min(markets.len());
+        out.push_str("\nTop winners:\n");
+        for m in markets
+            .iter()
+            .take(top)
+            .filter(|m| m.net_pnl() > Decimal::ZERO)
+        {
+            out.push_str(&format!("  +${:.2} {}\n", m.net_pnl(), m.question));
+        }
+        out.push_str("Top losers:\n");
+        for m in markets
+            .iter()
+            .rev()
+            .take(top)
+            .filter(|m| m.net_pnl() < Decimal::ZERO)
+        {
+            out.push_str(&format!("  -${:.2} {}\n", -m.net_pnl(), m.question));
+        }
+    }
+
+    let exits = markets
+        .iter()
+        .filter_map(|m| m.exit_reason.map(|r| format!("{r:?}: {}", m.question)));
+    let events: Vec<String> = risk_events.iter().cloned().chain(exits).collect();
+    if !events.is_empty() {
+        out.push_str("\nRisk events:\n");
+        for event in events {
+            out.push_str(&format!("  {event}\n"));
+        }
+    }
+    out
+}
+
+/// Net PnL of each finished UTC day, for the portfolio and each live market.
+#[derive(Debug, Clone, Default)]
+pub struct DailyPnl {
+    pub portfolio: BTreeMap<NaiveDate, Decimal>,
+    /// By condition ID: the question and its PnL on each day it traded
+    pub markets: HashMap<String, (String, BTreeMap<NaiveDate, Decimal>)>,
+}
+
+impl DailyPnl {
+    /// The finished days among the `window_days` before `today`, from the
+    /// dated archives of `path` and, for days already compacted, the
+    /// portfolio totals in its history file. Days the bot didn't run are
+    /// left out rather than counted as flat.
+    pub fn load(path: &Path, today: NaiveDate, window_days: u32) -> Result<Self> {
+        let start = today - chrono::Duration::days(i64::from(window_days));
+        let in_window = |date: NaiveDate| date >= start && date < today;
+        let mut pnl = Self::default();
+        for day in load_history(path)?
+            .into_iter()
+            .filter(|s| in_window(s.date))
+        {
+            let net = day.spread_pnl + day.reward_pnl + day.rebate_pnl - day.fees_paid;
+            pnl.portfolio.insert(day.date, net);
+        }
+        for (date, archive) in list_archives(path)? {
+            if !in_window(date) {
+                continue;
+            }
+            let day = PortfolioMetrics::load(&archive)?;
+            pnl.portfolio.insert(date, day.net_pnl());
+            for m in day.live_markets() {
+                let (_, days) = pnl
+                    .markets
+                    .entry(m.condition_id.clone())
+                    .or_insert_with(|| (m.question.clone(), BTreeMap::new()));
+                days.insert(date, m.net_pnl());
+            }
+        }
+        Ok(pnl)
+    }
+}
+
+/// Risk-adjusted statistics of a daily PnL series. The ratios assume a
+/// zero risk-free rate and are annualized over 365 days; they don't depend
+/// on the capital base, so dollar PnL stands in for returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceStats {
+    pub days: usize,
+    /// Mean over standard deviation; none under two days or when flat
+    pub sharpe: Option<Decimal>,
+    /// Mean over downside deviation; none under two days or without a
+    /// losing day
+    pub sortino: Option<Decimal>,
+    /// Fraction of days with positive PnL
+    pub win_rate: Decimal,
+    pub avg_win: Decimal,
+    /// Mean size of a losing day, as a positive amount
+    pub avg_loss: Decimal,
+}
+
+impl PerformanceStats {
+    pub fn from_daily(pnl: &[Decimal]) -> Option<Self> {
+        if pnl.is_empty() {
+            return None;
+        }
+        let days = Decimal::from(pnl.len());
+        let mean = pnl.iter().sum::<Decimal>() / days;
+        let annualize = dec!(365).sqrt()?;
+        let ratio = |deviation: Option<Decimal>| {
+            deviation
+                .filter(|d| !d.is_zero() && pnl.len() >= 2)
+                .map(|d| (mean / d * annualize).round_dp(2))
+        };
+        let variance = pnl.iter().map(|p| (p - mean) * (p - mean)).sum::<Decimal>()
+            / Decimal::from(pnl.len().saturating_sub(1).max(1));
+        let downside = pnl
+            .iter()
+            .map(|p| p.min(&Decimal::ZERO).powi(2))
+            .sum::<Decimal>()
+            / days;
+        let average = |xs: Vec<Decimal>| {
+            if xs.is_empty() {
+                Decimal::ZERO
+            } else {
+                xs.iter().sum::<Decimal>() / Decimal::from(xs.len())
+            }
+        };
+        let wins: Vec<Decimal> = pnl.iter().copied().filter(|p| *p > Decimal::ZERO).collect();
+        let losses: Vec<Decimal> = pnl.iter().copied().filter(|p| *p < Decimal::ZERO).collect();
+        Some(Self {
+            days: pnl.len(),
+            sharpe: ratio(variance.sqrt()),
+            sortino: ratio(downside.sqrt()),
+            win_rate: Decimal::from(wins.len()) / days,
+            avg_win: average(wins),
+            avg_loss: -average(losses),
+        })
+    }
+}
+
+/// Portfolio and per-market performance over the days in `pnl`, best
+/// Sharpe first; none before the first finished day.
+pub fn format_performance(pnl: &DailyPnl) -> Option<String> {
+    let portfolio: Vec<Decimal> = pnl.portfolio.values().copied().collect();
+    let total = PerformanceStats::from_daily(&portfolio)?;
+    let line = |name: &str, s: &PerformanceStats| {
+        let ratio = |r: Option<Decimal>| r.map_or("-".to_string(), |r| format!("{r:.2}"));
+        format!(
+            "{name}: Sharpe {}, Sortino {}, {:.0}% winning days of {} (avg +${:.2} / -${:.2})\n",
+            ratio(s.sharpe),
+            ratio(s.sortino),
+            s.win_rate * dec!(100),
+            s.days,
+            s.avg_win,
+            s.avg_loss
+        )
+    };
+    let mut out = format!(
+        "--- Performance (last {} days, annualized) ---\n",
+        total.days
+    );
+    out.push_str(&line("Portfolio", &total));
+
+    let mut markets: Vec<(&String, PerformanceStats)> = pnl
+        .markets
+        .values()
+        .filter_map(|(question, days)| {
+            let series: Vec<Decimal> = days.values().copied().collect();
+            Some((question, PerformanceStats::from_daily(&series)?))
+        })
+        .collect();
+    markets.sort_by(|a, b| b.1.sharpe.cmp(&a.1.sharpe).then(a.0.cmp(b.0)));
+    for (question, stats) in &markets {
+        let q = short_question(question, 38);
+        out.push_str(&format!("  {}", line(&q, stats)));
+    }
+    Some(out)
+}
+
+/// Performance over the last `window_days` finished days of the metrics
+/// file at `path`, for `status` and the daily summary.
+pub fn performance_report(path: &Path, window_days: u32) -> Result<Option<String>> {
+    let pnl = DailyPnl::load(path, Utc::now().date_naive(), window_days)?;
+    Ok(format_performance(&pnl))
+}
+
+/// Write via a temp file and rename so readers never see a partial file.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents).with_context(|| format!("writing {tmp:?}"))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replacing {path:?}"))?;
+    Ok(())
+}
+
+/// `metrics.json` -> `metrics-2025-01-31.json`
+fn archive_path(path: &Path, day: NaiveDate) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{day}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{day}"),
+    };
+    path.with_file_name(name)
+}
+
+/// The day of a dated archive of `path`, if `candidate` is one.
+fn archive_date(path: &Path, candidate: &Path) -> Option<NaiveDate> {
+    let stem = path.file_stem()?.to_string_lossy();
+    if candidate.extension() != path.extension() {
+        return None;
+    }
+    let candidate_stem = candidate.file_stem()?.to_string_lossy();
+    let date = candidate_stem.strip_prefix(&format!("{stem}-"))?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Dated archives of `path`, oldest first.
+fn list_archives(path: &Path) -> Result<Vec<(NaiveDate, PathBuf)>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut archives = Vec::new();
+    for entry in std::fs::read_dir(dir).context("listing metrics archives")? {
+        let archive = entry?.path();
+        if let Some(date) = archive_date(path, &archive) {
+            archives.push((date, archive));
+        }
+    }
+    archives.sort();
+    Ok(archives)
+}
+
+/// `metrics.json` -> `metrics-history.json`
+fn history_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}-history.json"))
+}
+
+/// Compacted day totals of `path`; none before the first compaction.
+fn load_history(path: &Path) -> Result<Vec<DaySummary>> {
+    let history_file = history_path(path);
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(&history_file).context("reading metrics history")?;
+    serde_json::from_str(&contents).context("parsing metrics history")
 }
 
 /// Send a Telegram alert message.
@@ -207,6 +811,133 @@ pub async fn send_telegram_alert(
     Ok(())
 }
 
+/// One of our fills, normalized from CLOB trade history.
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub market: String,
+    pub token_id: String,
+    pub is_buy: bool,
+    pub price: Decimal,
+    pub size: Decimal,
+    /// Taker fee paid (zero for maker fills)
+    pub fee: Decimal,
+    /// Estimated maker rebate earned (zero for taker fills)
+    pub rebate: Decimal,
+}
+
+/// Realized trading results for one market over a period.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeSummary {
+    pub trades: u64,
+    pub volume: Decimal,
+    pub fees_paid: Decimal,
+    pub rebates: Decimal,
+    pub realized_pnl: Decimal,
+}
+
+/// Summarize chronologically ordered fills per market using average cost.
+/// Sells of tokens bought before the first fill have no cost basis and
+/// contribute volume but no realized PnL.
+pub fn summarize_fills(fills: &[Fill]) -> BTreeMap<String, TradeSummary> {
+    // (held size, average cost) per token
+    let mut positions: HashMap<&str, (Decimal, Decimal)> = HashMap::new();
+    let mut summaries: BTreeMap<String, TradeSummary> = BTreeMap::new();
+
+    for fill in fills {
+        let summary = summaries.entry(fill.market.clone()).or_default();
+        summary.trades += 1;
+        summary.volume += fill.size * fill.price;
+        summary.fees_paid += fill.fee;
+        summary.rebates += fill.rebate;
+
+        let (held, avg_cost) = positions
+            .entry(fill.token_id.as_str())
+            .or_insert((Decimal::ZERO, Decimal::ZERO));
+        if fill.is_buy {
+            let new_held = *held + fill.size;
+            if new_held > Decimal::ZERO {
+                *avg_cost = (*held * *avg_cost + fill.size * fill.price) / new_held;
+            }
+            *held = new_held;
+        } else {
+            let closed = fill.size.min(*held);
+            summary.realized_pnl += (fill.price - *avg_cost) * closed;
+            *held -= closed;
+        }
+    }
+
+    for summary in summaries.values_mut() {
+        summary.realized_pnl += summary.rebates - summary.fees_paid;
+    }
+    summaries
+}
+
+/// Open buy lots per token, matched first-in-first-out against sells to
+/// measure the edge captured on each completed round trip.
+#[derive(Debug, Clone, Default)]
+pub struct RoundTrips {
+    /// (price, remaining size) per token, oldest first
+    open: HashMap<String, VecDeque<(Decimal, Decimal)>>,
+}
+
+impl RoundTrips {
+    /// Book a fill and return the spread captured by any round trips it
+    /// closes. Like `summarize_fills`, sells with no open buy lot have no
+    /// cost basis and capture nothing.
+    pub fn record(
+        &mut self,
+        token_id: &str,
+        is_buy: bool,
+        price: Decimal,
+        size: Decimal,
+    ) -> Decimal {
+        let lots = self.open.entry(token_id.to_string()).or_default();
+        if is_buy {
+            lots.push_back((price, size));
+            return Decimal::ZERO;
+        }
+
+        let mut remaining = size;
+        let mut captured = Decimal::ZERO;
+        while remaining > Decimal::ZERO {
+            let Some((cost, lot_size)) = lots.front_mut() else {
+                break;
+            };
+            let matched = remaining.min(*lot_size);
+            captured += (price - *cost) * matched;
+            *lot_size -= matched;
+            remaining -= matched;
+            if lot_size.is_zero() {
+                lots.pop_front();
+            }
+        }
+        captured
+    }
+
+    /// Unmatched buy size still open for a token.
+    pub fn open_size(&self, token_id: &str) -> Decimal {
+        self.open
+            .get(token_id)
+            .map(|lots| lots.iter().map(|(_, size)| *size).sum())
+            .unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Shorten a market question to at most `max_chars` characters for a table
+/// column. Cut on characters, not bytes, so non-ASCII questions don't split
+/// a code point.
+pub fn short_question(question: &str, max_chars: usize) -> String {
+    if question.chars().count() > max_chars {
+        let head: String = question.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{head}...")
+    } else {
+        question.to_string()
+    }
+}
+
+/// Reads one figure off a market's metrics, for comparison tables.
+pub type MetricFn = fn(&MarketMetrics) -> Decimal;
+
 /// Format a status dashboard string for the CLI.
 pub fn format_dashboard(
     portfolio: &PortfolioMetrics,
@@ -228,6 +959,15 @@ pub fn format_dashboard(
         "  Rewards:     ${:.4}\n",
         portfolio.total_reward_pnl()
     ));
+    out.push_str(&format!(
+        "  Rebates:     ${:.4}\n",
+        portfolio.total_rebate_pnl()
+    ));
+    out.push_str(&format!(
+        "  Fees paid:  -${:.4}\n",
+        portfolio.total_fees_paid()
+    ));
+    out.push_str(&format!("Net PnL:       ${:.4}\n", portfolio.net_pnl()));
     out.push_str(&format!("Total fills:   {}\n", portfolio.total_fills()));
     out.push_str(&format!(
         "Avg fill rate: {:.1}%\n",
@@ -237,6 +977,10 @@ pub fn format_dashboard(
         "Avg uptime:    {:.1}%\n",
         portfolio.avg_uptime()
     ));
+    out.push_str(&format!(
+        "Avg in-band:   {:.1}%\n",
+        portfolio.avg_band_uptime()
+    ));
 
     out.push_str("\n--- Markets ---\n");
     out.push_str(&format!(
@@ -247,17 +991,103 @@ pub fn format_dashboard(
     out.push('\n');
 
     for (question, midpoint, inventory, orders) in market_engines {
-        let q = if question.len() > 38 {
-            format!("{}...", &question[..35])
-        } else {
-            question.clone()
-        };
+        let q = short_question(question, 38);
         out.push_str(&format!(
             "{:<40} {:>8.4} {:>10.1} {:>8}\n",
             q, midpoint, inventory, orders
         ));
     }
 
+    let mut banded: Vec<&MarketMetrics> = portfolio
+        .markets
+        .values()
+        .filter(|m| m.band_minutes > 0)
+        .collect();
+    if !banded.is_empty() {
+        banded.sort_by(|a, b| a.question.cmp(&b.question));
+        let day = portfolio
+            .day
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "today".into());
+        out.push_str(&format!("\n--- Reward-Band Uptime ({day} UTC) ---\n"));
+        for m in banded {
+            let q = short_question(&m.question, 38);
+            out.push_str(&format!(
+                "{:<40} {:>6.1}% of {} min\n",
+                q,
+                m.band_uptime_pct(),
+                m.band_minutes
+            ));
+        }
+    }
+
+    let mut scored: Vec<&MarketMetrics> = portfolio
+        .markets
+        .values()
+        .filter(|m| !m.market_score_today.is_zero())
+        .collect();
+    if !scored.is_empty() {
+        scored.sort_by(|a, b| a.question.cmp(&b.question));
+        out.push_str("\n--- Reward Score Today ---\n");
+        out.push_str(&format!(
+            "{:<40} {:>7} {:>10} {:>10}\n",
+            "Question", "Share", "Pool", "Projected"
+        ));
+        for m in scored {
+            let q = short_question(&m.question, 38);
+            out.push_str(&format!(
+                "{:<40} {:>6.2}% {:>10.2} {:>10.2}\n",
+                q,
+                m.score_share_today() * dec!(100),
+                m.reward_pool,
+                m.projected_reward_today()
+            ));
+        }
+        out.push_str(&format!(
+            "Projected today's reward: ${:.2}\n",
+            portfolio.projected_reward_today()
+        ));
+    }
+
+    let paper = portfolio.paper_markets();
+    if !paper.is_empty() {
+        let live = portfolio.live_markets().count();
+        let live_avg = if live > 0 {
+            portfolio.net_pnl() / Decimal::from(live)
+        } else {
+            Decimal::ZERO
+        };
+        out.push_str("\n--- Paper Markets (dry-run, not in totals) ---\n");
+        out.push_str(&format!(
+            "{:<40} {:>10} {:>6} {:>10}\n",
+            "Question", "Net PnL", "Fills", "Inventory"
+        ));
+        for m in paper {
+            let q = short_question(&m.question, 38);
+            out.push_str(&format!(
+                "{:<40} {:>10.4} {:>6} {:>10.1}\n",
+                q,
+                m.net_pnl(),
+                m.total_fills,
+                m.inventory_yes - m.inventory_no
+            ));
+        }
+        out.push_str(&format!("Live average per market: ${live_avg:.4}\n"));
+    }
+
+    let mut quoted: Vec<&MarketMetrics> = portfolio
+        .markets
+        .values()
+        .filter(|m| !m.quote_distances.is_empty())
+        .collect();
+    if !quoted.is_empty() {
+        quoted.sort_by(|a, b| a.question.cmp(&b.question));
+        out.push_str("\n--- Quote Distance from Mid (cents) ---\n");
+        for m in quoted {
+            out.push_str(&format_distance_histogram(m));
+        }
+    }
+
     if !portfolio.daily_rewards.is_empty() {
         out.push_str("\n--- Recent Rewards ---\n");
         for reward in portfolio.daily_rewards.iter().rev().take(7) {
@@ -271,10 +1101,53 @@ pub fn format_dashboard(
     out
 }
 
+/// One market's quote-distance histogram, with buckets past the reward
+/// band flagged.
+fn format_distance_histogram(m: &MarketMetrics) -> String {
+    const BAR_WIDTH: u64 = 30;
+    let mut out = match m.band_cents {
+        Some(band) => format!("{} (band {:.1}c)\n", m.question, band),
+        None => format!("{}\n", m.question),
+    };
+    let max = m.quote_distances.values().max().map_or(1, |&c| c.max(1));
+    for (&bucket, &count) in &m.quote_distances {
+        let from = Decimal::from(bucket) / dec!(2);
+        let to = from + dec!(0.5);
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max) as usize);
+        let outside = if m.band_cents.is_some_and(|band| from >= band) {
+            "  outside band"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "  {from:>4.1}-{to:<4.1} {bar:<30} {count}{outside}\n"
+        ));
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_quote_distance_histogram() {
+        let mut m = MarketMetrics::new("a".into(), "Alpha".into());
+        m.band_cents = Some(dec!(3));
+        m.record_quote_distance(dec!(0.012));
+        m.record_quote_distance(dec!(-0.014));
+        m.record_quote_distance(dec!(0.031));
+        assert_eq!(m.quote_distances, BTreeMap::from([(2, 2), (6, 1)]));
+        let text = format_distance_histogram(&m);
+        assert!(text.starts_with("Alpha (band 3.0c)\n"));
+        assert!(text.contains(" 1.0-1.5 "));
+        assert!(text.contains(" 3.0-3.5 ") && text.trim_end().ends_with("1  outside band"));
+        assert_eq!(
+            crate::quoter::band_depth_pct(dec!(0.50), dec!(0.488), dec!(0.02)),
+            Some(dec!(40))
+        );
+    }
+
     #[test]
     fn test_market_metrics_fill_rate() {
         let mut m = MarketMetrics::new("test".into(), "Test?".into());
@@ -295,6 +1168,66 @@ mod tests {
         assert_eq!(m.uptime_pct(), dec!(80));
     }
 
+    #[test]
+    fn test_round_trips_fifo() {
+        let mut trips = RoundTrips::default();
+        assert_eq!(
+            trips.record("yes", true, dec!(0.48), dec!(10)),
+            Decimal::ZERO
+        );
+        assert_eq!(
+            trips.record("yes", true, dec!(0.50), dec!(10)),
+            Decimal::ZERO
+        );
+
+        // Closes the 0.48 lot and half of the 0.50 lot
+        let captured = trips.record("yes", false, dec!(0.52), dec!(15));
+        assert_eq!(captured, dec!(0.04) * dec!(10) + dec!(0.02) * dec!(5));
+        assert_eq!(trips.open_size("yes"), dec!(5));
+
+        // No cost basis beyond what was bought
+        assert_eq!(
+            trips.record("yes", false, dec!(0.55), dec!(10)),
+            dec!(0.05) * dec!(5)
+        );
+        assert_eq!(
+            trips.record("no", false, dec!(0.50), dec!(10)),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_daily_summary() {
+        let mut winner = MarketMetrics::new("w".into(), "Winner?".into());
+        winner.record_fill(dec!(3));
+        winner.record_band_minutes(60, true);
+        let mut loser = MarketMetrics::new("l".into(), "Loser?".into());
+        loser.record_fee(dec!(1));
+        loser.exit_reason = Some(ExitReason::StopLoss);
+        let mut day = PortfolioMetrics::new();
+        day.day = NaiveDate::from_ymd_opt(2025, 1, 1);
+        day.markets.insert("w".into(), winner);
+        day.markets.insert("l".into(), loser);
+
+        let estimates = HashMap::from([("w".to_string(), dec!(10))]);
+        let summary = format_daily_summary(&day, &estimates, &["Kill switch tripped".into()]);
+        assert!(summary.contains("2025-01-01"));
+        assert!(summary.contains("Net PnL: $2.00"));
+        assert!(summary.contains("Rewards est $10.00 vs booked $0.00"));
+        assert!(summary.contains("+$3.00 Winner?"));
+        assert!(summary.contains("-$1.00 Loser?"));
+        assert!(summary.contains("StopLoss: Loser?"));
+        assert!(summary.contains("Kill switch tripped"));
+    }
+
+    #[test]
+    fn test_band_uptime() {
+        let mut m = MarketMetrics::new("test".into(), "Test?".into());
+        m.record_band_minutes(45, true);
+        m.record_band_minutes(15, false);
+        assert_eq!(m.band_uptime_pct(), dec!(75));
+    }
+
     #[test]
     fn test_portfolio_total_pnl() {
         let mut p = PortfolioMetrics::new();
@@ -310,6 +1243,40 @@ mod tests {
         assert_eq!(p.total_pnl(), dec!(21));
     }
 
+    #[test]
+    fn test_net_pnl_deducts_fees() {
+        let mut m = MarketMetrics::new("a".into(), "Q1".into());
+        m.record_rebate(dec!(2));
+        m.record_fee(dec!(0.5));
+        assert_eq!(m.total_pnl(), dec!(2));
+        assert_eq!(m.net_pnl(), dec!(1.5));
+    }
+
+    #[test]
+    fn test_summarize_fills_average_cost() {
+        let fill = |is_buy: bool, price: Decimal, size: Decimal| Fill {
+            market: "m1".into(),
+            token_id: "yes".into(),
+            is_buy,
+            price,
+            size,
+            fee: Decimal::ZERO,
+            rebate: Decimal::ZERO,
+        };
+        let fills = vec![
+            fill(true, dec!(0.40), dec!(100)),
+            fill(true, dec!(0.50), dec!(100)),
+            // avg cost 0.45; sell 100 @ 0.55 realizes 10
+            fill(false, dec!(0.55), dec!(100)),
+            // only 100 held; the extra 50 has no cost basis
+            fill(false, dec!(0.45), dec!(150)),
+        ];
+        let summary = &summarize_fills(&fills)["m1"];
+        assert_eq!(summary.trades, 4);
+        assert_eq!(summary.realized_pnl, dec!(10));
+        assert_eq!(summary.volume, dec!(212.5));
+    }
+
     #[test]
     fn test_metrics_save_load() {
         let mut p = PortfolioMetrics::new();
@@ -322,4 +1289,135 @@ mod tests {
         assert_eq!(loaded.markets.len(), 1);
         std::fs::remove_file(&path).ok();
     }
+
+    #[test]
+    fn test_short_question_cuts_on_characters() {
+        assert_eq!(short_question("Will it rain?", 38), "Will it rain?");
+        let question = "Will the Zürich–Genève rail line reopen before München?";
+        let short = short_question(question, 38);
+        assert_eq!(short.chars().count(), 38);
+        assert!(short.starts_with("Will the Zürich–Genève"), "{short}");
+        assert!(short.ends_with("..."), "{short}");
+    }
+
+    #[test]
+    fn test_rotation_and_compaction() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_rotation");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.json");
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+
+        let mut market = MarketMetrics::new("a".into(), "A?".into());
+        market.record_fill(dec!(2));
+        let mut p = PortfolioMetrics::new();
+        p.day = Some(day(1));
+        p.markets.insert("a".into(), market);
+        p.save(&path).unwrap();
+
+        // Same day: nothing to rotate
+        assert!(PortfolioMetrics::rotate(&path, day(1)).unwrap().is_none());
+        assert!(PortfolioMetrics::rotate(&path, day(2)).unwrap().is_some());
+        assert!(!path.exists());
+        assert!(dir.join("metrics-2025-01-01.json").exists());
+
+        // Within retention the archive stays; past it, it folds into history
+        assert_eq!(
+            PortfolioMetrics::compact_archives(&path, day(5), 7).unwrap(),
+            0
+        );
+        assert_eq!(
+            PortfolioMetrics::compact_archives(&path, day(9), 7).unwrap(),
+            1
+        );
+        assert!(!dir.join("metrics-2025-01-01.json").exists());
+        let history: Vec<DaySummary> = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("metrics-history.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].spread_pnl, dec!(2));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_daily_pnl_statistics() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_daily_pnl");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.json");
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+
+        // Day 1 is compacted to totals; days 2-4 are archived per market,
+        // alongside a paper market that stays out of the figures
+        let history = vec![DaySummary {
+            date: day(1),
+            markets: 1,
+            spread_pnl: dec!(2),
+            reward_pnl: Decimal::ZERO,
+            rebate_pnl: Decimal::ZERO,
+            fees_paid: Decimal::ZERO,
+            total_fills: 1,
+            total_orders: 2,
+        }];
+        std::fs::write(
+            dir.join("metrics-history.json"),
+            serde_json::to_string(&history).unwrap(),
+        )
+        .unwrap();
+        for (d, spread) in [(2, dec!(-1)), (3, dec!(3)), (4, dec!(-1))] {
+            let mut market = MarketMetrics::new("a".into(), "A?".into());
+            market.record_fill(spread);
+            let mut paper = MarketMetrics::new("p".into(), "P?".into());
+            paper.dry_run = true;
+            paper.record_fill(dec!(100));
+            let mut p = PortfolioMetrics::new();
+            p.day = Some(day(d));
+            p.markets.insert("a".into(), market);
+            p.markets.insert("p".into(), paper);
+            p.save(&archive_path(&path, day(d))).unwrap();
+        }
+
+        let pnl = DailyPnl::load(&path, day(5), 30).unwrap();
+        let series: Vec<Decimal> = pnl.portfolio.values().copied().collect();
+        assert_eq!(series, vec![dec!(2), dec!(-1), dec!(3), dec!(-1)]);
+        assert_eq!(pnl.markets.len(), 1);
+        assert_eq!(pnl.markets["a"].1.len(), 3);
+        // Today's and out-of-window days are left out
+        assert_eq!(DailyPnl::load(&path, day(3), 1).unwrap().portfolio.len(), 1);
+
+        let stats = PerformanceStats::from_daily(&series).unwrap();
+        assert_eq!(stats.win_rate, dec!(0.5));
+        assert_eq!(stats.avg_win, dec!(2.5));
+        assert_eq!(stats.avg_loss, dec!(1));
+        // Mean 0.75 over sd 2.06 and downside deviation 0.71, times sqrt(365)
+        assert_eq!(stats.sharpe, Some(dec!(6.95)));
+        assert_eq!(stats.sortino, Some(dec!(20.26)));
+        assert_eq!(
+            PerformanceStats::from_daily(&[dec!(1)]).unwrap().sharpe,
+            None
+        );
+
+        let report = format_performance(&pnl).unwrap();
+        assert!(report.contains("Portfolio: Sharpe 6.95"), "{report}");
+        assert!(report.contains("A?:") && !report.contains("P?"), "{report}");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_persist_markets_merges_snapshots() {
+        let path = std::env::temp_dir().join("polymarket_lp_test_persist_metrics.json");
+        let _ = std::fs::remove_file(&path);
+        let mut a = MarketMetrics::new("a".into(), "A?".into());
+        PortfolioMetrics::persist_markets(&path, [a.clone()]).unwrap();
+
+        a.record_fill(dec!(0.5));
+        let b = MarketMetrics::new("b".into(), "B?".into());
+        PortfolioMetrics::persist_markets(&path, [a, b]).unwrap();
+
+        let loaded = PortfolioMetrics::load(&path).unwrap();
+        assert_eq!(loaded.markets.len(), 2);
+        assert_eq!(loaded.markets["a"].total_fills, 1);
+        std::fs::remove_file(&path).ok();
+    }
 }
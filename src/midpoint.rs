@@ -0,0 +1,116 @@
+use anyhow::{Result, ensure};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::exchange::BookSnapshot;
+
+/// A price anchor a market's midpoint can come from. Engines walk the
+/// configured chain in order and quote around the first healthy source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MidpointSource {
+    /// Latest midpoint pushed over the WebSocket; healthy while connected
+    /// and fresher than `midpoint_stale_secs`
+    Ws,
+    /// Size-weighted top of the REST order book
+    Microprice,
+    /// The CLOB's own midpoint endpoint
+    Rest,
+    /// Price of the last trade
+    LastTrade,
+}
+
+impl fmt::Display for MidpointSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidpointSource::Ws => write!(f, "ws"),
+            MidpointSource::Microprice => write!(f, "microprice"),
+            MidpointSource::Rest => write!(f, "rest"),
+            MidpointSource::LastTrade => write!(f, "last_trade"),
+        }
+    }
+}
+
+/// Best bid and ask weighted by the size opposite them, so the price leans
+/// toward the thinner side, which is the one more likely to give way.
+pub fn microprice(book: &BookSnapshot) -> Option<Decimal> {
+    let (bid, bid_size) = book.bids.iter().copied().max_by_key(|(price, _)| *price)?;
+    let (ask, ask_size) = book.asks.iter().copied().min_by_key(|(price, _)| *price)?;
+    let total = bid_size + ask_size;
+    if total.is_zero() {
+        return Some((bid + ask) / Decimal::TWO);
+    }
+    Some((bid * ask_size + ask * bid_size) / total)
+}
+
+/// Reject prices no binary market can trade at.
+pub fn validate(midpoint: Decimal) -> Result<Decimal> {
+    ensure!(
+        midpoint > Decimal::ZERO && midpoint < Decimal::ONE,
+        "midpoint {midpoint} outside (0, 1)"
+    );
+    Ok(midpoint)
+}
+
+/// Health of each source in a market's chain.
+#[derive(Debug, Clone, Default)]
+pub struct SourceHealth {
+    /// Latest WebSocket midpoint and when it arrived
+    ws: Option<(Instant, Decimal)>,
+    /// When each REST source last failed
+    failed_at: HashMap<MidpointSource, Instant>,
+    /// Source the latest midpoint came from
+    pub current: Option<MidpointSource>,
+}
+
+impl SourceHealth {
+    pub fn record_ws(&mut self, at: Instant, midpoint: Decimal) {
+        self.ws = Some((at, midpoint));
+    }
+
+    /// The WebSocket midpoint, unless it is older than `stale`.
+    pub fn ws_midpoint(&self, now: Instant, stale: Duration) -> Option<Decimal> {
+        self.ws
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= stale)
+            .map(|(_, mid)| mid)
+    }
+
+    /// Whether a REST source is out of its `retry` backoff after a failure.
+    pub fn ready(&self, source: MidpointSource, now: Instant, retry: Duration) -> bool {
+        self.failed_at
+            .get(&source)
+            .is_none_or(|at| now.saturating_duration_since(*at) >= retry)
+    }
+
+    pub fn mark_failed(&mut self, source: MidpointSource, now: Instant) {
+        self.failed_at.insert(source, now);
+    }
+
+    /// Record the source that served the latest midpoint; true if it isn't
+    /// the one that served the last.
+    pub fn mark_served(&mut self, source: MidpointSource) -> bool {
+        self.failed_at.remove(&source);
+        self.current.replace(source) != Some(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_microprice_leans_to_thin_side() {
+        let book = BookSnapshot {
+            bids: vec![(dec!(0.40), dec!(50)), (dec!(0.48), dec!(300))],
+            asks: vec![(dec!(0.52), dec!(100)), (dec!(0.60), dec!(10))],
+        };
+        // 3x the size on the bid pushes the price toward the ask
+        assert_eq!(microprice(&book), Some(dec!(0.51)));
+        assert_eq!(microprice(&BookSnapshot::default()), None);
+        assert!(validate(dec!(1.2)).is_err());
+    }
+}
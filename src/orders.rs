@@ -1,16 +1,30 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, stream};
 use polymarket_client_sdk::auth;
+use polymarket_client_sdk::auth::ApiKey;
 use polymarket_client_sdk::auth::Signer;
 use polymarket_client_sdk::clob;
-use polymarket_client_sdk::clob::types::{OrderType, Side};
-use polymarket_client_sdk::types::{Decimal, U256};
+use polymarket_client_sdk::clob::types::request::{
+    CancelMarketOrderRequest, OrdersRequest, TradesRequest,
+};
+use polymarket_client_sdk::clob::types::response::{OpenOrderResponse, TradeResponse};
+use polymarket_client_sdk::clob::types::{OrderType, Side, TraderSide};
+use polymarket_client_sdk::types::{Address, Decimal, B256, U256};
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Table;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 use std::str::FromStr;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, instrument, warn};
 
-use crate::quoter::Quote;
+use crate::exchange::{BookSnapshot, ExchangeApi, NewOrder};
+use crate::metrics::Fill;
+use crate::quoter::{self, Quote};
 
 /// Represents an order we've placed on the exchange.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackedOrder {
     pub order_id: String,
     pub token_id: String,
@@ -21,7 +35,7 @@ pub struct TrackedOrder {
     pub status: OrderStatus,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OrderStatus {
     Open,
     PartiallyFilled,
@@ -29,133 +43,472 @@ pub enum OrderStatus {
     Cancelled,
 }
 
-/// Place a batch of limit orders for a market.
+impl TrackedOrder {
+    pub fn is_active(&self) -> bool {
+        self.status == OrderStatus::Open || self.status == OrderStatus::PartiallyFilled
+    }
+}
+
+/// Orders left resting at shutdown (`--keep-orders-on-exit`), for the next
+/// run to adopt instead of cancelling and reposting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetainedOrders {
+    pub saved_at: Option<DateTime<Utc>>,
+    /// Keyed by condition ID
+    pub markets: BTreeMap<String, RetainedMarket>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetainedMarket {
+    /// Midpoint the orders were quoted around
+    pub last_midpoint: Option<Decimal>,
+    pub orders: Vec<TrackedOrder>,
+}
+
+impl RetainedOrders {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing retained orders {}", path.display()))
+    }
+
+    /// Read the file without consuming it. A missing file means the last
+    /// run cancelled everything.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading retained orders {}", path.display()))?;
+        let retained = serde_json::from_str(&content).context("parsing retained orders")?;
+        Ok(Some(retained))
+    }
+
+    /// Load and delete the file, so orders are adopted at most once.
+    pub fn take(path: &Path) -> Result<Option<Self>> {
+        let retained = Self::load(path)?;
+        if retained.is_some() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("removing retained orders {}", path.display()))?;
+        }
+        Ok(retained)
+    }
+
+    /// Every active order ID, across all markets.
+    pub fn active_ids(&self) -> Vec<String> {
+        self.markets
+            .values()
+            .flat_map(|m| &m.orders)
+            .filter(|o| o.is_active())
+            .map(|o| o.order_id.clone())
+            .collect()
+    }
+}
+
+/// USDC locked by resting buy orders (unfilled size at the limit price).
+pub fn resting_buy_notional(orders: &[TrackedOrder]) -> Decimal {
+    orders
+        .iter()
+        .filter(|o| o.side == Side::Buy && o.is_active())
+        .map(|o| (o.size - o.filled).max(Decimal::ZERO) * o.price)
+        .sum()
+}
+
+/// USDC a set of buy orders would lock if all were resting.
+pub fn buy_notional(orders: &[NewOrder]) -> Decimal {
+    orders
+        .iter()
+        .filter(|o| o.side == Side::Buy)
+        .map(|o| o.size * o.price)
+        .sum()
+}
+
+/// Max orders per `post_orders` call.
+const POST_BATCH_SIZE: usize = 15;
+/// Batches in flight at once, so later batches sign while earlier ones post.
+const POST_PIPELINE_DEPTH: usize = 2;
+/// Max order IDs per cancel call.
+const CANCEL_BATCH_SIZE: usize = 20;
+
+/// Last-line price checks applied to every order before it is posted, in
+/// YES terms (a NO buy at `p` is a YES sell at `1 - p`).
+#[derive(Debug, Clone, Copy)]
+pub struct PriceGuard {
+    /// YES fair value the ladder was built around
+    pub fair_value: Decimal,
+    /// Max distance from fair value, in price units
+    pub max_deviation: Decimal,
+    /// Best YES bid / ask on the book, when known
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+}
+
+impl PriceGuard {
+    pub fn new(fair_value: Decimal, max_deviation: Decimal) -> Self {
+        Self {
+            fair_value,
+            max_deviation,
+            best_bid: None,
+            best_ask: None,
+        }
+    }
+
+    /// Why an order would be rejected, if it would.
+    pub fn violation(&self, order: &NewOrder, token_yes_id: &str) -> Option<String> {
+        let (side, price) = if order.token_id == token_yes_id {
+            (order.side, order.price)
+        } else {
+            let flipped = if order.side == Side::Buy {
+                Side::Sell
+            } else {
+                Side::Buy
+            };
+            (flipped, Decimal::ONE - order.price)
+        };
+
+        let deviation = (price - self.fair_value).abs();
+        if deviation > self.max_deviation {
+            return Some(format!(
+                "{deviation} from fair value {} (max {})",
+                self.fair_value, self.max_deviation
+            ));
+        }
+        match side {
+            Side::Buy => self
+                .best_ask
+                .filter(|ask| price >= *ask)
+                .map(|ask| format!("buy at {price} crosses best ask {ask}")),
+            Side::Sell => self
+                .best_bid
+                .filter(|bid| price <= *bid)
+                .map(|bid| format!("sell at {price} crosses best bid {bid}")),
+            _ => None,
+        }
+    }
+
+    /// Drop (and log) orders that fail the guard.
+    pub fn filter(&self, orders: Vec<NewOrder>, token_yes_id: &str) -> Vec<NewOrder> {
+        orders
+            .into_iter()
+            .filter(|o| match self.violation(o, token_yes_id) {
+                Some(reason) => {
+                    warn!(
+                        token_id = %o.token_id,
+                        side = ?o.side,
+                        price = %o.price,
+                        reason = %reason,
+                        "Order blocked by price guard"
+                    );
+                    false
+                }
+                None => true,
+            })
+            .collect()
+    }
+}
+
+/// Place a batch of limit orders for a market, skipping any that fail the
+/// price guard.
 pub async fn place_quotes(
-    client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-    signer: &impl Signer,
+    exchange: &impl ExchangeApi,
     token_yes_id: &str,
     token_no_id: &str,
     quotes: &[Quote],
+    guard: &PriceGuard,
 ) -> Result<Vec<TrackedOrder>> {
-    let yes_id = U256::from_str(token_yes_id).context("parsing YES token ID")?;
-    let no_id = U256::from_str(token_no_id).context("parsing NO token ID")?;
+    let orders = guard.filter(quote_orders(token_yes_id, token_no_id, quotes), token_yes_id);
+    submit_orders(exchange, &orders).await
+}
 
-    let mut signed_orders = Vec::new();
-    let mut order_metadata = Vec::new();
+/// The four orders behind each quote level: YES bid/ask plus the
+/// complementary NO bid/ask.
+pub fn quote_orders(token_yes_id: &str, token_no_id: &str, quotes: &[Quote]) -> Vec<NewOrder> {
+    let mut orders = Vec::new();
+    let order = |token_id: &str, side: Side, price: Decimal, size: Decimal| NewOrder {
+        token_id: token_id.to_string(),
+        side,
+        price,
+        size,
+    };
 
     for quote in quotes {
         // YES token BID (buying YES)
-        let yes_bid = client
-            .limit_order()
-            .token_id(yes_id)
-            .side(Side::Buy)
-            .price(quote.bid_price)
-            .size(quote.size)
-            .order_type(OrderType::GTC)
-            .build()
-            .await
-            .context("building YES bid order")?;
-        let signed = client.sign(signer, yes_bid).await.context("signing YES bid")?;
-        order_metadata.push((token_yes_id.to_string(), Side::Buy, quote.bid_price, quote.size));
-        signed_orders.push(signed);
+        orders.push(order(
+            token_yes_id,
+            Side::Buy,
+            quote.bid_price,
+            quote.bid_size,
+        ));
 
         // YES token ASK (selling YES)
-        let yes_ask = client
-            .limit_order()
-            .token_id(yes_id)
-            .side(Side::Sell)
-            .price(quote.ask_price)
-            .size(quote.size)
-            .order_type(OrderType::GTC)
-            .build()
-            .await
-            .context("building YES ask order")?;
-        let signed = client.sign(signer, yes_ask).await.context("signing YES ask")?;
-        order_metadata.push((token_yes_id.to_string(), Side::Sell, quote.ask_price, quote.size));
-        signed_orders.push(signed);
+        orders.push(order(
+            token_yes_id,
+            Side::Sell,
+            quote.ask_price,
+            quote.ask_size,
+        ));
 
         // NO token BID (complementary price)
         let no_bid_price = Decimal::ONE - quote.ask_price;
         if no_bid_price > Decimal::ZERO {
-            let no_bid = client
-                .limit_order()
-                .token_id(no_id)
-                .side(Side::Buy)
-                .price(no_bid_price)
-                .size(quote.size)
-                .order_type(OrderType::GTC)
-                .build()
-                .await
-                .context("building NO bid order")?;
-            let signed = client.sign(signer, no_bid).await.context("signing NO bid")?;
-            order_metadata.push((token_no_id.to_string(), Side::Buy, no_bid_price, quote.size));
-            signed_orders.push(signed);
+            orders.push(order(token_no_id, Side::Buy, no_bid_price, quote.ask_size));
         }
 
         // NO token ASK (complementary price)
         let no_ask_price = Decimal::ONE - quote.bid_price;
         if no_ask_price < Decimal::ONE {
-            let no_ask = client
-                .limit_order()
-                .token_id(no_id)
-                .side(Side::Sell)
-                .price(no_ask_price)
-                .size(quote.size)
-                .order_type(OrderType::GTC)
-                .build()
-                .await
-                .context("building NO ask order")?;
-            let signed = client.sign(signer, no_ask).await.context("signing NO ask")?;
-            order_metadata.push((token_no_id.to_string(), Side::Sell, no_ask_price, quote.size));
-            signed_orders.push(signed);
-        }
-    }
-
-    if signed_orders.is_empty() {
-        return Ok(vec![]);
+            orders.push(order(token_no_id, Side::Sell, no_ask_price, quote.bid_size));
+        }
     }
+    // One-sided quotes leave the side they don't quote at zero size
+    orders.retain(|o| o.size > Decimal::ZERO);
+    orders
+}
 
-    // Batch post (up to 15 per call)
-    let mut tracked = Vec::new();
-    let mut meta_iter = order_metadata.into_iter();
+/// Trim sells to the tokens on hand to deliver them, innermost levels
+/// first, dropping sells with nothing left behind them. `holdings` lists
+/// deliverable size per token ID; tokens not listed count as none held.
+pub fn back_sells(orders: Vec<NewOrder>, holdings: &[(&str, Decimal)]) -> Vec<NewOrder> {
+    let mut left: Vec<(&str, Decimal)> = holdings.to_vec();
+    orders
+        .into_iter()
+        .filter_map(|mut o| {
+            if o.side != Side::Sell {
+                return Some(o);
+            }
+            let (_, available) = left.iter_mut().find(|(id, _)| *id == o.token_id)?;
+            o.size = quoter::round_size(o.size.min(*available));
+            *available -= o.size;
+            (o.size > Decimal::ZERO).then_some(o)
+        })
+        .collect()
+}
+
+/// Trim buys so each token's resting buy notional stays within `limit`,
+/// innermost levels first, dropping buys once a token's budget is spent.
+pub fn cap_buy_notional(orders: Vec<NewOrder>, limit: Decimal) -> Vec<NewOrder> {
+    let mut spent: HashMap<String, Decimal> = HashMap::new();
+    orders
+        .into_iter()
+        .filter_map(|mut o| {
+            if o.side != Side::Buy || o.price <= Decimal::ZERO {
+                return Some(o);
+            }
+            let spent = spent.entry(o.token_id.clone()).or_default();
+            let room = (limit - *spent).max(Decimal::ZERO);
+            o.size = quoter::round_size(o.size.min(room / o.price));
+            *spent += o.size * o.price;
+            (o.size > Decimal::ZERO).then_some(o)
+        })
+        .collect()
+}
+
+/// Size filled at each ladder level (token, side, price) in its current
+/// cycle. Replacement orders only post what's left of the level's target,
+/// so a partial fill followed by a requote doesn't double the intended
+/// exposure. A cycle ends once the target has filled in full or the ladder
+/// moves off the level's price.
+#[derive(Debug, Clone, Default)]
+pub struct LevelFills {
+    filled: HashMap<(String, Side, Decimal), Decimal>,
+}
+
+impl LevelFills {
+    pub fn record(&mut self, token_id: &str, side: Side, price: Decimal, size: Decimal) {
+        *self
+            .filled
+            .entry((token_id.to_string(), side, price))
+            .or_default() += size;
+    }
 
-    // Drain signed_orders into batches of 15
-    let mut remaining = signed_orders;
-    while !remaining.is_empty() {
-        let batch: Vec<_> = remaining
-            .drain(..remaining.len().min(15))
+    /// Shrink each order to its level's target minus what has filled there
+    /// this cycle. Levels filled in full start a new cycle at full size, and
+    /// levels the new ladder no longer quotes are forgotten.
+    pub fn remaining(&mut self, mut ladder: Vec<NewOrder>) -> Vec<NewOrder> {
+        let levels: HashSet<(String, Side, Decimal)> = ladder
+            .iter()
+            .map(|o| (o.token_id.clone(), o.side, o.price))
             .collect();
-        let batch_size = batch.len();
-        let batch_meta: Vec<_> = (&mut meta_iter).take(batch_size).collect();
+        self.filled.retain(|level, _| levels.contains(level));
+        for o in &mut ladder {
+            let level = (o.token_id.clone(), o.side, o.price);
+            let Some(filled) = self.filled.get(&level).copied() else {
+                continue;
+            };
+            if filled >= o.size {
+                self.filled.remove(&level);
+                continue;
+            }
+            debug!(
+                side = ?o.side,
+                price = %o.price,
+                target = %o.size,
+                filled = %filled,
+                "Level partially filled, posting remainder"
+            );
+            o.size -= filled;
+        }
+        ladder
+    }
 
-        let responses = client
-            .post_orders(batch)
-            .await
-            .context("posting order batch")?;
+    pub fn clear(&mut self) {
+        self.filled.clear();
+    }
+}
+
+/// Round every order to the exchange's precision (price to tick, size to
+/// two decimals) and drop any that end up invalid, e.g. a size that rounds
+/// to zero.
+pub fn normalize_orders(orders: Vec<NewOrder>, tick_size: Decimal) -> Vec<NewOrder> {
+    orders
+        .into_iter()
+        .map(|o| NewOrder {
+            price: quoter::round_price(o.price, tick_size),
+            size: quoter::round_size(o.size),
+            ..o
+        })
+        .filter(|o| {
+            let valid = quoter::is_valid_order(o.price, o.size, tick_size);
+            if !valid {
+                debug!(price = %o.price, size = %o.size, tick = %tick_size, "Dropping invalid order");
+            }
+            valid
+        })
+        .collect()
+}
+
+/// Render an order ladder as a table for review before it goes live.
+pub fn format_ladder(token_yes_id: &str, orders: &[NewOrder]) -> String {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Token", "Side", "Price", "Size", "Notional"]);
+    for o in orders {
+        let token = if o.token_id == token_yes_id { "YES" } else { "NO" };
+        table.add_row(vec![
+            token.to_string(),
+            format!("{:?}", o.side),
+            o.price.to_string(),
+            o.size.to_string(),
+            format!("${:.2}", o.price * o.size),
+        ]);
+    }
+    table.to_string()
+}
+
+/// Render one token's book around `midpoint`, asks above bids, with our
+/// resting size and reward-band standing per level. Levels holding our
+/// orders are shown even past `depth`, and even if the book hasn't caught
+/// up with them yet.
+pub fn format_book(
+    book: &BookSnapshot,
+    own: &HashMap<(Side, Decimal), Decimal>,
+    midpoint: Decimal,
+    max_spread: Option<Decimal>,
+    depth: usize,
+) -> String {
+    let levels = |resting: &[(Decimal, Decimal)], side: Side| {
+        let mut levels: BTreeMap<Decimal, Decimal> = BTreeMap::new();
+        for (price, size) in resting {
+            *levels.entry(*price).or_default() += *size;
+        }
+        for (own_side, price) in own.keys() {
+            if *own_side == side {
+                levels.entry(*price).or_default();
+            }
+        }
+        levels
+    };
+    let asks = levels(&book.asks, Side::Sell);
+    let bids = levels(&book.bids, Side::Buy);
+    let shown = |side: Side, (i, (price, _)): &(usize, (&Decimal, &Decimal))| {
+        *i < depth || own.contains_key(&(side, **price))
+    };
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Side", "Price", "Size", "Ours", "vs Mid", "Band"]);
+    let level = |side: Side, price: Decimal, size: Decimal| {
+        let ours = own
+            .get(&(side, price))
+            .map(|size| format!("{size} ◀"))
+            .unwrap_or_default();
+        let band = match max_spread {
+            Some(spread) if quoter::in_reward_band(midpoint, price, spread) => "in",
+            Some(_) => "out",
+            None => "-",
+        };
+        vec![
+            if side == Side::Buy { "BID" } else { "ASK" }.to_string(),
+            price.to_string(),
+            size.to_string(),
+            ours,
+            format!("{:+.1}¢", (price - midpoint) * Decimal::ONE_HUNDRED),
+            band.to_string(),
+        ]
+    };
+    let asks: Vec<_> = asks
+        .iter()
+        .enumerate()
+        .filter(|level| shown(Side::Sell, level))
+        .collect();
+    for (_, (price, size)) in asks.into_iter().rev() {
+        table.add_row(level(Side::Sell, *price, *size));
+    }
+    table.add_row(vec!["MID".to_string(), midpoint.to_string()]);
+    for (_, (price, size)) in bids
+        .iter()
+        .rev()
+        .enumerate()
+        .filter(|level| shown(Side::Buy, level))
+    {
+        table.add_row(level(Side::Buy, *price, *size));
+    }
+    table.to_string()
+}
+
+/// Post orders in batches and track the ones the exchange accepted.
+#[instrument(name = "post_orders", skip_all, fields(count = orders.len()))]
+pub async fn submit_orders(
+    exchange: &impl ExchangeApi,
+    orders: &[NewOrder],
+) -> Result<Vec<TrackedOrder>> {
+    if orders.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Batch post (up to 15 per call), pipelined
+    let batches: Vec<_> = orders
+        .chunks(POST_BATCH_SIZE)
+        .map(|batch| async move { (batch, exchange.post_orders(batch).await) })
+        .collect();
+    let mut batches = stream::iter(batches).buffered(POST_PIPELINE_DEPTH);
+    let mut tracked = Vec::new();
+    while let Some((batch, responses)) = batches.next().await {
+        let responses = responses?;
 
-        for (resp, meta) in responses.iter().zip(batch_meta.iter()) {
+        for (resp, meta) in responses.iter().zip(batch.iter()) {
             if resp.success {
                 info!(
                     order_id = %resp.order_id,
-                    side = ?meta.1,
-                    price = %meta.2,
-                    size = %meta.3,
+                    side = ?meta.side,
+                    price = %meta.price,
+                    size = %meta.size,
                     "Order placed"
                 );
                 tracked.push(TrackedOrder {
                     order_id: resp.order_id.clone(),
-                    token_id: meta.0.clone(),
-                    side: meta.1.clone(),
-                    price: meta.2,
-                    size: meta.3,
+                    token_id: meta.token_id.clone(),
+                    side: meta.side,
+                    price: meta.price,
+                    size: meta.size,
                     filled: Decimal::ZERO,
                     status: OrderStatus::Open,
                 });
             } else {
                 warn!(
-                    error = resp.error_msg.as_deref().unwrap_or("unknown"),
-                    side = ?meta.1,
-                    price = %meta.2,
+                    error = resp.error.as_deref().unwrap_or("unknown"),
+                    side = ?meta.side,
+                    price = %meta.price,
                     "Order placement failed"
                 );
             }
@@ -166,60 +519,232 @@ pub async fn place_quotes(
     Ok(tracked)
 }
 
-/// Cancel a list of orders by ID.
-pub async fn cancel_orders(
+/// Build and sign (without posting) a minimal order so a wrong signature type
+/// or funder address fails at startup rather than on the first real requote.
+pub async fn validate_wallet_setup(
     client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-    order_ids: &[String],
-) -> Result<usize> {
+    signer: &impl Signer,
+    token_id: &str,
+) -> Result<()> {
+    let token = U256::from_str(token_id).context("parsing token ID")?;
+    let order = client
+        .limit_order()
+        .token_id(token)
+        .side(Side::Buy)
+        .price(Decimal::new(1, 2))
+        .size(Decimal::new(5, 0))
+        .order_type(OrderType::GTC)
+        .build()
+        .await
+        .context("building validation order")?;
+    client
+        .sign(signer, order)
+        .await
+        .context("signing validation order (check wallet.signature_type / funder_address)")?;
+    info!("Wallet setup validated with a dry order build");
+    Ok(())
+}
+
+/// Cancel a list of orders by ID.
+#[instrument(name = "cancel_orders", skip_all, fields(count = order_ids.len()))]
+pub async fn cancel_orders(exchange: &impl ExchangeApi, order_ids: &[String]) -> Result<usize> {
     if order_ids.is_empty() {
         return Ok(0);
     }
 
-    let id_refs: Vec<&str> = order_ids.iter().map(|s| s.as_str()).collect();
     let mut cancelled = 0;
-
-    for chunk in id_refs.chunks(20) {
-        let resp = client
-            .cancel_orders(chunk)
-            .await
-            .context("cancelling orders")?;
-
-        cancelled += resp.canceled.len();
-
-        if !resp.not_canceled.is_empty() {
+    for chunk in order_ids.chunks(CANCEL_BATCH_SIZE) {
+        let count = exchange.cancel_orders(chunk).await?;
+        if count < chunk.len() {
             debug!(
-                count = resp.not_canceled.len(),
+                count = chunk.len() - count,
                 "Some orders not cancelled (may already be filled)"
             );
         }
+        cancelled += count;
     }
 
     info!(cancelled, total = order_ids.len(), "Orders cancelled");
     Ok(cancelled)
 }
 
-/// Cancel all orders on the exchange.
-pub async fn cancel_all(
+/// Cursor the CLOB returns once the last page has been read.
+const END_CURSOR: &str = "LTE=";
+
+/// Fetch every open order for the wallet, following pagination.
+pub async fn fetch_open_orders(
     client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-) -> Result<()> {
-    client
-        .cancel_all_orders()
+) -> Result<Vec<OpenOrderResponse>> {
+    let req = OrdersRequest::builder().build();
+    let mut orders = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let page = client
+            .orders(&req, cursor)
+            .await
+            .context("fetching open orders")?;
+        orders.extend(page.data);
+        if page.next_cursor.is_empty() || page.next_cursor == END_CURSOR {
+            break;
+        }
+        cursor = Some(page.next_cursor);
+    }
+
+    debug!(count = orders.len(), "Fetched open orders");
+    Ok(orders)
+}
+
+/// Fetch the wallet's trades matched between two unix timestamps, oldest first.
+pub async fn fetch_trades(
+    client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
+    after: i64,
+    before: i64,
+) -> Result<Vec<TradeResponse>> {
+    let req = TradesRequest::builder().after(after).before(before).build();
+    let mut trades = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let page = client
+            .trades(&req, cursor)
+            .await
+            .context("fetching trade history")?;
+        trades.extend(page.data);
+        if page.next_cursor.is_empty() || page.next_cursor == END_CURSOR {
+            break;
+        }
+        cursor = Some(page.next_cursor);
+    }
+
+    trades.sort_by_key(|t| t.match_time);
+    debug!(count = trades.len(), "Fetched trade history");
+    Ok(trades)
+}
+
+/// Our side of a trade: one fill per maker order of ours (by API key or
+/// maker address) when we provided liquidity, or the taker fill (with its
+/// fee) when we crossed the book. A maker trade also lists the other
+/// makers matched against the same taker, which are left out.
+pub fn trade_fills(trade: &TradeResponse, our_key: ApiKey, our_address: Address) -> Vec<Fill> {
+    let market = trade.market.to_string();
+    if trade.trader_side == TraderSide::Maker {
+        trade
+            .maker_orders
+            .iter()
+            .filter(|m| m.owner == our_key || m.maker_address == our_address)
+            .map(|m| Fill {
+                market: market.clone(),
+                token_id: m.asset_id.to_string(),
+                is_buy: m.side == Side::Buy,
+                price: m.price,
+                size: m.matched_amount,
+                fee: Decimal::ZERO,
+                rebate: quoter::estimate_maker_rebate(m.price, m.matched_amount, m.fee_rate_bps),
+            })
+            .collect()
+    } else {
+        vec![Fill {
+            market,
+            token_id: trade.asset_id.to_string(),
+            is_buy: trade.side == Side::Buy,
+            price: trade.price,
+            size: trade.size,
+            fee: quoter::taker_fee(trade.price, trade.size, trade.fee_rate_bps),
+            rebate: Decimal::ZERO,
+        }]
+    }
+}
+
+/// Cancel all orders in one market (by condition ID).
+pub async fn cancel_market(
+    client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
+    condition_id: &str,
+) -> Result<usize> {
+    let market = B256::from_str(condition_id).context("parsing condition ID")?;
+    let req = CancelMarketOrderRequest::builder().market(market).build();
+    let resp = client
+        .cancel_market_orders(&req)
         .await
-        .context("cancelling all orders")?;
+        .context("cancelling market orders")?;
+    info!(
+        condition_id,
+        cancelled = resp.canceled.len(),
+        "Market orders cancelled"
+    );
+    Ok(resp.canceled.len())
+}
+
+/// Cancel all orders on the exchange.
+pub async fn cancel_all(exchange: &impl ExchangeApi) -> Result<()> {
+    exchange.cancel_all().await?;
     info!("All orders cancelled");
     Ok(())
 }
 
+/// Orphaned orders found by a cleanup pass, by market.
+#[derive(Debug, Default)]
+pub struct CleanupReport {
+    pub orphans: BTreeMap<String, Vec<String>>,
+    /// How many the exchange confirmed cancelled (0 on a dry run)
+    pub cancelled: usize,
+}
+
+impl CleanupReport {
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.values().map(Vec::len).sum()
+    }
+
+    /// One row per market, for the `cleanup` command.
+    pub fn format(&self) -> String {
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Market", "Orders"]);
+        for (market, ids) in &self.orphans {
+            table.add_row(vec![market.clone(), ids.len().to_string()]);
+        }
+        table.to_string()
+    }
+}
+
+/// Cancel every open order in a market outside `managed`. Idempotent: a
+/// second pass finds nothing left to cancel.
+pub async fn cleanup_orphans(
+    exchange: &impl ExchangeApi,
+    managed: &HashSet<String>,
+    dry_run: bool,
+) -> Result<CleanupReport> {
+    let mut report = CleanupReport::default();
+    for order in exchange.open_orders().await? {
+        if !managed.contains(&order.market) {
+            report
+                .orphans
+                .entry(order.market)
+                .or_default()
+                .push(order.order_id);
+        }
+    }
+    if dry_run {
+        return Ok(report);
+    }
+    for (market, ids) in &report.orphans {
+        let cancelled = cancel_orders(exchange, ids).await?;
+        info!(market = %market, orders = ids.len(), cancelled, "Cancelled orphan orders");
+        report.cancelled += cancelled;
+    }
+    Ok(report)
+}
+
 /// Reconcile tracked orders with exchange state to detect fills.
 pub async fn reconcile_orders(
-    client: &clob::Client<auth::state::Authenticated<auth::Normal>>,
-    tracked: &mut Vec<TrackedOrder>,
+    exchange: &impl ExchangeApi,
+    tracked: &mut [TrackedOrder],
 ) -> Result<()> {
     for order in tracked.iter_mut() {
         if order.status == OrderStatus::Filled || order.status == OrderStatus::Cancelled {
             continue;
         }
-        match client.order(&order.order_id).await {
+        match exchange.order_state(&order.order_id).await {
             Ok(resp) => {
                 let matched = resp.size_matched;
                 let orig_size = resp.original_size;
@@ -244,3 +769,296 @@ pub async fn reconcile_orders(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::mock::{MockExchange, Op};
+    use proptest::prelude::*;
+    use rust_decimal_macros::dec;
+
+    fn quotes(levels: u32) -> Vec<Quote> {
+        (0..levels)
+            .map(|level| Quote {
+                bid_price: dec!(0.49) - Decimal::from(level) / dec!(100),
+                ask_price: dec!(0.51) + Decimal::from(level) / dec!(100),
+                bid_size: dec!(100),
+                ask_size: dec!(100),
+                level,
+            })
+            .collect()
+    }
+
+    proptest! {
+        /// Whatever the strategy parameters, every order that reaches the
+        /// exchange is on tick, inside (0, 1), and sized to two decimals.
+        #[test]
+        fn prop_generated_orders_are_exchange_valid(
+            fine_tick in any::<bool>(),
+            mid_bps in 1u32..9999,
+            offset_milli in 0u32..5000,
+            min_offset_milli in 0u32..2000,
+            size_ten_thousandths in 1u64..100_000_000,
+            levels in 1u32..6,
+            skew_pct in -50i64..=50,
+            size_skew_pct in 0i64..=200,
+            fee_bps in proptest::option::of(0u32..1000),
+        ) {
+            let tick_size = if fine_tick { dec!(0.001) } else { dec!(0.01) };
+            let params = quoter::QuoteParams {
+                midpoint: Decimal::new(mid_bps as i64, 4),
+                base_offset_cents: Decimal::new(offset_milli as i64, 3),
+                min_offset_cents: Decimal::new(min_offset_milli as i64, 3),
+                tick_size,
+                order_size: Decimal::new(size_ten_thousandths as i64, 4),
+                num_levels: levels,
+                fee_rate_bps: fee_bps,
+                max_incentive_spread: None,
+                min_incentive_size: None,
+                inventory_skew: Decimal::new(skew_pct, 2),
+                shade: Decimal::ZERO,
+                tick_spaced_levels: false,
+                size_skew: Decimal::new(size_skew_pct, 2),
+            };
+            let quotes = quoter::generate_quotes(&params);
+            let orders = normalize_orders(quote_orders("yes", "no", &quotes), tick_size);
+            for o in &orders {
+                prop_assert!(
+                    quoter::is_valid_order(o.price, o.size, tick_size),
+                    "invalid order {:?} at tick {}", o, tick_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cap_buy_notional_per_token() {
+        let ladder = quote_orders("yes", "no", &quotes(3));
+        let capped = cap_buy_notional(ladder, dec!(80));
+        for token in ["yes", "no"] {
+            let buys: Vec<&NewOrder> = capped
+                .iter()
+                .filter(|o| o.token_id == token && o.side == Side::Buy)
+                .collect();
+            // 100 @ 0.49 fits whole, the 0.48 level is trimmed to the rest
+            assert_eq!(buys.len(), 2);
+            assert_eq!(buys[1].size, dec!(64.58));
+            let notional: Decimal = buys.iter().map(|o| o.size * o.price).sum();
+            assert!(notional <= dec!(80));
+        }
+        assert_eq!(capped.iter().filter(|o| o.side == Side::Sell).count(), 6);
+    }
+
+    fn guard() -> PriceGuard {
+        PriceGuard::new(dec!(0.50), dec!(0.05))
+    }
+
+    #[test]
+    fn test_price_guard_blocks_outliers_and_crosses() {
+        let order = |token: &str, side: Side, price: Decimal| NewOrder {
+            token_id: token.into(),
+            side,
+            price,
+            size: dec!(10),
+        };
+        let mut guard = guard();
+        assert!(guard.violation(&order("yes", Side::Buy, dec!(0.46)), "yes").is_none());
+        assert!(guard.violation(&order("yes", Side::Buy, dec!(0.44)), "yes").is_some());
+        // NO buy at 0.40 is a YES sell at 0.60: too far from fair value
+        assert!(guard.violation(&order("no", Side::Buy, dec!(0.40)), "yes").is_some());
+
+        guard.best_bid = Some(dec!(0.49));
+        guard.best_ask = Some(dec!(0.51));
+        assert!(guard.violation(&order("yes", Side::Buy, dec!(0.51)), "yes").is_some());
+        assert!(guard.violation(&order("yes", Side::Sell, dec!(0.49)), "yes").is_some());
+        // NO sell at 0.50 is a YES buy at 0.50: inside the spread
+        assert!(guard.violation(&order("no", Side::Sell, dec!(0.50)), "yes").is_none());
+
+        let kept = guard.filter(
+            vec![
+                order("yes", Side::Buy, dec!(0.50)),
+                order("yes", Side::Buy, dec!(0.52)),
+            ],
+            "yes",
+        );
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[test]
+    fn test_format_book_marks_our_levels_and_band() {
+        let book = BookSnapshot {
+            bids: vec![
+                (dec!(0.49), dec!(100)),
+                (dec!(0.48), dec!(50)),
+                (dec!(0.40), dec!(10)),
+            ],
+            asks: vec![(dec!(0.51), dec!(80)), (dec!(0.60), dec!(20))],
+        };
+        let own = HashMap::from([
+            ((Side::Buy, dec!(0.40)), dec!(10)),
+            ((Side::Sell, dec!(0.52)), dec!(25)),
+        ]);
+        let table = format_book(&book, &own, dec!(0.50), Some(dec!(0.03)), 1);
+        let rows: Vec<&str> = table.lines().filter(|l| l.starts_with('│')).collect();
+        let cells = |row: &str| -> Vec<String> {
+            row.split(['│', '┆'])
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect()
+        };
+        // Depth 1 plus our levels further out, asks above bids
+        let prices: Vec<String> = rows[1..].iter().map(|r| cells(r)[1].clone()).collect();
+        assert_eq!(prices, ["0.52", "0.51", "0.50", "0.49", "0.40"]);
+        // Our ask sits where the book doesn't show it yet
+        assert_eq!(cells(rows[1]), ["ASK", "0.52", "0", "25 ◀", "+2.0¢", "in"]);
+        assert_eq!(
+            cells(rows[5]),
+            ["BID", "0.40", "10", "10 ◀", "-10.0¢", "out"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_place_quotes_batches_posts() {
+        let exchange = MockExchange::new();
+        // 5 levels x 4 legs = 20 orders -> batches of 15 + 5
+        let tracked = place_quotes(&exchange, "yes", "no", &quotes(5), &guard()).await.unwrap();
+        assert_eq!(tracked.len(), 20);
+        assert_eq!(exchange.state().post_batches, vec![15, 5]);
+
+        let no_bid = tracked
+            .iter()
+            .find(|o| o.token_id == "no" && o.side == Side::Buy && o.price == dec!(0.49))
+            .expect("NO bid at 1 - ask");
+        assert_eq!(no_bid.status, OrderStatus::Open);
+    }
+
+    #[tokio::test]
+    async fn test_submit_orders_pipelined_batches_track_in_order() {
+        let exchange = MockExchange::new();
+        let ladder = quote_orders("yes", "no", &quotes(10));
+        let tracked = submit_orders(&exchange, &ladder).await.unwrap();
+        assert_eq!(tracked.len(), 40);
+        assert_eq!(exchange.state().post_batches, vec![15, 15, 10]);
+        // Each tracked order lines up with the order it was posted for
+        let state = exchange.state();
+        for (order, posted) in tracked.iter().zip(&ladder) {
+            assert_eq!(&state.open[&order.order_id].0, posted);
+            assert_eq!((order.side, order.price), (posted.side, posted.price));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_place_quotes_post_failure_propagates() {
+        let exchange = MockExchange::new();
+        exchange.fail(Op::Post);
+        assert!(place_quotes(&exchange, "yes", "no", &quotes(1), &guard()).await.is_err());
+        assert!(exchange.state().open.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_place_quotes_skips_rejected_orders() {
+        let exchange = MockExchange::new();
+        exchange.state().reject_orders = true;
+        let tracked = place_quotes(&exchange, "yes", "no", &quotes(1), &guard()).await.unwrap();
+        assert!(tracked.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_orders_tolerates_status_failures() {
+        let exchange = MockExchange::new();
+        let mut tracked = place_quotes(&exchange, "yes", "no", &quotes(1), &guard())
+            .await
+            .unwrap();
+        let filled_id = tracked[0].order_id.clone();
+        let partial_id = tracked[1].order_id.clone();
+        exchange.fill(&filled_id, dec!(100));
+        exchange.fill(&partial_id, dec!(40));
+
+        exchange.fail(Op::OrderState);
+        reconcile_orders(&exchange, &mut tracked).await.unwrap();
+        assert!(tracked.iter().all(|o| o.status == OrderStatus::Open));
+
+        exchange.heal(Op::OrderState);
+        reconcile_orders(&exchange, &mut tracked).await.unwrap();
+        assert_eq!(tracked[0].status, OrderStatus::Filled);
+        assert_eq!(tracked[1].status, OrderStatus::PartiallyFilled);
+        assert_eq!(tracked[1].filled, dec!(40));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_orders_counts_only_live_orders() {
+        let exchange = MockExchange::new();
+        let tracked = place_quotes(&exchange, "yes", "no", &quotes(1), &guard()).await.unwrap();
+        let mut ids: Vec<String> = tracked.iter().map(|o| o.order_id.clone()).collect();
+        ids.push("already-gone".into());
+        assert_eq!(cancel_orders(&exchange, &ids).await.unwrap(), 4);
+
+        exchange.fail(Op::Cancel);
+        assert!(cancel_orders(&exchange, &ids).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retained_orders_taken_once() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_retained_orders");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("retained_orders.json");
+
+        let exchange = MockExchange::new();
+        let mut tracked = place_quotes(&exchange, "yes", "no", &quotes(1), &guard())
+            .await
+            .unwrap();
+        tracked[0].status = OrderStatus::Filled;
+        let mut retained = RetainedOrders::default();
+        retained.markets.insert(
+            "0xabc".into(),
+            RetainedMarket {
+                last_midpoint: Some(dec!(0.50)),
+                orders: tracked,
+            },
+        );
+        retained.save(&path).unwrap();
+
+        let loaded = RetainedOrders::take(&path).unwrap().unwrap();
+        assert_eq!(loaded.active_ids().len(), 3);
+        assert_eq!(loaded.markets["0xabc"].last_midpoint, Some(dec!(0.50)));
+        assert!(RetainedOrders::take(&path).unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_cancels_only_unmanaged_markets() {
+        let exchange = MockExchange::new();
+        place_quotes(&exchange, "yes", "no", &quotes(1), &guard())
+            .await
+            .unwrap();
+        {
+            let mut state = exchange.state();
+            state.token_markets.insert("yes".into(), "0xmanaged".into());
+            state.token_markets.insert("no".into(), "0xmanaged".into());
+        }
+        let stray = NewOrder {
+            token_id: "stray".into(),
+            side: Side::Buy,
+            price: dec!(0.30),
+            size: dec!(10),
+        };
+        exchange.post_orders(&[stray.clone(), stray]).await.unwrap();
+        exchange
+            .state()
+            .token_markets
+            .insert("stray".into(), "0xorphan".into());
+
+        let managed = HashSet::from(["0xmanaged".to_string()]);
+        let preview = cleanup_orphans(&exchange, &managed, true).await.unwrap();
+        assert_eq!(preview.orphan_count(), 2);
+        assert_eq!(exchange.state().open.len(), 6);
+
+        let report = cleanup_orphans(&exchange, &managed, false).await.unwrap();
+        assert_eq!(report.cancelled, 2);
+        assert_eq!(exchange.state().open.len(), 4);
+        let again = cleanup_orphans(&exchange, &managed, false).await.unwrap();
+        assert_eq!(again.orphan_count(), 0);
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::time::{Duration, Instant};
+
+use crate::exchange::MarketData;
+use crate::risk;
+
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Parse a span like `30m`, `1h`, `7d`, or `2w`.
+pub fn parse_span(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid span {s:?} (expected e.g. 30m, 1h, 7d)"))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => return Err(format!("unknown unit in {s:?} (expected m, h, d, or w)")),
+    };
+    if n == 0 {
+        return Err(format!("span {s:?} must be positive"));
+    }
+    Ok(Duration::from_secs(n * unit_secs))
+}
+
+/// A token's price points `(unix seconds, price)` over the last `range`,
+/// one per `interval`, oldest first.
+pub async fn fetch_history(
+    exchange: &impl MarketData,
+    token_id: &str,
+    range: Duration,
+    interval: Duration,
+) -> Result<Vec<(i64, Decimal)>> {
+    let end = chrono::Utc::now().timestamp();
+    let start = end - range.as_secs() as i64;
+    let fidelity = (interval.as_secs() / 60).max(1) as u32;
+    exchange.price_history(token_id, start, end, fidelity).await
+}
+
+/// One-line chart of `prices` at most `width` characters wide, scaled
+/// between their low and high. Longer series keep the last price of each
+/// bucket.
+pub fn sparkline(prices: &[Decimal], width: usize) -> String {
+    if prices.is_empty() || width == 0 {
+        return String::new();
+    }
+    let buckets = prices.len().min(width);
+    let sampled: Vec<Decimal> = (0..buckets)
+        .map(|i| prices[((i + 1) * prices.len()).div_ceil(buckets) - 1])
+        .collect();
+    let low = sampled.iter().copied().min().unwrap_or_default();
+    let high = sampled.iter().copied().max().unwrap_or_default();
+    let top = Decimal::from(SPARK_BARS.len() - 1);
+    sampled
+        .iter()
+        .map(|p| {
+            let bar = if high > low {
+                ((*p - low) / (high - low) * top).round()
+            } else {
+                Decimal::ZERO
+            };
+            SPARK_BARS[bar.to_usize().unwrap_or(0).min(SPARK_BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Daily volatility of a price history, as the engine estimates it from
+/// live midpoints.
+pub fn daily_volatility(history: &[(i64, Decimal)]) -> Option<Decimal> {
+    let first = history.first()?.0;
+    let base = Instant::now();
+    let samples: Vec<(Instant, Decimal)> = history
+        .iter()
+        .map(|(t, p)| (base + Duration::from_secs((t - first).max(0) as u64), *p))
+        .collect();
+    risk::daily_volatility(&samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_span_and_sparkline() {
+        assert_eq!(parse_span("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_span("7d"), Ok(Duration::from_secs(7 * 86400)));
+        assert!(parse_span("0h").is_err());
+        assert!(parse_span("5y").is_err());
+        assert!(parse_span("h").is_err());
+
+        let prices = [dec!(0.40), dec!(0.45), dec!(0.50), dec!(0.60)];
+        assert_eq!(sparkline(&prices, 10), "▁▃▅█");
+        // Squeezed to the last price of each half
+        assert_eq!(sparkline(&prices, 2), "▁█");
+        assert_eq!(sparkline(&[dec!(0.5); 3], 10), "▁▁▁");
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::metrics::{self, MarketMetrics};
+
+/// One exported gauge: name, help text, and its per-market value.
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&MarketMetrics) -> Decimal,
+}
+
+const GAUGES: &[Gauge] = &[
+    Gauge {
+        name: "polymarket_lp_spread_pnl_usd",
+        help: "Spread capture booked today",
+        value: |m| m.spread_pnl,
+    },
+    Gauge {
+        name: "polymarket_lp_reward_pnl_usd",
+        help: "Liquidity rewards booked today",
+        value: |m| m.reward_pnl,
+    },
+    Gauge {
+        name: "polymarket_lp_rebate_pnl_usd",
+        help: "Maker rebates booked today",
+        value: |m| m.rebate_pnl,
+    },
+    Gauge {
+        name: "polymarket_lp_fees_paid_usd",
+        help: "Taker fees paid today",
+        value: |m| m.fees_paid,
+    },
+    Gauge {
+        name: "polymarket_lp_net_pnl_usd",
+        help: "PnL after fees today",
+        value: |m| m.net_pnl(),
+    },
+    Gauge {
+        name: "polymarket_lp_fills",
+        help: "Fills today",
+        value: |m| Decimal::from(m.total_fills),
+    },
+    Gauge {
+        name: "polymarket_lp_orders",
+        help: "Orders placed today",
+        value: |m| Decimal::from(m.total_orders),
+    },
+    Gauge {
+        name: "polymarket_lp_inventory_yes_shares",
+        help: "YES shares held",
+        value: |m| m.inventory_yes,
+    },
+    Gauge {
+        name: "polymarket_lp_inventory_no_shares",
+        help: "NO shares held",
+        value: |m| m.inventory_no,
+    },
+    Gauge {
+        name: "polymarket_lp_midpoint",
+        help: "Last YES midpoint",
+        value: |m| m.last_midpoint.unwrap_or(Decimal::ZERO),
+    },
+    Gauge {
+        name: "polymarket_lp_band_uptime_ratio",
+        help: "Share of sampled minutes in the reward band today",
+        value: |m| m.band_uptime_pct() / Decimal::ONE_HUNDRED,
+    },
+    Gauge {
+        name: "polymarket_lp_projected_reward_usd",
+        help: "Today's reward if our score share so far holds",
+        value: |m| m.projected_reward_today(),
+    },
+];
+
+/// Metrics in the Prometheus text exposition format, one series per market
+/// labelled by condition ID and question.
+pub fn render(markets: &[MarketMetrics]) -> String {
+    let mut markets: Vec<&MarketMetrics> = markets.iter().collect();
+    markets.sort_by(|a, b| a.condition_id.cmp(&b.condition_id));
+    let mut out = String::new();
+    for gauge in GAUGES {
+        let _ = writeln!(out, "# HELP {} {}", gauge.name, gauge.help);
+        let _ = writeln!(out, "# TYPE {} gauge", gauge.name);
+        for m in &markets {
+            let _ = writeln!(
+                out,
+                "{}{{condition_id=\"{}\",market=\"{}\"}} {}",
+                gauge.name,
+                escape_label(&m.condition_id),
+                escape_label(&m.question),
+                (gauge.value)(m).normalize()
+            );
+        }
+    }
+    out
+}
+
+/// Replace the node_exporter textfile at `path` (which should end in
+/// `.prom`). Written via a temp file so the collector never reads half of it.
+pub fn write_textfile(path: &Path, markets: &[MarketMetrics]) -> Result<()> {
+    metrics::write_atomic(path, &render(markets))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_render_textfile() {
+        let mut m = MarketMetrics::new("0xabc".into(), "Will \"X\" win?".into());
+        m.spread_pnl = dec!(1.50);
+        m.total_fills = 3;
+        let text = render(&[m]);
+        assert!(text.contains("# TYPE polymarket_lp_spread_pnl_usd gauge\n"));
+        assert!(text.contains(
+            "polymarket_lp_spread_pnl_usd{condition_id=\"0xabc\",market=\"Will \\\"X\\\" win?\"} 1.5\n"
+        ));
+        assert!(text.contains(
+            "polymarket_lp_fills{condition_id=\"0xabc\",market=\"Will \\\"X\\\" win?\"} 3\n"
+        ));
+    }
+}
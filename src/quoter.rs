@@ -1,12 +1,16 @@
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
 /// A proposed quote with bid and ask prices for a single token side.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Quote {
     pub bid_price: Decimal,
     pub ask_price: Decimal,
-    pub size: Decimal,
+    /// Size of the YES bid and its mirror NO ask
+    pub bid_size: Decimal,
+    /// Size of the YES ask and its mirror NO bid
+    pub ask_size: Decimal,
     pub level: u32,
 }
 
@@ -27,6 +31,36 @@ pub struct QuoteParams {
     pub min_incentive_size: Option<Decimal>,
     /// Inventory skew: positive = long (widen bid, tighten ask), negative = short
     pub inventory_skew: Decimal,
+    /// How far (0-1) to move the offset from base toward the edge of the
+    /// reward band, under adverse selection
+    pub shade: Decimal,
+    /// Space levels one tick apart instead of 10% of the base offset
+    pub tick_spaced_levels: bool,
+    /// How strongly inventory skew moves size from bids to asks (0 = the
+    /// same size both sides)
+    pub size_skew: Decimal,
+}
+
+/// Standard CLOB tick size for prices between the extremes.
+pub const DEFAULT_TICK_SIZE: Decimal = dec!(0.01);
+/// Finer tick Polymarket uses once a market trades below 0.10 or above 0.90.
+pub const EXTREME_TICK_SIZE: Decimal = dec!(0.001);
+const EXTREME_PRICE_LOW: Decimal = dec!(0.10);
+const EXTREME_PRICE_HIGH: Decimal = dec!(0.90);
+
+/// Whether a midpoint is in the fine-tick regime near 0 or 1.
+pub fn is_extreme_price(midpoint: Decimal) -> bool {
+    midpoint < EXTREME_PRICE_LOW || midpoint > EXTREME_PRICE_HIGH
+}
+
+/// Tick size implied by the price regime. Used as a fallback when the CLOB
+/// tick size can't be fetched.
+pub fn expected_tick_size(midpoint: Decimal) -> Decimal {
+    if is_extreme_price(midpoint) {
+        EXTREME_TICK_SIZE
+    } else {
+        DEFAULT_TICK_SIZE
+    }
 }
 
 /// Compute the fee-aware offset.
@@ -44,10 +78,62 @@ pub fn compute_offset(params: &QuoteParams) -> Decimal {
         base_offset
     };
 
-    let min_offset = params.min_offset_cents / dec!(100);
+    // Never quote closer than one tick, or alignment would land on the midpoint
+    let min_offset = (params.min_offset_cents / dec!(100)).max(params.tick_size);
     fee_offset.max(min_offset)
 }
 
+/// An offset in cents: the tick count at this tick size when one is set,
+/// else the cents value as configured.
+pub fn offset_cents(cents: Decimal, ticks: Option<u32>, tick_size: Decimal) -> Decimal {
+    ticks.map_or(cents, |ticks| Decimal::from(ticks) * tick_size * dec!(100))
+}
+
+/// Whether an offset in cents lands on a whole number of ticks. Otherwise
+/// prices round to the nearest tick and the offset quoted isn't the one set.
+pub fn is_whole_ticks(cents: Decimal, tick_size: Decimal) -> bool {
+    tick_size.is_zero() || (cents / dec!(100) % tick_size).is_zero()
+}
+
+/// Share of collected taker fees paid back to makers (20% crypto, 25% sports).
+/// Use the lower figure so rebate estimates stay conservative.
+pub const MAKER_REBATE_SHARE: Decimal = dec!(0.20);
+
+/// Taker fee charged on a fill: size * fee_rate * p * (1-p).
+pub fn taker_fee(price: Decimal, size: Decimal, fee_rate_bps: Decimal) -> Decimal {
+    let fee_rate = fee_rate_bps / dec!(10000);
+    size * fee_rate * price * (Decimal::ONE - price)
+}
+
+/// Estimate the maker rebate earned by a fill on a fee-enabled market.
+/// Makers receive `MAKER_REBATE_SHARE` of the fee the taker paid.
+pub fn estimate_maker_rebate(price: Decimal, size: Decimal, fee_rate_bps: Decimal) -> Decimal {
+    taker_fee(price, size, fee_rate_bps) * MAKER_REBATE_SHARE
+}
+
+/// Share sizes are accepted to two decimal places.
+pub const SIZE_DECIMALS: u32 = 2;
+
+/// Round a share size down to the exchange's precision.
+pub fn round_size(size: Decimal) -> Decimal {
+    size.round_dp_with_strategy(SIZE_DECIMALS, RoundingStrategy::ToZero)
+}
+
+/// Snap a price to the tick and trim it to the tick's decimal places.
+pub fn round_price(price: Decimal, tick_size: Decimal) -> Decimal {
+    align_to_tick(price, tick_size).round_dp(tick_size.scale())
+}
+
+/// Whether a price/size pair is accepted by the exchange for this tick.
+pub fn is_valid_order(price: Decimal, size: Decimal, tick_size: Decimal) -> bool {
+    let on_tick = tick_size.is_zero() || (price % tick_size).is_zero();
+    on_tick
+        && price >= tick_size
+        && price <= Decimal::ONE - tick_size
+        && size > Decimal::ZERO
+        && size.round_dp(SIZE_DECIMALS) == size
+}
+
 /// Align a price to the market's tick size (round to nearest tick).
 pub fn align_to_tick(price: Decimal, tick_size: Decimal) -> Decimal {
     if tick_size.is_zero() {
@@ -56,14 +142,171 @@ pub fn align_to_tick(price: Decimal, tick_size: Decimal) -> Decimal {
     (price / tick_size).round() * tick_size
 }
 
+/// Passive price for selling a token out of inventory: the first tick at or
+/// above `fair`, kept inside the valid (0, 1) range.
+pub fn unwind_ask(fair: Decimal, tick_size: Decimal) -> Decimal {
+    if tick_size.is_zero() {
+        return fair;
+    }
+    ((fair / tick_size).ceil() * tick_size)
+        .round_dp(tick_size.scale())
+        .max(tick_size)
+        .min(Decimal::ONE - tick_size)
+}
+
 /// Generate quotes for a given set of parameters.
 /// Returns quotes for each level on both sides.
 pub fn generate_quotes(params: &QuoteParams) -> Vec<Quote> {
+    generate_sided_quotes(params, Sides::Both)
+}
+
+/// What to quote when the midpoint nears 0 or 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtremePolicy {
+    /// Keep quoting both sides; levels that don't fit are dropped
+    TwoSided,
+    /// Quote only the side with room: asks near 0, bids near 1
+    #[default]
+    OneSided,
+    /// Quote nothing until the midpoint comes back
+    Suspend,
+}
+
+/// Which sides of the book to quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sides {
+    Both,
+    /// YES bids (and NO asks) only
+    BidsOnly,
+    /// YES asks (and NO bids) only
+    AsksOnly,
+    None,
+}
+
+impl Sides {
+    fn bids(self) -> bool {
+        matches!(self, Self::Both | Self::BidsOnly)
+    }
+
+    fn asks(self) -> bool {
+        matches!(self, Self::Both | Self::AsksOnly)
+    }
+}
+
+impl std::fmt::Display for Sides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Both => "two-sided",
+            Self::BidsOnly => "bids only",
+            Self::AsksOnly => "asks only",
+            Self::None => "suspended",
+        })
+    }
+}
+
+/// Sides to quote at `midpoint` under `policy`. Within `extreme` of 0 or 1
+/// the bid (near 0) or ask (near 1) has no room under the price bounds, so
+/// one-sided quoting keeps only the other side and suspending quotes
+/// nothing.
+pub fn extreme_sides(midpoint: Decimal, extreme: Decimal, policy: ExtremePolicy) -> Sides {
+    let (low, high) = (midpoint < extreme, midpoint > Decimal::ONE - extreme);
+    match policy {
+        ExtremePolicy::TwoSided => Sides::Both,
+        _ if !low && !high => Sides::Both,
+        ExtremePolicy::Suspend => Sides::None,
+        ExtremePolicy::OneSided if low => Sides::AsksOnly,
+        ExtremePolicy::OneSided => Sides::BidsOnly,
+    }
+}
+
+/// Top of the book as other makers have left it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BookState {
+    #[default]
+    Normal,
+    /// Best bid and ask one tick apart
+    Collapsed,
+    /// Best bid at or above the best ask
+    Crossed,
+}
+
+impl std::fmt::Display for BookState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Normal => "normal",
+            Self::Collapsed => "collapsed",
+            Self::Crossed => "crossed",
+        })
+    }
+}
+
+/// Classify the spread between `best_bid` and `best_ask`. A missing side
+/// counts as normal.
+pub fn book_state(
+    best_bid: Option<Decimal>,
+    best_ask: Option<Decimal>,
+    tick: Decimal,
+) -> BookState {
+    match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) if bid >= ask => BookState::Crossed,
+        (Some(bid), Some(ask)) if ask - bid <= tick => BookState::Collapsed,
+        _ => BookState::Normal,
+    }
+}
+
+/// What to do while the book is collapsed or crossed, where resting quotes
+/// inside the band only get picked off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollapsePolicy {
+    /// Keep quoting as normal
+    Ignore,
+    /// Quote `collapse_widen_ticks` further from the midpoint
+    Widen,
+    /// Cancel and quote nothing until the book normalizes
+    #[default]
+    Suspend,
+}
+
+/// `generate_quotes` for only some sides of the book. A side left out gets
+/// zero size, and a level is kept as long as the sides quoted are valid.
+pub fn generate_sided_quotes(params: &QuoteParams, sides: Sides) -> Vec<Quote> {
+    if sides == Sides::None {
+        return Vec::new();
+    }
     let base_offset = compute_offset(params);
-    let mut quotes = Vec::new();
+    let band_edge = shading_edge(params, base_offset);
+    let step = if params.tick_spaced_levels {
+        params.tick_size
+    } else {
+        base_offset / dec!(10) // each level 10% wider
+    };
+    let (mut bid_size, mut ask_size) = skewed_sizes(params);
+    if !sides.bids() {
+        bid_size = Decimal::ZERO;
+    }
+    if !sides.asks() {
+        ask_size = Decimal::ZERO;
+    }
+    let mut quotes: Vec<Quote> = Vec::new();
 
     for level in 0..params.num_levels {
-        let level_offset = base_offset + base_offset * Decimal::new(level as i64, 1); // each level 10% wider
+        let mut level_offset = base_offset + step * Decimal::from(level);
+        // Shading slides the ladder toward the band edge but never past it,
+        // compressing it into [base, edge] so levels stay a tick apart
+        // rather than piling onto the edge
+        if let Some(edge) = band_edge {
+            let tick = params.tick_size;
+            let shaded = base_offset
+                + (edge - base_offset) * params.shade
+                + step.max(tick) * Decimal::from(level);
+            let outside = Decimal::from(params.num_levels - 1 - level);
+            level_offset = shaded
+                .min(edge - tick * outside)
+                .max(base_offset + tick * Decimal::from(level))
+                .min(edge);
+        }
 
         // Apply inventory skew: if long, widen bid (less aggressive buying), tighten ask
         let skew = params.inventory_skew;
@@ -73,21 +316,42 @@ pub fn generate_quotes(params: &QuoteParams) -> Vec<Quote> {
         let raw_bid = params.midpoint - bid_offset;
         let raw_ask = params.midpoint + ask_offset;
 
-        let bid_price = align_to_tick(raw_bid, params.tick_size);
-        let ask_price = align_to_tick(raw_ask, params.tick_size);
+        // Near the extremes, pin to the first valid tick instead of dropping the
+        // level, as long as it stays on the right side of the midpoint
+        let (lowest, highest) = (params.tick_size, Decimal::ONE - params.tick_size);
+        let bid_price = round_price(raw_bid, params.tick_size).max(lowest);
+        let ask_price = round_price(raw_ask, params.tick_size).min(highest);
 
-        // Validate price bounds
-        if bid_price <= Decimal::ZERO || ask_price >= Decimal::ONE {
+        // Validate price bounds on the sides being quoted
+        let (bid_ok, ask_ok) = (bid_price < params.midpoint, ask_price > params.midpoint);
+        if (sides.bids() && !bid_ok) || (sides.asks() && !ask_ok) {
             continue;
         }
-        if bid_price >= ask_price {
+        if sides == Sides::Both && bid_price >= ask_price {
+            continue;
+        }
+
+        // Every level past the bound pins to the same tick; only the first
+        // keeps its size there, so the bound doesn't stack the whole ladder
+        let bid_pinned = bid_price == lowest
+            && quotes
+                .iter()
+                .any(|q| q.bid_price == lowest && q.bid_size > Decimal::ZERO);
+        let ask_pinned = ask_price == highest
+            && quotes
+                .iter()
+                .any(|q| q.ask_price == highest && q.ask_size > Decimal::ZERO);
+        let level_bid = if bid_pinned { Decimal::ZERO } else { bid_size };
+        let level_ask = if ask_pinned { Decimal::ZERO } else { ask_size };
+        if level_bid.is_zero() && level_ask.is_zero() {
             continue;
         }
 
         quotes.push(Quote {
             bid_price,
             ask_price,
-            size: params.order_size,
+            bid_size: level_bid,
+            ask_size: level_ask,
             level,
         });
     }
@@ -95,6 +359,145 @@ pub fn generate_quotes(params: &QuoteParams) -> Vec<Quote> {
     quotes
 }
 
+/// Bid and ask sizes for every level. Long inventory shrinks bids and
+/// grows asks by `inventory_skew * size_skew` of the order size, and short
+/// inventory the reverse, so the book leans toward flattening. A shrunk side
+/// never drops below the reward minimum size.
+pub fn skewed_sizes(params: &QuoteParams) -> (Decimal, Decimal) {
+    let shift = params.order_size * params.inventory_skew * params.size_skew;
+    let floor = params
+        .min_incentive_size
+        .unwrap_or(Decimal::ZERO)
+        .min(params.order_size);
+    let side = |size: Decimal| round_size(size.max(floor));
+    (
+        side(params.order_size - shift),
+        side(params.order_size + shift),
+    )
+}
+
+/// The widest offset whose tick-rounded prices still earn rewards, if
+/// shading is active and the band is wider than `base_offset`.
+fn shading_edge(params: &QuoteParams, base_offset: Decimal) -> Option<Decimal> {
+    if params.shade <= Decimal::ZERO {
+        return None;
+    }
+    let band = params.max_incentive_spread?;
+    // Rounding to the tick moves a price by at most half a tick
+    let edge = if offset_in_band(params, band, band) {
+        band
+    } else {
+        band - params.tick_size / dec!(2)
+    };
+    (edge > base_offset).then_some(edge)
+}
+
+/// How many tick-spaced levels fit between the base offset and the edge of
+/// the reward band, between 1 and `max_levels`. Without a known band, the
+/// full `max_levels`.
+pub fn band_levels(params: &QuoteParams, max_levels: u32) -> u32 {
+    let Some(band) = params.max_incentive_spread else {
+        return max_levels.max(1);
+    };
+    let base_offset = compute_offset(params);
+    let mut levels = 0;
+    while levels < max_levels
+        && offset_in_band(
+            params,
+            base_offset + params.tick_size * Decimal::from(levels),
+            band,
+        )
+    {
+        levels += 1;
+    }
+    levels.max(1)
+}
+
+/// Bounds for perturbing each ladder so its sizes and spacing aren't a
+/// fixed, recognizable pattern. Draws are reproducible from the seed and
+/// the ladder's epoch, so ladders pre-signed for the next requote match it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Jitter {
+    /// Largest size change either way, as a percentage of the size
+    pub size_pct: Decimal,
+    /// Most ticks a level's prices may move away from the midpoint
+    pub offset_ticks: u32,
+    pub seed: u64,
+}
+
+impl Jitter {
+    pub fn is_off(&self) -> bool {
+        self.size_pct <= Decimal::ZERO && self.offset_ticks == 0
+    }
+
+    /// Uniform-ish draw for one quantity of one level of one ladder.
+    fn draw(&self, epoch: u64, level: u32, quantity: u8) -> u64 {
+        draw(self.seed, epoch, level, quantity)
+    }
+}
+
+/// Deterministic uniform-ish draw keyed by a per-run seed, the ladder, the
+/// level, and which quantity of the level it is for.
+pub fn draw(seed: u64, epoch: u64, level: u32, quantity: u8) -> u64 {
+    // splitmix64 finalizer over the combined key
+    let mut z = seed
+        ^ epoch.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ ((u64::from(level) << 8) | u64::from(quantity)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Perturb a ladder within `jitter`'s bounds. Sizes move up to `size_pct`
+/// either way but never below the reward minimum; prices only move away
+/// from the midpoint, and never out of the reward band they were in.
+pub fn jitter_quotes(quotes: &mut [Quote], params: &QuoteParams, jitter: &Jitter, epoch: u64) {
+    if jitter.is_off() {
+        return;
+    }
+    let (mid, tick) = (params.midpoint, params.tick_size);
+    let floor = params.min_incentive_size.unwrap_or(Decimal::ZERO);
+    let in_band = |price: Decimal| {
+        params
+            .max_incentive_spread
+            .is_none_or(|band| in_reward_band(mid, price, band))
+    };
+    for q in quotes {
+        let size = |size: Decimal, quantity: u8| {
+            // Fraction in [-1, 1] in steps of 0.001
+            let fraction = (Decimal::from(jitter.draw(epoch, q.level, quantity) % 2001)
+                - dec!(1000))
+                / dec!(1000);
+            let jittered =
+                round_size(size * (Decimal::ONE + fraction * jitter.size_pct / dec!(100)));
+            jittered.max(floor.min(size))
+        };
+        let ticks = |quantity: u8| {
+            let n = jitter.draw(epoch, q.level, quantity) % (u64::from(jitter.offset_ticks) + 1);
+            tick * Decimal::from(n)
+        };
+        let (bid_size, ask_size) = (size(q.bid_size, 0), size(q.ask_size, 1));
+        let bid = (q.bid_price - ticks(2)).max(tick);
+        let ask = (q.ask_price + ticks(3)).min(Decimal::ONE - tick);
+        q.bid_size = bid_size;
+        q.ask_size = ask_size;
+        if in_band(bid) || !in_band(q.bid_price) {
+            q.bid_price = bid;
+        }
+        if in_band(ask) || !in_band(q.ask_price) {
+            q.ask_price = ask;
+        }
+    }
+}
+
+/// Whether both tick-rounded prices `offset` either side of the midpoint
+/// earn rewards.
+fn offset_in_band(params: &QuoteParams, offset: Decimal, band: Decimal) -> bool {
+    let (mid, tick) = (params.midpoint, params.tick_size);
+    in_reward_band(mid, round_price(mid - offset, tick), band)
+        && in_reward_band(mid, round_price(mid + offset, tick), band)
+}
+
 /// Calculate the quadratic incentive score for a quote.
 /// S(v, s) = ((v - s) / v)^2 * b
 /// where v = max_incentive_spread, s = distance from midpoint, b = order_size
@@ -132,6 +535,49 @@ pub fn estimate_score(
     ratio * ratio * size
 }
 
+/// USDC locked by the buy orders for one quote level: the YES bid plus the
+/// complementary NO bid at `1 - ask`. The sell legs lock tokens, not USDC.
+pub fn quote_capital(quote: &Quote) -> Decimal {
+    quote.bid_size * quote.bid_price + quote.ask_size * (Decimal::ONE - quote.ask_price)
+}
+
+/// Whether a resting order is close enough to the midpoint to earn rewards.
+pub fn in_reward_band(midpoint: Decimal, price: Decimal, max_spread: Decimal) -> bool {
+    (midpoint - price).abs() <= max_spread
+}
+
+/// How far into the reward band `price` sits, in percent: 0 at the band
+/// edge, 100 at the midpoint, negative outside the band.
+pub fn band_depth_pct(midpoint: Decimal, price: Decimal, max_spread: Decimal) -> Option<Decimal> {
+    if max_spread <= Decimal::ZERO {
+        return None;
+    }
+    Some((max_spread - (midpoint - price).abs()) / max_spread * dec!(100))
+}
+
+/// Sum of reward scores for resting orders on one side of the book,
+/// given as (price, size) pairs.
+pub fn book_side_score(
+    midpoint: Decimal,
+    orders: &[(Decimal, Decimal)],
+    max_spread: Option<Decimal>,
+    min_size: Option<Decimal>,
+) -> Decimal {
+    orders
+        .iter()
+        .map(|&(price, size)| estimate_score(midpoint, price, size, max_spread, min_size))
+        .sum()
+}
+
+/// Our fraction of the reward pool given our score and everyone else's.
+pub fn reward_share(our_score: Decimal, competitor_score: Decimal) -> Decimal {
+    let total = our_score + competitor_score;
+    if total.is_zero() {
+        return Decimal::ZERO;
+    }
+    our_score / total
+}
+
 /// Calculate the two-sided bonus.
 /// Q_min = min(Q_bid, Q_ask). Single-sided orders get divided by 3.
 pub fn two_sided_score(bid_score: Decimal, ask_score: Decimal) -> Decimal {
@@ -158,6 +604,9 @@ mod tests {
             max_incentive_spread: None,
             min_incentive_size: None,
             inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ZERO,
         };
         let offset = compute_offset(&params);
         assert_eq!(offset, dec!(0.01)); // 1.0 cents = 0.01
@@ -176,6 +625,9 @@ mod tests {
             max_incentive_spread: None,
             min_incentive_size: None,
             inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ZERO,
         };
         let offset = compute_offset(&params);
         // fee_at_mid = 0.02 * 0.50 * 0.50 = 0.005
@@ -183,6 +635,16 @@ mod tests {
         assert_eq!(offset, dec!(0.0125));
     }
 
+    #[test]
+    fn test_offsets_in_ticks_follow_tick_size() {
+        assert_eq!(offset_cents(dec!(1.5), Some(3), dec!(0.01)), dec!(3));
+        assert_eq!(offset_cents(dec!(1.5), Some(3), dec!(0.001)), dec!(0.3));
+        assert_eq!(offset_cents(dec!(1.5), None, dec!(0.001)), dec!(1.5));
+        assert!(is_whole_ticks(dec!(1.5), dec!(0.001)));
+        assert!(!is_whole_ticks(dec!(1.5), dec!(0.01)));
+        assert!(!is_whole_ticks(dec!(0.25), dec!(0.001)));
+    }
+
     #[test]
     fn test_align_to_tick() {
         assert_eq!(align_to_tick(dec!(0.4567), dec!(0.01)), dec!(0.46));
@@ -191,6 +653,26 @@ mod tests {
         assert_eq!(align_to_tick(dec!(0.4567), dec!(0.0001)), dec!(0.4567));
     }
 
+    #[test]
+    fn test_round_price_and_size() {
+        assert_eq!(round_price(dec!(0.45678), dec!(0.01)).to_string(), "0.46");
+        assert_eq!(round_price(dec!(0.45678), dec!(0.001)).to_string(), "0.457");
+        assert_eq!(round_size(dec!(123.456)), dec!(123.45));
+        assert_eq!(round_size(dec!(0.009)), Decimal::ZERO);
+        assert!(is_valid_order(dec!(0.46), dec!(10.5), dec!(0.01)));
+        assert!(!is_valid_order(dec!(0.465), dec!(10), dec!(0.01)));
+        assert!(!is_valid_order(dec!(0.46), dec!(10.005), dec!(0.01)));
+        assert!(!is_valid_order(dec!(1.0), dec!(10), dec!(0.01)));
+    }
+
+    #[test]
+    fn test_unwind_ask() {
+        assert_eq!(unwind_ask(dec!(0.453), dec!(0.01)), dec!(0.46));
+        assert_eq!(unwind_ask(dec!(0.45), dec!(0.01)), dec!(0.45));
+        assert_eq!(unwind_ask(dec!(0.9995), dec!(0.001)), dec!(0.999));
+        assert_eq!(unwind_ask(dec!(0.0001), dec!(0.001)), dec!(0.001));
+    }
+
     #[test]
     fn test_generate_quotes_basic() {
         let params = QuoteParams {
@@ -204,6 +686,9 @@ mod tests {
             max_incentive_spread: None,
             min_incentive_size: None,
             inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ZERO,
         };
         let quotes = generate_quotes(&params);
         assert_eq!(quotes.len(), 2);
@@ -212,6 +697,221 @@ mod tests {
         assert_eq!(quotes[0].ask_price, dec!(0.51));
     }
 
+    #[test]
+    fn test_shading_stays_in_reward_band() {
+        let params = QuoteParams {
+            midpoint: dec!(0.505),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.01),
+            order_size: dec!(500),
+            num_levels: 3,
+            fee_rate_bps: None,
+            max_incentive_spread: Some(dec!(0.03)),
+            min_incentive_size: None,
+            inventory_skew: Decimal::ZERO,
+            shade: Decimal::ONE,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ZERO,
+        };
+        // Full shade compresses the ladder into the band a tick apart
+        // instead of stacking every level on the edge
+        let full = generate_quotes(&QuoteParams {
+            midpoint: dec!(0.50),
+            ..params
+        });
+        let bids: Vec<Decimal> = full.iter().map(|q| q.bid_price).collect();
+        assert_eq!(bids, vec![dec!(0.49), dec!(0.48), dec!(0.47)]);
+        // Off-tick midpoint: the edge backs off half a tick so rounding
+        // can't push a price out of the band
+        for q in generate_quotes(&params) {
+            assert!(in_reward_band(params.midpoint, q.bid_price, dec!(0.03)));
+            assert!(in_reward_band(params.midpoint, q.ask_price, dec!(0.03)));
+        }
+
+        let half = generate_quotes(&QuoteParams {
+            midpoint: dec!(0.50),
+            shade: dec!(0.5),
+            num_levels: 2,
+            max_incentive_spread: Some(dec!(0.05)),
+            ..params
+        });
+        // Offset 0.01 + (0.05 - 0.01) * 0.5 = 0.03, then a tick further out
+        assert_eq!(half[0].bid_price, dec!(0.47));
+        assert_eq!(half[0].ask_price, dec!(0.53));
+        assert_eq!(half[1].bid_price, dec!(0.46));
+    }
+
+    #[test]
+    fn test_band_levels() {
+        let params = QuoteParams {
+            midpoint: dec!(0.50),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.01),
+            order_size: dec!(500),
+            num_levels: 1,
+            fee_rate_bps: None,
+            max_incentive_spread: Some(dec!(0.035)),
+            min_incentive_size: None,
+            inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: true,
+            size_skew: Decimal::ZERO,
+        };
+        // Offsets 1c, 2c, 3c fit a 3.5c band; a tight band still gets one
+        assert_eq!(band_levels(&params, 5), 3);
+        assert_eq!(band_levels(&params, 2), 2);
+        let tight = QuoteParams {
+            max_incentive_spread: Some(dec!(0.005)),
+            ..params
+        };
+        assert_eq!(band_levels(&tight, 5), 1);
+
+        let quotes = generate_quotes(&QuoteParams {
+            num_levels: 3,
+            ..params
+        });
+        let bids: Vec<Decimal> = quotes.iter().map(|q| q.bid_price).collect();
+        assert_eq!(bids, vec![dec!(0.49), dec!(0.48), dec!(0.47)]);
+        assert_eq!(quotes[2].ask_price, dec!(0.53));
+    }
+
+    #[test]
+    fn test_size_skew() {
+        let params = QuoteParams {
+            midpoint: dec!(0.50),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.01),
+            order_size: dec!(500),
+            num_levels: 2,
+            fee_rate_bps: None,
+            max_incentive_spread: Some(dec!(0.03)),
+            min_incentive_size: Some(dec!(100)),
+            inventory_skew: dec!(0.4),
+            shade: Decimal::ZERO,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ONE,
+        };
+        // Long: 500 * 0.4 moves from the bid to the ask
+        let quotes = generate_quotes(&params);
+        assert_eq!(
+            (quotes[1].bid_size, quotes[1].ask_size),
+            (dec!(300), dec!(700))
+        );
+        let orders = crate::orders::quote_orders("yes", "no", &quotes[..1]);
+        let sizes: Vec<Decimal> = orders.iter().map(|o| o.size).collect();
+        assert_eq!(sizes, vec![dec!(300), dec!(700), dec!(700), dec!(300)]);
+
+        // Short and strongly skewed: the ask bottoms out at the reward minimum
+        let short = QuoteParams {
+            inventory_skew: dec!(-0.5),
+            size_skew: dec!(2),
+            ..params
+        };
+        assert_eq!(skewed_sizes(&short), (dec!(1000), dec!(100)));
+    }
+
+    #[test]
+    fn test_expected_tick_size() {
+        assert_eq!(expected_tick_size(dec!(0.50)), dec!(0.01));
+        assert_eq!(expected_tick_size(dec!(0.10)), dec!(0.01));
+        assert_eq!(expected_tick_size(dec!(0.05)), dec!(0.001));
+        assert_eq!(expected_tick_size(dec!(0.95)), dec!(0.001));
+    }
+
+    #[test]
+    fn test_generate_quotes_near_extreme() {
+        let params = QuoteParams {
+            midpoint: dec!(0.015),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.001),
+            order_size: dec!(500),
+            num_levels: 2,
+            fee_rate_bps: None,
+            max_incentive_spread: None,
+            min_incentive_size: None,
+            inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: false,
+            size_skew: Decimal::ZERO,
+        };
+        let quotes = generate_quotes(&params);
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].bid_price, dec!(0.005));
+        assert_eq!(quotes[0].ask_price, dec!(0.025));
+        // Level 1 raw bid 0.004 stays above zero on the fine tick
+        assert_eq!(quotes[1].bid_price, dec!(0.004));
+
+        // On the coarse tick the same bid would round to zero and get pinned to 0.01
+        let coarse = generate_quotes(&QuoteParams {
+            tick_size: dec!(0.01),
+            ..params
+        });
+        assert_eq!(coarse[0].bid_price, dec!(0.01));
+        // Level 1 pins to the same bid, which is left out rather than stacked
+        assert_eq!(
+            coarse
+                .iter()
+                .filter(|q| q.bid_price == dec!(0.01) && q.bid_size > Decimal::ZERO)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_extreme_policy_quotes_one_side() {
+        let params = QuoteParams {
+            midpoint: dec!(0.008),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.01),
+            order_size: dec!(100),
+            num_levels: 2,
+            fee_rate_bps: None,
+            max_incentive_spread: None,
+            min_incentive_size: None,
+            inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: true,
+            size_skew: Decimal::ZERO,
+        };
+        // The bid can't go under the midpoint, so two-sided quotes nothing
+        assert!(generate_quotes(&params).is_empty());
+
+        let extreme = dec!(0.05);
+        let sides = extreme_sides(params.midpoint, extreme, ExtremePolicy::OneSided);
+        assert_eq!(sides, Sides::AsksOnly);
+        let quotes = generate_sided_quotes(&params, sides);
+        assert_eq!(quotes.len(), 2);
+        assert!(
+            quotes
+                .iter()
+                .all(|q| q.bid_size.is_zero() && q.ask_size == dec!(100))
+        );
+        assert_eq!(quotes[0].ask_price, dec!(0.02));
+
+        assert_eq!(
+            extreme_sides(dec!(0.97), extreme, ExtremePolicy::OneSided),
+            Sides::BidsOnly
+        );
+        assert_eq!(
+            extreme_sides(dec!(0.97), extreme, ExtremePolicy::Suspend),
+            Sides::None
+        );
+        assert_eq!(
+            extreme_sides(dec!(0.97), extreme, ExtremePolicy::TwoSided),
+            Sides::Both
+        );
+        assert_eq!(
+            extreme_sides(dec!(0.50), extreme, ExtremePolicy::Suspend),
+            Sides::Both
+        );
+        assert!(generate_sided_quotes(&params, Sides::None).is_empty());
+    }
+
     #[test]
     fn test_estimate_score() {
         let score = estimate_score(
@@ -249,6 +949,44 @@ mod tests {
         assert_eq!(score, Decimal::ZERO);
     }
 
+    #[test]
+    fn test_taker_fee_and_rebate() {
+        // taker fee = 1000 * 0.02 * 0.5 * 0.5 = 5.0, maker share 20% = 1.0
+        assert_eq!(taker_fee(dec!(0.50), dec!(1000), dec!(200)), dec!(5.0));
+        assert_eq!(estimate_maker_rebate(dec!(0.50), dec!(1000), dec!(200)), dec!(1.0));
+        assert_eq!(estimate_maker_rebate(dec!(0.50), dec!(1000), Decimal::ZERO), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_quote_capital() {
+        let quote = Quote {
+            bid_price: dec!(0.49),
+            ask_price: dec!(0.51),
+            bid_size: dec!(100),
+            ask_size: dec!(100),
+            level: 0,
+        };
+        // 100 * 0.49 + 100 * 0.49
+        assert_eq!(quote_capital(&quote), dec!(98));
+    }
+
+    #[test]
+    fn test_in_reward_band() {
+        assert!(in_reward_band(dec!(0.50), dec!(0.47), dec!(0.03)));
+        assert!(in_reward_band(dec!(0.50), dec!(0.53), dec!(0.03)));
+        assert!(!in_reward_band(dec!(0.50), dec!(0.46), dec!(0.03)));
+    }
+
+    #[test]
+    fn test_book_side_score_and_share() {
+        let bids = [(dec!(0.49), dec!(1000)), (dec!(0.40), dec!(5000))];
+        // Only the 0.49 bid is inside the 5 cent band: 640
+        let score = book_side_score(dec!(0.50), &bids, Some(dec!(0.05)), None);
+        assert_eq!(score, dec!(640));
+        assert_eq!(reward_share(dec!(160), dec!(640)), dec!(0.2));
+        assert_eq!(reward_share(Decimal::ZERO, Decimal::ZERO), Decimal::ZERO);
+    }
+
     #[test]
     fn test_two_sided_score() {
         // Balanced: both sides score 640
@@ -257,4 +995,51 @@ mod tests {
         // Q_min=100, surplus=540/3=180, total=280
         assert_eq!(two_sided_score(dec!(640), dec!(100)), dec!(280));
     }
+
+    #[test]
+    fn test_jitter_stays_in_bounds_and_band() {
+        let params = QuoteParams {
+            midpoint: dec!(0.50),
+            base_offset_cents: dec!(1.0),
+            min_offset_cents: dec!(0.5),
+            tick_size: dec!(0.01),
+            order_size: dec!(100),
+            num_levels: 3,
+            fee_rate_bps: None,
+            max_incentive_spread: Some(dec!(0.03)),
+            min_incentive_size: Some(dec!(95)),
+            inventory_skew: Decimal::ZERO,
+            shade: Decimal::ZERO,
+            tick_spaced_levels: true,
+            size_skew: Decimal::ZERO,
+        };
+        let fixed = generate_quotes(&params);
+        let jitter = Jitter {
+            size_pct: dec!(10),
+            offset_ticks: 2,
+            seed: 42,
+        };
+        let jittered = |epoch| {
+            let mut quotes = fixed.clone();
+            jitter_quotes(&mut quotes, &params, &jitter, epoch);
+            quotes
+        };
+
+        let mut sizes = std::collections::HashSet::new();
+        for epoch in 0..50 {
+            for (q, base) in jittered(epoch).iter().zip(&fixed) {
+                assert!((dec!(95)..=dec!(110)).contains(&q.bid_size));
+                assert!(q.bid_price <= base.bid_price && q.ask_price >= base.ask_price);
+                assert!(in_reward_band(dec!(0.50), q.bid_price, dec!(0.03)));
+                assert!(in_reward_band(dec!(0.50), q.ask_price, dec!(0.03)));
+                sizes.insert(q.bid_size);
+            }
+        }
+        assert!(sizes.len() > 10);
+        // Same epoch, same ladder: pre-signed orders still match
+        assert_eq!(jittered(7), jittered(7));
+        let mut untouched = fixed.clone();
+        jitter_quotes(&mut untouched, &params, &Jitter::default(), 7);
+        assert_eq!(untouched, fixed);
+    }
 }
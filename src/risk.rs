@@ -1,8 +1,15 @@
-use rust_decimal::Decimal;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, MathematicalOps};
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
-use crate::config::{RiskConfig, StrategyConfig};
+use crate::config::{CorrelationGroup, RiskConfig, StrategyConfig};
+use crate::scanner::MarketInfo;
 
 /// Inventory state for a single market.
 #[derive(Debug, Clone)]
@@ -42,6 +49,109 @@ impl MarketInventory {
     }
 }
 
+/// A block of tokens acquired at one time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lot {
+    pub quantity: Decimal,
+    pub acquired_at: Instant,
+}
+
+/// Held tokens as FIFO lots, so the age of inventory is known. Sales consume
+/// the oldest lots first.
+#[derive(Debug, Clone, Default)]
+pub struct InventoryLots {
+    lots: VecDeque<Lot>,
+}
+
+impl InventoryLots {
+    pub fn total(&self) -> Decimal {
+        self.lots.iter().map(|l| l.quantity).sum()
+    }
+
+    /// Bring the lots in line with the current position: growth becomes a new
+    /// lot stamped `now`, shrinkage consumes the oldest lots.
+    pub fn sync_to(&mut self, quantity: Decimal, now: Instant) {
+        let held = self.total();
+        if quantity > held {
+            self.lots.push_back(Lot {
+                quantity: quantity - held,
+                acquired_at: now,
+            });
+            return;
+        }
+
+        let mut to_remove = held - quantity.max(Decimal::ZERO);
+        while to_remove > Decimal::ZERO {
+            let Some(front) = self.lots.front_mut() else {
+                break;
+            };
+            if front.quantity <= to_remove {
+                to_remove -= front.quantity;
+                self.lots.pop_front();
+            } else {
+                front.quantity -= to_remove;
+                to_remove = Decimal::ZERO;
+            }
+        }
+    }
+
+    /// Age of the oldest lot still held.
+    pub fn oldest_age(&self, now: Instant) -> Option<Duration> {
+        self.lots
+            .front()
+            .map(|l| now.saturating_duration_since(l.acquired_at))
+    }
+
+    /// Quantity held for at least `min_age`.
+    pub fn aged_quantity(&self, now: Instant, min_age: Duration) -> Decimal {
+        self.lots
+            .iter()
+            .filter(|l| now.saturating_duration_since(l.acquired_at) >= min_age)
+            .map(|l| l.quantity)
+            .sum()
+    }
+}
+
+/// Extra skew in [0, 0.5] for inventory older than `max_age`: zero at
+/// `max_age`, ramping linearly to 0.5 at twice `max_age`.
+pub fn age_skew(age: Duration, max_age: Duration) -> Decimal {
+    if max_age.is_zero() || age <= max_age {
+        return Decimal::ZERO;
+    }
+    let over = Decimal::from((age - max_age).as_secs());
+    let max = Decimal::from(max_age.as_secs().max(1));
+    (over / max).min(Decimal::ONE) * dec!(0.5)
+}
+
+/// What a market's inventory cap is scaled to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InventoryCapBasis {
+    /// `inventory_cap` as is, for every market
+    #[default]
+    Fixed,
+    /// A fraction of the market's 24h volume
+    Volume,
+    /// A fraction of the liquidity resting in the market's book
+    Depth,
+}
+
+/// Inventory cap for one market. Under the `volume` or `depth` basis it is
+/// `inventory_cap_fraction` of that figure, counting a share per USDC
+/// (conservative at any price below 1), kept between one order and
+/// `inventory_cap`.
+pub fn scaled_inventory_cap(market: &MarketInfo, strategy: &StrategyConfig) -> Decimal {
+    let base = match strategy.inventory_cap_basis {
+        InventoryCapBasis::Fixed => return strategy.inventory_cap,
+        InventoryCapBasis::Volume => market.volume_24hr,
+        InventoryCapBasis::Depth => market.liquidity,
+    };
+    (base * strategy.inventory_cap_fraction)
+        .max(strategy.order_size)
+        .min(strategy.inventory_cap)
+        .round_dp(0)
+}
+
 /// Risk decision for quoting on a specific side.
 #[derive(Debug, Clone, PartialEq)]
 pub enum QuoteSideDecision {
@@ -119,15 +229,215 @@ pub fn inventory_check(
     (bid_decision, ask_decision)
 }
 
+/// Net YES exposure of each market after netting correlated positions, in
+/// that market's own orientation. `positions` maps condition ID to net YES
+/// (YES - NO) shares. A YES position in one market offsets YES in its
+/// inverse, and adds to YES in a market that moves with it.
+pub fn correlated_exposure(
+    positions: &HashMap<String, Decimal>,
+    groups: &[CorrelationGroup],
+) -> HashMap<String, Decimal> {
+    let mut exposure = positions.clone();
+    for group in groups {
+        let members: Vec<(&String, Decimal)> = group
+            .markets
+            .iter()
+            .map(|id| (id, Decimal::ONE))
+            .chain(group.inverse.iter().map(|id| (id, -Decimal::ONE)))
+            .filter(|(id, _)| positions.contains_key(*id))
+            .collect();
+        let group_net: Decimal = members
+            .iter()
+            .map(|(id, sign)| positions[*id] * sign)
+            .sum();
+        for (id, sign) in members {
+            // Add the rest of the group, seen from this market's side
+            let others = group_net * sign - positions[id];
+            if let Some(e) = exposure.get_mut(id) {
+                *e += others;
+            }
+        }
+    }
+    exposure
+}
+
+/// One-sided 95% z-score.
+const Z_95: Decimal = dec!(1.645);
+
+/// Daily volatility of a midpoint series (in price units), scaling the
+/// average squared change per sample up to one day. Assumes zero drift.
+/// Needs at least three samples.
+pub fn daily_volatility(samples: &[(Instant, Decimal)]) -> Option<Decimal> {
+    if samples.len() < 3 {
+        return None;
+    }
+    let span = samples[samples.len() - 1]
+        .0
+        .saturating_duration_since(samples[0].0);
+    let span_secs = Decimal::from(span.as_millis() as u64) / dec!(1000);
+    if span_secs.is_zero() {
+        return None;
+    }
+
+    let steps = Decimal::from(samples.len() - 1);
+    let sum_sq: Decimal = samples
+        .windows(2)
+        .map(|w| (w[1].1 - w[0].1) * (w[1].1 - w[0].1))
+        .sum();
+    let step_variance = sum_sq / steps;
+    let steps_per_day = dec!(86400) / (span_secs / steps);
+    (step_variance * steps_per_day).sqrt()
+}
+
+/// 1-day 95% VaR of a net YES position, capped at the most it could lose
+/// (the price can't go below 0 or above 1).
+pub fn position_var(net_yes: Decimal, midpoint: Decimal, daily_vol: Decimal) -> Decimal {
+    let var = net_yes.abs() * daily_vol * Z_95;
+    let max_loss = if net_yes >= Decimal::ZERO {
+        net_yes * midpoint
+    } else {
+        -net_yes * (Decimal::ONE - midpoint)
+    };
+    var.min(max_loss)
+}
+
+/// Matured markouts kept for the toxicity average.
+const MAX_MARKOUTS: usize = 20;
+
+/// Adverse selection measured by markouts: how far the midpoint moved
+/// against each fill once `horizon` has passed.
+#[derive(Debug, Clone, Default)]
+pub struct AdverseSelection {
+    /// Unmatured fills as (time, YES-equivalent price, +1 long / -1 short YES)
+    pending: VecDeque<(Instant, Decimal, Decimal)>,
+    /// Adverse move of each matured fill, in cents (0 if it moved our way)
+    markouts: VecDeque<Decimal>,
+}
+
+impl AdverseSelection {
+    /// Track a fill that left us long (`long_yes`) or short YES at
+    /// `yes_price`.
+    pub fn record_fill(&mut self, at: Instant, yes_price: Decimal, long_yes: bool) {
+        let sign = if long_yes {
+            Decimal::ONE
+        } else {
+            -Decimal::ONE
+        };
+        self.pending.push_back((at, yes_price, sign));
+    }
+
+    /// Mark fills at least `horizon` old against `midpoint`.
+    pub fn mature(&mut self, now: Instant, midpoint: Decimal, horizon: Duration) {
+        while let Some(&(at, price, sign)) = self.pending.front() {
+            if now.saturating_duration_since(at) < horizon {
+                break;
+            }
+            self.pending.pop_front();
+            let markout = sign * (midpoint - price) * dec!(100);
+            self.markouts.push_back((-markout).max(Decimal::ZERO));
+            if self.markouts.len() > MAX_MARKOUTS {
+                self.markouts.pop_front();
+            }
+        }
+    }
+
+    /// Mean adverse markout over recent fills, in cents.
+    pub fn toxicity_cents(&self) -> Decimal {
+        if self.markouts.is_empty() {
+            return Decimal::ZERO;
+        }
+        self.markouts.iter().sum::<Decimal>() / Decimal::from(self.markouts.len())
+    }
+}
+
+/// How far (0-1) to shade quotes toward the reward-band edge: zero below
+/// `start_cents` of toxicity, one at `full_cents`, shaped by raising the
+/// linear ramp to `curve` (above 1 stays tight longer, below 1 backs off
+/// sooner). A zero `start_cents` disables shading.
+pub fn shade_intensity(
+    toxicity_cents: Decimal,
+    start_cents: Decimal,
+    full_cents: Decimal,
+    curve: Decimal,
+) -> Decimal {
+    if start_cents <= Decimal::ZERO || toxicity_cents <= start_cents {
+        return Decimal::ZERO;
+    }
+    if toxicity_cents >= full_cents || full_cents <= start_cents {
+        return Decimal::ONE;
+    }
+    let ramp = (toxicity_cents - start_cents) / (full_cents - start_cents);
+    if curve <= Decimal::ZERO {
+        return ramp;
+    }
+    ramp.checked_powd(curve)
+        .unwrap_or(ramp)
+        .clamp(Decimal::ZERO, Decimal::ONE)
+}
+
+/// VaR contribution of one market.
+#[derive(Debug, Clone)]
+pub struct MarketVar {
+    pub market: String,
+    pub net_yes: Decimal,
+    /// None until enough midpoints have been sampled
+    pub daily_vol: Option<Decimal>,
+    pub var: Decimal,
+}
+
+/// Portfolio 1-day 95% VaR and the limit it is checked against.
+#[derive(Debug, Clone)]
+pub struct VarReport {
+    pub markets: Vec<MarketVar>,
+    pub portfolio_var: Decimal,
+    /// Zero when the check is disabled
+    pub limit: Decimal,
+}
+
+impl VarReport {
+    pub fn breached(&self) -> bool {
+        self.limit > Decimal::ZERO && self.portfolio_var > self.limit
+    }
+}
+
+/// Build a VaR report from (market, net YES, midpoint, daily volatility)
+/// rows. Markets are treated as independent, so the portfolio figure is the
+/// root sum of squares of the per-market VaRs.
+pub fn var_report(
+    positions: Vec<(String, Decimal, Decimal, Option<Decimal>)>,
+    risk_config: &RiskConfig,
+) -> VarReport {
+    let markets: Vec<MarketVar> = positions
+        .into_iter()
+        .map(|(market, net_yes, midpoint, daily_vol)| MarketVar {
+            var: daily_vol.map_or(Decimal::ZERO, |vol| position_var(net_yes, midpoint, vol)),
+            market,
+            net_yes,
+            daily_vol,
+        })
+        .collect();
+    let sum_sq: Decimal = markets.iter().map(|m| m.var * m.var).sum();
+    VarReport {
+        portfolio_var: sum_sq.sqrt().unwrap_or(Decimal::ZERO),
+        limit: risk_config.max_total_capital * risk_config.var_limit_fraction,
+        markets,
+    }
+}
+
+/// Sum of unrealized PnL across markets at their midpoints.
+pub fn total_unrealized_pnl(inventories: &[(&str, &MarketInventory, Decimal)]) -> Decimal {
+    inventories
+        .iter()
+        .map(|(_, inv, mid)| inv.unrealized_pnl(*mid))
+        .sum()
+}
+
 /// Check if the kill switch should be triggered based on total losses.
 pub fn should_kill_switch(
     inventories: &[(&str, &MarketInventory, Decimal)], // (market_name, inventory, midpoint)
     risk_config: &RiskConfig,
 ) -> bool {
-    let total_pnl: Decimal = inventories
-        .iter()
-        .map(|(_, inv, mid)| inv.unrealized_pnl(*mid))
-        .sum();
+    let total_pnl = total_unrealized_pnl(inventories);
 
     if total_pnl < -risk_config.kill_switch_loss {
         warn!(
@@ -141,6 +451,92 @@ pub fn should_kill_switch(
     false
 }
 
+/// Why a market stopped quoting and switched to unwinding its inventory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    /// Inventory outlived twice `max_inventory_age_secs`
+    InventoryAge,
+    /// Market fell out of the ranking at rescan while holding inventory
+    Delisted,
+    /// Set to unwind with the `state` command
+    Operator,
+}
+
+/// Per-market unrealized PnL thresholds. Zero disables a threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExitLimits {
+    pub stop_loss: Decimal,
+    pub take_profit: Decimal,
+}
+
+impl ExitLimits {
+    pub fn from_config(risk_config: &RiskConfig) -> Self {
+        Self {
+            stop_loss: risk_config.stop_loss,
+            take_profit: risk_config.take_profit,
+        }
+    }
+
+    /// The exit triggered by a market's PnL, if any.
+    pub fn check(&self, pnl: Decimal) -> Option<ExitReason> {
+        if self.stop_loss > Decimal::ZERO && pnl <= -self.stop_loss {
+            Some(ExitReason::StopLoss)
+        } else if self.take_profit > Decimal::ZERO && pnl >= self.take_profit {
+            Some(ExitReason::TakeProfit)
+        } else {
+            None
+        }
+    }
+}
+
+/// Record of a tripped kill switch. Quoting stays stopped until a human
+/// acknowledges the loss with `resume --ack-loss`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchLatch {
+    pub tripped_at: DateTime<Utc>,
+    pub total_pnl: Decimal,
+    pub threshold: Decimal,
+}
+
+/// Risk state persisted across restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskState {
+    pub kill_switch: Option<KillSwitchLatch>,
+}
+
+impl RiskState {
+    /// Load from disk; a missing file means nothing is latched.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading risk state {}", path.display()))?;
+        serde_json::from_str(&content).context("parsing risk state")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing risk state {}", path.display()))
+    }
+
+    pub fn is_latched(&self) -> bool {
+        self.kill_switch.is_some()
+    }
+
+    pub fn trip(&mut self, total_pnl: Decimal, threshold: Decimal) {
+        self.kill_switch = Some(KillSwitchLatch {
+            tripped_at: Utc::now(),
+            total_pnl,
+            threshold,
+        });
+    }
+}
+
 /// Calculate optimal capital allocation across markets.
 /// Returns fraction of total capital to allocate to each market.
 pub fn allocate_capital(
@@ -179,6 +575,42 @@ pub fn allocate_capital(
         .collect()
 }
 
+/// `allocate_capital` without allocations too small to be worth quoting.
+/// Each market comes with its minimum viable allocation; while any share
+/// falls short of its minimum, the lowest-scoring short market is dropped
+/// and capital is split again among the rest. Dropped markets are left out
+/// of the result.
+pub fn allocate_viable_capital(
+    markets: &[(String, Decimal, Decimal)], // (market_id, reward_score, minimum)
+    total_capital: Decimal,
+    max_per_market: Decimal,
+) -> Vec<(String, Decimal)> {
+    let mut kept: Vec<&(String, Decimal, Decimal)> = markets.iter().collect();
+    loop {
+        let scores: Vec<(String, Decimal)> = kept
+            .iter()
+            .map(|(id, score, _)| (id.clone(), *score))
+            .collect();
+        let allocations = allocate_capital(&scores, total_capital, max_per_market);
+        let short = kept
+            .iter()
+            .zip(&allocations)
+            .filter(|((_, _, min), (_, allocation))| allocation < min)
+            .min_by_key(|((_, score, _), _)| *score)
+            .map(|((id, _, min), (_, allocation))| (id.clone(), *min, *allocation));
+        let Some((id, min, allocation)) = short else {
+            return allocations;
+        };
+        warn!(
+            market = %id,
+            allocation = %allocation,
+            minimum = %min,
+            "Allocation below minimum viable, dropping market"
+        );
+        kept.retain(|(kept_id, _, _)| *kept_id != id);
+    }
+}
+
 /// Determine if holding tokens near resolution is worthwhile.
 /// Near-resolution tokens (>0.90 or <0.10) earn ~4% APY equivalent.
 pub fn holding_reward_factor(midpoint: Decimal, days_to_resolution: Option<u32>) -> Decimal {
@@ -242,6 +674,48 @@ mod tests {
         assert!(matches!(ask, QuoteSideDecision::Adjusted { .. }));
     }
 
+    #[test]
+    fn test_scaled_inventory_cap() {
+        let market = MarketInfo {
+            condition_id: "c".into(),
+            question: "Q".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(20000),
+            volume: dec!(500000),
+            volume_24hr: dec!(40000),
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: None,
+            end_date: None,
+            score: Decimal::ZERO,
+        };
+        let mut config = StrategyConfig {
+            inventory_cap: dec!(5000),
+            inventory_cap_fraction: dec!(0.05),
+            order_size: dec!(100),
+            ..Default::default()
+        };
+        assert_eq!(scaled_inventory_cap(&market, &config), dec!(5000));
+        config.inventory_cap_basis = InventoryCapBasis::Volume;
+        assert_eq!(scaled_inventory_cap(&market, &config), dec!(2000));
+        config.inventory_cap_basis = InventoryCapBasis::Depth;
+        assert_eq!(scaled_inventory_cap(&market, &config), dec!(1000));
+
+        // Never above the configured cap, nor below one order
+        config.inventory_cap_fraction = Decimal::ONE;
+        assert_eq!(scaled_inventory_cap(&market, &config), dec!(5000));
+        let dry = MarketInfo {
+            liquidity: Decimal::ZERO,
+            ..market
+        };
+        assert_eq!(scaled_inventory_cap(&dry, &config), dec!(100));
+    }
+
     #[test]
     fn test_unrealized_pnl() {
         let inv = MarketInventory {
@@ -268,6 +742,23 @@ mod tests {
         assert_eq!(allocations[1].1, dec!(500)); // 25% of 2000
     }
 
+    #[test]
+    fn test_viable_allocation_drops_and_redistributes() {
+        let markets = vec![
+            ("a".to_string(), dec!(100), dec!(100)),
+            ("b".to_string(), dec!(60), dec!(100)),
+            ("c".to_string(), dec!(40), dec!(300)),
+        ];
+        // c's 20% of 1000 is short of its 300; a and b split its share
+        let allocations = allocate_viable_capital(&markets, dec!(1000), dec!(1000));
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(allocations[0], ("a".to_string(), dec!(625)));
+        assert_eq!(allocations[1], ("b".to_string(), dec!(375)));
+
+        // Nothing viable: everything is dropped
+        assert!(allocate_viable_capital(&markets, dec!(50), dec!(1000)).is_empty());
+    }
+
     #[test]
     fn test_holding_reward_factor() {
         // High confidence near resolution
@@ -279,6 +770,155 @@ mod tests {
         assert_eq!(factor, Decimal::ZERO);
     }
 
+    #[test]
+    fn test_risk_state_latch_persists() {
+        let path = std::env::temp_dir().join("polymarket_lp_test_risk_state.json");
+        let _ = std::fs::remove_file(&path);
+        assert!(!RiskState::load(&path).unwrap().is_latched());
+
+        let mut state = RiskState::default();
+        state.trip(dec!(-150), dec!(100));
+        state.save(&path).unwrap();
+
+        let loaded = RiskState::load(&path).unwrap();
+        assert!(loaded.is_latched());
+        assert_eq!(loaded.kill_switch.unwrap().total_pnl, dec!(-150));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_inventory_lots_fifo() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(600);
+        let mut lots = InventoryLots::default();
+        lots.sync_to(dec!(100), start);
+        lots.sync_to(dec!(150), later);
+        assert_eq!(lots.total(), dec!(150));
+
+        // Selling 120 consumes the first lot and 20 of the second
+        lots.sync_to(dec!(30), later);
+        assert_eq!(lots.total(), dec!(30));
+        assert_eq!(lots.oldest_age(later), Some(Duration::ZERO));
+
+        let end = later + Duration::from_secs(60);
+        assert_eq!(lots.aged_quantity(end, Duration::from_secs(60)), dec!(30));
+        assert_eq!(lots.aged_quantity(end, Duration::from_secs(61)), Decimal::ZERO);
+
+        lots.sync_to(Decimal::ZERO, end);
+        assert_eq!(lots.oldest_age(end), None);
+    }
+
+    #[test]
+    fn test_age_skew_ramps() {
+        let max = Duration::from_secs(3600);
+        assert_eq!(age_skew(Duration::from_secs(3600), max), Decimal::ZERO);
+        assert_eq!(age_skew(Duration::from_secs(5400), max), dec!(0.25));
+        assert_eq!(age_skew(Duration::from_secs(7200), max), dec!(0.5));
+        assert_eq!(age_skew(Duration::from_secs(99999), max), dec!(0.5));
+        assert_eq!(age_skew(Duration::from_secs(99999), Duration::ZERO), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_correlated_exposure_nets_inverse() {
+        let positions: HashMap<String, Decimal> = [
+            ("a".to_string(), dec!(100)),
+            ("b".to_string(), dec!(50)),
+            ("c".to_string(), dec!(80)),
+            ("solo".to_string(), dec!(-30)),
+        ]
+        .into();
+        let groups = vec![CorrelationGroup {
+            markets: vec!["a".into(), "b".into()],
+            inverse: vec!["c".into(), "missing".into()],
+        }];
+        let exposure = correlated_exposure(&positions, &groups);
+        // Group net in a/b orientation: 100 + 50 - 80 = 70
+        assert_eq!(exposure["a"], dec!(70));
+        assert_eq!(exposure["b"], dec!(70));
+        assert_eq!(exposure["c"], dec!(-70));
+        assert_eq!(exposure["solo"], dec!(-30));
+        assert!(!exposure.contains_key("missing"));
+    }
+
+    #[test]
+    fn test_daily_volatility() {
+        let start = Instant::now();
+        // Alternating +/-1c moves once a minute -> 1c per step, 1440 steps/day
+        let samples: Vec<(Instant, Decimal)> = (0..11)
+            .map(|i| {
+                let mid = if i % 2 == 0 { dec!(0.50) } else { dec!(0.51) };
+                (start + Duration::from_secs(60 * i), mid)
+            })
+            .collect();
+        let vol = daily_volatility(&samples).unwrap();
+        // 0.01 * sqrt(1440) ~= 0.3795
+        assert!((vol - dec!(0.3795)).abs() < dec!(0.001), "vol = {vol}");
+        assert_eq!(daily_volatility(&samples[..2]), None);
+    }
+
+    #[test]
+    fn test_var_report() {
+        // Capped at the position value when volatility is huge
+        assert_eq!(position_var(dec!(100), dec!(0.2), dec!(1)), dec!(20));
+        assert_eq!(position_var(dec!(-100), dec!(0.2), dec!(1)), dec!(80));
+        assert_eq!(position_var(dec!(1000), dec!(0.5), dec!(0.01)), dec!(16.45));
+
+        let risk = RiskConfig {
+            max_total_capital: dec!(100),
+            var_limit_fraction: dec!(0.1),
+            ..Default::default()
+        };
+        let report = var_report(
+            vec![
+                ("a".into(), dec!(1000), dec!(0.5), Some(dec!(0.01))),
+                ("b".into(), dec!(500), dec!(0.5), None),
+            ],
+            &risk,
+        );
+        assert_eq!(report.portfolio_var, dec!(16.45));
+        assert_eq!(report.limit, dec!(10));
+        assert!(report.breached());
+        assert_eq!(report.markets[1].var, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_exit_limits() {
+        let limits = ExitLimits {
+            stop_loss: dec!(20),
+            take_profit: dec!(50),
+        };
+        assert_eq!(limits.check(dec!(-19.99)), None);
+        assert_eq!(limits.check(dec!(-20)), Some(ExitReason::StopLoss));
+        assert_eq!(limits.check(dec!(49)), None);
+        assert_eq!(limits.check(dec!(50)), Some(ExitReason::TakeProfit));
+        assert_eq!(ExitLimits::default().check(dec!(-1000)), None);
+    }
+
+    #[test]
+    fn test_markout_toxicity_and_shading() {
+        let start = Instant::now();
+        let horizon = Duration::from_secs(60);
+        let mut adverse = AdverseSelection::default();
+        // Bought YES at 0.50 and sold YES at 0.52; the midpoint then drops
+        adverse.record_fill(start, dec!(0.50), true);
+        adverse.record_fill(start, dec!(0.52), false);
+        adverse.mature(start + Duration::from_secs(30), dec!(0.47), horizon);
+        assert_eq!(adverse.toxicity_cents(), Decimal::ZERO);
+        adverse.mature(start + horizon, dec!(0.47), horizon);
+        // Long lost 3c; the short gained, counting as zero
+        assert_eq!(adverse.toxicity_cents(), dec!(1.5));
+
+        let linear = |toxicity| shade_intensity(toxicity, dec!(0.5), dec!(2), dec!(1));
+        assert_eq!(linear(dec!(0.4)), Decimal::ZERO);
+        assert_eq!(linear(dec!(1.25)), dec!(0.5));
+        assert_eq!(linear(dec!(3)), Decimal::ONE);
+        // Convex curve shades later
+        let convex = shade_intensity(dec!(1.25), dec!(0.5), dec!(2), dec!(2));
+        assert_eq!(convex, dec!(0.25));
+        let off = shade_intensity(dec!(3), Decimal::ZERO, dec!(2), dec!(1));
+        assert_eq!(off, Decimal::ZERO);
+    }
+
     #[test]
     fn test_kill_switch() {
         let inv = MarketInventory {
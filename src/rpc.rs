@@ -0,0 +1,159 @@
+use anyhow::{bail, Context, Result};
+use polymarket_client_sdk::types::{Address, U256};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+
+/// USDC.e collateral token on Polygon.
+pub const USDC_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+/// Conditional Tokens Framework contract holding the ERC-1155 outcome tokens.
+pub const CTF_ADDRESS: &str = "0x4D97DCd97eC945f40cF65F87097ACe5EA0476045";
+/// USDC.e and outcome tokens both use 6 decimals.
+const TOKEN_DECIMALS: u32 = 6;
+
+/// `balanceOf(address)` selector (ERC-20).
+const ERC20_BALANCE_OF: &str = "70a08231";
+/// `balanceOf(address,uint256)` selector (ERC-1155).
+const ERC1155_BALANCE_OF: &str = "00fdd58e";
+
+/// Minimal Polygon JSON-RPC reader for wallet balances.
+/// On-chain balances are the source of truth; engine inventory is reconciled against them.
+pub struct PolygonRpc {
+    url: String,
+    http: reqwest::Client,
+}
+
+/// On-chain YES/NO holdings for one market.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OnchainPosition {
+    pub yes: Decimal,
+    pub no: Decimal,
+}
+
+impl PolygonRpc {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// USDC.e balance of `owner`.
+    pub async fn usdc_balance(&self, owner: Address) -> Result<Decimal> {
+        let data = format!("0x{ERC20_BALANCE_OF}{}", encode_address(owner));
+        let result = self.eth_call(USDC_ADDRESS, &data).await?;
+        decode_token_amount(&result)
+    }
+
+    /// Conditional token balance of `owner` for a CLOB token ID (decimal string).
+    pub async fn token_balance(&self, owner: Address, token_id: &str) -> Result<Decimal> {
+        let data = format!(
+            "0x{ERC1155_BALANCE_OF}{}{}",
+            encode_address(owner),
+            encode_token_id(token_id)?
+        );
+        let result = self.eth_call(CTF_ADDRESS, &data).await?;
+        decode_token_amount(&result)
+    }
+
+    /// YES and NO balances for a market's token pair.
+    pub async fn position(
+        &self,
+        owner: Address,
+        token_yes_id: &str,
+        token_no_id: &str,
+    ) -> Result<OnchainPosition> {
+        Ok(OnchainPosition {
+            yes: self.token_balance(owner, token_yes_id).await?,
+            no: self.token_balance(owner, token_no_id).await?,
+        })
+    }
+
+    /// Current gas price in gwei.
+    pub async fn gas_price_gwei(&self) -> Result<Decimal> {
+        let result = self.request("eth_gasPrice", json!([])).await?;
+        let wei = decode_uint(&result)?;
+        Ok(Decimal::from_i128_with_scale(wei as i128, 9))
+    }
+
+    async fn eth_call(&self, to: &str, data: &str) -> Result<String> {
+        self.request("eth_call", json!([{ "to": to, "data": data }, "latest"])).await
+    }
+
+    async fn request(&self, method: &str, params: serde_json::Value) -> Result<String> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let resp: serde_json::Value = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("sending {method}"))?
+            .json()
+            .await
+            .with_context(|| format!("parsing {method} response"))?;
+
+        if let Some(err) = resp.get("error") {
+            bail!("{method} failed: {err}");
+        }
+        resp.get("result")
+            .and_then(|r| r.as_str())
+            .map(|s| s.to_string())
+            .with_context(|| format!("{method} response missing result"))
+    }
+}
+
+/// ABI-encode an address as a 32-byte word (hex, no prefix).
+fn encode_address(addr: Address) -> String {
+    let hex = addr.to_string();
+    format!("{:0>64}", hex.trim_start_matches("0x").to_lowercase())
+}
+
+/// ABI-encode a decimal token ID as a 32-byte word (hex, no prefix).
+fn encode_token_id(token_id: &str) -> Result<String> {
+    let id = U256::from_str(token_id).context("parsing token ID")?;
+    Ok(format!("{:064x}", id))
+}
+
+/// Decode a hex quantity or uint256 word.
+fn decode_uint(hex: &str) -> Result<u128> {
+    let digits = hex.trim_start_matches("0x").trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u128::from_str_radix(digits, 16).context("value exceeds u128")
+}
+
+/// Decode a uint256 return value into a 6-decimal token amount.
+pub fn decode_token_amount(hex: &str) -> Result<Decimal> {
+    let raw = decode_uint(hex)?;
+    Ok(Decimal::from_i128_with_scale(raw as i128, TOKEN_DECIMALS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_decode_token_amount() {
+        // 1_500_000 raw units = 1.5 tokens
+        let word = format!("0x{:064x}", 1_500_000u64);
+        assert_eq!(decode_token_amount(&word).unwrap(), dec!(1.5));
+        assert_eq!(decode_token_amount("0x").unwrap(), Decimal::ZERO);
+        assert_eq!(decode_token_amount(&format!("0x{}", "0".repeat(64))).unwrap(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_encode_token_id() {
+        let encoded = encode_token_id("255").unwrap();
+        assert_eq!(encoded.len(), 64);
+        assert!(encoded.ends_with("ff"));
+    }
+}
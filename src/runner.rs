@@ -0,0 +1,548 @@
+use anyhow::{Result, bail};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::signal;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::alerts::{Alert, AlertRule, Alerter, Severity};
+use crate::clock::SkewMonitor;
+use crate::config::{self, Config};
+use crate::db;
+use crate::engine::{self, QuoteEngine};
+use crate::exchange::{ExchangeApi, MarketData};
+use crate::metrics;
+use crate::orders::RetainedOrders;
+use crate::prometheus;
+use crate::signals::{Control, Controls};
+use crate::supervisor::{self, RestartPolicy, Supervisor, TaskFailure};
+use crate::ws::{WsEvent, WsManager};
+
+/// How often the loop checks whether GTD orders are due for replacement.
+const DEADMAN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a single-market run does with the engine's quotes: place them on
+/// the exchange, or only log them.
+pub trait OrderExecutor: Send {
+    /// REST poll: fetch the midpoint and quote on it if due.
+    fn tick(&mut self, engine: &mut QuoteEngine) -> impl Future<Output = Result<()>> + Send;
+
+    /// Quote around `midpoint` after a WS event or an expiry refresh.
+    fn requote(
+        &mut self,
+        engine: &mut QuoteEngine,
+        midpoint: Decimal,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn set_paused(
+        &mut self,
+        engine: &mut QuoteEngine,
+        paused: bool,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Use the idle time after an update, e.g. to presign the next ladder.
+    fn after_update(&mut self, _engine: &QuoteEngine) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Periodic upkeep such as persisting metrics and checking the clock.
+    fn housekeeping(&mut self, _engine: &mut QuoteEngine) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Wind down at the end of the run, leaving orders resting for the
+    /// next run if `keep_orders`.
+    fn stop(
+        &mut self,
+        _engine: &mut QuoteEngine,
+        _keep_orders: bool,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+/// Places real orders and keeps the metrics file and clock check current.
+pub struct LiveExecutor<'a, X> {
+    exchange: &'a X,
+    config: &'a Config,
+    skew_monitor: SkewMonitor,
+    alerter: Alerter,
+}
+
+impl<'a, X: ExchangeApi> LiveExecutor<'a, X> {
+    pub fn new(exchange: &'a X, config: &'a Config) -> Self {
+        Self {
+            exchange,
+            config,
+            skew_monitor: SkewMonitor::new(
+                config.monitoring.clock_skew_check_secs,
+                config.monitoring.max_clock_skew_ms,
+            ),
+            alerter: Alerter::new(config.alerts.clone()),
+        }
+    }
+
+    /// Run a server-time check if one is due, alerting on drift.
+    pub async fn check_clock_skew(&mut self) {
+        let now = Instant::now();
+        if !self.skew_monitor.due(now) {
+            return;
+        }
+        let alert = self.skew_monitor.check(now, self.exchange).await;
+        self.exchange.set_clock_skew(self.skew_monitor.skew);
+        if let Some((severity, message)) = alert {
+            let alert = Alert::new(AlertRule::ClockSkew, severity, message);
+            self.alerter.send(&self.config.monitoring, alert).await;
+        }
+    }
+}
+
+impl<X: ExchangeApi> OrderExecutor for LiveExecutor<'_, X> {
+    async fn tick(&mut self, engine: &mut QuoteEngine) -> Result<()> {
+        engine.tick_live(self.exchange).await
+    }
+
+    async fn requote(&mut self, engine: &mut QuoteEngine, midpoint: Decimal) -> Result<()> {
+        engine.requote(self.exchange, midpoint).await
+    }
+
+    async fn set_paused(&mut self, engine: &mut QuoteEngine, paused: bool) -> Result<()> {
+        engine.set_paused(self.exchange, paused).await
+    }
+
+    async fn after_update(&mut self, engine: &QuoteEngine) {
+        engine.presign_next(self.exchange).await;
+    }
+
+    async fn housekeeping(&mut self, engine: &mut QuoteEngine) {
+        save_engine_metrics(engine, self.config).await;
+        self.check_clock_skew().await;
+    }
+
+    async fn stop(&mut self, engine: &mut QuoteEngine, keep_orders: bool) {
+        stop_quoting(engine, self.exchange, keep_orders, self.config).await;
+        save_engine_metrics(engine, self.config).await;
+    }
+}
+
+/// Logs the quotes a live run would place; needs only market data.
+pub struct DryRunExecutor<'a, M> {
+    data: &'a M,
+}
+
+impl<'a, M: MarketData> DryRunExecutor<'a, M> {
+    pub fn new(data: &'a M) -> Self {
+        Self { data }
+    }
+}
+
+impl<M: MarketData> OrderExecutor for DryRunExecutor<'_, M> {
+    async fn tick(&mut self, engine: &mut QuoteEngine) -> Result<()> {
+        if engine.paused {
+            return Ok(());
+        }
+        engine.tick_dry_run(self.data).await
+    }
+
+    async fn requote(&mut self, engine: &mut QuoteEngine, midpoint: Decimal) -> Result<()> {
+        engine.sync_tick_size(self.data, midpoint).await;
+        engine.requote_dry_run(midpoint);
+        Ok(())
+    }
+
+    async fn set_paused(&mut self, engine: &mut QuoteEngine, paused: bool) -> Result<()> {
+        engine.paused = paused;
+        Ok(())
+    }
+}
+
+/// The event loop of a single-market run: reacts to WS events while the
+/// WebSocket anchors the midpoint and polls over REST otherwise, alongside
+/// control signals, task supervision, and periodic upkeep. Live and dry
+/// runs differ only in their `OrderExecutor`.
+pub struct Runner<'a, E> {
+    engine: QuoteEngine,
+    executor: E,
+    config: &'a Config,
+    config_path: &'a Path,
+    keep_orders: bool,
+    ws: Option<WsManager>,
+    ws_rx: Option<mpsc::Receiver<WsEvent>>,
+    watchdog: Supervisor,
+    alerter: Alerter,
+}
+
+impl<'a, E: OrderExecutor> Runner<'a, E> {
+    pub fn new(
+        engine: QuoteEngine,
+        executor: E,
+        config: &'a Config,
+        config_path: &'a Path,
+    ) -> Self {
+        Self {
+            engine,
+            executor,
+            config,
+            config_path,
+            keep_orders: false,
+            ws: None,
+            ws_rx: None,
+            watchdog: Supervisor::new(RestartPolicy::from_config(&config.monitoring)),
+            alerter: Alerter::new(config.alerts.clone()),
+        }
+    }
+
+    /// Take events from a started WebSocket.
+    pub fn with_ws(mut self, ws: WsManager, rx: mpsc::Receiver<WsEvent>) -> Self {
+        self.engine.ws_connected = true;
+        self.ws = Some(ws);
+        self.ws_rx = Some(rx);
+        self
+    }
+
+    /// Supervise the background tasks spawned under `watchdog`.
+    pub fn with_watchdog(mut self, watchdog: Supervisor) -> Self {
+        self.watchdog = watchdog;
+        self
+    }
+
+    /// Leave orders resting at a clean shutdown for the next run to adopt.
+    pub fn with_keep_orders(mut self, keep_orders: bool) -> Self {
+        self.keep_orders = keep_orders;
+        self
+    }
+
+    /// Quote until Ctrl+C, or until a critical background task can't be
+    /// revived, in which case every order is cancelled and an error returned.
+    pub async fn run(mut self) -> Result<()> {
+        let mut controls = Controls::install()?;
+        let save_every = Duration::from_secs(self.config.monitoring.metrics_save_secs.max(1));
+        let mut metrics_timer = tokio::time::interval(save_every);
+        let mut watchdog_timer = tokio::time::interval(supervisor::CHECK_INTERVAL);
+        let mut deadman_timer = tokio::time::interval(DEADMAN_CHECK_INTERVAL);
+        let prune_secs = self.config.retention.prune_interval_secs;
+        let mut prune_timer = tokio::time::interval(Duration::from_secs(prune_secs.max(1)));
+        // Kept across iterations: a sleep rebuilt every pass would restart
+        // whenever a faster timer fires and the REST tick would never come due
+        let next_poll = tokio::time::sleep(self.engine.poll_interval());
+        tokio::pin!(next_poll);
+        // One listener for the whole loop: a Ctrl+C landing while a branch
+        // is running, with no fresh one waiting, would be dropped
+        let ctrl_c = signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        let failure = loop {
+            tokio::select! {
+                _ = &mut ctrl_c => {
+                    info!("Shutdown signal received");
+                    break None;
+                }
+                _ = watchdog_timer.tick() => {
+                    if let Some(failure) = supervise(&mut self.watchdog, &mut self.alerter, self.config).await {
+                        break Some(failure);
+                    }
+                }
+                // GTD orders need replacing before they lapse, even when
+                // no WS event would trigger a requote
+                _ = deadman_timer.tick(), if self.engine.deadman_refresh_due() => {
+                    if let Some(mid) = self.engine.last_midpoint
+                        && let Err(e) = self.executor.requote(&mut self.engine, mid).await
+                    {
+                        warn!(error = %e, "Failed to refresh expiring orders");
+                    }
+                }
+                _ = prune_timer.tick(), if prune_secs > 0 && self.engine.database.is_some() => {
+                    if let Some(database) = &self.engine.database {
+                        db::prune_in_background(database.clone(), self.config.retention.clone()).await;
+                    }
+                }
+                // Controls are only taken between updates, never mid-requote
+                control = controls.recv() => self.apply_control(control).await,
+                Some(event) = next_ws_event(&mut self.ws_rx) => {
+                    if self.engine.handle_ws_event(event) {
+                        if let Some(mid) = self.engine.last_midpoint
+                            && !self.engine.halted
+                            && !self.engine.paused
+                            && let Err(e) = self.executor.requote(&mut self.engine, mid).await
+                        {
+                            warn!(error = %e, "Failed to requote");
+                        }
+                        self.executor.after_update(&self.engine).await;
+                    }
+                    send_engine_alerts(&mut self.engine, &mut self.alerter, self.config).await;
+                }
+                // REST tick when WS isn't anchoring the midpoint
+                _ = &mut next_poll, if !self.engine.ws_anchored() => {
+                    if let Err(e) = self.executor.tick(&mut self.engine).await {
+                        warn!(error = %e, "Engine tick error");
+                    }
+                    let due = tokio::time::Instant::now() + self.engine.poll_interval();
+                    next_poll.as_mut().reset(due);
+                    self.executor.after_update(&self.engine).await;
+                    send_engine_alerts(&mut self.engine, &mut self.alerter, self.config).await;
+                }
+                // WS mode has no REST ticks, so sample uptime here
+                _ = metrics_timer.tick() => {
+                    if self.engine.ws_connected {
+                        self.engine.record_metrics_tick();
+                    }
+                    self.executor.housekeeping(&mut self.engine).await;
+                }
+            }
+        };
+
+        if let Some(ws) = &self.ws {
+            ws.shutdown();
+        }
+        let keep_orders = self.keep_orders && failure.is_none();
+        self.executor.stop(&mut self.engine, keep_orders).await;
+        match failure {
+            Some(failure) if self.engine.dry_run => bail!("{failure}"),
+            Some(failure) => bail!("{failure}; stopped with all orders cancelled"),
+            None => Ok(()),
+        }
+    }
+
+    /// Act on a runtime control signal.
+    async fn apply_control(&mut self, control: Control) {
+        match control {
+            Control::TogglePause => {
+                let paused = !self.engine.paused;
+                match self.executor.set_paused(&mut self.engine, paused).await {
+                    Ok(()) => info!(
+                        paused,
+                        "Quoting {}",
+                        if paused { "paused" } else { "resumed" }
+                    ),
+                    Err(e) => warn!(error = %e, "Failed to cancel orders on pause"),
+                }
+            }
+            Control::DumpStatus => {
+                info!(
+                    "Status snapshot\n{}",
+                    engine::status_snapshot([&self.engine])
+                );
+                self.executor.housekeeping(&mut self.engine).await;
+            }
+            Control::Reload => match config::Config::load(self.config_path) {
+                Ok(reloaded) => {
+                    let mut strategy = reloaded.strategy;
+                    reloaded
+                        .markets
+                        .apply_overrides(&self.engine.market.condition_id, &mut strategy);
+                    self.engine.reconfigure(strategy, &reloaded.risk);
+                    self.engine.capital_limit = Some(
+                        reloaded
+                            .risk
+                            .max_per_market
+                            .min(reloaded.risk.max_total_capital),
+                    );
+                    info!("Config reloaded");
+                }
+                Err(e) => warn!(error = %e, "Config reload failed, keeping current settings"),
+            },
+        }
+    }
+}
+
+/// Next WS event, or never when no WebSocket is running.
+pub async fn next_ws_event(rx: &mut Option<mpsc::Receiver<WsEvent>>) -> Option<WsEvent> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Restart background tasks that died, alerting on any given up on.
+/// Returns the first critical one, which calls for a safe stop.
+pub async fn supervise(
+    watchdog: &mut Supervisor,
+    alerter: &mut Alerter,
+    config: &Config,
+) -> Option<TaskFailure> {
+    let mut critical = None;
+    for failure in watchdog.check(Instant::now()) {
+        let severity = if failure.critical {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        };
+        let alert = Alert::new(AlertRule::TaskFailure, severity, failure.to_string());
+        alerter.send(&config.monitoring, alert).await;
+        if failure.critical && critical.is_none() {
+            critical = Some(failure);
+        }
+    }
+    critical
+}
+
+/// Write the orders left resting at shutdown. False if they couldn't be
+/// recorded and should be cancelled instead.
+pub fn keep_retained(retained: &RetainedOrders, config: &Config) -> bool {
+    let path = Path::new(&config.monitoring.retained_orders_file);
+    match retained.save(path) {
+        Ok(()) => {
+            info!(
+                orders = retained.active_ids().len(),
+                file = %path.display(),
+                "Leaving orders resting for the next run"
+            );
+            true
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to record retained orders, cancelling instead");
+            false
+        }
+    }
+}
+
+/// At shutdown, record the engine's orders for the next run to adopt when
+/// `keep_orders` is set, else cancel them. Cancels anyway if the record
+/// can't be written, so no order is left untracked.
+async fn stop_quoting(
+    engine: &mut QuoteEngine,
+    exchange: &impl ExchangeApi,
+    keep_orders: bool,
+    config: &Config,
+) {
+    if keep_orders {
+        let mut retained = RetainedOrders {
+            saved_at: Some(chrono::Utc::now()),
+            ..Default::default()
+        };
+        retained
+            .markets
+            .insert(engine.market.condition_id.clone(), engine.retained_orders());
+        if keep_retained(&retained, config) {
+            return;
+        }
+    }
+    info!("Cancelling all orders...");
+    if let Err(e) = engine.cancel_all(exchange).await {
+        warn!(error = %e, "Error cancelling orders during shutdown");
+    }
+}
+
+/// Alert on order-flow anomalies and, if enabled, on fills whose markout
+/// has matured.
+async fn send_engine_alerts(engine: &mut QuoteEngine, alerter: &mut Alerter, config: &Config) {
+    if let Some(anomaly) = engine.flow_anomaly.take() {
+        let message = format!(
+            "⚠️ Order-flow anomaly on {}: {anomaly}",
+            engine.market.question
+        );
+        let alert = Alert::new(AlertRule::FlowAnomaly, Severity::Warning, message);
+        alerter.send(&config.monitoring, alert).await;
+    }
+    let notices = engine.fills.take_ready();
+    if !config.monitoring.fill_alerts {
+        return;
+    }
+    for notice in notices {
+        alerter.send(&config.monitoring, notice.alert()).await;
+    }
+}
+
+/// Write a single-market engine's metrics, sending the daily summary and
+/// starting a new day's counters when the metrics file rotates.
+async fn save_engine_metrics(engine: &mut QuoteEngine, config: &Config) {
+    let path = PathBuf::from(&config.monitoring.metrics_file);
+    let retention = config.monitoring.metrics_retention_days;
+    if let Some(textfile) = &config.monitoring.prometheus_textfile
+        && let Err(e) = prometheus::write_textfile(Path::new(textfile), &[engine.final_metrics()])
+    {
+        warn!(error = %e, "Failed to write Prometheus textfile");
+    }
+    for _ in 0..2 {
+        match metrics::autosave(path.clone(), vec![engine.final_metrics()], retention).await {
+            Ok(Some(finished)) => {
+                if config.monitoring.daily_summary {
+                    let estimates = HashMap::from([(
+                        engine.market.condition_id.clone(),
+                        engine.market.reward_daily_estimate,
+                    )]);
+                    let mut summary = metrics::format_daily_summary(&finished, &estimates, &[]);
+                    match metrics::performance_report(
+                        &path,
+                        config.monitoring.performance_window_days,
+                    ) {
+                        Ok(Some(report)) => summary.push_str(&format!("\n{report}")),
+                        Ok(None) => {}
+                        Err(e) => warn!(error = %e, "Failed to compute performance statistics"),
+                    }
+                    if let Err(e) = metrics::send_telegram_alert(
+                        &config.monitoring.telegram_bot_token,
+                        &config.monitoring.telegram_chat_id,
+                        &summary,
+                    )
+                    .await
+                    {
+                        warn!(error = %e, "Failed to send daily summary");
+                    }
+                }
+                engine.metrics.roll_day();
+            }
+            Ok(None) => return,
+            Err(e) => {
+                warn!(error = %e, "Failed to save metrics");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StrategyConfig;
+    use crate::exchange::mock::MockExchange;
+    use crate::scanner::MarketInfo;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_runner_stops_when_critical_task_dies() {
+        let market = MarketInfo {
+            condition_id: "cond".into(),
+            question: "Test market".into(),
+            token_yes_id: "1".into(),
+            token_no_id: "2".into(),
+            active: true,
+            closed: false,
+            liquidity: dec!(1000),
+            volume: dec!(10000),
+            volume_24hr: Decimal::ZERO,
+            reward_daily_estimate: dec!(10),
+            fee_rate_bps: None,
+            tick_size: "0.01".into(),
+            rewards_min_size: None,
+            rewards_max_spread: Some(dec!(0.03)),
+            end_date: None,
+            score: dec!(100),
+        };
+        let engine = QuoteEngine::new(market, StrategyConfig::default(), true);
+        let exchange = MockExchange::new().with_midpoint("1", dec!(0.50));
+        let config = Config::default();
+        let mut watchdog = Supervisor::new(RestartPolicy {
+            max_restarts: 0,
+            backoff: Duration::ZERO,
+        });
+        watchdog.spawn("market_ws", true, || async {});
+
+        let result = Runner::new(
+            engine,
+            DryRunExecutor::new(&exchange),
+            &config,
+            Path::new("config.toml"),
+        )
+        .with_watchdog(watchdog)
+        .run();
+        let error = tokio::time::timeout(Duration::from_secs(5), result)
+            .await
+            .expect("runner should stop on its own")
+            .unwrap_err();
+        assert!(error.to_string().contains("market_ws kept failing"));
+    }
+}
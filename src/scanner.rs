@@ -1,9 +1,17 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use polymarket_client_sdk::gamma;
 use polymarket_client_sdk::gamma::types::request::MarketsRequest;
+use polymarket_client_sdk::gamma::types::response::Market;
+use polymarket_client_sdk::types::B256;
 use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use tracing::info;
 
+use crate::client::Timed;
+
 /// Processed market info relevant for LP decisions.
 #[derive(Debug, Clone)]
 pub struct MarketInfo {
@@ -15,17 +23,70 @@ pub struct MarketInfo {
     pub closed: bool,
     pub liquidity: Decimal,
     pub volume: Decimal,
+    /// Traded over the last 24 hours, in USDC
+    pub volume_24hr: Decimal,
     pub reward_daily_estimate: Decimal,
     pub fee_rate_bps: Option<i32>,
     pub tick_size: String,
     pub rewards_min_size: Option<Decimal>,
     pub rewards_max_spread: Option<Decimal>,
-    /// Higher = better opportunity (reward / existing liquidity)
+    /// When the market is scheduled to end, if Gamma says
+    pub end_date: Option<DateTime<Utc>>,
+    /// Higher = better opportunity (reward / existing liquidity, unless
+    /// `markets.score_weights` sets a formula)
     pub score: Decimal,
 }
 
-/// Fetch all active markets from Gamma API and extract LP-relevant info.
-pub async fn scan_markets(gamma_client: &gamma::Client) -> Result<Vec<MarketInfo>> {
+impl MarketInfo {
+    /// Fee-enabled markets charge takers and pay makers a rebate on fills.
+    pub fn is_fee_enabled(&self) -> bool {
+        self.fee_rate_bps.is_some_and(|bps| bps > 0)
+    }
+}
+
+/// A user-defined market score: each weight times its field, summed.
+/// Replaces the reward/liquidity ratio for ranking when configured.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    /// Per $ of estimated daily reward
+    pub reward: Decimal,
+    /// Per $ of existing liquidity (usually negative)
+    pub liquidity: Decimal,
+    /// Per $ of traded volume
+    pub volume: Decimal,
+    /// Per cent of reward band (max incentive spread)
+    pub spread: Decimal,
+    /// Per day until the market's end date (0 days when unknown)
+    pub days_to_end: Decimal,
+    /// Per unit of the built-in reward/liquidity score
+    pub reward_per_liquidity: Decimal,
+}
+
+impl ScoreWeights {
+    pub fn score(&self, market: &MarketInfo, now: DateTime<Utc>) -> Decimal {
+        let days_to_end = market.end_date.map_or(Decimal::ZERO, |end| {
+            Decimal::from((end - now).num_seconds().max(0)) / dec!(86400)
+        });
+        let spread_cents = market.rewards_max_spread.unwrap_or_default() * dec!(100);
+        self.reward * market.reward_daily_estimate
+            + self.liquidity * market.liquidity
+            + self.volume * market.volume
+            + self.spread * spread_cents
+            + self.days_to_end * days_to_end
+            + self.reward_per_liquidity * reward_liquidity_score(market)
+    }
+}
+
+/// Score multiplier applied to fee-enabled markets when `prefer_fee_enabled` is set.
+const FEE_ENABLED_SCORE_BOOST: Decimal = dec!(1.5);
+
+/// Fetch all active markets from Gamma API and extract LP-relevant info,
+/// scored by `weights` when given.
+pub async fn scan_markets(
+    gamma_client: &Timed<gamma::Client>,
+    weights: Option<&ScoreWeights>,
+) -> Result<Vec<MarketInfo>> {
     info!("Scanning active markets via Gamma API...");
 
     let request = MarketsRequest::builder()
@@ -34,95 +95,190 @@ pub async fn scan_markets(gamma_client: &gamma::Client) -> Result<Vec<MarketInfo
         .build();
 
     let markets = gamma_client
-        .markets(&request)
+        .send(async { Ok(gamma_client.client().markets(&request).await?) })
         .await
         .context("fetching markets from Gamma API")?;
 
     info!(count = markets.len(), "Fetched markets from Gamma");
 
-    let mut results = Vec::new();
-    for market in &markets {
-        let condition_id = match &market.condition_id {
-            Some(id) => id.to_string(),
-            None => continue,
-        };
+    let mut results: Vec<MarketInfo> = markets.iter().filter_map(to_market_info).collect();
+    if let Some(weights) = weights {
+        let now = Utc::now();
+        for m in &mut results {
+            m.score = weights.score(m, now);
+        }
+    }
 
-        let question = market
-            .question
-            .clone()
-            .unwrap_or_else(|| "Unknown".into());
+    // Sort by score descending
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
 
-        let active = market.active.unwrap_or(false);
-        let closed = market.closed.unwrap_or(true);
-        if !active || closed {
-            continue;
-        }
+    info!(eligible = results.len(), "Market scan complete");
 
-        // Extract token IDs
-        let tokens = match &market.clob_token_ids {
-            Some(ids) if ids.len() >= 2 => ids.clone(),
-            _ => continue,
-        };
+    Ok(results)
+}
 
-        let liquidity = market.liquidity.unwrap_or(Decimal::ZERO);
-        let volume = market.volume.unwrap_or(Decimal::ZERO);
+/// Extract LP-relevant info from a Gamma market. Returns None for inactive,
+/// closed, or malformed markets.
+fn to_market_info(market: &Market) -> Option<MarketInfo> {
+    let condition_id = market.condition_id.as_ref()?.to_string();
 
-        // Use competitive field as a proxy for reward attractiveness
-        let reward_daily = market.competitive.unwrap_or(Decimal::ZERO);
+    let question = market
+        .question
+        .clone()
+        .unwrap_or_else(|| "Unknown".into());
 
-        let tick_size = market
-            .order_price_min_tick_size
-            .map(|d| d.to_string())
-            .unwrap_or_else(|| "0.01".into());
+    let active = market.active.unwrap_or(false);
+    let closed = market.closed.unwrap_or(true);
+    if !active || closed {
+        return None;
+    }
 
-        let rewards_min_size = market.rewards_min_size;
-        let rewards_max_spread = market.rewards_max_spread;
+    // Extract token IDs
+    let tokens = match &market.clob_token_ids {
+        Some(ids) if ids.len() >= 2 => ids.clone(),
+        _ => return None,
+    };
 
-        let fee_rate_bps = market.taker_base_fee;
+    let liquidity = market.liquidity.unwrap_or(Decimal::ZERO);
+    let volume = market.volume.unwrap_or(Decimal::ZERO);
+    let volume_24hr = market.volume_24hr.unwrap_or(Decimal::ZERO);
 
-        // Score: reward / liquidity ratio (higher = less competition per reward dollar)
-        let score = if liquidity > Decimal::ZERO {
-            reward_daily / liquidity * Decimal::new(10000, 0)
-        } else if reward_daily > Decimal::ZERO {
-            Decimal::new(99999, 0)
-        } else {
-            Decimal::ZERO
-        };
+    // Use competitive field as a proxy for reward attractiveness
+    let reward_daily = market.competitive.unwrap_or(Decimal::ZERO);
 
-        results.push(MarketInfo {
-            condition_id,
-            question,
-            token_yes_id: tokens[0].to_string(),
-            token_no_id: tokens[1].to_string(),
-            active,
-            closed,
-            liquidity,
-            volume,
-            reward_daily_estimate: reward_daily,
-            fee_rate_bps,
-            tick_size,
-            rewards_min_size,
-            rewards_max_spread,
-            score,
-        });
+    let tick_size = market
+        .order_price_min_tick_size
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| "0.01".into());
+
+    let rewards_min_size = market.rewards_min_size;
+    let rewards_max_spread = market.rewards_max_spread;
+
+    let fee_rate_bps = market.taker_base_fee;
+
+    let mut info = MarketInfo {
+        condition_id,
+        question,
+        token_yes_id: tokens[0].to_string(),
+        token_no_id: tokens[1].to_string(),
+        active,
+        closed,
+        liquidity,
+        volume,
+        volume_24hr,
+        reward_daily_estimate: reward_daily,
+        fee_rate_bps,
+        tick_size,
+        rewards_min_size,
+        rewards_max_spread,
+        end_date: market.end_date,
+        score: Decimal::ZERO,
+    };
+    info.score = reward_liquidity_score(&info);
+    Some(info)
+}
+
+/// Default score: reward / liquidity ratio (higher = less competition per
+/// reward dollar).
+fn reward_liquidity_score(market: &MarketInfo) -> Decimal {
+    if market.liquidity > Decimal::ZERO {
+        market.reward_daily_estimate / market.liquidity * Decimal::new(10000, 0)
+    } else if market.reward_daily_estimate > Decimal::ZERO {
+        Decimal::new(99999, 0)
+    } else {
+        Decimal::ZERO
     }
+}
 
-    // Sort by score descending
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+/// Fetch fresh info for specific markets, e.g. to pick up tick size or
+/// reward band changes mid-session.
+pub async fn fetch_markets(
+    gamma_client: &Timed<gamma::Client>,
+    condition_ids: &[String],
+) -> Result<Vec<MarketInfo>> {
+    let ids: Vec<B256> = condition_ids
+        .iter()
+        .filter_map(|id| B256::from_str(id).ok())
+        .collect();
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
 
-    info!(eligible = results.len(), "Market scan complete");
+    let request = MarketsRequest::builder().condition_ids(ids).build();
+    let markets = gamma_client
+        .send(async { Ok(gamma_client.client().markets(&request).await?) })
+        .await
+        .context("fetching market details from Gamma API")?;
 
-    Ok(results)
+    Ok(markets.iter().filter_map(to_market_info).collect())
+}
+
+/// Gamma fields beyond `MarketInfo` that quoting doesn't need but are worth
+/// seeing when inspecting one market.
+#[derive(Debug, Clone)]
+pub struct MarketDetails {
+    pub info: MarketInfo,
+    pub end_date: Option<DateTime<Utc>>,
+    pub maker_fee_bps: Option<i32>,
+    pub order_min_size: Option<Decimal>,
+    pub volume_24hr: Option<Decimal>,
+    pub neg_risk: bool,
+}
+
+/// Fetch everything Gamma has on one market; `None` if it isn't found or
+/// isn't active.
+pub async fn fetch_market_details(
+    gamma_client: &Timed<gamma::Client>,
+    condition_id: &str,
+) -> Result<Option<MarketDetails>> {
+    let id = B256::from_str(condition_id).context("parsing condition ID")?;
+    let request = MarketsRequest::builder().condition_ids(vec![id]).build();
+    let markets = gamma_client
+        .send(async { Ok(gamma_client.client().markets(&request).await?) })
+        .await
+        .context("fetching market details from Gamma API")?;
+
+    Ok(markets.iter().find_map(|market| {
+        Some(MarketDetails {
+            info: to_market_info(market)?,
+            end_date: market.end_date,
+            maker_fee_bps: market.maker_base_fee,
+            order_min_size: market.order_min_size,
+            volume_24hr: market.volume_24hr,
+            neg_risk: market.neg_risk.unwrap_or(false),
+        })
+    }))
 }
 
 /// Rank markets and filter by minimum daily reward threshold.
-pub fn rank_markets(markets: &[MarketInfo], min_daily_reward: Decimal, max_count: usize) -> Vec<MarketInfo> {
-    markets
+/// With `prefer_fee_enabled`, fee-enabled markets get a score boost so they rank
+/// (and receive capital) ahead of comparable fee-free markets.
+pub fn rank_markets(
+    markets: &[MarketInfo],
+    min_daily_reward: Decimal,
+    max_count: usize,
+    prefer_fee_enabled: bool,
+) -> Vec<MarketInfo> {
+    let mut eligible: Vec<MarketInfo> = markets
         .iter()
         .filter(|m| m.reward_daily_estimate >= min_daily_reward)
-        .take(max_count)
         .cloned()
-        .collect()
+        .collect();
+
+    if prefer_fee_enabled {
+        // Rank on the boosted score but keep `score` itself as reported
+        let boosted = |m: &MarketInfo| {
+            if m.is_fee_enabled() {
+                m.score * FEE_ENABLED_SCORE_BOOST
+            } else {
+                m.score
+            }
+        };
+        eligible.sort_by_key(|m| std::cmp::Reverse(boosted(m)));
+    }
+
+    eligible.truncate(max_count);
+    eligible
 }
 
 #[cfg(test)]
@@ -139,7 +295,7 @@ mod tests {
         // Pre-sort by score descending (as scan_markets does)
         let mut markets = markets;
         markets.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-        let ranked = rank_markets(&markets, Decimal::new(5, 0), 10);
+        let ranked = rank_markets(&markets, Decimal::new(5, 0), 10, false);
         assert_eq!(ranked.len(), 2); // A=10, C=20 pass; B=2 fails
         assert_eq!(ranked[0].question, "C"); // C has higher score (200 vs 100)
     }
@@ -151,10 +307,51 @@ mod tests {
             make_test_market("B", Decimal::new(50, 0), Decimal::new(1000, 0)),
             make_test_market("C", Decimal::new(30, 0), Decimal::new(1000, 0)),
         ];
-        let ranked = rank_markets(&markets, Decimal::ZERO, 2);
+        let ranked = rank_markets(&markets, Decimal::ZERO, 2, false);
         assert_eq!(ranked.len(), 2);
     }
 
+    #[test]
+    fn test_rank_markets_prefers_fee_enabled() {
+        let mut fee_market = make_test_market("Fee", Decimal::new(80, 0), Decimal::new(1000, 0));
+        fee_market.fee_rate_bps = Some(200);
+        let markets = vec![
+            make_test_market("Plain", Decimal::new(100, 0), Decimal::new(1000, 0)),
+            fee_market,
+        ];
+        let ranked = rank_markets(&markets, Decimal::ZERO, 10, false);
+        assert_eq!(ranked[0].question, "Plain");
+
+        // 800 * 1.5 = 1200 beats 1000
+        let ranked = rank_markets(&markets, Decimal::ZERO, 10, true);
+        assert_eq!(ranked[0].question, "Fee");
+        assert_eq!(ranked[0].score, Decimal::new(800, 0));
+    }
+
+    #[test]
+    fn test_score_weights_formula() {
+        let now = Utc::now();
+        let mut market = make_test_market("A", dec!(50), dec!(10000));
+        market.rewards_max_spread = Some(dec!(0.03));
+        market.end_date = Some(now + chrono::Duration::days(30));
+        let weights = ScoreWeights {
+            reward: dec!(2),
+            liquidity: dec!(-0.001),
+            spread: dec!(1),
+            days_to_end: dec!(0.5),
+            ..ScoreWeights::default()
+        };
+        // 100 - 10 + 3 + 15
+        assert_eq!(weights.score(&market, now), dec!(108));
+
+        // The built-in ratio is available as a term
+        let ratio = ScoreWeights {
+            reward_per_liquidity: Decimal::ONE,
+            ..ScoreWeights::default()
+        };
+        assert_eq!(ratio.score(&market, now), market.score);
+    }
+
     fn make_test_market(question: &str, reward: Decimal, liquidity: Decimal) -> MarketInfo {
         let score = if liquidity > Decimal::ZERO {
             reward / liquidity * Decimal::new(10000, 0)
@@ -170,11 +367,13 @@ mod tests {
             closed: false,
             liquidity,
             volume: Decimal::new(10000, 0),
+            volume_24hr: Decimal::ZERO,
             reward_daily_estimate: reward,
             fee_rate_bps: None,
             tick_size: "0.01".into(),
             rewards_min_size: None,
             rewards_max_spread: None,
+            end_date: None,
             score,
         }
     }
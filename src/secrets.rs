@@ -0,0 +1,143 @@
+use age::secrecy::SecretString;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// CLOB API credentials, used instead of deriving them from the wallet key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApiCredentials {
+    pub key: String,
+    pub secret: String,
+    pub passphrase: String,
+}
+
+/// Contents of the encrypted secrets file: a TOML document sealed with an
+/// age passphrase. Every field is optional; anything missing falls back to
+/// the plaintext environment variable or config value.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Secrets {
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub api: Option<ApiCredentials>,
+    #[serde(default)]
+    pub telegram_bot_token: Option<String>,
+}
+
+impl std::fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secrets")
+            .field(
+                "private_key",
+                &self.private_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("api", &self.api.as_ref().map(|_| "<redacted>"))
+            .field(
+                "telegram_bot_token",
+                &self.telegram_bot_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+impl Secrets {
+    /// Names of the fields the file provides, for `secrets check`.
+    pub fn present(&self) -> Vec<&'static str> {
+        [
+            ("private_key", self.private_key.is_some()),
+            ("api", self.api.is_some()),
+            ("telegram_bot_token", self.telegram_bot_token.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+
+    /// Parse the plaintext TOML form.
+    pub fn parse(plaintext: &str) -> Result<Self> {
+        toml::from_str(plaintext).context("parsing secrets")
+    }
+
+    /// Decrypt and parse the secrets file at `path`.
+    pub fn load(path: &Path, passphrase: SecretString) -> Result<Self> {
+        let sealed =
+            std::fs::read(path).with_context(|| format!("reading secrets file {path:?}"))?;
+        let plaintext = decrypt(&sealed, passphrase)
+            .with_context(|| format!("decrypting secrets file {path:?}"))?;
+        Self::parse(&plaintext)
+    }
+}
+
+/// Seal `plaintext` with an age scrypt passphrase.
+pub fn encrypt(plaintext: &str, passphrase: SecretString) -> Result<Vec<u8>> {
+    let encryptor = age::Encryptor::with_user_passphrase(passphrase);
+    let mut sealed = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut sealed)?;
+    writer.write_all(plaintext.as_bytes())?;
+    writer.finish()?;
+    Ok(sealed)
+}
+
+pub fn decrypt(sealed: &[u8], passphrase: SecretString) -> Result<String> {
+    let decryptor = age::Decryptor::new(sealed)?;
+    let identity = age::scrypt::Identity::new(passphrase);
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .context("wrong passphrase or not a passphrase-encrypted file")?;
+    let mut plaintext = String::new();
+    reader.read_to_string(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// The passphrase from `env_var` if set, otherwise prompted for on the
+/// terminal.
+pub fn passphrase(env_var: &str) -> Result<SecretString> {
+    if let Ok(value) = std::env::var(env_var) {
+        return Ok(SecretString::from(value));
+    }
+    let value = rpassword::prompt_password("Secrets passphrase: ")
+        .with_context(|| format!("reading passphrase (or set {env_var})"))?;
+    Ok(SecretString::from(value))
+}
+
+/// Prompt twice for a new passphrase unless `env_var` provides one.
+pub fn new_passphrase(env_var: &str) -> Result<SecretString> {
+    if let Ok(value) = std::env::var(env_var) {
+        return Ok(SecretString::from(value));
+    }
+    let value = rpassword::prompt_password("New secrets passphrase: ")?;
+    if value.is_empty() {
+        bail!("passphrase must not be empty");
+    }
+    if rpassword::prompt_password("Repeat passphrase: ")? != value {
+        bail!("passphrases do not match");
+    }
+    Ok(SecretString::from(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_round_trip() {
+        let plaintext = r#"
+            private_key = "0xabc"
+            [api]
+            key = "k"
+            secret = "s"
+            passphrase = "p"
+        "#;
+        let sealed = encrypt(plaintext, SecretString::from("hunter2".to_string())).unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("0xabc"));
+        assert!(decrypt(&sealed, SecretString::from("wrong".to_string())).is_err());
+
+        let opened = decrypt(&sealed, SecretString::from("hunter2".to_string())).unwrap();
+        let secrets = Secrets::parse(&opened).unwrap();
+        assert_eq!(secrets.private_key.as_deref(), Some("0xabc"));
+        assert_eq!(secrets.api.unwrap().secret, "s");
+        assert!(secrets.telegram_bot_token.is_none());
+        assert!(!format!("{:?}", Secrets::parse(&opened).unwrap()).contains("0xabc"));
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+
+/// Runtime controls sent as Unix signals, for servers with no other way
+/// into a running bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// SIGUSR1: cancel all orders and stop quoting, or resume
+    TogglePause,
+    /// SIGUSR2: log a full status snapshot and write the metrics file
+    DumpStatus,
+    /// SIGHUP: re-read the config file
+    Reload,
+}
+
+/// Listeners for the control signals. Off Unix there are none and `recv`
+/// never resolves.
+pub struct Controls {
+    #[cfg(unix)]
+    pause: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    status: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    reload: tokio::signal::unix::Signal,
+}
+
+impl Controls {
+    #[cfg(unix)]
+    pub fn install() -> Result<Self> {
+        use anyhow::Context;
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let listen = |kind: SignalKind, name: &str| {
+            signal(kind).with_context(|| format!("installing {name} handler"))
+        };
+        Ok(Self {
+            pause: listen(SignalKind::user_defined1(), "SIGUSR1")?,
+            status: listen(SignalKind::user_defined2(), "SIGUSR2")?,
+            reload: listen(SignalKind::hangup(), "SIGHUP")?,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn install() -> Result<Self> {
+        Ok(Self {})
+    }
+
+    /// Wait for the next control signal.
+    #[cfg(unix)]
+    pub async fn recv(&mut self) -> Control {
+        tokio::select! {
+            _ = self.pause.recv() => Control::TogglePause,
+            _ = self.status.recv() => Control::DumpStatus,
+            _ = self.reload.recv() => Control::Reload,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub async fn recv(&mut self) -> Control {
+        std::future::pending().await
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::metrics::PortfolioMetrics;
+
+/// Snapshots older than this are dropped when a new one is recorded.
+const MAX_AGE_DAYS: i64 = 7;
+
+/// What `status` saw of one market.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    pub question: String,
+    pub net_pnl: Decimal,
+    pub total_fills: u64,
+    /// YES minus NO shares
+    pub inventory: Decimal,
+}
+
+/// One `status` invocation's view of the portfolio, keyed by condition ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub markets: BTreeMap<String, MarketSnapshot>,
+}
+
+impl StatusSnapshot {
+    pub fn capture(portfolio: &PortfolioMetrics, taken_at: DateTime<Utc>) -> Self {
+        let markets = portfolio
+            .markets
+            .iter()
+            .map(|(id, m)| {
+                let snapshot = MarketSnapshot {
+                    question: m.question.clone(),
+                    net_pnl: m.net_pnl(),
+                    total_fills: m.total_fills,
+                    inventory: m.inventory_yes - m.inventory_no,
+                };
+                (id.clone(), snapshot)
+            })
+            .collect();
+        Self { taken_at, markets }
+    }
+
+    pub fn net_pnl(&self) -> Decimal {
+        self.markets.values().map(|m| m.net_pnl).sum()
+    }
+}
+
+/// Change in one market between two snapshots. Markets new since the
+/// baseline count from zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDelta {
+    pub question: String,
+    pub pnl: Decimal,
+    pub fills: u64,
+    pub inventory: Decimal,
+}
+
+/// Markets that moved between `baseline` and `current`, by question.
+pub fn diff(baseline: &StatusSnapshot, current: &StatusSnapshot) -> Vec<MarketDelta> {
+    let mut deltas: Vec<MarketDelta> = current
+        .markets
+        .iter()
+        .map(|(id, now)| {
+            let before = baseline.markets.get(id);
+            MarketDelta {
+                question: now.question.clone(),
+                pnl: now.net_pnl - before.map_or(Decimal::ZERO, |b| b.net_pnl),
+                fills: now
+                    .total_fills
+                    .saturating_sub(before.map_or(0, |b| b.total_fills)),
+                inventory: now.inventory - before.map_or(Decimal::ZERO, |b| b.inventory),
+            }
+        })
+        .filter(|d| !d.pnl.is_zero() || d.fills > 0 || !d.inventory.is_zero())
+        .collect();
+    deltas.sort_by(|a, b| a.question.cmp(&b.question));
+    deltas
+}
+
+/// Snapshots from past `status` runs, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusHistory {
+    pub snapshots: Vec<StatusSnapshot>,
+}
+
+impl StatusHistory {
+    /// Load from disk; a missing file means no earlier snapshots.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading status history {}", path.display()))?;
+        serde_json::from_str(&content).context("parsing status history")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing status history {}", path.display()))
+    }
+
+    /// The snapshot to compare against: the latest one, or with `since`
+    /// the latest taken at least that long before `now`.
+    pub fn baseline(
+        &self,
+        now: DateTime<Utc>,
+        since: Option<chrono::Duration>,
+    ) -> Option<&StatusSnapshot> {
+        match since {
+            None => self.snapshots.last(),
+            Some(window) => self
+                .snapshots
+                .iter()
+                .rev()
+                .find(|s| s.taken_at <= now - window),
+        }
+    }
+
+    /// Append `snapshot`, dropping those past the retention window.
+    pub fn record(&mut self, snapshot: StatusSnapshot) {
+        let cutoff = snapshot.taken_at - chrono::Duration::days(MAX_AGE_DAYS);
+        self.snapshots.retain(|s| s.taken_at >= cutoff);
+        self.snapshots.push(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MarketMetrics;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_status_diff_against_baseline() {
+        let start = Utc::now();
+        let mut portfolio = PortfolioMetrics::new();
+        let mut m = MarketMetrics::new("a".into(), "Alpha".into());
+        m.spread_pnl = dec!(1.5);
+        m.total_fills = 2;
+        m.inventory_yes = dec!(100);
+        portfolio.markets.insert("a".into(), m);
+
+        let mut history = StatusHistory::default();
+        history.record(StatusSnapshot::capture(&portfolio, start));
+
+        let m = portfolio.markets.get_mut("a").unwrap();
+        m.spread_pnl = dec!(2.0);
+        m.total_fills = 5;
+        m.inventory_no = dec!(40);
+        portfolio
+            .markets
+            .insert("b".into(), MarketMetrics::new("b".into(), "Beta".into()));
+        let later = start + chrono::Duration::minutes(30);
+        let current = StatusSnapshot::capture(&portfolio, later);
+
+        let baseline = history.baseline(later, None).unwrap();
+        assert_eq!(
+            diff(baseline, &current),
+            vec![MarketDelta {
+                question: "Alpha".into(),
+                pnl: dec!(0.5),
+                fills: 3,
+                inventory: dec!(-40),
+            }]
+        );
+        // No snapshot is an hour old yet
+        assert!(
+            history
+                .baseline(later, Some(chrono::Duration::hours(1)))
+                .is_none()
+        );
+        history.record(current);
+        assert_eq!(history.snapshots.len(), 2);
+    }
+}
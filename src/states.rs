@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Operator-set state of one market in a multi-market run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineState {
+    /// Quoting as normal
+    #[default]
+    Active,
+    /// Orders cancelled and no quoting; inventory and fills still tracked
+    Paused,
+    /// Sell-only until flat, then idle
+    Unwinding,
+    /// Sell-only until flat, then removed and kept out like `Retired`
+    Removing,
+    /// Orders cancelled, engine removed, and kept out of rescans
+    Retired,
+}
+
+impl EngineState {
+    /// Whether the market is out of the bot, or on its way out.
+    pub fn is_removed(self) -> bool {
+        matches!(self, Self::Retired | Self::Removing)
+    }
+}
+
+impl fmt::Display for EngineState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Unwinding => "unwinding",
+            Self::Removing => "removing",
+            Self::Retired => "retired",
+        })
+    }
+}
+
+impl FromStr for EngineState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "active" => Ok(Self::Active),
+            "paused" => Ok(Self::Paused),
+            "unwinding" => Ok(Self::Unwinding),
+            "removing" => Ok(Self::Removing),
+            "retired" => Ok(Self::Retired),
+            other => Err(format!(
+                "unknown state {other:?} (expected active, paused, unwinding, removing, or retired)"
+            )),
+        }
+    }
+}
+
+/// Per-market states set with the `state` command. The running bot re-reads
+/// the file every loop, so a market can be paused or retired without a
+/// restart, and the states carry over to the next run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketStates {
+    /// Keyed by condition ID; markets not listed are active
+    pub markets: BTreeMap<String, EngineState>,
+    /// Markets added with `add-market`, quoted alongside the ranked ones
+    /// and kept through rescans
+    #[serde(default)]
+    pub added: BTreeSet<String>,
+}
+
+impl MarketStates {
+    /// Load from disk; a missing file means every market is active.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading market states {}", path.display()))?;
+        serde_json::from_str(&content).context("parsing market states")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing market states {}", path.display()))
+    }
+
+    pub fn get(&self, condition_id: &str) -> EngineState {
+        self.markets.get(condition_id).copied().unwrap_or_default()
+    }
+
+    /// Set a market's state; active markets are dropped from the file and
+    /// removed ones stop being added.
+    pub fn set(&mut self, condition_id: &str, state: EngineState) {
+        if state == EngineState::Active {
+            self.markets.remove(condition_id);
+        } else {
+            self.markets.insert(condition_id.to_string(), state);
+        }
+        if state.is_removed() {
+            self.added.remove(condition_id);
+        }
+    }
+
+    /// Add a market by hand, clearing a removal that would keep it out.
+    /// Returns false if it was already added.
+    pub fn add(&mut self, condition_id: &str) -> bool {
+        if self.get(condition_id).is_removed() {
+            self.markets.remove(condition_id);
+        }
+        self.added.insert(condition_id.to_string())
+    }
+
+    /// Markets added in `next` that aren't in `self`.
+    pub fn additions(&self, next: &Self) -> Vec<String> {
+        next.added.difference(&self.added).cloned().collect()
+    }
+
+    /// Markets whose state differs between `self` and `next`, with the
+    /// state in each.
+    pub fn changes(&self, next: &Self) -> Vec<(String, EngineState, EngineState)> {
+        let mut ids: Vec<&String> = self.markets.keys().chain(next.markets.keys()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter()
+            .filter_map(|id| {
+                let (from, to) = (self.get(id), next.get(id));
+                (from != to).then(|| (id.clone(), from, to))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_market_states_round_trip_and_changes() {
+        let path = std::env::temp_dir().join("polymarket_lp_test_market_states.json");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(MarketStates::load(&path).unwrap(), MarketStates::default());
+
+        let mut states = MarketStates::default();
+        states.set("a", EngineState::Paused);
+        states.set("b", EngineState::Retired);
+        states.save(&path).unwrap();
+        let loaded = MarketStates::load(&path).unwrap();
+        assert_eq!(loaded, states);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut next = loaded.clone();
+        next.set("a", EngineState::Active);
+        next.set("c", EngineState::Unwinding);
+        assert!(!next.markets.contains_key("a"));
+        assert_eq!(
+            loaded.changes(&next),
+            vec![
+                ("a".into(), EngineState::Paused, EngineState::Active),
+                ("c".into(), EngineState::Active, EngineState::Unwinding),
+            ]
+        );
+        // Adding a retired market brings it back; retiring drops the addition
+        assert!(next.add("b"));
+        assert!(!next.add("b"));
+        assert_eq!(next.get("b"), EngineState::Active);
+        assert_eq!(loaded.additions(&next), vec!["b".to_string()]);
+        next.set("b", EngineState::Retired);
+        assert!(next.added.is_empty());
+        assert_eq!("Paused".parse(), Ok(EngineState::Paused));
+        assert!("stopped".parse::<EngineState>().is_err());
+    }
+}
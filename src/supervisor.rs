@@ -0,0 +1,227 @@
+use futures::FutureExt;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, info, warn};
+
+use crate::config::MonitoringConfig;
+
+/// How often run loops should call `Supervisor::check`.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait between restarts of a failing task.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A task that ran this long before dying starts its restart count afresh.
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    /// Restarts in a row before a task is given up on
+    pub max_restarts: u32,
+    /// Wait before the first restart, doubling with each one after
+    pub backoff: Duration,
+}
+
+impl RestartPolicy {
+    pub fn from_config(config: &MonitoringConfig) -> Self {
+        Self {
+            max_restarts: config.task_max_restarts,
+            backoff: Duration::from_secs(config.task_restart_backoff_secs),
+        }
+    }
+
+    fn delay(&self, restarts: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << restarts.min(16))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// A task the supervisor stopped restarting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskFailure {
+    pub name: String,
+    /// The run can't carry on safely without it
+    pub critical: bool,
+    pub restarts: u32,
+}
+
+impl fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Background task {} kept failing after {} restarts",
+            self.name, self.restarts
+        )
+    }
+}
+
+struct Task {
+    name: String,
+    critical: bool,
+    spawn: Box<dyn Fn() -> JoinHandle<()> + Send>,
+    handle: Option<JoinHandle<()>>,
+    started: Instant,
+    restarts: u32,
+    restart_at: Option<Instant>,
+}
+
+/// Watches spawned tasks for panics and silent exits and restarts them with
+/// backoff. Run loops poll `check`, so a task that can't be revived surfaces
+/// where the orders can be dealt with.
+pub struct Supervisor {
+    policy: RestartPolicy,
+    tasks: Vec<Task>,
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Spawn `task` now, and again from scratch whenever it dies.
+    pub fn spawn<F, Fut>(&mut self, name: impl Into<String>, critical: bool, task: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let spawn = Box::new(move || tokio::spawn(task()));
+        let handle = spawn();
+        self.tasks.push(Task {
+            name: name.into(),
+            critical,
+            spawn,
+            handle: Some(handle),
+            started: Instant::now(),
+            restarts: 0,
+            restart_at: None,
+        });
+    }
+
+    /// Schedule restarts for tasks that died and start those whose backoff
+    /// is over. Returns the tasks given up on in this pass.
+    pub fn check(&mut self, now: Instant) -> Vec<TaskFailure> {
+        let mut failures = Vec::new();
+        for task in &mut self.tasks {
+            if let Some(mut handle) = task.handle.take_if(|h| h.is_finished()) {
+                match (&mut handle).now_or_never() {
+                    Some(Err(e)) if e.is_panic() => {
+                        error!(task = %task.name, panic = panic_message(e), "Task panicked");
+                    }
+                    _ => warn!(task = %task.name, "Task exited unexpectedly"),
+                }
+                if now.saturating_duration_since(task.started) >= STABLE_AFTER {
+                    task.restarts = 0;
+                }
+                if task.restarts >= self.policy.max_restarts {
+                    error!(task = %task.name, restarts = task.restarts, "Giving up on task");
+                    failures.push(TaskFailure {
+                        name: task.name.clone(),
+                        critical: task.critical,
+                        restarts: task.restarts,
+                    });
+                    continue;
+                }
+                let delay = self.policy.delay(task.restarts);
+                info!(
+                    task = %task.name,
+                    delay_ms = delay.as_millis() as u64,
+                    "Restarting task after backoff"
+                );
+                task.restart_at = Some(now + delay);
+            }
+            if task.restart_at.is_some_and(|at| now >= at) {
+                task.restart_at = None;
+                task.restarts += 1;
+                task.started = now;
+                task.handle = Some((task.spawn)());
+                info!(task = %task.name, restarts = task.restarts, "Task restarted");
+            }
+        }
+        failures
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        for handle in self.tasks.iter().filter_map(|t| t.handle.as_ref()) {
+            handle.abort();
+        }
+    }
+}
+
+fn panic_message(e: JoinError) -> String {
+    let panic = e.into_panic();
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn check_until(
+        supervisor: &mut Supervisor,
+        done: impl Fn(&[TaskFailure]) -> bool,
+    ) -> Vec<TaskFailure> {
+        let mut failures = Vec::new();
+        for _ in 0..200 {
+            failures.extend(supervisor.check(Instant::now()));
+            if done(&failures) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        failures
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_panicked_task_then_gives_up() {
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            backoff: Duration::ZERO,
+        };
+        let mut supervisor = Supervisor::new(policy);
+        let runs = Arc::new(AtomicU32::new(0));
+
+        // Panics twice, then settles
+        let counter = runs.clone();
+        supervisor.spawn("flaky", true, move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("flaky task");
+                }
+                std::future::pending::<()>().await;
+            }
+        });
+        let failures = check_until(&mut supervisor, |_| runs.load(Ordering::SeqCst) == 3).await;
+        assert!(failures.is_empty());
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        // Exits every time: given up on after two restarts
+        supervisor.spawn("doomed", false, || async {});
+        let failures = check_until(&mut supervisor, |f| !f.is_empty()).await;
+        assert_eq!(
+            failures,
+            vec![TaskFailure {
+                name: "doomed".into(),
+                critical: false,
+                restarts: 2,
+            }]
+        );
+        assert!(supervisor.check(Instant::now()).is_empty());
+    }
+}
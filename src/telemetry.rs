@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::config::MonitoringConfig;
+
+/// When `monitoring.log_file` starts a new file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// New file every hour, suffixed `.YYYY-MM-DD-HH`
+    Hourly,
+    /// New file every UTC day, suffixed `.YYYY-MM-DD`
+    Daily,
+    /// Roll to `.1`, `.2`, ... once the file reaches `log_max_size_mb`
+    Size,
+    /// A single ever-growing file
+    Never,
+}
+
+/// Keeps the log writer and OTLP pipeline alive; dropping it flushes
+/// buffered log lines and spans.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+    _log_guard: Option<WorkerGuard>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            warn!(error = %e, "Failed to flush OTLP spans");
+        }
+    }
+}
+
+/// Install the global subscriber: human-readable stdout at `log_level`
+/// (or `RUST_LOG`), a plain-text log file at `log_file_level` when
+/// `monitoring.log_file` is set, and span export over OTLP/HTTP when
+/// `monitoring.otlp_endpoint` is set.
+pub fn init(monitoring: &MonitoringConfig) -> Result<Telemetry> {
+    let provider = match &monitoring.otlp_endpoint {
+        Some(endpoint) => Some(otlp_provider(endpoint, &monitoring.otlp_service_name)?),
+        None => None,
+    };
+    let otel_layer = provider.as_ref().map(|p| {
+        tracing_opentelemetry::layer()
+            .with_tracer(p.tracer("polymarket-lp"))
+            .with_filter(stdout_filter(&monitoring.log_level))
+    });
+
+    let (file_layer, log_guard) = match &monitoring.log_file {
+        Some(path) => {
+            let writer = log_writer(
+                Path::new(path),
+                monitoring.log_rotation,
+                monitoring.log_max_size_mb,
+                monitoring.log_max_files,
+            )?;
+            let (writer, guard) = tracing_appender::non_blocking(writer);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer)
+                .with_filter(EnvFilter::new(&monitoring.log_file_level));
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(stdout_filter(&monitoring.log_level)))
+        .with(file_layer)
+        .with(otel_layer)
+        .init();
+
+    Ok(Telemetry {
+        provider,
+        _log_guard: log_guard,
+    })
+}
+
+fn stdout_filter(log_level: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level))
+}
+
+fn otlp_provider(endpoint: &str, service_name: &str) -> Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .with_context(|| format!("building OTLP exporter for {endpoint}"))?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build())
+}
+
+fn log_writer(
+    path: &Path,
+    rotation: LogRotation,
+    max_size_mb: u64,
+    max_files: usize,
+) -> Result<Box<dyn Write + Send>> {
+    if rotation == LogRotation::Size {
+        let writer = SizeRotatingFile::open(path, max_size_mb * 1024 * 1024, max_files)?;
+        return Ok(Box::new(writer));
+    }
+    let rotation = match rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        _ => Rotation::NEVER,
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .with_context(|| format!("log_file {path:?} has no file name"))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(name.to_string_lossy())
+        .max_log_files(max_files.max(1))
+        .build(dir)
+        .with_context(|| format!("opening log file {path:?}"))?;
+    Ok(Box::new(appender))
+}
+
+/// Log file that rolls to `<path>.1` once it would exceed `max_bytes`,
+/// shifting older rolls up and keeping at most `max_files` of them.
+pub struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<Self> {
+        let file = open_append(path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file,
+            written,
+        })
+    }
+
+    fn rolled(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rolled(self.max_files));
+            for n in (1..self.max_files).rev() {
+                let from = self.rolled(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rolled(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rolled(1))?;
+            self.file = open_append(&self.path).map_err(io::Error::other)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating log directory {dir:?}"))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening log file {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_rotation() {
+        let dir = std::env::temp_dir().join("polymarket_lp_test_log_rotation");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bot.log");
+
+        let mut log = SizeRotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        // Each line overflows the 10-byte limit, so every write rolls; only
+        // the two newest rolls survive
+        let read = |p: PathBuf| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(path.clone()), "fourth\n");
+        assert_eq!(read(log.rolled(1)), "third\n");
+        assert_eq!(read(log.rolled(2)), "second\n");
+        assert!(!log.rolled(3).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
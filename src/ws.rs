@@ -1,13 +1,20 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use futures::StreamExt;
 use polymarket_client_sdk::auth;
+use polymarket_client_sdk::clob::types::TraderSide;
 use polymarket_client_sdk::clob::ws;
 use polymarket_client_sdk::types::{B256, U256};
+use polymarket_client_sdk::ws::config::Config as WsConfig;
 use rust_decimal::Decimal;
 use std::str::FromStr;
+use std::time::Duration;
 use tokio::sync::{mpsc, watch};
 use tracing::{debug, info, warn};
 
+use crate::client;
+use crate::config::EndpointsConfig;
+use crate::supervisor::Supervisor;
+
 /// Events from the WebSocket feed relevant to the quoting engine.
 #[derive(Debug, Clone)]
 pub enum WsEvent {
@@ -24,6 +31,10 @@ pub enum WsEvent {
         order_id: String,
         size: Decimal,
         price: Decimal,
+        /// Fee rate the trade was matched at, if reported.
+        fee_rate_bps: Option<Decimal>,
+        /// True if our order was resting (maker), false if it crossed (taker).
+        is_maker: bool,
     },
     /// Connection lost, falling back to REST.
     Disconnected,
@@ -35,39 +46,57 @@ pub enum WsEvent {
 pub struct WsManager {
     event_tx: mpsc::Sender<WsEvent>,
     shutdown_tx: watch::Sender<bool>,
+    assets_tx: watch::Sender<Vec<U256>>,
 }
 
 impl WsManager {
-    /// Start WebSocket subscriptions for the given assets.
-    /// Returns the manager and a receiver for events.
+    /// Start WebSocket subscriptions for the given assets, as tasks under
+    /// `supervisor`. Returns the manager and a receiver for events.
     pub async fn start(
+        endpoints: &EndpointsConfig,
         token_ids: Vec<String>,
         market_condition_id: Option<String>,
         credentials: Option<(auth::Credentials, polymarket_client_sdk::types::Address)>,
+        supervisor: &mut Supervisor,
     ) -> Result<(Self, mpsc::Receiver<WsEvent>)> {
         let (event_tx, event_rx) = mpsc::channel(256);
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
-        let asset_ids: Vec<U256> = token_ids
-            .iter()
-            .filter_map(|id| U256::from_str(id).ok())
-            .collect();
+        let (assets_tx, assets_rx) = watch::channel(parse_assets(&token_ids));
 
         // Spawn the market data subscription task
         let tx = event_tx.clone();
-        let ids = asset_ids.clone();
-        let mut rx = shutdown_rx.clone();
-        tokio::spawn(async move {
-            loop {
-                if *rx.borrow() {
-                    break;
-                }
-                if let Err(e) = run_market_subscription(&tx, &ids, &mut rx).await {
-                    warn!(error = %e, "Market WS subscription error, reconnecting...");
-                    let _ = tx.send(WsEvent::Disconnected).await;
-                    // Exponential backoff up to 30s
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                    let _ = tx.send(WsEvent::Reconnected).await;
+        let url = endpoints.ws_url.clone();
+        let first_message = client::timeout(endpoints.ws_timeout_secs);
+        let ws_config = ws_config(endpoints);
+        let shutdown = shutdown_rx.clone();
+        let config = ws_config.clone();
+        supervisor.spawn("market_ws", true, move || {
+            let (tx, url, config) = (tx.clone(), url.clone(), config.clone());
+            let mut rx = shutdown.clone();
+            let mut assets = assets_rx.clone();
+            async move {
+                loop {
+                    if *rx.borrow() {
+                        break;
+                    }
+                    if let Err(e) =
+                        run_market_subscription(
+                            &tx,
+                            &url,
+                            &config,
+                            &mut assets,
+                            first_message,
+                            &mut rx,
+                        )
+                        .await
+                    {
+                        warn!(error = %e, "Market WS subscription error, reconnecting...");
+                        let _ = tx.send(WsEvent::Disconnected).await;
+                        // Exponential backoff up to 30s
+                        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                        let _ = tx.send(WsEvent::Reconnected).await;
+                    }
                 }
             }
         });
@@ -76,17 +105,30 @@ impl WsManager {
         if let Some((creds, address)) = credentials {
             if let Some(cond_id) = market_condition_id {
                 let tx = event_tx.clone();
-                let mut rx = shutdown_rx.clone();
-                tokio::spawn(async move {
-                    loop {
-                        if *rx.borrow() {
-                            break;
-                        }
-                        if let Err(e) =
-                            run_user_subscription(&tx, &creds, address, &cond_id, &mut rx).await
-                        {
-                            warn!(error = %e, "User WS subscription error, reconnecting...");
-                            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                let url = endpoints.ws_url.clone();
+                let shutdown = shutdown_rx.clone();
+                supervisor.spawn("user_ws", true, move || {
+                    let (tx, url, config, creds, cond_id) = (
+                        tx.clone(),
+                        url.clone(),
+                        ws_config.clone(),
+                        creds.clone(),
+                        cond_id.clone(),
+                    );
+                    let mut rx = shutdown.clone();
+                    async move {
+                        loop {
+                            if *rx.borrow() {
+                                break;
+                            }
+                            if let Err(e) = run_user_subscription(
+                                &tx, &url, &config, &creds, address, &cond_id, &mut rx,
+                            )
+                            .await
+                            {
+                                warn!(error = %e, "User WS subscription error, reconnecting...");
+                                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                            }
                         }
                     }
                 });
@@ -97,32 +139,89 @@ impl WsManager {
             Self {
                 event_tx,
                 shutdown_tx,
+                assets_tx,
             },
             event_rx,
         ))
     }
 
+    /// Replace the subscribed tokens, resubscribing only if they changed.
+    pub fn set_tokens(&self, token_ids: &[String]) {
+        let mut next = parse_assets(token_ids);
+        next.sort();
+        self.assets_tx.send_if_modified(|assets| {
+            let mut current = assets.clone();
+            current.sort();
+            if current == next {
+                return false;
+            }
+            info!(
+                from = assets.len(),
+                to = next.len(),
+                "WebSocket market subscriptions changed"
+            );
+            *assets = next;
+            true
+        });
+    }
+
     /// Shutdown all WebSocket connections.
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(true);
     }
 }
 
+fn parse_assets(token_ids: &[String]) -> Vec<U256> {
+    token_ids
+        .iter()
+        .filter_map(|id| U256::from_str(id).ok())
+        .collect()
+}
+
+/// Client settings for both feeds: a connection whose heartbeat goes
+/// unanswered for `ws_timeout_secs` is treated as dead.
+fn ws_config(endpoints: &EndpointsConfig) -> WsConfig {
+    let mut config = WsConfig::default();
+    if let Some(timeout) = client::timeout(endpoints.ws_timeout_secs) {
+        config.heartbeat_timeout = timeout;
+        // Ping often enough that a short timeout still sees a few pongs
+        config.heartbeat_interval = config.heartbeat_interval.min(timeout / 3);
+    }
+    config
+}
+
+/// Stream midpoints until shutdown or the token set changes. Fails if
+/// nothing arrives within `first_message` of subscribing, so a dead
+/// endpoint gets reconnected.
 async fn run_market_subscription(
     tx: &mpsc::Sender<WsEvent>,
-    asset_ids: &[U256],
+    url: &str,
+    config: &WsConfig,
+    assets: &mut watch::Receiver<Vec<U256>>,
+    first_message: Option<Duration>,
     shutdown_rx: &mut watch::Receiver<bool>,
 ) -> Result<()> {
-    let ws_client = ws::Client::default();
+    let asset_ids = assets.borrow_and_update().clone();
+    if asset_ids.is_empty() {
+        // Nothing to stream until a market is added
+        tokio::select! {
+            _ = shutdown_rx.changed() => {}
+            Ok(()) = assets.changed() => {}
+        }
+        return Ok(());
+    }
+    let ws_client = ws::Client::new(url, config.clone()).context("creating WS client")?;
 
     // Subscribe to midpoint updates
     let stream = ws_client
-        .subscribe_midpoints(asset_ids.to_vec())
+        .subscribe_midpoints(asset_ids.clone())
         .context("subscribing to midpoints")?;
     let mut stream = Box::pin(stream);
 
     info!(assets = asset_ids.len(), "WebSocket market subscription started");
 
+    let mut awaiting_first = first_message.map(|limit| Box::pin(tokio::time::sleep(limit)));
+
     loop {
         tokio::select! {
             _ = shutdown_rx.changed() => {
@@ -130,7 +229,15 @@ async fn run_market_subscription(
                     break;
                 }
             }
+            Ok(()) = assets.changed() => {
+                info!("Resubscribing WebSocket for the new market set");
+                return Ok(());
+            }
+            _ = async { awaiting_first.as_mut().unwrap().await }, if awaiting_first.is_some() => {
+                bail!("no market data within {}s of subscribing", first_message.unwrap_or_default().as_secs());
+            }
             item = stream.next() => {
+                awaiting_first = None;
                 match item {
                     Some(Ok(update)) => {
                         debug!(
@@ -161,12 +268,15 @@ async fn run_market_subscription(
 
 async fn run_user_subscription(
     tx: &mpsc::Sender<WsEvent>,
+    url: &str,
+    config: &WsConfig,
     credentials: &auth::Credentials,
     address: polymarket_client_sdk::types::Address,
     market_condition_id: &str,
     shutdown_rx: &mut watch::Receiver<bool>,
 ) -> Result<()> {
-    let ws_client = ws::Client::default();
+    let our_key = credentials.key();
+    let ws_client = ws::Client::new(url, config.clone()).context("creating WS client")?;
     let ws_auth = ws_client
         .authenticate(credentials.clone(), address)
         .context("authenticating WS client")?;
@@ -197,11 +307,27 @@ async fn run_user_subscription(
                             price = %trade.price,
                             "WS trade fill"
                         );
-                        let _ = tx.send(WsEvent::OrderFill {
-                            order_id: trade.taker_order_id.clone().unwrap_or_default(),
-                            size: trade.size,
-                            price: trade.price,
-                        }).await;
+                        if trade.trader_side == Some(TraderSide::Maker) {
+                            // The maker orders matched against the taker include
+                            // other wallets'; ours are the ones under our API key
+                            for maker in trade.maker_orders.iter().filter(|m| m.owner == our_key) {
+                                let _ = tx.send(WsEvent::OrderFill {
+                                    order_id: maker.order_id.clone(),
+                                    size: maker.matched_amount,
+                                    price: maker.price,
+                                    fee_rate_bps: trade.fee_rate_bps,
+                                    is_maker: true,
+                                }).await;
+                            }
+                        } else {
+                            let _ = tx.send(WsEvent::OrderFill {
+                                order_id: trade.taker_order_id.clone().unwrap_or_default(),
+                                size: trade.size,
+                                price: trade.price,
+                                fee_rate_bps: trade.fee_rate_bps,
+                                is_maker: false,
+                            }).await;
+                        }
                     }
                     Some(Err(e)) => {
                         warn!(error = %e, "User WS stream error");
@@ -0,0 +1,117 @@
+//! End-to-end runs of the bot binary against the in-process mock exchange.
+//!
+//! These catch wiring regressions between config, clients, WS subscriptions,
+//! and the run loop that unit tests with the in-memory mock can't see.
+#![cfg(unix)]
+
+mod support;
+
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use support::{MockExchange, NO_TOKEN, YES_TOKEN};
+
+#[tokio::test]
+async fn test_scan_lists_mock_markets() {
+    let exchange = MockExchange::start().await;
+    let dir = support::scratch_dir("scan");
+    let config = exchange.write_config(&dir);
+
+    let output = support::bot(&dir, &config)
+        .args(["scan", "--min-reward", "0"])
+        .output()
+        .await
+        .expect("running scan");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "scan failed: {output:?}");
+    assert!(
+        stdout.contains(support::QUESTION),
+        "market missing from:\n{stdout}"
+    );
+    assert!(exchange.hits("/markets") > 0);
+}
+
+#[tokio::test]
+async fn test_dry_run_polls_rest_midpoints() {
+    let exchange = MockExchange::start().await;
+    let dir = support::scratch_dir("dry_run_rest");
+    let config = exchange.write_config(&dir);
+
+    let child = support::bot(&dir, &config)
+        .args(["run", "--no-ws", "--market", support::CONDITION_ID])
+        .spawn()
+        .expect("starting bot");
+
+    assert!(
+        support::wait_for(Duration::from_secs(15), || exchange.hits("/midpoint") >= 2).await,
+        "bot never polled the midpoint"
+    );
+    exchange.set_midpoint(YES_TOKEN, dec!(0.60));
+    let polled = exchange.hits("/midpoint");
+    assert!(
+        support::wait_for(Duration::from_secs(10), || exchange.hits("/midpoint")
+            > polled)
+        .await
+    );
+
+    let output = support::interrupt(child).await;
+    assert!(output.status.success(), "bot exited uncleanly: {output:?}");
+    assert_eq!(exchange.posted_orders(), 0, "dry run must not post orders");
+}
+
+#[tokio::test]
+async fn test_dry_run_subscribes_to_market_channel() {
+    let exchange = MockExchange::start().await;
+    let dir = support::scratch_dir("dry_run_ws");
+    let config = exchange.write_config(&dir);
+
+    let child = support::bot(&dir, &config)
+        .args(["run", "--market", support::CONDITION_ID])
+        .spawn()
+        .expect("starting bot");
+
+    assert!(
+        support::wait_for(Duration::from_secs(15), || {
+            let subscribed = exchange.subscribed_assets();
+            subscribed.iter().any(|id| id == YES_TOKEN)
+                && subscribed.iter().any(|id| id == NO_TOKEN)
+        })
+        .await,
+        "bot never subscribed to both tokens"
+    );
+
+    let output = support::interrupt(child).await;
+    assert!(output.status.success(), "bot exited uncleanly: {output:?}");
+}
+
+#[tokio::test]
+async fn test_live_run_posts_then_cancels_on_shutdown() {
+    let exchange = MockExchange::start().await;
+    let dir = support::scratch_dir("live_run");
+    let config = exchange.write_config(&dir);
+
+    let child = support::bot(&dir, &config)
+        .args([
+            "run",
+            "--live",
+            "--no-ws",
+            "--market",
+            support::CONDITION_ID,
+        ])
+        .spawn()
+        .expect("starting bot");
+
+    assert!(
+        support::wait_for(Duration::from_secs(20), || exchange.open_orders() > 0).await,
+        "bot never rested any orders"
+    );
+
+    let output = support::interrupt(child).await;
+    assert!(output.status.success(), "bot exited uncleanly: {output:?}");
+    assert!(exchange.posted_orders() > 0);
+    assert_eq!(
+        exchange.open_orders(),
+        0,
+        "orders left resting after shutdown"
+    );
+}
@@ -0,0 +1,497 @@
+//! In-process stand-in for the Polymarket CLOB, Gamma, and Data APIs.
+//!
+//! Serves the subset of endpoints the bot touches, over plain HTTP and WS on
+//! a loopback port, so the real binary can be pointed at it through the
+//! `[endpoints]` config section.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, delete, get, post};
+use axum::{Json, Router};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path as FsPath, PathBuf};
+use std::process::Output;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+pub const CONDITION_ID: &str = "0x5eed000000000000000000000000000000000000000000000000000000000001";
+pub const YES_TOKEN: &str =
+    "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+pub const NO_TOKEN: &str =
+    "52114319501245915516055106046884209969926127482827954674443846427813813222426";
+pub const QUESTION: &str = "Will the mock exchange stay up?";
+
+/// Well-known development key; never funded anywhere.
+pub const TEST_PRIVATE_KEY: &str =
+    "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+type Shared = Arc<Mutex<MockState>>;
+
+#[derive(Default)]
+struct MockState {
+    midpoints: HashMap<String, Decimal>,
+    /// Resting orders by ID, as posted
+    orders: BTreeMap<String, Value>,
+    posted: usize,
+    /// "METHOD /path" for every request served
+    requests: Vec<String>,
+    /// Asset IDs received in market-channel subscriptions
+    subscribed: Vec<String>,
+}
+
+/// A running mock exchange. The server stops when the test's runtime does.
+pub struct MockExchange {
+    addr: SocketAddr,
+    state: Shared,
+}
+
+impl MockExchange {
+    pub async fn start() -> Self {
+        let mut initial = MockState::default();
+        initial.midpoints.insert(YES_TOKEN.into(), dec!(0.50));
+        initial.midpoints.insert(NO_TOKEN.into(), dec!(0.50));
+        let state = Arc::new(Mutex::new(initial));
+
+        let app = Router::new()
+            // Gamma
+            .route("/markets", get(markets))
+            // Data API
+            .route("/positions", get(|| async { Json(json!([])) }))
+            // CLOB
+            .route("/time", get(server_time))
+            .route("/auth/api-key", post(api_key))
+            .route("/auth/derive-api-key", get(api_key))
+            .route("/midpoint", get(midpoint))
+            .route(
+                "/tick-size",
+                get(|| async { Json(json!({ "minimum_tick_size": 0.01 })) }),
+            )
+            .route(
+                "/neg-risk",
+                get(|| async { Json(json!({ "neg_risk": false })) }),
+            )
+            .route(
+                "/fee-rate",
+                get(|| async { Json(json!({ "base_fee": 0 })) }),
+            )
+            .route("/book", get(book))
+            .route("/last-trade-price", get(last_trade_price))
+            .route("/balance-allowance", get(balance_allowance))
+            .route("/order", post(post_order).delete(cancel_order))
+            .route("/orders", post(post_orders).delete(cancel_orders))
+            .route("/cancel-all", delete(cancel_all))
+            .route("/data/order/{id}", get(order))
+            // WebSocket channels
+            .route("/ws/market", any(market_channel))
+            .route("/ws/user", any(user_channel))
+            .layer(middleware::from_fn_with_state(state.clone(), record))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("binding mock exchange");
+        let addr = listener.local_addr().expect("mock exchange address");
+        tokio::spawn(async move {
+            axum::serve(listener, app)
+                .await
+                .expect("serving mock exchange");
+        });
+
+        Self { addr, state }
+    }
+
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    pub fn set_midpoint(&self, token_id: &str, midpoint: Decimal) {
+        self.lock().midpoints.insert(token_id.into(), midpoint);
+    }
+
+    /// How many requests hit `path`, across methods.
+    pub fn hits(&self, path: &str) -> usize {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|r| r.split_once(' ').is_some_and(|(_, p)| p == path))
+            .count()
+    }
+
+    pub fn posted_orders(&self) -> usize {
+        self.lock().posted
+    }
+
+    pub fn open_orders(&self) -> usize {
+        self.lock().orders.len()
+    }
+
+    pub fn subscribed_assets(&self) -> Vec<String> {
+        self.lock().subscribed.clone()
+    }
+
+    /// Write a config that points every endpoint at this server.
+    pub fn write_config(&self, dir: &FsPath) -> PathBuf {
+        let config = format!(
+            r#"
+[wallet]
+private_key_env = "POLYMARKET_LP_TEST_KEY"
+
+[strategy]
+requote_interval_secs = 1
+rest_poll_interval_secs = 1
+
+[endpoints]
+clob_url = "{http}"
+ws_url = "{ws}"
+gamma_url = "{http}"
+data_url = "{http}"
+clob_timeout_secs = 5
+ws_timeout_secs = 5
+gamma_timeout_secs = 5
+data_timeout_secs = 5
+"#,
+            http = self.http_url(),
+            ws = self.ws_url(),
+        );
+        let path = dir.join("config.toml");
+        std::fs::write(&path, config).expect("writing test config");
+        path
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A scratch directory for one test's config and state files.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("polymarket_lp_e2e_{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("creating scratch dir");
+    dir
+}
+
+/// The bot binary, run from `dir` so its state files land there.
+pub fn bot(dir: &FsPath, config: &FsPath) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_polymarket-lp"));
+    cmd.current_dir(dir)
+        .arg("--config")
+        .arg(config)
+        .env("POLYMARKET_LP_TEST_KEY", TEST_PRIVATE_KEY)
+        .env("RUST_LOG", "info")
+        .kill_on_drop(true)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    cmd
+}
+
+/// Poll `done` until it holds or `limit` passes.
+pub async fn wait_for(limit: Duration, done: impl Fn() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + limit;
+    while tokio::time::Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    done()
+}
+
+/// Send Ctrl+C to a running bot and collect its output once it exits.
+pub async fn interrupt(child: Child) -> Output {
+    let pid = child.id().expect("bot already exited");
+    let status = Command::new("kill")
+        .args(["-INT", &pid.to_string()])
+        .status()
+        .await
+        .expect("sending SIGINT");
+    assert!(status.success(), "kill -INT {pid} failed");
+    tokio::time::timeout(Duration::from_secs(20), child.wait_with_output())
+        .await
+        .expect("bot did not shut down after SIGINT")
+        .expect("collecting bot output")
+}
+
+async fn record(State(state): State<Shared>, request: Request, next: Next) -> Response {
+    let line = format!("{} {}", request.method(), request.uri().path());
+    state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .requests
+        .push(line);
+    next.run(request).await
+}
+
+async fn markets() -> Json<Value> {
+    Json(json!([{
+        "id": "1",
+        "question": QUESTION,
+        "conditionId": CONDITION_ID,
+        "slug": "mock-exchange-uptime",
+        "active": true,
+        "closed": false,
+        "acceptingOrders": true,
+        "enableOrderBook": true,
+        "clobTokenIds": format!("[\"{YES_TOKEN}\", \"{NO_TOKEN}\"]"),
+        "outcomes": "[\"Yes\", \"No\"]",
+        "liquidity": "5000",
+        "volume": "25000",
+        "competitive": 0.9,
+        "orderPriceMinTickSize": 0.01,
+        "orderMinSize": 5,
+        "rewardsMinSize": 20,
+        "rewardsMaxSpread": 3.5,
+        "endDate": "2099-01-01T00:00:00Z"
+    }]))
+}
+
+async fn server_time() -> Json<i64> {
+    Json(chrono::Utc::now().timestamp())
+}
+
+async fn api_key() -> Json<Value> {
+    Json(json!({
+        "apiKey": "00000000-0000-4000-8000-000000000001",
+        "secret": "c2VjcmV0LXNlY3JldC1zZWNyZXQtc2VjcmV0LXNlY3I=",
+        "passphrase": "mock-passphrase"
+    }))
+}
+
+fn token_param(params: &HashMap<String, String>) -> String {
+    params.get("token_id").cloned().unwrap_or_default()
+}
+
+fn midpoint_of(state: &Shared, token_id: &str) -> Option<Decimal> {
+    state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .midpoints
+        .get(token_id)
+        .copied()
+}
+
+async fn midpoint(
+    State(state): State<Shared>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match midpoint_of(&state, &token_param(&params)) {
+        Some(mid) => Json(json!({ "mid": mid.to_string() })).into_response(),
+        None => not_found("unknown token"),
+    }
+}
+
+fn book_json(token_id: &str, midpoint: Decimal) -> Value {
+    json!({
+        "market": CONDITION_ID,
+        "asset_id": token_id,
+        "timestamp": chrono::Utc::now().timestamp_millis().to_string(),
+        "hash": "0x00",
+        "bids": [{ "price": (midpoint - dec!(0.01)).to_string(), "size": "200" }],
+        "asks": [{ "price": (midpoint + dec!(0.01)).to_string(), "size": "200" }],
+        "min_order_size": "5",
+        "tick_size": "0.01",
+        "neg_risk": false
+    })
+}
+
+async fn book(
+    State(state): State<Shared>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let token_id = token_param(&params);
+    match midpoint_of(&state, &token_id) {
+        Some(mid) => Json(book_json(&token_id, mid)).into_response(),
+        None => not_found("unknown token"),
+    }
+}
+
+async fn last_trade_price(
+    State(state): State<Shared>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match midpoint_of(&state, &token_param(&params)) {
+        Some(mid) => Json(json!({ "price": mid.to_string(), "side": "BUY" })).into_response(),
+        None => not_found("unknown token"),
+    }
+}
+
+async fn balance_allowance() -> Json<Value> {
+    Json(json!({ "balance": "1000000000", "allowances": {} }))
+}
+
+/// Accept a signed order and rest it, returning the exchange's response.
+fn rest_order(state: &mut MockState, posted: Value) -> Value {
+    state.posted += 1;
+    let id = format!("0x{:064x}", state.posted);
+    state.orders.insert(id.clone(), posted);
+    json!({
+        "success": true,
+        "errorMsg": "",
+        "orderID": id,
+        "status": "live",
+        "makingAmount": "",
+        "takingAmount": "",
+        "transactionsHashes": []
+    })
+}
+
+async fn post_order(State(state): State<Shared>, Json(body): Json<Value>) -> Json<Value> {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    Json(rest_order(&mut state, body))
+}
+
+async fn post_orders(State(state): State<Shared>, Json(body): Json<Vec<Value>>) -> Json<Value> {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let responses: Vec<Value> = body
+        .into_iter()
+        .map(|order| rest_order(&mut state, order))
+        .collect();
+    Json(Value::Array(responses))
+}
+
+fn cancel(state: &Shared, ids: impl IntoIterator<Item = String>) -> Json<Value> {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let mut canceled = Vec::new();
+    let mut not_canceled = serde_json::Map::new();
+    for id in ids {
+        if state.orders.remove(&id).is_some() {
+            canceled.push(id);
+        } else {
+            not_canceled.insert(id, json!("order not found"));
+        }
+    }
+    Json(json!({ "canceled": canceled, "not_canceled": not_canceled }))
+}
+
+async fn cancel_order(State(state): State<Shared>, Json(body): Json<Value>) -> Json<Value> {
+    let id = body["orderID"].as_str().unwrap_or_default().to_string();
+    cancel(&state, [id])
+}
+
+async fn cancel_orders(State(state): State<Shared>, Json(ids): Json<Vec<String>>) -> Json<Value> {
+    cancel(&state, ids)
+}
+
+async fn cancel_all(State(state): State<Shared>) -> Json<Value> {
+    let ids: Vec<String> = {
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.orders.keys().cloned().collect()
+    };
+    cancel(&state, ids)
+}
+
+/// Share size of a posted order, from its fixed-point (6 decimal) amounts.
+fn order_size(posted: &Value) -> Decimal {
+    let order = &posted["order"];
+    let field = if order["side"] == "SELL" {
+        "makerAmount"
+    } else {
+        "takerAmount"
+    };
+    order[field]
+        .as_str()
+        .and_then(|amount| amount.parse::<Decimal>().ok())
+        .map(|amount| amount / dec!(1_000_000))
+        .unwrap_or_default()
+}
+
+async fn order(State(state): State<Shared>, Path(id): Path<String>) -> Response {
+    let state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(posted) = state.orders.get(&id) else {
+        return not_found("order not found");
+    };
+    let order = &posted["order"];
+    Json(json!({
+        "id": id,
+        "status": "LIVE",
+        "owner": posted["owner"],
+        "maker_address": order["maker"],
+        "market": CONDITION_ID,
+        "asset_id": order["tokenId"],
+        "side": order["side"],
+        "original_size": order_size(posted).to_string(),
+        "size_matched": "0",
+        "price": "0.5",
+        "outcome": "Yes",
+        "expiration": "0",
+        "order_type": posted["orderType"],
+        "associate_trades": [],
+        "created_at": chrono::Utc::now().timestamp()
+    }))
+    .into_response()
+}
+
+fn not_found(error: &str) -> Response {
+    (
+        axum::http::StatusCode::NOT_FOUND,
+        Json(json!({ "error": error })),
+    )
+        .into_response()
+}
+
+async fn market_channel(State(state): State<Shared>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| stream_books(state, socket))
+}
+
+/// Answer each subscription with a book snapshot per asset, then keep the
+/// connection alive until the client drops it.
+async fn stream_books(state: Shared, mut socket: WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        if text.as_str() == "PING" {
+            let _ = socket.send(Message::Text("PONG".into())).await;
+            continue;
+        }
+        let Ok(request) = serde_json::from_str::<Value>(text.as_str()) else {
+            continue;
+        };
+        let assets: Vec<String> = request["assets_ids"]
+            .as_array()
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let books: Vec<Value> = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.subscribed.extend(assets.iter().cloned());
+            assets
+                .iter()
+                .filter_map(|id| {
+                    let mid = state.midpoints.get(id)?;
+                    let mut book = book_json(id, *mid);
+                    book["event_type"] = json!("book");
+                    Some(book)
+                })
+                .collect()
+        };
+        let payload = Value::Array(books).to_string();
+        if socket.send(Message::Text(payload.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn user_channel(upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(|mut socket| async move {
+        while let Some(Ok(message)) = socket.recv().await {
+            if matches!(&message, Message::Text(text) if text.as_str() == "PING") {
+                let _ = socket.send(Message::Text("PONG".into())).await;
+            }
+        }
+    })
+}